@@ -0,0 +1,22 @@
+// Proves and exports a Fibonacci trace using nothing but the prelude, so that making any of those items private
+// breaks `cargo build --examples`.
+//
+//     cargo run --example prelude
+
+use plonky3_fibonacci::prelude::*;
+
+fn main() -> Result<(), FibVisError> {
+    let num_steps = 16;
+    let config: M31KeccakConfig = ConfigBuilder::new().num_steps(num_steps).build()?;
+    let air = FibonacciAir::new(num_steps);
+    let final_value = fibonacci_final_value(FieldChoice::Mersenne31, num_steps, FIBONACCI_START);
+    let public_values = air.public_values::<Mersenne31>(final_value);
+    let trace: RowMajorMatrix<Mersenne31> = generate_fibonacci_trace(num_steps, FIBONACCI_START);
+    let vis = VisData { final_value, ..vis_data_from_matrix(&trace, &FIBONACCI_COLUMNS)? };
+
+    let proof = prove(&config, &air, trace, &public_values);
+    verify(&config, &air, &proof, &public_values)?;
+    write_trace_json(std::path::Path::new("web/trace_data.json"), &vis)?;
+    println!("proved and exported {num_steps} rows ending in {}", vis.final_value);
+    Ok(())
+}
@@ -0,0 +1,822 @@
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir, PairBuilder};
+use p3_field::{Field, PrimeCharacteristicRing};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_uni_stark::{
+    prove_with_preprocessed, setup_preprocessed, verify_with_preprocessed, Proof, StarkGenericConfig, Val,
+};
+use p3_util::log2_strict_usize;
+
+use crate::error::FibVisError;
+use crate::trace::{
+    generate_bit_decomp_trace, generate_collatz_trace, generate_counter_trace, generate_fibonacci_trace,
+    generate_fibonacci_with_index_trace, generate_linear_recurrence_trace, generate_lucas_trace,
+    generate_mul_fibonacci_trace, generate_padded_fibonacci_trace, generate_tribonacci_trace, FIBONACCI_START,
+};
+
+/// Where an AIR's sequence sits in its trace, so that the shape and final-value checks work for every sequence.
+pub trait StepCount {
+    /// Number of rows holding the sequence.
+    fn num_steps(&self) -> usize;
+
+    /// Number of rows of the trace, larger than num_steps when the trace is padded.
+    fn height(&self) -> usize {
+        self.num_steps()
+    }
+
+    /// Column of the last real row that must hold the final value.
+    fn final_column(&self) -> usize;
+}
+
+/// Two-column AIR for a Fibonacci-like sequence: row i holds (a(i), a(i+1)) with a(i+2) = a(i) + a(i+1). The
+/// boundary values are public values rather than part of the AIR, laid out as in public_values: the first
+/// row must equal (initial_a, initial_b) and the last row's second column must equal the claimed final value.
+///
+/// A padded AIR takes any num_steps: its trace (generate_padded_fibonacci_trace) is filled up to the next power
+/// of two with rows of zeros and has a third is_real column, 1 on the num_steps real rows and 0 after them, and a
+/// fourth row column holding each row's index. Transitions are only enforced into real rows, and the final value
+/// binds to the last real row, whose index must be num_steps - 1: the proof shows that the sequence reaches the
+/// final value at row num_steps - 1, as an unpadded one does.
+pub struct FibonacciAir {
+    pub num_steps: usize,
+    pub initial_a: u32,
+    pub initial_b: u32,
+    pub padded: bool,
+}
+
+impl FibonacciAir {
+    /// AIR for a trace of `num_steps` rows of the canonical sequence starting from (0, 1).
+    pub fn new(num_steps: usize) -> Self {
+        Self::with_initial(num_steps, FIBONACCI_START)
+    }
+
+    /// AIR for a trace of `num_steps` rows starting from `(initial_a, initial_b)`, e.g. (2, 1) for the Lucas numbers.
+    pub fn with_initial(num_steps: usize, (initial_a, initial_b): (u32, u32)) -> Self {
+        Self { num_steps, initial_a, initial_b, padded: false }
+    }
+
+    /// AIR for `num_steps` rows starting from `initial`, padded to the next power of two.
+    pub fn padded(num_steps: usize, initial: (u32, u32)) -> Self {
+        Self { padded: true, ..Self::with_initial(num_steps, initial) }
+    }
+
+    /// The public values to prove and verify a trace of this AIR ending in `final_value` with.
+    pub fn public_values<F: PrimeCharacteristicRing>(&self, final_value: u64) -> Vec<F> {
+        vec![F::from_u32(self.initial_a), F::from_u32(self.initial_b), F::from_u64(final_value)]
+    }
+}
+
+/// Number of public values FibonacciAir reads: initial_a, initial_b and the final value.
+pub const NUM_PUBLIC_VALUES: usize = 3;
+
+impl StepCount for FibonacciAir {
+    fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+
+    fn height(&self) -> usize {
+        if self.padded {
+            self.num_steps.next_power_of_two()
+        } else {
+            self.num_steps
+        }
+    }
+
+    fn final_column(&self) -> usize {
+        1
+    }
+}
+
+impl<F: Field> BaseAir<F> for FibonacciAir {
+    fn width(&self) -> usize {
+        // For current and next Fibonacci number, and the is_real selector and row counter when padded
+        if self.padded {
+            4
+        } else {
+            2
+        }
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for FibonacciAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0).unwrap();
+        let next = main.row_slice(1).unwrap();
+
+        let public_values = builder.public_values();
+        let (a0, a1, final_value) = (public_values[0], public_values[1], public_values[2]);
+
+        // Enforce starting values
+        builder.when_first_row().assert_eq(local[0], a0);
+        builder.when_first_row().assert_eq(local[1], a1);
+
+        if self.padded {
+            eval_padded(builder, &local, &next, final_value, self.num_steps);
+            return;
+        }
+
+        // Enforce state transition constraints
+        eval_transition(builder, &local, &next);
+
+        // Constrain the final value
+        builder.when_last_row().assert_eq(local[1], final_value);
+    }
+}
+
+// The constraints of a padded FibonacciAir besides its first two, of degree 2 at most. is_real is boolean, starts
+// at 1 and never goes back from 0 to 1, so the real rows are a prefix. Transitions hold into every real row, and the
+// row where is_real drops to 0, or the last row if it never does, holds the final value. The row column counts 0, 1,
+// 2, ... down the whole trace and is num_steps - 1 on that row, so the prefix is exactly num_steps rows long.
+fn eval_padded<AB: AirBuilder>(
+    builder: &mut AB,
+    local: &[AB::Var],
+    next: &[AB::Var],
+    final_value: AB::Var,
+    num_steps: usize,
+) {
+    let (is_real, next_is_real) = (local[2], next[2]);
+    let (row, next_row) = (local[3], next[3]);
+    let last_real_row = AB::Expr::from_usize(num_steps - 1);
+    builder.when_first_row().assert_one(is_real);
+    builder.when_first_row().assert_zero(row);
+    builder.assert_bool(is_real);
+
+    let mut when_transition = builder.when_transition();
+    when_transition.assert_zero(next_is_real * (AB::Expr::ONE - is_real));
+    when_transition.when(next_is_real).assert_eq(next[0], local[1]);
+    when_transition.when(next_is_real).assert_eq(next[1], local[0] + local[1]);
+    when_transition.when(is_real - next_is_real).assert_eq(local[1], final_value);
+    when_transition.assert_eq(next_row, row + AB::Expr::ONE);
+    when_transition.when(is_real - next_is_real).assert_eq(row, last_real_row.clone());
+
+    let mut when_last_row = builder.when_last_row();
+    when_last_row.when(is_real).assert_eq(local[1], final_value);
+    when_last_row.when(is_real).assert_eq(row, last_real_row);
+}
+
+/// FibonacciAir with the step index in a preprocessed column: preprocessed_trace holds 0, 1, ..., num_steps - 1,
+/// which the verifier fixes instead of the prover committing to it, and the last row's index must equal
+/// num_steps - 1. The main trace, its constraints and public values are those of FibonacciAir. It is proven as
+/// SequenceAir::Indexed through SequenceAir::prove, which commits to the preprocessed trace alongside the main one.
+pub struct IndexedFibonacciAir {
+    pub fibonacci: FibonacciAir,
+}
+
+impl IndexedFibonacciAir {
+    /// AIR for a trace of `num_steps` rows starting from `initial`, with a `num_steps`-row index column.
+    pub fn new(num_steps: usize, initial: (u32, u32)) -> Self {
+        Self { fibonacci: FibonacciAir::with_initial(num_steps, initial) }
+    }
+
+    /// The public values to prove and verify a trace of this AIR ending in `final_value` with.
+    pub fn public_values<F: PrimeCharacteristicRing>(&self, final_value: u64) -> Vec<F> {
+        self.fibonacci.public_values(final_value)
+    }
+}
+
+impl StepCount for IndexedFibonacciAir {
+    fn num_steps(&self) -> usize {
+        self.fibonacci.num_steps
+    }
+
+    fn final_column(&self) -> usize {
+        1
+    }
+}
+
+impl<F: Field> BaseAir<F> for IndexedFibonacciAir {
+    fn width(&self) -> usize {
+        2
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        Some(generate_counter_trace(self.fibonacci.num_steps))
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues + PairBuilder> Air<AB> for IndexedFibonacciAir {
+    fn eval(&self, builder: &mut AB) {
+        self.fibonacci.eval(builder);
+
+        let preprocessed = builder.preprocessed();
+        let step = preprocessed.row_slice(0).unwrap()[0];
+        builder.when_last_row().assert_eq(step, AB::Expr::from_usize(self.fibonacci.num_steps - 1));
+    }
+}
+
+/// FibonacciAir with the step index as a committed column in front: row i holds (i, a(i), a(i+1)). Unlike
+/// IndexedFibonacciAir the prover commits to the index, so the AIR pins it down itself: the first row's index is 0,
+/// every next row's is one more, and the last row's is num_steps - 1. The public values are those of FibonacciAir.
+pub struct FibonacciWithIndexAir {
+    pub num_steps: usize,
+    pub initial_a: u32,
+    pub initial_b: u32,
+}
+
+impl FibonacciWithIndexAir {
+    /// AIR for a trace of `num_steps` rows starting from `(initial_a, initial_b)`.
+    pub fn new(num_steps: usize, (initial_a, initial_b): (u32, u32)) -> Self {
+        Self { num_steps, initial_a, initial_b }
+    }
+
+    /// The public values to prove and verify a trace of this AIR ending in `final_value` with.
+    pub fn public_values<F: PrimeCharacteristicRing>(&self, final_value: u64) -> Vec<F> {
+        vec![F::from_u32(self.initial_a), F::from_u32(self.initial_b), F::from_u64(final_value)]
+    }
+}
+
+impl StepCount for FibonacciWithIndexAir {
+    fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+
+    fn final_column(&self) -> usize {
+        2
+    }
+}
+
+impl<F: Field> BaseAir<F> for FibonacciWithIndexAir {
+    fn width(&self) -> usize {
+        // The step index, then the current and next Fibonacci number
+        3
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for FibonacciWithIndexAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0).unwrap();
+        let next = main.row_slice(1).unwrap();
+
+        let public_values = builder.public_values();
+        let (a0, a1, final_value) = (public_values[0], public_values[1], public_values[2]);
+
+        // Enforce the starting index and values
+        builder.when_first_row().assert_zero(local[0]);
+        builder.when_first_row().assert_eq(local[1], a0);
+        builder.when_first_row().assert_eq(local[2], a1);
+
+        // The index counts the rows, the sequence columns follow the Fibonacci transition
+        builder.when_transition().assert_eq(next[0], local[0] + AB::Expr::ONE);
+        eval_transition(builder, &local[1..], &next[1..]);
+
+        // The last row is row num_steps - 1 and holds the final value
+        builder.when_last_row().assert_eq(local[0], AB::Expr::from_usize(self.num_steps - 1));
+        builder.when_last_row().assert_eq(local[2], final_value);
+    }
+}
+
+/// The Lucas numbers 2, 1, 3, 4, 7, ...: the same columns, transition and final-value constraint as FibonacciAir,
+/// but with the first row fixed to (2, 1) in the AIR itself. The only public value is the final value.
+pub struct LucasAir {
+    pub num_steps: usize,
+}
+
+impl LucasAir {
+    /// AIR for a trace of `num_steps` rows.
+    pub fn new(num_steps: usize) -> Self {
+        Self { num_steps }
+    }
+
+    /// The public values to prove and verify a trace of this AIR ending in `final_value` with.
+    pub fn public_values<F: PrimeCharacteristicRing>(&self, final_value: u64) -> Vec<F> {
+        vec![F::from_u64(final_value)]
+    }
+}
+
+impl StepCount for LucasAir {
+    fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+
+    fn final_column(&self) -> usize {
+        1
+    }
+}
+
+impl<F: Field> BaseAir<F> for LucasAir {
+    fn width(&self) -> usize {
+        2
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for LucasAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0).unwrap();
+        let next = main.row_slice(1).unwrap();
+        let final_value = builder.public_values()[0];
+
+        builder.when_first_row().assert_eq(local[0], AB::Expr::TWO);
+        builder.when_first_row().assert_eq(local[1], AB::Expr::ONE);
+
+        eval_transition(builder, &local, &next);
+
+        builder.when_last_row().assert_eq(local[1], final_value);
+    }
+}
+
+// next = (b, a + b), shared by the Fibonacci-like AIRs.
+fn eval_transition<AB: AirBuilder>(builder: &mut AB, local: &[AB::Var], next: &[AB::Var]) {
+    builder.when_transition().assert_eq(next[0], local[1]);
+    builder.when_transition().assert_eq(next[1], local[0] + local[1]);
+}
+
+/// Three-column AIR for the Tribonacci numbers 0, 0, 1, 1, 2, 4, 7, ...: row i holds (T(i), T(i+1), T(i+2)), the
+/// first row is fixed to (0, 0, 1), every next row is (b, c, a + b + c), and the last row's third column must
+/// equal the final value, the only public value.
+pub struct TribonacciAir {
+    pub num_steps: usize,
+}
+
+impl TribonacciAir {
+    /// AIR for a trace of `num_steps` rows.
+    pub fn new(num_steps: usize) -> Self {
+        Self { num_steps }
+    }
+
+    /// The public values to prove and verify a trace of this AIR ending in `final_value` with.
+    pub fn public_values<F: PrimeCharacteristicRing>(&self, final_value: u64) -> Vec<F> {
+        vec![F::from_u64(final_value)]
+    }
+}
+
+impl StepCount for TribonacciAir {
+    fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+
+    fn final_column(&self) -> usize {
+        2
+    }
+}
+
+impl<F: Field> BaseAir<F> for TribonacciAir {
+    fn width(&self) -> usize {
+        3
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for TribonacciAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0).unwrap();
+        let next = main.row_slice(1).unwrap();
+        let final_value = builder.public_values()[0];
+
+        builder.when_first_row().assert_eq(local[0], AB::Expr::ZERO);
+        builder.when_first_row().assert_eq(local[1], AB::Expr::ZERO);
+        builder.when_first_row().assert_eq(local[2], AB::Expr::ONE);
+
+        builder.when_transition().assert_eq(next[0], local[1]);
+        builder.when_transition().assert_eq(next[1], local[2]);
+        builder.when_transition().assert_eq(next[2], local[0] + local[1] + local[2]);
+
+        builder.when_last_row().assert_eq(local[2], final_value);
+    }
+}
+
+/// The product recurrence 1, 2, 2, 4, 8, 32, 256, ...: row i holds (a, b), the first row is fixed to (1, 2) and
+/// every next row is (b, a * b). Its transition constraint is degree 2, unlike the linear ones of the other
+/// sequences, so the quotient polynomial has real work to do. The only public value is the final value.
+pub struct MulFibonacciAir {
+    pub num_steps: usize,
+}
+
+impl MulFibonacciAir {
+    /// AIR for a trace of `num_steps` rows.
+    pub fn new(num_steps: usize) -> Self {
+        Self { num_steps }
+    }
+
+    /// The public values to prove and verify a trace of this AIR ending in `final_value` with.
+    pub fn public_values<F: PrimeCharacteristicRing>(&self, final_value: u64) -> Vec<F> {
+        vec![F::from_u64(final_value)]
+    }
+}
+
+impl StepCount for MulFibonacciAir {
+    fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+
+    fn final_column(&self) -> usize {
+        1
+    }
+}
+
+impl<F: Field> BaseAir<F> for MulFibonacciAir {
+    fn width(&self) -> usize {
+        2
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for MulFibonacciAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0).unwrap();
+        let next = main.row_slice(1).unwrap();
+        let final_value = builder.public_values()[0];
+
+        builder.when_first_row().assert_eq(local[0], AB::Expr::ONE);
+        builder.when_first_row().assert_eq(local[1], AB::Expr::TWO);
+
+        builder.when_transition().assert_eq(next[0], local[1]);
+        builder.when_transition().assert_eq(next[1], local[0] * local[1]);
+
+        builder.when_last_row().assert_eq(local[1], final_value);
+    }
+}
+
+/// Any order-k linear recurrence x(n) = c(1) x(n-1) + ... + c(k) x(n-k), with Fibonacci (1, 1), Tribonacci
+/// (1, 1, 1) and the Pell numbers (2, 1) as special cases. Row i holds x(i), ..., x(i+k-1), one column per
+/// coefficient, and every next row shifts that window by one and appends the next value; eval builds these
+/// constraints from `coeffs`. Like FibonacciAir the first row and the final value are public values, laid out
+/// as in public_values.
+pub struct LinearRecurrenceAir {
+    pub num_steps: usize,
+    pub coeffs: Vec<u64>,  // c(1), ..., c(k)
+    pub initial: Vec<u64>, // x(0), ..., x(k-1), as many as there are coefficients
+}
+
+impl LinearRecurrenceAir {
+    /// AIR for a trace of `num_steps` rows of the recurrence with `coeffs` starting from `initial`.
+    pub fn new(num_steps: usize, coeffs: Vec<u64>, initial: Vec<u64>) -> Self {
+        Self { num_steps, coeffs, initial }
+    }
+
+    /// The public values to prove and verify a trace of this AIR ending in `final_value` with.
+    pub fn public_values<F: PrimeCharacteristicRing>(&self, final_value: u64) -> Vec<F> {
+        self.initial.iter().chain([&final_value]).map(|&v| F::from_u64(v)).collect()
+    }
+}
+
+impl StepCount for LinearRecurrenceAir {
+    fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+
+    fn final_column(&self) -> usize {
+        self.coeffs.len() - 1
+    }
+}
+
+impl<F: Field> BaseAir<F> for LinearRecurrenceAir {
+    fn width(&self) -> usize {
+        self.coeffs.len()
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for LinearRecurrenceAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0).unwrap();
+        let next = main.row_slice(1).unwrap();
+        let public_values = builder.public_values().to_vec();
+        let k = self.coeffs.len();
+
+        for j in 0..k {
+            builder.when_first_row().assert_eq(local[j], public_values[j]);
+        }
+
+        // next = (x(i+1), ..., x(i+k-1), c(1) x(i+k-1) + ... + c(k) x(i))
+        for j in 0..k - 1 {
+            builder.when_transition().assert_eq(next[j], local[j + 1]);
+        }
+        let combination = self
+            .coeffs
+            .iter()
+            .enumerate()
+            .fold(AB::Expr::ZERO, |acc, (j, &c)| acc + local[k - 1 - j] * AB::Expr::from_u64(c));
+        builder.when_transition().assert_eq(next[k - 1], combination);
+
+        builder.when_last_row().assert_eq(local[k - 1], public_values[k]);
+    }
+}
+
+/// A Collatz orbit n -> n / 2 for even n, n -> 3n + 1 for odd n, starting from `seed`. Row i holds (value,
+/// parity), where the parity column is a witness the prover fills in: it is constrained to be boolean and selects
+/// the branch, 2 next.value = value + parity (5 value + 2), which is n / 2 for parity 0 and 3n + 1 for parity 1.
+/// The field has no notion of parity, so the AIR checks the step each parity selects, not that it is the actual
+/// parity of the value; ruling out an odd value halved in the field would take a bit decomposition. A third is_real
+/// column is 1 on the rows before the orbit reaches 1 and 0 from its first 1 on: those rows hold 1 with parity 0,
+/// and the transition's extra 1 - is_real term makes it 2 next.value = 1 + 1 there, so the trace repeats 1 to its
+/// end instead of going around the 1, 4, 2 cycle. is_real may only drop, and every constraint stays of degree 2.
+/// The public values are the seed and the final value.
+pub struct CollatzAir {
+    pub num_steps: usize,
+    pub seed: u64,
+}
+
+impl CollatzAir {
+    /// AIR for a trace of `num_steps` rows of the orbit of `seed`.
+    pub fn new(num_steps: usize, seed: u64) -> Self {
+        Self { num_steps, seed }
+    }
+
+    /// The public values to prove and verify a trace of this AIR ending in `final_value` with.
+    pub fn public_values<F: PrimeCharacteristicRing>(&self, final_value: u64) -> Vec<F> {
+        vec![F::from_u64(self.seed), F::from_u64(final_value)]
+    }
+}
+
+impl StepCount for CollatzAir {
+    fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+
+    fn final_column(&self) -> usize {
+        0
+    }
+}
+
+impl<F: Field> BaseAir<F> for CollatzAir {
+    fn width(&self) -> usize {
+        3 // value, parity and is_real
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for CollatzAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0).unwrap();
+        let next = main.row_slice(1).unwrap();
+        let public_values = builder.public_values();
+        let (seed, final_value) = (public_values[0], public_values[1]);
+        let (value, parity, is_real) = (local[0], local[1], local[2]);
+        let padding = AB::Expr::ONE - is_real;
+
+        builder.when_first_row().assert_eq(value, seed);
+        builder.assert_bool(parity);
+        builder.assert_bool(is_real);
+        builder.when(padding.clone()).assert_one(value);
+        builder.when(padding.clone()).assert_zero(parity);
+
+        let odd_step = value * AB::Expr::from_u32(5) + AB::Expr::TWO;
+        let mut when_transition = builder.when_transition();
+        when_transition.assert_zero(next[2] * padding.clone());
+        when_transition.assert_eq(next[0] * AB::Expr::TWO, parity * odd_step + value + padding);
+
+        builder.when_last_row().assert_eq(value, final_value);
+    }
+}
+
+/// The smallest AIR there is: a single column that starts at 0 and grows by 1 every row, so its last row holds
+/// num_steps - 1. The only public value is the final value.
+pub struct CounterAir {
+    pub num_steps: usize,
+}
+
+impl CounterAir {
+    /// AIR for a trace of `num_steps` rows.
+    pub fn new(num_steps: usize) -> Self {
+        Self { num_steps }
+    }
+
+    /// The public values to prove and verify a trace of this AIR ending in `final_value` with.
+    pub fn public_values<F: PrimeCharacteristicRing>(&self, final_value: u64) -> Vec<F> {
+        vec![F::from_u64(final_value)]
+    }
+}
+
+impl StepCount for CounterAir {
+    fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+
+    fn final_column(&self) -> usize {
+        0
+    }
+}
+
+impl<F: Field> BaseAir<F> for CounterAir {
+    fn width(&self) -> usize {
+        1
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for CounterAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0).unwrap();
+        let next = main.row_slice(1).unwrap();
+        let final_value = builder.public_values()[0];
+
+        builder.when_first_row().assert_zero(local[0]);
+        builder.when_transition().assert_eq(next[0], local[0] + AB::Expr::ONE);
+        builder.when_last_row().assert_eq(local[0], final_value);
+    }
+}
+
+/// Number of bits BitDecompAir splits each value into.
+pub const BIT_DECOMP_BITS: usize = 8;
+
+/// A range check by bit decomposition: row i holds a value and its BIT_DECOMP_BITS bits, least significant first.
+/// Every bit is constrained to be boolean and the value to be their weighted sum, which together prove that every
+/// value is below 2^8. The rows carry no transition; generate_bit_decomp_trace fills them with the row index
+/// modulo 256. The only public value is the last row's value.
+pub struct BitDecompAir {
+    pub num_steps: usize,
+}
+
+impl BitDecompAir {
+    /// AIR for a trace of `num_steps` rows.
+    pub fn new(num_steps: usize) -> Self {
+        Self { num_steps }
+    }
+
+    /// The public values to prove and verify a trace of this AIR ending in `final_value` with.
+    pub fn public_values<F: PrimeCharacteristicRing>(&self, final_value: u64) -> Vec<F> {
+        vec![F::from_u64(final_value)]
+    }
+}
+
+impl StepCount for BitDecompAir {
+    fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+
+    fn final_column(&self) -> usize {
+        0
+    }
+}
+
+impl<F: Field> BaseAir<F> for BitDecompAir {
+    fn width(&self) -> usize {
+        1 + BIT_DECOMP_BITS // the value and its bits
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for BitDecompAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0).unwrap();
+        let final_value = builder.public_values()[0];
+        let (value, bits) = (local[0], &local[1..=BIT_DECOMP_BITS]);
+
+        for &bit in bits {
+            builder.assert_bool(bit);
+        }
+        let recomposed = bits.iter().rev().fold(AB::Expr::ZERO, |acc, &bit| acc * AB::Expr::TWO + bit);
+        builder.assert_eq(value, recomposed);
+
+        builder.when_last_row().assert_eq(value, final_value);
+    }
+}
+
+/// Whichever AIR --sequence (or --recurrence) selected. The pipeline proves and verifies this one type, each
+/// variant keeps its own constraints and public value layout.
+pub enum SequenceAir {
+    Fibonacci(FibonacciAir),
+    FibonacciWithIndex(FibonacciWithIndexAir),
+    Lucas(LucasAir),
+    Tribonacci(TribonacciAir),
+    MulFibonacci(MulFibonacciAir),
+    Linear(LinearRecurrenceAir),
+    Collatz(CollatzAir),
+    Counter(CounterAir),
+    BitDecomp(BitDecompAir),
+    Indexed(IndexedFibonacciAir),
+}
+
+impl SequenceAir {
+    /// The public values of the selected AIR for a trace ending in `final_value`.
+    pub fn public_values<F: PrimeCharacteristicRing>(&self, final_value: u64) -> Vec<F> {
+        match self {
+            SequenceAir::Fibonacci(air) => air.public_values(final_value),
+            SequenceAir::FibonacciWithIndex(air) => air.public_values(final_value),
+            SequenceAir::Lucas(air) => air.public_values(final_value),
+            SequenceAir::Tribonacci(air) => air.public_values(final_value),
+            SequenceAir::MulFibonacci(air) => air.public_values(final_value),
+            SequenceAir::Linear(air) => air.public_values(final_value),
+            SequenceAir::Collatz(air) => air.public_values(final_value),
+            SequenceAir::Counter(air) => air.public_values(final_value),
+            SequenceAir::BitDecomp(air) => air.public_values(final_value),
+            SequenceAir::Indexed(air) => air.public_values(final_value),
+        }
+    }
+}
+
+impl SequenceAir {
+    /// Width of the AIR's preprocessed trace, 0 without one, for evaluating its constraints symbolically.
+    pub fn preprocessed_width(&self) -> usize {
+        match self {
+            SequenceAir::Indexed(_) => 1, // the step column
+            _ => 0,
+        }
+    }
+
+    /// The trace this AIR accepts, generated from the parameters it was built with.
+    pub fn generate_trace<F: Field>(&self) -> RowMajorMatrix<F> {
+        match self {
+            SequenceAir::Fibonacci(air) if air.padded => generate_padded_fibonacci_trace(air.num_steps, (air.initial_a, air.initial_b)),
+            SequenceAir::Fibonacci(air) => generate_fibonacci_trace(air.num_steps, (air.initial_a, air.initial_b)),
+            SequenceAir::FibonacciWithIndex(air) => generate_fibonacci_with_index_trace(air.num_steps, (air.initial_a, air.initial_b)),
+            SequenceAir::Lucas(air) => generate_lucas_trace(air.num_steps),
+            SequenceAir::Tribonacci(air) => generate_tribonacci_trace(air.num_steps),
+            SequenceAir::MulFibonacci(air) => generate_mul_fibonacci_trace(air.num_steps),
+            SequenceAir::Linear(air) => generate_linear_recurrence_trace(air.num_steps, &air.coeffs, &air.initial),
+            SequenceAir::Collatz(air) => generate_collatz_trace(air.num_steps, air.seed),
+            SequenceAir::Counter(air) => generate_counter_trace(air.num_steps),
+            SequenceAir::BitDecomp(air) => generate_bit_decomp_trace(air.num_steps),
+            SequenceAir::Indexed(IndexedFibonacciAir { fibonacci: air }) => {
+                generate_fibonacci_trace(air.num_steps, (air.initial_a, air.initial_b))
+            }
+        }
+    }
+
+    /// uni-stark's prove for this AIR, committing to its preprocessed trace first when it has one (see
+    /// IndexedFibonacciAir), so that the prover never commits to those columns itself.
+    pub fn prove<SC: StarkGenericConfig>(
+        &self,
+        config: &SC,
+        trace: RowMajorMatrix<Val<SC>>,
+        public_values: &[Val<SC>],
+    ) -> Proof<SC> {
+        let preprocessed = setup_preprocessed(config, self, log2_strict_usize(self.height())).map(|(data, _)| data);
+        prove_with_preprocessed(config, self, trace, public_values, preprocessed.as_ref())
+    }
+
+    /// uni-stark's verify for this AIR, against the commitment to its preprocessed trace, which the verifier
+    /// derives from the AIR just as prove did.
+    pub fn verify<SC: StarkGenericConfig>(
+        &self,
+        config: &SC,
+        proof: &Proof<SC>,
+        public_values: &[Val<SC>],
+    ) -> Result<(), FibVisError> {
+        let preprocessed = setup_preprocessed(config, self, log2_strict_usize(self.height())).map(|(_, key)| key);
+        Ok(verify_with_preprocessed(config, self, proof, public_values, preprocessed.as_ref())?)
+    }
+
+    fn step_count(&self) -> &dyn StepCount {
+        match self {
+            SequenceAir::Fibonacci(air) => air,
+            SequenceAir::FibonacciWithIndex(air) => air,
+            SequenceAir::Lucas(air) => air,
+            SequenceAir::Tribonacci(air) => air,
+            SequenceAir::MulFibonacci(air) => air,
+            SequenceAir::Linear(air) => air,
+            SequenceAir::Collatz(air) => air,
+            SequenceAir::Counter(air) => air,
+            SequenceAir::BitDecomp(air) => air,
+            SequenceAir::Indexed(air) => air,
+        }
+    }
+}
+
+impl StepCount for SequenceAir {
+    fn num_steps(&self) -> usize {
+        self.step_count().num_steps()
+    }
+
+    fn height(&self) -> usize {
+        self.step_count().height()
+    }
+
+    fn final_column(&self) -> usize {
+        self.step_count().final_column()
+    }
+}
+
+impl<F: Field> BaseAir<F> for SequenceAir {
+    fn width(&self) -> usize {
+        match self {
+            SequenceAir::Fibonacci(air) => BaseAir::<F>::width(air),
+            SequenceAir::FibonacciWithIndex(air) => BaseAir::<F>::width(air),
+            SequenceAir::Lucas(air) => BaseAir::<F>::width(air),
+            SequenceAir::Tribonacci(air) => BaseAir::<F>::width(air),
+            SequenceAir::MulFibonacci(air) => BaseAir::<F>::width(air),
+            SequenceAir::Linear(air) => BaseAir::<F>::width(air),
+            SequenceAir::Collatz(air) => BaseAir::<F>::width(air),
+            SequenceAir::Counter(air) => BaseAir::<F>::width(air),
+            SequenceAir::BitDecomp(air) => BaseAir::<F>::width(air),
+            SequenceAir::Indexed(air) => BaseAir::<F>::width(air),
+        }
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        match self {
+            SequenceAir::Indexed(air) => BaseAir::<F>::preprocessed_trace(air),
+            _ => None,
+        }
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues + PairBuilder> Air<AB> for SequenceAir {
+    fn eval(&self, builder: &mut AB) {
+        match self {
+            SequenceAir::Fibonacci(air) => air.eval(builder),
+            SequenceAir::FibonacciWithIndex(air) => air.eval(builder),
+            SequenceAir::Lucas(air) => air.eval(builder),
+            SequenceAir::Tribonacci(air) => air.eval(builder),
+            SequenceAir::MulFibonacci(air) => air.eval(builder),
+            SequenceAir::Linear(air) => air.eval(builder),
+            SequenceAir::Collatz(air) => air.eval(builder),
+            SequenceAir::Counter(air) => air.eval(builder),
+            SequenceAir::BitDecomp(air) => air.eval(builder),
+            SequenceAir::Indexed(air) => air.eval(builder),
+        }
+    }
+}
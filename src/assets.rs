@@ -0,0 +1,56 @@
+//! A minimal visualizer the binary writes next to its trace export, so that a first run outside `src/` (where
+//! the full visualizer in `src/web/` is) still leaves a page to open: `index.html`, which names the export, and
+//! `viewer.js`, which fetches it and shows the trace as a table with a chart of the sequence's growth. Opened from
+//! file://, where it cannot fetch, the page reads a --format js export of the same name instead. Both are
+//! embedded in the binary. A directory with an index.html already has its page, the full visualizer or one edited
+//! by hand, and is left alone.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::error::FibVisError;
+use crate::export::write_atomically;
+
+/// The page, with TRACE_FILE and TRACE_SCRIPT where the export's file names go, see index_html.
+pub const INDEX_HTML: &str = include_str!("web/scaffold/index.html");
+pub const VIEWER_JS: &str = include_str!("web/scaffold/viewer.js");
+
+/// Placeholder in INDEX_HTML for the file name of the trace export.
+pub const TRACE_FILE: &str = "{{TRACE_FILE}}";
+/// ... and for the script setting TRACE_DATA, the export's name with a `.js` extension.
+pub const TRACE_SCRIPT: &str = "{{TRACE_SCRIPT}}";
+
+/// What write_assets did with one of the files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetStatus {
+    Written,
+    Kept, // it was there already
+}
+
+/// INDEX_HTML for the trace export `trace_file`, a path relative to the page, e.g. `trace_data.json`, which
+/// loads `trace_data.js` first.
+pub fn index_html(trace_file: &str) -> String {
+    let escape = |s: &str| s.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;");
+    let script = Path::new(trace_file).with_extension("js");
+    INDEX_HTML.replace(TRACE_FILE, &escape(trace_file)).replace(TRACE_SCRIPT, &escape(&script.to_string_lossy()))
+}
+
+/// Writes index.html for `trace_file` and viewer.js into `dir`, unless it has an index.html, and then keeps a
+/// viewer.js that is there; `force` overwrites both. Gives back each file's path and what happened to it.
+pub fn write_assets(dir: &Path, trace_file: &str, force: bool) -> Result<Vec<(PathBuf, AssetStatus)>, FibVisError> {
+    let index = dir.join("index.html");
+    if index.exists() && !force {
+        return Ok(vec![(index, AssetStatus::Kept)]);
+    }
+    let assets = [(index, index_html(trace_file)), (dir.join("viewer.js"), VIEWER_JS.to_string())];
+    let mut written = Vec::with_capacity(assets.len());
+    for (path, contents) in assets {
+        if path.exists() && !force {
+            written.push((path, AssetStatus::Kept));
+            continue;
+        }
+        write_atomically(&path, |out: &mut dyn Write| Ok(out.write_all(contents.as_bytes())?))?;
+        written.push((path, AssetStatus::Written));
+    }
+    Ok(written)
+}
@@ -0,0 +1,78 @@
+//! Several runs side by side, for the dashboard to overlay them on one chart: `compare` proves two or more run
+//! configs, e.g. Keccak256 against Poseidon2 or 256 steps against 4096, and writes a Comparison of their stats
+//! to comparison.json. The traces stay in their own exports, which the runs name by path.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::export::Timings;
+use crate::stark_config::{FieldChoice, FriParams, HashChoice};
+
+/// One run of a comparison: what it was run with and what it produced.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RunStats {
+    pub label: String,               // e.g. the config file's name, unique within the comparison
+    pub num_steps: usize,
+    pub sequence: String,            // as the export records it, e.g. `Fibonacci`
+    pub field: FieldChoice,
+    pub hash: HashChoice,
+    pub fri: FriParams,
+    pub trace_path: Option<PathBuf>, // the run's trace export, not embedded
+    pub proof_path: Option<PathBuf>,
+    pub proof_bytes: Option<usize>,  // None when the run did not get to a proof
+    pub timings: Timings,
+    pub verified: bool,
+    pub error: Option<String>,       // why the run failed, if it did
+}
+
+/// How a run differs from the baseline, the comparison's first run: the run's value minus the baseline's.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RunDelta {
+    pub label: String,
+    pub baseline: String,
+    pub num_steps: i64,
+    pub proof_bytes: Option<i64>,       // None unless both runs have a proof
+    pub proof_bytes_ratio: Option<f64>, // the run's proof size over the baseline's
+    pub timings: Timings,               // per phase, negative where the run was faster
+}
+
+/// What comparison.json holds.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Comparison {
+    pub runs: Vec<RunStats>,
+    pub deltas: Vec<RunDelta>, // one per run after the first, in the same order
+}
+
+impl Comparison {
+    /// The comparison of `runs` against the first of them.
+    pub fn new(runs: Vec<RunStats>) -> Self {
+        let deltas = match runs.split_first() {
+            Some((baseline, others)) => others.iter().map(|run| RunDelta::new(baseline, run)).collect(),
+            None => Vec::new(),
+        };
+        Self { runs, deltas }
+    }
+}
+
+impl RunDelta {
+    /// `run` against `baseline`.
+    pub fn new(baseline: &RunStats, run: &RunStats) -> Self {
+        let (a, b) = (&baseline.timings, &run.timings);
+        let sizes = baseline.proof_bytes.zip(run.proof_bytes);
+        Self {
+            label: run.label.clone(),
+            baseline: baseline.label.clone(),
+            num_steps: run.num_steps as i64 - baseline.num_steps as i64,
+            proof_bytes: sizes.map(|(a, b)| b as i64 - a as i64),
+            proof_bytes_ratio: sizes.filter(|&(a, _)| a > 0).map(|(a, b)| b as f64 / a as f64),
+            timings: Timings {
+                trace_gen_ms: b.trace_gen_ms - a.trace_gen_ms,
+                export_ms: b.export_ms - a.export_ms,
+                prove_ms: b.prove_ms - a.prove_ms,
+                verify_ms: b.verify_ms - a.verify_ms,
+                total_ms: b.total_ms - a.total_ms,
+            },
+        }
+    }
+}
@@ -0,0 +1,568 @@
+use p3_air::{Air, BaseAir};
+use p3_commit::LagrangeSelectors;
+use p3_field::{ExtensionField, Field};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_uni_stark::{get_symbolic_constraints, Entry, SymbolicAirBuilder, SymbolicExpression};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::FibVisError;
+
+/// One constraint of an AIR as uni-stark sees it, recorded by evaluating the AIR against its symbolic builder,
+/// so the export lists exactly what the prover enforces.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct ConstraintDesc {
+    pub kind: ConstraintKind, // the row selector the constraint is gated by
+    pub expression: String,   // the expression that must vanish, e.g. `next[0] - local[1]`
+    pub degree: usize,        // degree in the trace columns, before uni-stark multiplies in the row selector
+    pub tree: ConstraintExpr, // the same expression as a tree, without the row selector
+}
+
+/// Which rows a constraint applies to.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConstraintKind {
+    EveryRow,
+    FirstRow,
+    Transition,
+    LastRow,
+}
+
+impl ConstraintKind {
+    /// Degree of the row selector uni-stark multiplies the constraint by. The first- and last-row selectors are
+    /// polynomials of degree 1 over the trace domain, the transition selector is a constant factor there.
+    pub fn selector_degree(self) -> usize {
+        match self {
+            ConstraintKind::FirstRow | ConstraintKind::LastRow => 1,
+            ConstraintKind::EveryRow | ConstraintKind::Transition => 0,
+        }
+    }
+
+    /// Whether the selector is nonzero on `row` of a trace with `height` rows.
+    pub fn applies_to(self, row: usize, height: usize) -> bool {
+        match self {
+            ConstraintKind::EveryRow => true,
+            ConstraintKind::FirstRow => row == 0,
+            ConstraintKind::Transition => row + 1 < height,
+            ConstraintKind::LastRow => row + 1 == height,
+        }
+    }
+
+    /// The kind as the messages name it, e.g. `first-row`.
+    pub fn label(self) -> &'static str {
+        match self {
+            ConstraintKind::EveryRow => "every-row",
+            ConstraintKind::FirstRow => "first-row",
+            ConstraintKind::Transition => "transition",
+            ConstraintKind::LastRow => "last-row",
+        }
+    }
+}
+
+/// The degrees that decide how large the quotient polynomial gets, see constraint_report.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConstraintReport {
+    pub degrees: Vec<usize>,        // per constraint, including its row selector
+    pub max_degree: usize,          // the largest of them, at least 2 as uni-stark counts it
+    pub log_quotient_degree: usize, // the quotient is split into 2^this chunks of trace degree
+    pub quotient_degree: usize,
+    pub min_log_blowup: usize,      // smallest log_blowup the quotient fits in, and FRI needs at least 1
+}
+
+impl ConstraintReport {
+    /// The report for already extracted constraints. Follows uni-stark's get_log_quotient_degree: a constraint
+    /// of degree d makes the quotient degree d - 1 times the trace degree, rounded up to a power of two, and the
+    /// quotient domain it is evaluated on must fit in the trace's low-degree extension.
+    pub fn from_constraints(constraints: &[ConstraintDesc]) -> Self {
+        let degrees: Vec<usize> = constraints.iter().map(|c| c.degree + c.kind.selector_degree()).collect();
+        let max_degree = degrees.iter().copied().max().unwrap_or(0).max(2);
+        let quotient_degree = (max_degree - 1).next_power_of_two();
+        let log_quotient_degree = quotient_degree.ilog2() as usize;
+        Self { degrees, max_degree, log_quotient_degree, quotient_degree, min_log_blowup: log_quotient_degree.max(1) }
+    }
+
+    /// Rejects a log_blowup the quotient does not fit in, which uni-stark would only notice in the middle of
+    /// proving.
+    pub fn check_log_blowup(&self, log_blowup: usize) -> Result<(), FibVisError> {
+        if log_blowup < self.min_log_blowup {
+            return Err(FibVisError::Config(format!(
+                "log_blowup = {log_blowup} is too small for constraints of degree {}: the quotient has degree {} and needs log_blowup >= {}",
+                self.max_degree, self.quotient_degree, self.min_log_blowup
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for ConstraintReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} constraints of degree at most {} (with row selectors), quotient degree {}, needs log_blowup >= {}",
+            self.degrees.len(),
+            self.max_degree,
+            self.quotient_degree,
+            self.min_log_blowup
+        )
+    }
+}
+
+/// Which trace a cell belongs to.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceKind {
+    Main,
+    Preprocessed,
+}
+
+/// A constraint expression over trace cells, public values and constants.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ConstraintExpr {
+    /// `column` of the current row (offset 0) or the next one (offset 1).
+    Cell { trace: TraceKind, offset: usize, column: usize },
+    Public { index: usize },
+    Constant { value: String },
+    /// A row selector uni-stark multiplies in, when it is not the outermost factor.
+    Selector { kind: ConstraintKind },
+    Add { x: Box<ConstraintExpr>, y: Box<ConstraintExpr> },
+    Sub { x: Box<ConstraintExpr>, y: Box<ConstraintExpr> },
+    Mul { x: Box<ConstraintExpr>, y: Box<ConstraintExpr> },
+    Neg { x: Box<ConstraintExpr> },
+}
+
+/// The constraints of `air`, in the order its eval asserts them.
+pub fn extract_constraints<F: Field, A: Air<SymbolicAirBuilder<F>>>(
+    air: &A,
+    preprocessed_width: usize,
+    num_public_values: usize,
+) -> Vec<ConstraintDesc> {
+    get_symbolic_constraints(air, preprocessed_width, num_public_values)
+        .iter()
+        .map(|constraint| {
+            let (kind, tree) = split_selector(ConstraintExpr::from_symbolic(constraint));
+            ConstraintDesc { kind, expression: tree.to_string(), degree: tree.degree(), tree }
+        })
+        .collect()
+}
+
+/// The degree report of `air`'s constraints, see ConstraintReport.
+pub fn constraint_report<F: Field, A: Air<SymbolicAirBuilder<F>>>(
+    air: &A,
+    preprocessed_width: usize,
+    num_public_values: usize,
+) -> ConstraintReport {
+    ConstraintReport::from_constraints(&extract_constraints::<F, A>(air, preprocessed_width, num_public_values))
+}
+
+/// What each of `air`'s constraints evaluates to on every row of `trace`, indexed by (row, constraint) in the
+/// order extract_constraints lists them. A row pair satisfies a constraint when its entry is 0; rows the
+/// constraint's selector excludes (all but the first or last row, the last row for a transition) are left empty.
+/// The preprocessed trace, if the AIR has one, comes from the AIR itself.
+pub fn evaluate_constraints<F: Field, A: Air<SymbolicAirBuilder<F>>>(
+    air: &A,
+    trace: &RowMajorMatrix<F>,
+    public_values: &[F],
+) -> Vec<Vec<String>> {
+    evaluate_rows(air, trace, public_values, |window, _, kind, constraint| {
+        if kind.applies_to(window.row, window.height) { window.eval(constraint).to_string() } else { String::new() }
+    })
+}
+
+/// `air`'s constraints at a point off the trace domain, folded into one value the way uni-stark's verifier does
+/// it: each constraint in the order extract_constraints lists them, added to the sum so far times `alpha`. `main`
+/// holds the main trace's values opened at the point and at the one after it, `preprocessed` the same for the
+/// preprocessed trace, and `selectors` are the trace domain's row selectors at the point.
+pub fn fold_constraints_at<F: Field, EF: ExtensionField<F>, A: Air<SymbolicAirBuilder<F>>>(
+    air: &A,
+    main: [&[EF]; 2],
+    preprocessed: Option<[&[EF]; 2]>,
+    public_values: &[F],
+    selectors: &LagrangeSelectors<EF>,
+    alpha: EF,
+) -> EF {
+    let preprocessed_width = preprocessed.map_or(0, |p| p[0].len());
+    let point = PointWindow { main, preprocessed, public_values, selectors };
+    get_symbolic_constraints(air, preprocessed_width, public_values.len())
+        .iter()
+        .fold(EF::ZERO, |folded, constraint| folded * alpha + point.eval(constraint))
+}
+
+/// A constraint that does not hold on some row of a trace, see find_violations.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub row: usize,                 // the row the constraint was evaluated on; a transition also reads the next one
+    pub constraint: usize,          // its index in the extracted constraints
+    pub kind: ConstraintKind,
+    pub expression: String,         // the constraint as an equation, e.g. `next[0] = local[1]`
+    pub value: String,              // what it evaluated to instead of 0
+    pub expected: Option<String>,   // for an equation: its right-hand side ...
+    pub actual: Option<String>,     // ... and its left-hand side, which should have been equal
+    pub cells: Vec<(usize, usize)>, // (row, column) of every main trace cell the constraint reads there
+}
+
+/// "row 3: transition constraint next[1] = local[0] + local[1] violated (expected 5, got 7)"
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "row {}: {} constraint {} violated", self.row, self.kind.label(), self.expression)?;
+        match (&self.expected, &self.actual) {
+            (Some(expected), Some(actual)) => write!(f, " (expected {expected}, got {actual})"),
+            _ => write!(f, " (evaluates to {})", self.value),
+        }
+    }
+}
+
+/// Every (row, constraint) pair on which `air`'s constraints do not hold on `trace`, row by row. Empty exactly
+/// when the trace satisfies the AIR, so this explains a proof uni-stark refused to produce or that failed to
+/// verify.
+pub fn find_violations<F: Field, A: Air<SymbolicAirBuilder<F>>>(
+    air: &A,
+    trace: &RowMajorMatrix<F>,
+    public_values: &[F],
+) -> Vec<Violation> {
+    let descs = violation_descs(air, public_values);
+    evaluate_rows(air, trace, public_values, |window, index, kind, constraint| {
+        violation(&descs, window, index, kind, constraint)
+    })
+    .into_iter()
+    .flatten()
+    .flatten()
+    .collect()
+}
+
+/// evaluate_constraints and find_violations in one pass over the trace, for an export that carries both.
+pub fn evaluate_and_find_violations<F: Field, A: Air<SymbolicAirBuilder<F>>>(
+    air: &A,
+    trace: &RowMajorMatrix<F>,
+    public_values: &[F],
+) -> (Vec<Vec<String>>, Vec<Violation>) {
+    let descs = violation_descs(air, public_values);
+    let mut violations = Vec::new();
+    let evals = evaluate_rows(air, trace, public_values, |window, index, kind, constraint| {
+        if !kind.applies_to(window.row, window.height) {
+            return String::new();
+        }
+        violations.extend(violation(&descs, window, index, kind, constraint));
+        window.eval(constraint).to_string()
+    });
+    (evals, violations)
+}
+
+// The constraints of `air` as find_violations describes them.
+fn violation_descs<F: Field, A: Air<SymbolicAirBuilder<F>>>(air: &A, public_values: &[F]) -> Vec<ConstraintDesc> {
+    let preprocessed_width = air.preprocessed_trace().map_or(0, |p| p.width());
+    extract_constraints::<F, A>(air, preprocessed_width, public_values.len())
+}
+
+// The violation of constraint `index` on the row of `window`, if it applies there and does not hold.
+fn violation<F: Field>(
+    descs: &[ConstraintDesc],
+    window: &RowWindow<'_, F>,
+    index: usize,
+    kind: ConstraintKind,
+    constraint: &SymbolicExpression<F>,
+) -> Option<Violation> {
+    if !kind.applies_to(window.row, window.height) {
+        return None;
+    }
+    let value = window.eval(constraint);
+    if value == F::ZERO {
+        return None;
+    }
+    let tree = &descs[index].tree;
+    // An assert_eq(x, y) arrives as x - y behind its selector: report both sides
+    let (expression, sides) = match (tree, strip_selector(constraint)) {
+        (ConstraintExpr::Sub { x, y }, SymbolicExpression::Sub { x: lhs, y: rhs, .. }) => {
+            (format!("{x} = {y}"), Some((window.eval(rhs).to_string(), window.eval(lhs).to_string())))
+        }
+        _ => (format!("{tree} = 0"), None),
+    };
+    let mut cells = Vec::new();
+    tree.main_cells(&mut |offset, column| cells.push(((window.row + offset) % window.height, column)));
+    cells.sort_unstable();
+    cells.dedup();
+    Some(Violation {
+        row: window.row,
+        constraint: index,
+        kind,
+        expression,
+        value: value.to_string(),
+        expected: sides.as_ref().map(|(expected, _)| expected.clone()),
+        actual: sides.map(|(_, actual)| actual),
+        cells,
+    })
+}
+
+/// Checks `trace` against `air`'s constraints before it is proven, without any PCS work: a trace that breaks one
+/// would only fail deep inside uni-stark, after the commitments, as a panic (debug builds) or a proof that does
+/// not verify. The error names the first violation, e.g. `row 7: last-row constraint local[1] = public[2]
+/// violated (expected 21, got 34)`, and counts the others. A trace narrower or wider than the AIR, or of another
+/// height than its preprocessed trace, is a ShapeMismatch, as the constraints could not be evaluated on it.
+pub fn check_trace<F: Field, A: Air<SymbolicAirBuilder<F>>>(
+    air: &A,
+    trace: &RowMajorMatrix<F>,
+    public_values: &[F],
+) -> Result<(), FibVisError> {
+    let expected_width = <A as BaseAir<F>>::width(air);
+    let expected_height = <A as BaseAir<F>>::preprocessed_trace(air).map_or(trace.height(), |p| p.height());
+    if trace.width() != expected_width || trace.height() != expected_height {
+        return Err(FibVisError::ShapeMismatch {
+            expected_width,
+            expected_height,
+            width: trace.width(),
+            height: trace.height(),
+        });
+    }
+    let violations = find_violations(air, trace, public_values);
+    match violations.first() {
+        Some(first) => Err(FibVisError::ConstraintViolation { count: violations.len(), first: first.to_string() }),
+        None => Ok(()),
+    }
+}
+
+// The constraint without its row selector, when the selector is the whole condition.
+fn strip_selector<F>(constraint: &SymbolicExpression<F>) -> &SymbolicExpression<F> {
+    match constraint {
+        SymbolicExpression::Mul { x, y, .. }
+            if matches!(**x, SymbolicExpression::IsFirstRow | SymbolicExpression::IsLastRow | SymbolicExpression::IsTransition) =>
+        {
+            y
+        }
+        _ => constraint,
+    }
+}
+
+// Calls `f` with every row of `trace` and every constraint of `air`, collecting the results by row.
+fn evaluate_rows<F: Field, A: Air<SymbolicAirBuilder<F>>, T>(
+    air: &A,
+    trace: &RowMajorMatrix<F>,
+    public_values: &[F],
+    mut f: impl FnMut(&RowWindow<'_, F>, usize, ConstraintKind, &SymbolicExpression<F>) -> T,
+) -> Vec<Vec<T>> {
+    let preprocessed = air.preprocessed_trace();
+    let preprocessed_width = preprocessed.as_ref().map_or(0, |p| p.width());
+    let constraints = get_symbolic_constraints(air, preprocessed_width, public_values.len());
+    let kinds: Vec<ConstraintKind> =
+        constraints.iter().map(|c| split_selector(ConstraintExpr::from_symbolic(c)).0).collect();
+
+    let height = trace.height();
+    let row_of = |m: &RowMajorMatrix<F>, row: usize| {
+        let width = m.width();
+        m.values[row * width..(row + 1) * width].to_vec()
+    };
+    (0..height)
+        .map(|row| {
+            // The next row wraps around on the last one, as on the trace domain; no transition reads it there.
+            let window = RowWindow {
+                height,
+                row,
+                main: [row_of(trace, row), row_of(trace, (row + 1) % height)],
+                preprocessed: preprocessed.as_ref().map(|p| [row_of(p, row), row_of(p, (row + 1) % height)]),
+                public_values,
+            };
+            constraints.iter().zip(&kinds).enumerate().map(|(index, (c, &kind))| f(&window, index, kind, c)).collect()
+        })
+        .collect()
+}
+
+// The cells a constraint can read on one row: that row and the next of each trace, and the public values.
+struct RowWindow<'a, F> {
+    height: usize,
+    row: usize,
+    main: [Vec<F>; 2],
+    preprocessed: Option<[Vec<F>; 2]>,
+    public_values: &'a [F],
+}
+
+impl<F: Field> RowWindow<'_, F> {
+    fn eval(&self, expr: &SymbolicExpression<F>) -> F {
+        let indicator = |b: bool| if b { F::ONE } else { F::ZERO };
+        match expr {
+            SymbolicExpression::Variable(v) => match v.entry {
+                Entry::Main { offset } => self.main[offset][v.index],
+                Entry::Preprocessed { offset } => {
+                    self.preprocessed.as_ref().expect("the AIR reads a preprocessed trace it does not have")[offset][v.index]
+                }
+                Entry::Public => self.public_values[v.index],
+                _ => unreachable!("uni-stark AIRs only read the main and preprocessed traces and the public values"),
+            },
+            SymbolicExpression::IsFirstRow => indicator(self.row == 0),
+            SymbolicExpression::IsLastRow => indicator(self.row + 1 == self.height),
+            SymbolicExpression::IsTransition => indicator(self.row + 1 < self.height),
+            SymbolicExpression::Constant(c) => *c,
+            SymbolicExpression::Add { x, y, .. } => self.eval(x) + self.eval(y),
+            SymbolicExpression::Sub { x, y, .. } => self.eval(x) - self.eval(y),
+            SymbolicExpression::Mul { x, y, .. } => self.eval(x) * self.eval(y),
+            SymbolicExpression::Neg { x, .. } => -self.eval(x),
+        }
+    }
+}
+
+// What a constraint reads at an out-of-domain point: the opened trace values, over the extension field.
+struct PointWindow<'a, F, EF> {
+    main: [&'a [EF]; 2],
+    preprocessed: Option<[&'a [EF]; 2]>,
+    public_values: &'a [F],
+    selectors: &'a LagrangeSelectors<EF>,
+}
+
+impl<F: Field, EF: ExtensionField<F>> PointWindow<'_, F, EF> {
+    fn eval(&self, expr: &SymbolicExpression<F>) -> EF {
+        match expr {
+            SymbolicExpression::Variable(v) => match v.entry {
+                Entry::Main { offset } => self.main[offset][v.index],
+                Entry::Preprocessed { offset } => {
+                    self.preprocessed.expect("the AIR reads a preprocessed trace the proof does not open")[offset][v.index]
+                }
+                Entry::Public => EF::from(self.public_values[v.index]),
+                _ => unreachable!("uni-stark AIRs only read the main and preprocessed traces and the public values"),
+            },
+            SymbolicExpression::IsFirstRow => self.selectors.is_first_row,
+            SymbolicExpression::IsLastRow => self.selectors.is_last_row,
+            SymbolicExpression::IsTransition => self.selectors.is_transition,
+            SymbolicExpression::Constant(c) => EF::from(*c),
+            SymbolicExpression::Add { x, y, .. } => self.eval(x) + self.eval(y),
+            SymbolicExpression::Sub { x, y, .. } => self.eval(x) - self.eval(y),
+            SymbolicExpression::Mul { x, y, .. } => self.eval(x) * self.eval(y),
+            SymbolicExpression::Neg { x, .. } => -self.eval(x),
+        }
+    }
+}
+
+// A filtered assertion reaches uni-stark as `condition * body`, where the condition starts with the row selector,
+// e.g. `is_transition * next.is_real * (...)`. Takes the selector out of that product and keeps the other factors.
+fn split_selector(expr: ConstraintExpr) -> (ConstraintKind, ConstraintExpr) {
+    let mut factors = Vec::new();
+    let mut rest = expr;
+    while let ConstraintExpr::Mul { x, y } = rest {
+        factors.push(*y);
+        rest = *x;
+    }
+    factors.push(rest);
+    factors.reverse();
+
+    let Some(position) = factors.iter().position(|f| matches!(f, ConstraintExpr::Selector { .. })) else {
+        return (ConstraintKind::EveryRow, product(factors));
+    };
+    let ConstraintExpr::Selector { kind } = factors.remove(position) else { unreachable!() };
+    (kind, product(factors))
+}
+
+fn product(factors: Vec<ConstraintExpr>) -> ConstraintExpr {
+    factors
+        .into_iter()
+        .reduce(|x, y| ConstraintExpr::Mul { x: Box::new(x), y: Box::new(y) })
+        .unwrap_or(ConstraintExpr::Constant { value: "1".to_string() })
+}
+
+impl ConstraintExpr {
+    fn from_symbolic<F: Field>(expr: &SymbolicExpression<F>) -> Self {
+        let boxed = |e: &SymbolicExpression<F>| Box::new(Self::from_symbolic(e));
+        match expr {
+            SymbolicExpression::Variable(v) => match v.entry {
+                Entry::Main { offset } => ConstraintExpr::Cell { trace: TraceKind::Main, offset, column: v.index },
+                Entry::Preprocessed { offset } => ConstraintExpr::Cell { trace: TraceKind::Preprocessed, offset, column: v.index },
+                Entry::Public => ConstraintExpr::Public { index: v.index },
+                _ => unreachable!("uni-stark AIRs only read the main and preprocessed traces and the public values"),
+            },
+            SymbolicExpression::IsFirstRow => ConstraintExpr::Selector { kind: ConstraintKind::FirstRow },
+            SymbolicExpression::IsLastRow => ConstraintExpr::Selector { kind: ConstraintKind::LastRow },
+            SymbolicExpression::IsTransition => ConstraintExpr::Selector { kind: ConstraintKind::Transition },
+            SymbolicExpression::Constant(c) => ConstraintExpr::Constant { value: c.to_string() },
+            SymbolicExpression::Add { x, y, .. } => ConstraintExpr::Add { x: boxed(x), y: boxed(y) },
+            SymbolicExpression::Sub { x, y, .. } => ConstraintExpr::Sub { x: boxed(x), y: boxed(y) },
+            SymbolicExpression::Mul { x, y, .. } => ConstraintExpr::Mul { x: boxed(x), y: boxed(y) },
+            SymbolicExpression::Neg { x, .. } => ConstraintExpr::Neg { x: boxed(x) },
+        }
+    }
+
+    /// Degree in the trace cells; public values, constants and selectors count as 0.
+    pub fn degree(&self) -> usize {
+        match self {
+            ConstraintExpr::Cell { .. } => 1,
+            ConstraintExpr::Public { .. } | ConstraintExpr::Constant { .. } | ConstraintExpr::Selector { .. } => 0,
+            ConstraintExpr::Add { x, y } | ConstraintExpr::Sub { x, y } => x.degree().max(y.degree()),
+            ConstraintExpr::Mul { x, y } => x.degree() + y.degree(),
+            ConstraintExpr::Neg { x } => x.degree(),
+        }
+    }
+
+    // Calls `f` with the (offset, column) of every main trace cell the expression reads.
+    fn main_cells(&self, f: &mut impl FnMut(usize, usize)) {
+        match self {
+            ConstraintExpr::Cell { trace: TraceKind::Main, offset, column } => f(*offset, *column),
+            ConstraintExpr::Add { x, y } | ConstraintExpr::Sub { x, y } | ConstraintExpr::Mul { x, y } => {
+                x.main_cells(f);
+                y.main_cells(f);
+            }
+            ConstraintExpr::Neg { x } => x.main_cells(f),
+            _ => {}
+        }
+    }
+
+    // Binding strength for the rendering: sums below products below negation below everything else.
+    fn precedence(&self) -> u8 {
+        match self {
+            ConstraintExpr::Add { .. } | ConstraintExpr::Sub { .. } => 1,
+            ConstraintExpr::Mul { .. } => 2,
+            ConstraintExpr::Neg { .. } => 3,
+            _ => 4,
+        }
+    }
+
+    fn fmt_at(&self, f: &mut std::fmt::Formatter<'_>, min_precedence: u8) -> std::fmt::Result {
+        if self.precedence() < min_precedence {
+            write!(f, "(")?;
+            self.fmt_at(f, 0)?;
+            return write!(f, ")");
+        }
+        match self {
+            ConstraintExpr::Cell { trace, offset, column } => {
+                let row = match offset {
+                    0 => "local".to_string(),
+                    1 => "next".to_string(),
+                    n => format!("row{n}"),
+                };
+                match trace {
+                    TraceKind::Main => write!(f, "{row}[{column}]"),
+                    TraceKind::Preprocessed => write!(f, "preprocessed.{row}[{column}]"),
+                }
+            }
+            ConstraintExpr::Public { index } => write!(f, "public[{index}]"),
+            ConstraintExpr::Constant { value } => write!(f, "{value}"),
+            ConstraintExpr::Selector { kind } => match kind {
+                ConstraintKind::EveryRow => write!(f, "1"),
+                ConstraintKind::FirstRow => write!(f, "is_first_row"),
+                ConstraintKind::Transition => write!(f, "is_transition"),
+                ConstraintKind::LastRow => write!(f, "is_last_row"),
+            },
+            ConstraintExpr::Add { x, y } => {
+                x.fmt_at(f, 1)?;
+                write!(f, " + ")?;
+                y.fmt_at(f, 1)
+            }
+            ConstraintExpr::Sub { x, y } => {
+                x.fmt_at(f, 1)?;
+                write!(f, " - ")?;
+                y.fmt_at(f, 2)
+            }
+            ConstraintExpr::Mul { x, y } => {
+                x.fmt_at(f, 2)?;
+                write!(f, " * ")?;
+                y.fmt_at(f, 2)
+            }
+            ConstraintExpr::Neg { x } => {
+                write!(f, "-")?;
+                x.fmt_at(f, 3)
+            }
+        }
+    }
+}
+
+/// The expression in terms of `local[i]`, `next[i]` and `public[i]`, with only the parentheses it needs.
+impl std::fmt::Display for ConstraintExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_at(f, 0)
+    }
+}
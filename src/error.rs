@@ -0,0 +1,157 @@
+use std::fmt::Debug;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use p3_uni_stark::VerificationError;
+
+/// Every failure the library and the binary can run into. Each category maps to its own process exit code
+/// so that scripts can tell "proof rejected" apart from "disk full".
+#[derive(Debug, thiserror::Error)]
+pub enum FibVisError {
+    #[error("invalid configuration: {0}")]
+    Config(String),
+
+    #[error("{requested} needs the `{feature}` cargo feature, which this binary was built without; rebuild with `--features {feature}`")]
+    MissingFeature { feature: &'static str, requested: String },
+
+    #[error("{path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("could not export the trace to {path}: {source}")]
+    Export {
+        path: PathBuf,
+        #[source]
+        source: ExportError,
+    },
+
+    #[error("serialization failed: {0}")]
+    Serialization(String),
+
+    #[error("{path}: unsupported trace schema version {found}, this build reads versions 1 to {supported}")]
+    SchemaVersion { path: PathBuf, found: String, supported: u32 },
+
+    #[error("trace generation failed at row {row}: {reason}")]
+    TraceGeneration { row: usize, reason: String },
+
+    #[error("claimed final value {expected} does not match trace value {actual} at row {row}; omit --final-value to have it computed")]
+    FinalValueMismatch { expected: u64, actual: u64, row: usize },
+
+    #[error("the AIR expects a {expected_height}x{expected_width} trace, but the trace is {height}x{width}")]
+    ShapeMismatch { expected_width: usize, expected_height: usize, width: usize, height: usize },
+
+    #[error("the trace has {width} columns but {names} column names were given")]
+    Columns { width: usize, names: usize },
+
+    #[error("{count} constraint {} nonzero, the first one at {first}", evaluations(.count))]
+    ConstraintViolation { count: usize, first: String },
+
+    #[error("proving failed: {0}")]
+    Prove(String),
+
+    #[error("verification failed: {0}")]
+    Verify(VerifyFailure),
+}
+
+/// Why writing a trace export failed.
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error("JSON encoding failed: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("MessagePack encoding failed: {0}")]
+    Msgpack(#[from] rmp_serde::encode::Error),
+
+    #[error("CBOR encoding failed: {0}")]
+    Cbor(#[from] ciborium::ser::Error<io::Error>),
+
+    #[error("binary trace encoding failed: {0}")]
+    Bin(String),
+
+    #[error("the trace has {rows} rows, more than the {limit} a {format} export holds; use --format ndjson or bin for large traces")]
+    TooLarge { format: &'static str, rows: usize, limit: usize },
+}
+
+/// Why a proof was rejected. uni-stark's VerificationError is generic over the PCS error, which differs per
+/// field and hash, so its PCS part is kept as text.
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyFailure {
+    #[error("the proof has the wrong shape for this AIR and FRI configuration")]
+    InvalidProofShape,
+
+    #[error("the PCS opening proof is invalid: {0}")]
+    InvalidOpeningArgument(String),
+
+    #[error("the constraints do not hold at the out-of-domain point, is the final value right?")]
+    OodEvaluationMismatch,
+
+    #[error("{0}")]
+    Mismatch(String),
+
+    #[error("{0}")]
+    Other(String),
+}
+
+impl<PcsErr: Debug> From<VerificationError<PcsErr>> for VerifyFailure {
+    fn from(e: VerificationError<PcsErr>) -> Self {
+        match e {
+            VerificationError::InvalidProofShape => VerifyFailure::InvalidProofShape,
+            VerificationError::InvalidOpeningArgument(e) => VerifyFailure::InvalidOpeningArgument(format!("{e:?}")),
+            VerificationError::OodEvaluationMismatch => VerifyFailure::OodEvaluationMismatch,
+            other => VerifyFailure::Other(format!("{other:?}")),
+        }
+    }
+}
+
+impl<PcsErr: Debug> From<VerificationError<PcsErr>> for FibVisError {
+    fn from(e: VerificationError<PcsErr>) -> Self {
+        FibVisError::Verify(e.into())
+    }
+}
+
+impl FibVisError {
+    /// An I/O error on `path`.
+    pub fn io(path: &Path, source: io::Error) -> Self {
+        FibVisError::Io { path: path.to_path_buf(), source }
+    }
+
+    /// A failed trace export to `path`.
+    pub fn export(path: &Path, source: impl Into<ExportError>) -> Self {
+        FibVisError::Export { path: path.to_path_buf(), source: source.into() }
+    }
+
+    /// A proof rejected for a reason found before uni-stark looked at it, such as a proof file made for
+    /// other parameters.
+    pub fn mismatch(reason: String) -> Self {
+        FibVisError::Verify(VerifyFailure::Mismatch(reason))
+    }
+
+    /// Process exit code of the category this error belongs to.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            FibVisError::Config(_) | FibVisError::MissingFeature { .. } => 1,
+            FibVisError::Verify(_) => 2,
+            FibVisError::Io { .. } => 3,
+            FibVisError::Export { source: ExportError::Io(_), .. }
+            | FibVisError::Export { source: ExportError::Cbor(ciborium::ser::Error::Io(_)), .. } => 3,
+            FibVisError::Export { .. } | FibVisError::Serialization(_) | FibVisError::SchemaVersion { .. } => 4,
+            FibVisError::TraceGeneration { .. }
+            | FibVisError::FinalValueMismatch { .. }
+            | FibVisError::ShapeMismatch { .. }
+            | FibVisError::Columns { .. }
+            | FibVisError::ConstraintViolation { .. } => 5,
+            FibVisError::Prove(_) => 6,
+        }
+    }
+}
+
+// The ConstraintViolation message's subject for `count` of them.
+fn evaluations(count: &usize) -> &'static str {
+    if *count == 1 { "evaluation on the trace is" } else { "evaluations on the trace are" }
+}
@@ -0,0 +1,238 @@
+//! The pipeline's progress as events, for `serve` to stream on its `/events` endpoint: a tracing layer that turns
+//! the phase spans the timings and PhaseProgress come from into PipelineEvents, when they open and close. The spans
+//! carry what the events report as fields, declared empty and recorded once known, e.g. the number of rows.
+//!
+//! The events go to an EventLog, which keeps them all. A new run starts with its trace generation, and a client
+//! subscribing midway gets the history of the current run first, then every event as it comes in, those of the
+//! runs after it included.
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::{span, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// One step of the pipeline, as JSON tagged with its `event`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PipelineEvent {
+    /// The trace was generated, padding rows included.
+    TraceGenerated { rows: usize },
+    /// An export of `bytes` bytes was written to the file at `path`.
+    Exported { path: String, bytes: u64 },
+    ProvingStarted,
+    /// The prover is done after `ms` milliseconds, with a proof of `proof_bytes` once it is encoded.
+    ProvingFinished { ms: f64, proof_bytes: Option<usize> },
+    Verified { ok: bool },
+}
+
+/// The events of the current run, shared by the layer that pushes them and the clients that read them.
+#[derive(Clone, Default)]
+pub struct EventLog {
+    inner: Arc<(Mutex<History>, Condvar)>,
+}
+
+#[derive(Default)]
+struct History {
+    events: Vec<PipelineEvent>, // in order, of every run
+    run_start: usize,           // the first event of the current run
+    closed: bool,               // nothing more is coming, subscribers return None
+}
+
+/// Reads an EventLog from the start of the current run, see EventLog::subscribe.
+pub struct Subscription {
+    log: EventLog,
+    next: usize,
+}
+
+impl EventLog {
+    // A client that panicked while reading leaves the log as it was.
+    fn history(&self) -> MutexGuard<'_, History> {
+        self.inner.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Makes the events so far history, of a run that is over.
+    pub fn start_run(&self) {
+        let mut history = self.history();
+        history.run_start = history.events.len();
+    }
+
+    pub fn push(&self, event: PipelineEvent) {
+        self.history().events.push(event);
+        self.inner.1.notify_all();
+    }
+
+    /// The events of the current run so far.
+    pub fn events(&self) -> Vec<PipelineEvent> {
+        let history = self.history();
+        history.events[history.run_start..].to_vec()
+    }
+
+    /// Ends every subscription once it has read what is there.
+    pub fn close(&self) {
+        self.history().closed = true;
+        self.inner.1.notify_all();
+    }
+
+    /// A reader of the current run from its first event on.
+    pub fn subscribe(&self) -> Subscription {
+        Subscription { log: self.clone(), next: self.history().run_start }
+    }
+}
+
+impl Subscription {
+    /// The next event, waiting for it if need be; None once the log is closed and read to its end.
+    pub fn next(&mut self) -> Option<PipelineEvent> {
+        self.next_within(None)
+    }
+
+    /// As next, but None as well once `timeout` has passed without an event.
+    pub fn next_timeout(&mut self, timeout: Duration) -> Option<PipelineEvent> {
+        self.next_within(Some(Instant::now() + timeout))
+    }
+
+    /// Whether the log is closed, so that next_timeout's None is the end and not a timeout.
+    pub fn is_closed(&self) -> bool {
+        self.log.history().closed
+    }
+
+    fn next_within(&mut self, deadline: Option<Instant>) -> Option<PipelineEvent> {
+        let (lock, ready) = &*self.log.inner;
+        let mut history = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if let Some(event) = history.events.get(self.next) {
+                self.next += 1;
+                return Some(event.clone());
+            }
+            if history.closed {
+                return None;
+            }
+            history = match deadline {
+                None => ready.wait(history).unwrap_or_else(PoisonError::into_inner),
+                Some(deadline) => {
+                    let left = deadline.checked_duration_since(Instant::now())?;
+                    ready.wait_timeout(history, left).unwrap_or_else(PoisonError::into_inner).0
+                }
+            };
+        }
+    }
+}
+
+/// Tracing layer pushing the events of the phase spans to an EventLog:
+/// - "generate trace" starts a new run, and reports its `rows` once closed
+/// - "write export" reports its `path` and `bytes`, once the file is written: not for a failed one or stdout
+/// - "proving" reports its start, and once closed the time spent in it with its `proof_bytes`
+/// - "verification" reports whether it was `ok`
+pub struct PipelineEvents {
+    log: EventLog,
+}
+
+// What a phase span recorded, and how long it has been entered for.
+#[derive(Default)]
+struct Phase {
+    fields: Map<String, Value>,
+    busy: Duration,
+    entered: Option<Instant>,
+}
+
+impl Visit for Phase {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    // `%path` fields come through here, as their Display
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.fields.insert(field.name().to_string(), format!("{value:?}").into());
+    }
+}
+
+impl Phase {
+    fn event(&self, name: &str) -> Option<PipelineEvent> {
+        let number = |key: &str| self.fields.get(key).and_then(Value::as_u64);
+        match name {
+            "generate trace" => Some(PipelineEvent::TraceGenerated { rows: number("rows")? as usize }),
+            "write export" => {
+                let path = self.fields.get("path").and_then(Value::as_str)?.to_string();
+                Some(PipelineEvent::Exported { path, bytes: number("bytes")? })
+            }
+            "proving" => Some(PipelineEvent::ProvingFinished {
+                ms: self.busy.as_secs_f64() * 1000.0,
+                proof_bytes: number("proof_bytes").map(|bytes| bytes as usize),
+            }),
+            "verification" => Some(PipelineEvent::Verified { ok: self.fields.get("ok").and_then(Value::as_bool)? }),
+            _ => None,
+        }
+    }
+}
+
+impl PipelineEvents {
+    pub fn new(log: EventLog) -> Self {
+        Self { log }
+    }
+}
+
+const PHASES: [&str; 4] = ["generate trace", "write export", "proving", "verification"];
+
+impl<S> Layer<S> for PipelineEvents
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        if !PHASES.contains(&span.name()) {
+            return;
+        }
+        match span.name() {
+            "generate trace" => self.log.start_run(),
+            "proving" => self.log.push(PipelineEvent::ProvingStarted),
+            _ => {}
+        }
+        let mut phase = Phase::default();
+        attrs.record(&mut phase);
+        span.extensions_mut().insert(phase);
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        if let Some(phase) = span.extensions_mut().get_mut::<Phase>() {
+            values.record(phase);
+        }
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        if let Some(phase) = span.extensions_mut().get_mut::<Phase>() {
+            phase.entered = Some(Instant::now());
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        if let Some(phase) = span.extensions_mut().get_mut::<Phase>() {
+            phase.busy += phase.entered.take().map_or(Duration::ZERO, |entered| entered.elapsed());
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else { return };
+        if let Some(event) = span.extensions().get::<Phase>().and_then(|phase| phase.event(span.name())) {
+            self.log.push(event);
+        }
+    }
+}
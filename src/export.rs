@@ -0,0 +1,1928 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+#[cfg(not(target_arch = "wasm32"))]
+use std::ffi::OsString;
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs::File;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::{self, BufWriter, Read};
+use std::io::Write;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use clap::ValueEnum;
+#[cfg(not(target_arch = "wasm32"))]
+use flate2::read::GzDecoder;
+#[cfg(not(target_arch = "wasm32"))]
+use flate2::write::GzEncoder;
+#[cfg(not(target_arch = "wasm32"))]
+use flate2::Compression;
+use num_bigint::BigUint;
+use p3_air::BaseAir;
+use p3_field::{Field, PrimeCharacteristicRing, PrimeField32};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_keccak::Keccak256Hash;
+use p3_mersenne_31::Mersenne31;
+use p3_symmetric::CryptographicHasher;
+use schemars::JsonSchema;
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::air::{FibonacciAir, NUM_PUBLIC_VALUES};
+use crate::constraints::{extract_constraints, ConstraintDesc, ConstraintKind, ConstraintReport, Violation};
+use crate::error::{ExportError, FibVisError};
+use crate::progress::{row_progress, PROGRESS_CHUNK};
+use crate::proof_json::CommitmentRoot;
+use crate::sequence::Sequence;
+use crate::stark_config::{FieldChoice, FriParams, HashChoice, Seed};
+use crate::trace::first_reduced_row;
+
+// Macro for implementing the Serialize and Clone traits
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq)]
+
+/// VisData acts as the data container to serialize, the schema web/index.html reads.
+pub struct VisData {
+    pub schema_version: u32,                   // SCHEMA_VERSION of the build that wrote it, see load_vis_data
+    pub num_steps: usize,                      // unsigned int, number of fibonacci steps
+    pub sequence: String,                      // which recurrence the trace follows (Fibonacci, Lucas, Tribonacci, ...)
+    pub recurrence: Vec<u64>,                  // coefficients c(1), ..., c(k) of a LinearRecurrence trace, empty otherwise
+    pub final_value: u64,                      // expected final Fibonacci value constrained on the last row, a field element
+    pub public_values: Vec<String>,            // public values the proof is checked against (a0, a1, final value for Fibonacci)
+    pub field: FieldInfo,                      // the prime field the trace lives in (Mersenne31, BabyBear, KoalaBear) and its prime
+    pub hash: String,                          // name of the hash behind the Merkle commitments (Keccak256, Poseidon2, Blake3)
+    pub fri: FriParams,                        // FRI parameters the proof was (or would be) generated with
+    #[schemars(with = "String")]
+    pub seed: Seed,                            // bytes absorbed by the Fiat-Shamir challenger before anything else, as hex
+    pub columns: Vec<ColumnMeta>,              // name and meaning of each trace column, in order
+    pub air_info: AirInfo,                     // the AIR's shape at a glance, so the page does not hardcode it
+    pub constraints: Vec<ConstraintDesc>,      // the AIR's constraints as uni-stark evaluates them, with their degrees
+    pub degree_report: ConstraintReport,       // their degrees with row selectors, the quotient degree and the blowup it needs
+    #[serde(serialize_with = "serialize_cells", deserialize_with = "deserialize_cells")]
+    #[schemars(with = "Vec<Vec<Cell<'static>>>")]
+    pub trace: Vec<Vec<String>>,               // vector of vectors (matrix) of type String
+    pub constraint_evals: Vec<Vec<String>>,    // per row and constraint: its value on the trace, 0 if it holds, empty where it does not apply
+    pub violations: Vec<Violation>,            // the nonzero entries of constraint_evals, with the cells each one reads
+    pub preprocessed_columns: Vec<ColumnMeta>, // the same for each preprocessed column, empty when the AIR has none
+    #[serde(serialize_with = "serialize_cells", deserialize_with = "deserialize_cells")]
+    #[schemars(with = "Vec<Vec<Cell<'static>>>")]
+    pub preprocessed: Vec<Vec<String>>,        // the preprocessed trace, fixed by the AIR rather than committed by the prover
+    pub true_values: Vec<Vec<String>>,         // the same cells as exact integers, for the first TRUE_VALUES_MAX_ROWS rows
+    pub reduced: Vec<bool>,                    // per row: whether the field reduction changed any of its values
+    pub padding: Vec<bool>,                    // per row: whether it only pads the trace to a power of two, see FibonacciAir
+    pub first_reduced_row: Option<usize>,      // first row where the field trace departs from the true sequence
+    pub column_stats: Vec<ColumnStats>,        // per column: its range and the row it wraps around the modulus from
+    pub stats: Option<ProofStats>,             // proof size and phase timings, once the trace has been proven
+    pub verification: Option<Verification>,    // whether the proof was accepted, once proving and verifying are done
+    pub commitments: Vec<CommitmentRoot>,      // the proof's Merkle roots in protocol order, empty until it is proven
+    pub lde: Option<Lde>,                      // the trace columns' low-degree extension, with --export-lde only
+    pub selectors: Option<Selectors>,          // the row selectors per trace row, and over the LDE with --export-lde
+    pub windows: Option<Vec<RowWindow>>,       // every row with the next one as the constraints read them, with --export-windows
+    pub keyframes: Option<Vec<Keyframe>>,      // the trace cut into steps revealing its rows in order, with --keyframes
+    pub heatmap: Option<Heatmap>,              // constraint_evals as numbers to plot, only when some constraint is violated
+    pub tampered: Vec<(usize, usize)>,         // (row, column) of every cell --tamper overwrote, empty for an honest trace
+    pub repr: ValueRepr,                       // how the text formats write the trace and preprocessed cells, see ValueRepr
+}
+
+/// Version of the export's structure, written as `schema_version`. Bump it whenever a field is added, removed or
+/// changes shape, and teach upgrade_vis_data to bring the previous version up to it:
+///
+/// 1. exports from before the field existed: `num_steps`, `final_value` and `trace`, plus whichever later blocks
+///    the build that wrote them had, with `field` possibly a bare name and `columns` bare names
+/// 2. `schema_version` itself, with every block present
+/// 3. `verification`, the outcome of proving and verifying the trace
+/// 4. `proof_digest` and `proof_head` in `stats`
+/// 5. `commitments`, the Merkle roots of the proof
+/// 6. `lde`, the low-degree extension of the trace
+/// 7. `total_ms` in `stats.timings`
+/// 8. `memory` in `stats`, the heap usage of the run
+/// 9. `selectors`, the row selectors the constraints are gated by
+/// 10. `windows`, the pairs of adjacent rows the constraints read
+/// 11. `column_stats`, each column's range and first wrap-around
+/// 12. `keyframes`, the steps revealing the trace row by row
+/// 13. `heatmap`, the constraint evaluations of a trace that breaks some
+pub const SCHEMA_VERSION: u32 = 13;
+
+// Trace cells are decimal strings in JSON, which cannot hold every u64 as a number, and integers in the binary
+// formats (MessagePack, CBOR), which are smaller and need no parsing. Either is read back.
+fn serialize_cells<S: Serializer>(rows: &[Vec<String>], serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        return rows.serialize(serializer);
+    }
+    let mut seq = serializer.serialize_seq(Some(rows.len()))?;
+    for row in rows {
+        seq.serialize_element(&row.iter().map(|cell| Cell::from(cell.as_str())).collect::<Vec<_>>())?;
+    }
+    seq.end()
+}
+
+// Reads the cells of any export back as decimal strings, whichever --repr wrote them.
+fn deserialize_cells<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec<String>>, D::Error> {
+    let rows = Vec::<Vec<Cell>>::deserialize(deserializer)?;
+    Ok(rows.into_iter().map(|row| row.into_iter().map(Cell::into_decimal).collect()).collect())
+}
+
+// One trace cell as an export writes it: a number in the binary formats, a string in decimal or hex, or both
+// forms as `{dec, hex}`.
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum Cell<'a> {
+    Number(u64),
+    Text(Cow<'a, str>),
+    Both { dec: Cow<'a, str>, hex: Cow<'a, str> },
+}
+
+impl<'a> From<&'a str> for Cell<'a> {
+    fn from(cell: &'a str) -> Self {
+        cell.parse().map_or(Cell::Text(Cow::Borrowed(cell)), Cell::Number)
+    }
+}
+
+impl<'a> Cell<'a> {
+    // The decimal string `value` in the representation `repr`, with `nibbles` hex digits.
+    fn with_repr(value: &'a str, repr: ValueRepr, nibbles: usize) -> Self {
+        match repr {
+            ValueRepr::Decimal => Cell::Text(Cow::Borrowed(value)),
+            ValueRepr::Hex => Cell::Text(Cow::Owned(hex_cell(value, nibbles))),
+            ValueRepr::Both => Cell::Both { dec: Cow::Borrowed(value), hex: Cow::Owned(hex_cell(value, nibbles)) },
+        }
+    }
+
+    // Back to the decimal string it was made from.
+    fn into_decimal(self) -> String {
+        match self {
+            Cell::Number(v) => v.to_string(),
+            Cell::Text(s) => match s.strip_prefix("0x").map(|hex| u64::from_str_radix(hex, 16)) {
+                Some(Ok(v)) => v.to_string(),
+                _ => s.into_owned(),
+            },
+            Cell::Both { dec, .. } => dec.into_owned(),
+        }
+    }
+}
+
+impl std::fmt::Display for Cell<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Cell::Number(v) => write!(f, "{v}"),
+            Cell::Text(s) => f.write_str(s),
+            Cell::Both { dec, hex } => write!(f, "{dec} ({hex})"),
+        }
+    }
+}
+
+/// "21" -> "0x00000015" for `nibbles` = 8. A cell that is not a number is left as it is.
+pub fn hex_cell(value: &str, nibbles: usize) -> String {
+    value.parse::<u64>().map_or_else(|_| value.to_string(), |v| format!("0x{v:0nibbles$x}"))
+}
+
+/// How the text formats (JSON, NDJSON, CSV and the terminal table) write trace cells, see --repr.
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueRepr {
+    /// Decimal strings, "21"
+    #[default]
+    Decimal,
+    /// The canonical value in hex, zero-padded to the field's width, "0x00000015"
+    Hex,
+    /// Both, as {"dec": "21", "hex": "0x00000015"}
+    Both,
+}
+
+/// A valid trace and a tampered copy of it made with the same parameters, in one file (`bundle.json`, see
+/// --with-invalid) for the visualizer's comparison view.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TraceBundle {
+    pub valid: VisData,
+    pub invalid: VisData,
+    pub tamper: Vec<TamperedCell>,  // what was changed between the two
+    pub violations: Vec<Violation>, // the constraints the invalid copy breaks, the same as invalid.violations
+}
+
+/// One cell the invalid copy of a TraceBundle differs in.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TamperedCell {
+    pub row: usize,
+    pub column: usize,
+    pub old_value: String, // in the valid trace
+    pub new_value: String, // in the invalid one
+}
+
+impl TraceBundle {
+    /// Pairs `valid` with `invalid`, a copy of it with the cells listed in its `tampered` overwritten (see
+    /// VisData::with_tampered) and its violations found.
+    pub fn new(valid: VisData, invalid: VisData) -> Self {
+        let cell = |vis: &VisData, row: usize, column: usize| {
+            vis.trace.get(row).and_then(|r| r.get(column)).cloned().unwrap_or_default()
+        };
+        let tamper = invalid
+            .tampered
+            .iter()
+            .map(|&(row, column)| TamperedCell {
+                row,
+                column,
+                old_value: cell(&valid, row, column),
+                new_value: cell(&invalid, row, column),
+            })
+            .collect();
+        let violations = invalid.violations.clone();
+        Self { valid, invalid, tamper, violations }
+    }
+}
+
+/// The shape of the AIR behind an export, see VisData::with_air_info.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AirInfo {
+    pub sequence: String,             // same as VisData::sequence
+    pub width: usize,                 // BaseAir::width, the number of committed columns
+    pub columns: Vec<String>,         // their names
+    pub final_value: u64,             // the claimed value on the last row
+    pub num_constraints: usize,
+    pub boundary_constraints: usize,  // on the first or the last row only
+    pub transition_constraints: usize,
+    pub every_row_constraints: usize, // on all rows, such as a range check
+}
+
+/// How big the proof of an exported trace is and how long it took, see VisData::with_stats.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq)]
+pub struct ProofStats {
+    pub proof_bytes: usize,           // the postcard-encoded proof
+    pub proof_digest: Option<String>, // its Keccak-256 as hex, which `verify` checks a proof file against
+    pub proof_head: Option<String>,   // its first PROOF_HEAD_BYTES bytes in base64, to recognize it at a glance
+    pub timings: Timings,             // of the run that produced it, zero for phases it did not run
+    pub fri: FriParams,               // what the size and proving time depend on: queries, blowup, proof-of-work bits
+    pub memory: Option<MemoryStats>,  // heap usage of the same run, with --track-memory only
+}
+
+/// Bytes of the proof ProofStats::proof_head keeps.
+pub const PROOF_HEAD_BYTES: usize = 48;
+
+impl ProofStats {
+    /// The stats of the postcard-encoded `proof`: its size, digest and first bytes.
+    pub fn new(proof: &[u8], timings: Timings, fri: FriParams) -> Self {
+        Self {
+            proof_bytes: proof.len(),
+            proof_digest: Some(proof_digest(proof)),
+            proof_head: Some(BASE64.encode(&proof[..proof.len().min(PROOF_HEAD_BYTES)])),
+            timings,
+            fri,
+            memory: None,
+        }
+    }
+}
+
+/// Keccak-256 of the postcard-encoded proof as lowercase hex, which identifies the proof an export belongs to
+/// without embedding it.
+pub fn proof_digest(proof: &[u8]) -> String {
+    Keccak256Hash {}.hash_iter(proof.iter().copied()).iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Whether the proof of an exported trace was accepted, see VisData::with_verification. The frontend reads it
+/// to tell a verified trace from a rejected one.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq)]
+pub struct Verification {
+    pub verified: bool,
+    pub error: Option<String>,      // why proving or verifying failed, None once the proof verified
+    pub proof_bytes: Option<usize>, // the postcard-encoded proof, None when proving itself failed
+    pub verify_ms: f64,             // wall-clock time of the verifier, zero when it never ran
+}
+
+/// The low-degree extension of the trace, see lde::trace_lde: each trace column's polynomial evaluated over a domain
+/// 2^log_blowup times larger than the trace's, written separately from the trace itself.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq)]
+pub struct Lde {
+    pub log_blowup: usize,
+    pub rows: Vec<Vec<String>>,          // per point of the larger domain, in order: the columns' values in decimal
+    pub trace_rows: Vec<(usize, usize)>, // (LDE row, trace row) of every point the two domains share, to highlight
+}
+
+/// The row selectors uni-stark multiplies the constraints by, one column per ConstraintKind in the order of
+/// Selectors::KINDS, named like the `kind` of the constraints they gate.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq)]
+pub struct Selectors {
+    pub kinds: Vec<ConstraintKind>,         // the columns' kinds, always Selectors::KINDS
+    pub rows: Vec<Vec<u8>>,                 // per trace row: 1 where the selector is nonzero, that is the kind applies
+    pub lde_rows: Option<Vec<Vec<String>>>, // per LDE row: the selectors' values in decimal, see lde::selector_lde
+}
+
+/// One row of the trace with the row after it, as uni-stark's builder hands them to the constraints on that row:
+/// `main().row_slice(0)` and `row_slice(1)`. The trace wraps around, so the last row's next row is the first one.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq)]
+pub struct RowWindow {
+    pub local: Vec<String>, // the row the constraints are evaluated on, in decimal
+    pub next: Vec<String>,  // the row after it, the first row again for the last one
+    pub terminal: bool,     // the last row, where the transition constraints are turned off
+}
+
+/// One step of animating the trace being computed, see keyframes: the rows it reveals, from `row` on, and the
+/// constraints that fix them. Each row is in one keyframe only, so they add about the trace's size to the export.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Keyframe {
+    pub row: usize,              // the first row it reveals, the one after the previous keyframe's last
+    pub rows: Vec<Vec<String>>,  // the newly revealed rows, in decimal
+    pub constraints: Vec<usize>, // indices into `constraints` of the ones that compute those rows, in order
+}
+
+/// constraint_evals as a matrix to plot, trace rows by constraints, see heatmap. Every row has an entry per
+/// constraint: the canonical value of the evaluation, 0 where the constraint holds and null on the rows it does
+/// not apply to, so a first- or last-row constraint has a number on one row only.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Heatmap {
+    pub constraint_names: Vec<String>, // per constraint, in the order of `constraints`: its kind and expression
+    pub rows: usize,                   // the trace's height, the number of rows of `values`
+    pub values: Vec<Vec<Option<u64>>>, // per row and constraint, like constraint_evals but as numbers
+}
+
+impl Selectors {
+    /// Every kind of constraint, in the order of the columns.
+    pub const KINDS: [ConstraintKind; 4] =
+        [ConstraintKind::EveryRow, ConstraintKind::FirstRow, ConstraintKind::Transition, ConstraintKind::LastRow];
+
+    /// The selectors of a trace with `height` rows, and their values over the LDE when given.
+    pub fn new(height: usize, lde_rows: Option<Vec<Vec<String>>>) -> Self {
+        let rows = (0..height)
+            .map(|row| Self::KINDS.iter().map(|kind| u8::from(kind.applies_to(row, height))).collect())
+            .collect();
+        Self { kinds: Self::KINDS.to_vec(), rows, lde_rows }
+    }
+}
+
+impl Verification {
+    /// The outcome of a run that failed with `error`, or verified when there is none.
+    pub fn new(error: Option<&FibVisError>, proof_bytes: Option<usize>, verify_ms: f64) -> Self {
+        Self { verified: error.is_none(), error: error.map(ToString::to_string), proof_bytes, verify_ms }
+    }
+}
+
+/// Wall-clock milliseconds per phase, zero for phases that did not run.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Timings {
+    pub trace_gen_ms: f64,
+    pub export_ms: f64,
+    pub prove_ms: f64,
+    pub verify_ms: f64,
+    pub total_ms: f64, // the whole run up to when the timings were taken, writing files and everything in between
+}
+
+/// Heap usage of a run with --track-memory, see memory::CountingAllocator: its peak, and per phase the change in
+/// allocated bytes, which is negative for a phase that freed more than it allocated.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    pub peak_bytes: u64, // the most the process had allocated at once during the run
+    pub trace_gen_bytes: i64,
+    pub export_bytes: i64,
+    pub prove_bytes: i64,
+    pub verify_bytes: i64,
+}
+
+/// The prime field behind an export, so the visualizer can say what the values are reduced modulo.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FieldInfo {
+    pub name: String,            // as in FieldChoice::name, e.g. Mersenne31
+    pub modulus: String,         // the prime p in decimal, e.g. 2147483647 = 2^31 - 1
+    pub bits: usize,             // bit length of p
+    pub extension_degree: usize, // degree of the extension field the challenges are drawn from
+}
+
+impl FieldInfo {
+    /// The metadata of the field selected as `field`.
+    pub fn new(field: FieldChoice) -> Self {
+        Self {
+            name: field.name().to_string(),
+            modulus: field.modulus().to_string(),
+            bits: field.bits(),
+            extension_degree: field.challenge_degree(),
+        }
+    }
+}
+
+/// A trace column as the export describes it: the name the visualizer labels it with and what it holds.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ColumnMeta {
+    pub name: Cow<'static, str>,        // short label, e.g. `a`
+    pub description: Cow<'static, str>, // what the column holds, e.g. `current Fibonacci number`
+}
+
+/// One trace column at a glance, over the real rows (see VisData::with_padding), for the visualizer to mark where its
+/// values start to wrap around without redoing the exact arithmetic.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ColumnStats {
+    pub name: String,                  // the column's name, as in `columns`
+    pub min: String,                   // its smallest canonical value, in decimal
+    pub max: String,                   // and its largest
+    pub increasing: bool,              // whether no value is smaller than the one above it
+    pub first_wrap_row: Option<usize>, // first row whose true value is at least the modulus, None if no exported one is
+}
+
+impl ColumnMeta {
+    /// A column with a fixed name and description, for the column constants below.
+    pub const fn new(name: &'static str, description: &'static str) -> Self {
+        Self { name: Cow::Borrowed(name), description: Cow::Borrowed(description) }
+    }
+
+    /// A column whose name or description is only known at runtime.
+    pub fn owned(name: String, description: String) -> Self {
+        Self { name: Cow::Owned(name), description: Cow::Owned(description) }
+    }
+}
+
+/// Columns of the Fibonacci trace.
+pub const FIBONACCI_COLUMNS: [ColumnMeta; 2] =
+    [ColumnMeta::new("a", "current Fibonacci number"), ColumnMeta::new("b", "next Fibonacci number")];
+
+/// Columns of the FibonacciWithIndexAir trace, the step index and the two Fibonacci columns.
+pub const FIBONACCI_WITH_INDEX_COLUMNS: [ColumnMeta; 3] = [
+    ColumnMeta::new("i", "step index, 0 on the first row and one more on every next one"),
+    ColumnMeta::new("a", "current Fibonacci number"),
+    ColumnMeta::new("b", "next Fibonacci number"),
+];
+
+/// Columns of a padded Fibonacci trace, see FibonacciAir.
+pub const PADDED_FIBONACCI_COLUMNS: [ColumnMeta; 4] = [
+    ColumnMeta::new("a", "current Fibonacci number"),
+    ColumnMeta::new("b", "next Fibonacci number"),
+    ColumnMeta::new("is_real", "1 on the rows of the sequence, 0 on the padding rows after them"),
+    ColumnMeta::new("row", "index of the row, num_steps - 1 on the last row of the sequence"),
+];
+
+/// Columns of the Tribonacci trace.
+pub const TRIBONACCI_COLUMNS: [ColumnMeta; 3] = [
+    ColumnMeta::new("a", "current Tribonacci number"),
+    ColumnMeta::new("b", "next Tribonacci number"),
+    ColumnMeta::new("c", "the Tribonacci number after that"),
+];
+
+/// Columns of the Collatz trace.
+pub const COLLATZ_COLUMNS: [ColumnMeta; 3] = [
+    ColumnMeta::new("value", "current value of the orbit"),
+    ColumnMeta::new("parity", "witness selecting the step: 0 halves the value, 1 maps it to 3 * value + 1"),
+    ColumnMeta::new("is_real", "1 on the rows before the orbit reaches 1, 0 on the rows of 1 from there on"),
+];
+
+/// Columns of IndexedFibonacciAir's preprocessed trace.
+pub const INDEX_COLUMNS: [ColumnMeta; 1] = [ColumnMeta::new("step", "row index, fixed by the AIR")];
+
+/// Columns of the counter trace.
+pub const COUNTER_COLUMNS: [ColumnMeta; 1] = [ColumnMeta::new("count", "the row index, one more on every next row")];
+
+/// Columns of the bit decomposition trace, the value and its bits from the least significant one.
+pub const BIT_DECOMP_COLUMNS: [ColumnMeta; 9] = [
+    ColumnMeta::new("value", "the row index modulo 256"),
+    ColumnMeta::new("bit0", "bit 0 of value, the least significant"),
+    ColumnMeta::new("bit1", "bit 1 of value"),
+    ColumnMeta::new("bit2", "bit 2 of value"),
+    ColumnMeta::new("bit3", "bit 3 of value"),
+    ColumnMeta::new("bit4", "bit 4 of value"),
+    ColumnMeta::new("bit5", "bit 5 of value"),
+    ColumnMeta::new("bit6", "bit 6 of value"),
+    ColumnMeta::new("bit7", "bit 7 of value, the most significant"),
+];
+
+/// Columns of a LinearRecurrenceAir trace with `k` columns: a, b, c, ... like the named sequences, or x0, x1, ...
+/// once the alphabet runs out. Column j of row i holds x(i+j).
+pub fn linear_recurrence_columns(k: usize) -> Vec<ColumnMeta> {
+    (0..k)
+        .map(|j| {
+            let name = if k <= 26 { char::from(b'a' + j as u8).to_string() } else { format!("x{j}") };
+            let description = if j == 0 { "x(i), the current value".to_string() } else { format!("x(i+{j})") };
+            ColumnMeta::owned(name, description)
+        })
+        .collect()
+}
+
+/// The names of `columns`, in order.
+pub fn column_names(columns: &[ColumnMeta]) -> Vec<&str> {
+    columns.iter().map(|c| c.name.as_ref()).collect()
+}
+
+impl VisData {
+    /// Visualizer data for an already stringified Fibonacci `trace`, with the parameters it was generated and
+    /// proven with.
+    pub fn new(
+        num_steps: usize,
+        final_value: u64,
+        field: FieldChoice,
+        hash: HashChoice,
+        fri: FriParams,
+        seed: Seed,
+        trace: Vec<Vec<String>>,
+    ) -> Self {
+        Self {
+            num_steps,
+            sequence: Sequence::Fibonacci.name().to_string(),
+            final_value,
+            public_values: vec!["0".to_string(), "1".to_string(), final_value.to_string()],
+            field: FieldInfo::new(field),
+            hash: hash.name().to_string(),
+            fri,
+            seed,
+            columns: FIBONACCI_COLUMNS.to_vec(),
+            trace,
+            ..Self::default()
+        }
+        .with_constraints(extract_constraints::<Mersenne31, _>(&FibonacciAir::new(num_steps), 0, NUM_PUBLIC_VALUES))
+        .with_air_info::<Mersenne31, _>(&FibonacciAir::new(num_steps))
+    }
+}
+
+impl VisData {
+    /// Adds the exact values of the trace's leading rows (see Sequence::true_values), which rows were reduced
+    /// modulo `modulus` and the column stats. The trace itself is left untouched. The exported rows reach far past
+    /// the point where any sequence that is not all zeros outgrows a 32-bit prime, so they are enough to find the
+    /// first reduction. A row is reduced when one of its own exact values reaches `modulus`; the rows past the
+    /// exact ones are taken to be reduced like the last of them.
+    pub fn with_true_values(mut self, modulus: u32, true_values: Vec<Vec<BigUint>>) -> Self {
+        self.first_reduced_row = first_reduced_row(modulus, &true_values);
+        let real_rows = &self.trace[..self.num_steps.min(self.trace.len())];
+        self.column_stats = column_stats(&self.columns, real_rows, modulus, &true_values);
+        let p = BigUint::from(modulus);
+        let reduced: Vec<bool> = true_values.iter().map(|row| row.iter().any(|v| *v >= p)).collect();
+        let last = reduced.last().copied().unwrap_or(false);
+        self.reduced = (0..self.num_steps).map(|row| reduced.get(row).copied().unwrap_or(last)).collect();
+        self.true_values = true_values.iter().map(|row| row.iter().map(|v| v.to_string()).collect()).collect();
+        self
+    }
+
+    /// Sets the exported constraints and the degree report derived from them.
+    pub fn with_constraints(mut self, constraints: Vec<ConstraintDesc>) -> Self {
+        self.degree_report = ConstraintReport::from_constraints(&constraints);
+        self.constraints = constraints;
+        self
+    }
+
+    /// Adds the AIR summary for `air`, counting the constraints with_constraints set. Call after it and after
+    /// the sequence, columns and final value are filled in.
+    pub fn with_air_info<F: Field, A: BaseAir<F>>(mut self, air: &A) -> Self {
+        let count = |kinds: &[ConstraintKind]| self.constraints.iter().filter(|c| kinds.contains(&c.kind)).count();
+        self.air_info = AirInfo {
+            sequence: self.sequence.clone(),
+            width: air.width(),
+            columns: column_names(&self.columns).into_iter().map(str::to_string).collect(),
+            final_value: self.final_value,
+            num_constraints: self.constraints.len(),
+            boundary_constraints: count(&[ConstraintKind::FirstRow, ConstraintKind::LastRow]),
+            transition_constraints: count(&[ConstraintKind::Transition]),
+            every_row_constraints: count(&[ConstraintKind::EveryRow]),
+        };
+        self
+    }
+
+    /// Adds what each constraint evaluates to on each row, see evaluate_constraints.
+    pub fn with_constraint_evals(mut self, constraint_evals: Vec<Vec<String>>) -> Self {
+        self.constraint_evals = constraint_evals;
+        self
+    }
+
+    /// Adds the constraints the trace violates, see find_violations.
+    pub fn with_violations(mut self, violations: Vec<Violation>) -> Self {
+        self.violations = violations;
+        self
+    }
+
+    /// Adds the heatmap of constraint_evals, see heatmap, if any constraint is violated: an honest trace's is all
+    /// zeros, so only a tampered trace gets one. Call after with_constraints and with_constraint_evals.
+    pub fn with_heatmap(mut self) -> Self {
+        self.heatmap = heatmap(&self.constraints, &self.constraint_evals);
+        self
+    }
+
+    /// Adds an AIR's preprocessed trace `m` with one entry of `columns` per column, next to the committed trace.
+    pub fn with_preprocessed<F: Field>(mut self, m: &RowMajorMatrix<F>, columns: &[ColumnMeta]) -> Result<Self, FibVisError> {
+        if columns.len() != m.width() {
+            return Err(FibVisError::Columns { width: m.width(), names: columns.len() });
+        }
+        self.preprocessed_columns = columns.to_vec();
+        self.preprocessed = stringify_trace(m)?;
+        Ok(self)
+    }
+
+    /// Adds the stats of the trace's proof. The proof only exists after the export was built, so an export with
+    /// stats is written once proving (and verifying) is done.
+    pub fn with_stats(mut self, stats: ProofStats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Records whether the trace's proof verified. Like the stats, this is only known once the export was built,
+    /// so the binary writes the export after verifying, whether that succeeded or not.
+    pub fn with_verification(mut self, verification: Verification) -> Self {
+        self.verification = Some(verification);
+        self
+    }
+
+    /// Adds the Merkle roots of the trace's proof, see commitment_roots.
+    pub fn with_commitments(mut self, commitments: Vec<CommitmentRoot>) -> Self {
+        self.commitments = commitments;
+        self
+    }
+
+    /// Adds the low-degree extension of the trace, see lde::trace_lde.
+    pub fn with_lde(mut self, lde: Lde) -> Self {
+        self.lde = Some(lde);
+        self
+    }
+
+    /// Adds the row selectors over the trace's rows, and `lde_rows` from lde::selector_lde with the LDE.
+    pub fn with_selectors(mut self, lde_rows: Option<Vec<Vec<String>>>) -> Self {
+        self.selectors = Some(Selectors::new(self.trace.len(), lde_rows));
+        self
+    }
+
+    /// Adds the window of every trace row, see row_windows. They hold every row twice, so only --export-windows
+    /// adds them.
+    pub fn with_windows(mut self) -> Self {
+        self.windows = Some(row_windows(&self.trace));
+        self
+    }
+
+    /// Adds the trace cut into `frames` keyframes, see keyframes. Needs the constraints, see with_constraints.
+    pub fn with_keyframes(mut self, frames: usize) -> Self {
+        self.keyframes = Some(keyframes(&self.trace, &self.constraints, frames));
+        self
+    }
+
+    /// Reads an export back, see load_vis_data: JSON or any other format it reads, plain or gzipped, of any
+    /// known schema version.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_path(path: &Path) -> Result<Self, FibVisError> {
+        load_vis_data(path)
+    }
+
+    /// Has the text formats write the cells in `repr`. The cells themselves stay decimal strings.
+    pub fn with_repr(mut self, repr: ValueRepr) -> Self {
+        self.repr = repr;
+        self
+    }
+
+    /// Hex digits a cell takes up in the field, 8 for the 31-bit primes.
+    pub fn hex_nibbles(&self) -> usize {
+        if self.field.bits == 0 { 8 } else { self.field.bits.div_ceil(4) }
+    }
+
+    // `cells` in the representation of this export.
+    fn repr_rows<'a>(&self, cells: &'a [Vec<String>]) -> Vec<Vec<Cell<'a>>> {
+        let nibbles = self.hex_nibbles();
+        cells.iter().map(|row| row.iter().map(|v| Cell::with_repr(v, self.repr, nibbles)).collect()).collect()
+    }
+
+    /// Records which cells of the trace were overwritten on purpose, for the formats that highlight them.
+    pub fn with_tampered(mut self, cells: Vec<(usize, usize)>) -> Self {
+        self.tampered = cells;
+        self
+    }
+
+    /// Records that only the first `num_steps` rows of the trace are real and the rest pad it to a power of two.
+    /// Call before with_true_values, which marks real rows only.
+    pub fn with_padding(mut self, num_steps: usize) -> Self {
+        self.padding = (0..self.trace.len()).map(|row| row >= num_steps).collect();
+        self.num_steps = num_steps;
+        self
+    }
+}
+
+/// Visualizer data for any trace matrix: the stringified rows under `columns`, one per column of `m` as the AIR
+/// describes them, with `num_steps` set to the height and every other field left at its default for the caller
+/// to fill in.
+pub fn vis_data_from_matrix<F: Field>(m: &RowMajorMatrix<F>, columns: &[ColumnMeta]) -> Result<VisData, FibVisError> {
+    if columns.len() != m.width() {
+        return Err(FibVisError::Columns { width: m.width(), names: columns.len() });
+    }
+    Ok(VisData {
+        schema_version: SCHEMA_VERSION,
+        num_steps: m.height(),
+        columns: columns.to_vec(),
+        trace: stringify_trace(m)?,
+        ..VisData::default()
+    })
+}
+
+/// The stats of each of `columns` over the stringified `rows`, with the first row of `true_values` where the column
+/// reaches `modulus`. A column need not stay reduced from then on: a Collatz orbit comes back down to 1.
+pub fn column_stats(
+    columns: &[ColumnMeta],
+    rows: &[Vec<String>],
+    modulus: u32,
+    true_values: &[Vec<BigUint>],
+) -> Vec<ColumnStats> {
+    let p = BigUint::from(modulus);
+    columns
+        .iter()
+        .enumerate()
+        .map(|(col, meta)| {
+            let values: Vec<u64> = rows.iter().filter_map(|row| row.get(col)?.parse().ok()).collect();
+            let decimal = |v: Option<&u64>| v.map_or_else(String::new, ToString::to_string);
+            ColumnStats {
+                name: meta.name.to_string(),
+                min: decimal(values.iter().min()),
+                max: decimal(values.iter().max()),
+                increasing: values.windows(2).all(|w| w[0] <= w[1]),
+                first_wrap_row: true_values.iter().position(|row| row.get(col).is_some_and(|v| *v >= p)),
+            }
+        })
+        .collect()
+}
+
+/// The stringified `trace` revealed in `frames` steps of evenly spaced rows, at most one per row: keyframe `j` (from
+/// 1) ends at row `ceil(j * height / frames)`, so the last one completes the trace. A row is put down to the
+/// constraints that compute it from the rows before, the first-row ones for row 0 and the transition ones (on the
+/// row above) for every other row.
+pub fn keyframes(trace: &[Vec<String>], constraints: &[ConstraintDesc], frames: usize) -> Vec<Keyframe> {
+    let height = trace.len();
+    let frames = frames.clamp(1, height.max(1));
+    let of_kind = |kind: ConstraintKind| constraints.iter().enumerate().filter(move |(_, c)| c.kind == kind);
+    (1..=frames)
+        .map(|j| ((j - 1) * height).div_ceil(frames)..(j * height).div_ceil(frames))
+        .map(|rows| {
+            let mut computing = Vec::new();
+            if rows.start == 0 {
+                computing.extend(of_kind(ConstraintKind::FirstRow).map(|(i, _)| i));
+            }
+            if rows.end > rows.start.max(1) {
+                computing.extend(of_kind(ConstraintKind::Transition).map(|(i, _)| i));
+            }
+            computing.sort_unstable();
+            Keyframe { row: rows.start, rows: trace[rows].to_vec(), constraints: computing }
+        })
+        .collect()
+}
+
+/// The heatmap of the stringified `constraint_evals` of `constraints`, see evaluate_constraints, or None when all
+/// of them are 0 or empty. The constraints are named `kind: expression`, e.g. `transition: next[0] - local[1]`.
+pub fn heatmap(constraints: &[ConstraintDesc], constraint_evals: &[Vec<String>]) -> Option<Heatmap> {
+    let values: Vec<Vec<Option<u64>>> = constraint_evals
+        .iter()
+        .map(|row| (0..constraints.len()).map(|i| row.get(i).and_then(|eval| eval.parse().ok())).collect())
+        .collect();
+    if values.iter().flatten().all(|value| value.unwrap_or(0) == 0) {
+        return None;
+    }
+    Some(Heatmap {
+        constraint_names: constraints.iter().map(|c| format!("{}: {}", c.kind.label(), c.expression)).collect(),
+        rows: values.len(),
+        values,
+    })
+}
+
+/// The trace `keyframes` reveal, replayed in order as the visualizer does: each keyframe's rows go in from its
+/// `row` on, over anything an earlier one put there.
+pub fn replay_keyframes(keyframes: &[Keyframe]) -> Vec<Vec<String>> {
+    let mut trace = Vec::new();
+    for frame in keyframes {
+        trace.resize(frame.row, Vec::new());
+        trace.extend(frame.rows.iter().cloned());
+    }
+    trace
+}
+
+/// The windows of the stringified rows of a trace of any width, one per row in order, see RowWindow.
+pub fn row_windows(trace: &[Vec<String>]) -> Vec<RowWindow> {
+    let height = trace.len();
+    trace
+        .iter()
+        .enumerate()
+        .map(|(row, local)| RowWindow {
+            local: local.clone(),
+            next: trace[(row + 1) % height].clone(),
+            terminal: row + 1 == height,
+        })
+        .collect()
+}
+
+/// Create a mutable trace matrix (Vec<Vec<String>>). We populate it by iterating through Seong's trace variable with
+/// the p3_matrix::Matrix method signature for height (returns number of rows).
+pub fn stringify_trace<F: Field>(trace: &RowMajorMatrix<F>) -> Result<Vec<Vec<String>>, FibVisError> {
+    let mut trace_matrix = Vec::new();
+    let bar = row_progress(trace.height(), "stringify rows");
+
+    for i in 0..trace.height() {
+        let row = trace
+                        .row_slice(i)           // Accesses row i of the trace matrix, returning Some(&[F]) if exists, else None. 
+                        .ok_or_else(|| FibVisError::TraceGeneration { row: i, reason: "row is out of bounds".to_string() })?
+                        .iter()                 // Creates an iterator (pointer) over the elements of a specific row.
+                        .map(|v| v.to_string()) // For every element v produced/pointed by the iterator, convert it from the field element to String.
+                        .collect();             // Collects iterator, allocated memory on the heap, and pushes the strings into a Vec<String>.
+        trace_matrix.push(row); // Appends row (Vec<String>) to the end of trace_matrix (Vec<Vec<String>>).
+        if i % PROGRESS_CHUNK == 0 {
+            bar.set_position(i as u64);
+        }
+    }
+    bar.finish_and_clear();
+    Ok(trace_matrix)
+}
+
+/// The visualizer JSON for `vis`, exactly as write_trace_json would write it. This is the export path on wasm,
+/// where there is no file system to write to.
+pub fn trace_json_string(vis: &VisData) -> Result<String, FibVisError> {
+    let mut json = json_document(vis).map_err(|e| FibVisError::Serialization(format!("trace JSON: {e}")))?;
+    json.push('\n');
+    Ok(json)
+}
+
+/// Writes `data` in `format` to `output`, creating parent directories as needed. `-` writes to stdout.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_trace(data: &VisData, format: TraceFormat, output: &Path) -> Result<(), FibVisError> {
+    write_with(format.exporter(), data, output)
+}
+
+/// Writes `vis` as --format js to `path`, see JsExporter, or as an ES module with `esm` (JsModuleExporter). `-`
+/// writes to stdout.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_trace_js(path: &Path, vis: &VisData, esm: bool) -> Result<(), FibVisError> {
+    let exporter: &dyn TraceExporter = if esm { &JsModuleExporter } else { &JsExporter };
+    write_with(exporter, vis, path)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_with(exporter: &dyn TraceExporter, data: &VisData, output: &Path) -> Result<(), FibVisError> {
+    // `--output -` prints the export instead of writing a file.
+    if output == Path::new("-") {
+        let mut stdout = BufWriter::new(io::stdout().lock());
+        let written = exporter.write(data, &mut stdout).and_then(|()| Ok(stdout.flush()?));
+        return written.map_err(|e| FibVisError::export(output, e));
+    }
+    write_atomically(output, |out| exporter.write(data, out))
+}
+
+/// Writes `vis` as the visualizer JSON to `path`. Parent directories are created, and the file is written to a
+/// temporary sibling first and renamed into place, so the page never reads a half-written trace.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_trace_json(path: &Path, vis: &VisData) -> Result<(), FibVisError> {
+    write_atomically(path, |out| JsonExporter.write(vis, out))
+}
+
+/// Writes the trace of `vis` as CSV to `path`, see CsvExporter: a `step` column and then one column per trace
+/// column, one line per row. An empty trace gives just the header. Written atomically like write_trace_json.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_trace_csv(path: &Path, vis: &VisData) -> Result<(), FibVisError> {
+    write_atomically(path, |out| CsvExporter.write(vis, out))
+}
+
+/// Writes `vis` as NDJSON to `path`, see NdjsonExporter. The rows are streamed through a buffered writer, so the
+/// document is never held in memory as a whole, however long the trace.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_trace_ndjson(path: &Path, vis: &VisData) -> Result<(), FibVisError> {
+    write_atomically(path, |out| NdjsonExporter.write(vis, out))
+}
+
+/// Writes `bundle` as pretty-printed JSON to `path`, atomically like write_trace_json.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_trace_bundle(path: &Path, bundle: &TraceBundle) -> Result<(), FibVisError> {
+    write_atomically(path, |out| {
+        serde_json::to_writer_pretty(&mut *out, bundle)?;
+        Ok(out.write_all(b"\n")?)
+    })
+}
+
+/// Writes `data` in `format` gzip-compressed to `output`, which is expected to end in `.gz`; `-` writes the
+/// compressed bytes to stdout. Decompressed, the file is exactly what write_trace writes.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_trace_gz(data: &VisData, format: TraceFormat, output: &Path) -> Result<(), FibVisError> {
+    let exporter = format.exporter();
+    let compress = |out: &mut dyn Write| -> Result<(), ExportError> {
+        let mut gz = GzEncoder::new(out, Compression::default());
+        exporter.write(data, &mut gz)?;
+        gz.finish()?.flush()?;
+        Ok(())
+    };
+
+    if output == Path::new("-") {
+        let mut stdout = BufWriter::new(io::stdout().lock());
+        return compress(&mut stdout).map_err(|e| FibVisError::export(output, e));
+    }
+    write_atomically(output, compress)
+}
+
+/// Writes the trace of `vis` as a --format bin export to `path`, see BinExporter, and its column names and field
+/// next to it as a `.meta.json` sidecar (trace_data.bin -> trace_data.meta.json).
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_trace_bin(path: &Path, vis: &VisData) -> Result<(), FibVisError> {
+    write_atomically(path, |out| BinExporter.write(vis, out))?;
+    let meta = TraceBinMeta {
+        format_version: TRACE_BIN_VERSION,
+        num_steps: vis.num_steps,
+        num_rows: vis.trace.len(),
+        width: vis.columns.len(),
+        sequence: &vis.sequence,
+        columns: &vis.columns,
+        field: &vis.field,
+    };
+    let meta_path = path.with_extension("meta.json");
+    write_atomically(&meta_path, |out| {
+        serde_json::to_writer_pretty(&mut *out, &meta)?;
+        Ok(out.write_all(b"\n")?)
+    })
+}
+
+// Has `write` fill `.<name>.tmp` next to `path` through a buffered writer and renames it over `path`; the
+// temporary file is removed again if anything fails.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn write_atomically(path: &Path, write: impl FnOnce(&mut dyn Write) -> Result<(), ExportError>) -> Result<(), FibVisError> {
+    // Create the parent directory (e.g. web/) if it does not already exist.
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty());
+    if let Some(parent) = parent {
+        std::fs::create_dir_all(parent).map_err(|e| FibVisError::export(parent, e))?;
+    }
+    let name = path.file_name().ok_or_else(|| FibVisError::export(path, io::Error::new(io::ErrorKind::InvalidInput, "not a file path")))?;
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(name);
+    tmp_name.push(".tmp");
+    let tmp = parent.map_or_else(|| PathBuf::from(&tmp_name), |p| p.join(&tmp_name));
+
+    let written = File::create(&tmp).map_err(ExportError::from).and_then(|file| {
+        let mut out = BufWriter::new(file);
+        write(&mut out)?;
+        let file = out.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        Ok(std::fs::rename(&tmp, path)?)
+    });
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(FibVisError::export(path, e));
+    }
+    Ok(())
+}
+
+/// Trace export formats. Each one turns the VisData into the bytes of one file; adding a format means a new
+/// exporter plus a TraceFormat variant, nothing else has to change.
+pub trait TraceExporter {
+    /// File extension the output path gets for this format.
+    fn extension(&self) -> &'static str;
+
+    fn serialize(&self, data: &VisData) -> Result<Vec<u8>, ExportError>;
+
+    /// Writes the export to `out`. By default the serialized bytes in one go; a format that can be produced
+    /// piece by piece writes it as it goes instead.
+    fn write(&self, data: &VisData, out: &mut dyn Write) -> Result<(), ExportError> {
+        Ok(out.write_all(&self.serialize(data)?)?)
+    }
+
+    /// Size of the export in bytes, found by writing it into a counter instead of a file.
+    fn encoded_len(&self, data: &VisData) -> Result<u64, ExportError> {
+        let mut counter = ByteCounter(0);
+        self.write(data, &mut counter)?;
+        Ok(counter.0)
+    }
+}
+
+// A writer that only counts what is written to it, see TraceExporter::encoded_len.
+struct ByteCounter(u64);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The formats selectable with --format.
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceFormat {
+    Json,
+    Csv,
+    Msgpack,
+    Ndjson,
+    Cbor,
+    Bin,
+    Html,
+    Md,
+    Cells,
+    #[serde(rename = "cells-ndjson")]
+    CellsNdjson,
+    Js,
+}
+
+impl TraceFormat {
+    /// The exporter implementing this format.
+    pub fn exporter(self) -> &'static dyn TraceExporter {
+        match self {
+            TraceFormat::Json => &JsonExporter,
+            TraceFormat::Csv => &CsvExporter,
+            TraceFormat::Msgpack => &MsgpackExporter,
+            TraceFormat::Ndjson => &NdjsonExporter,
+            TraceFormat::Cbor => &CborExporter,
+            TraceFormat::Bin => &BinExporter,
+            TraceFormat::Html => &HtmlExporter,
+            TraceFormat::Md => &MarkdownExporter,
+            TraceFormat::Cells => &CellsExporter,
+            TraceFormat::CellsNdjson => &CellsNdjsonExporter,
+            TraceFormat::Js => &JsExporter,
+        }
+    }
+}
+
+// The format web/index.html reads.
+pub struct JsonExporter;
+
+impl TraceExporter for JsonExporter {
+    fn extension(&self) -> &'static str {
+        "json"
+    }
+
+    fn serialize(&self, data: &VisData) -> Result<Vec<u8>, ExportError> {
+        let mut json = json_document(data)?; // Convert vis_data into a JSON formatted string, with indentation and newlines.
+        json.push('\n');
+        Ok(json.into_bytes())
+    }
+}
+
+// The pretty-printed JSON of `data`, its trace and preprocessed cells written in `data.repr`.
+fn json_document(data: &VisData) -> Result<String, serde_json::Error> {
+    if data.repr == ValueRepr::Decimal {
+        return serde_json::to_string_pretty(data);
+    }
+    // serde_json keeps the keys in order (preserve_order), so the cells stay where they were
+    let mut doc = serde_json::to_value(data)?;
+    doc["trace"] = serde_json::to_value(data.repr_rows(&data.trace))?;
+    doc["preprocessed"] = serde_json::to_value(data.repr_rows(&data.preprocessed))?;
+    serde_json::to_string_pretty(&doc)
+}
+
+// Only the trace itself, one line per row under a `step,<columns>` header (`step,a,b` for Fibonacci). Fields are
+// quoted as RFC 4180 asks when they hold a comma, a quote or a line break, which only a column name can. With
+// --repr both every column is followed by its hex form, `step,a,a_hex,b,b_hex`.
+pub struct CsvExporter;
+
+impl TraceExporter for CsvExporter {
+    fn extension(&self) -> &'static str {
+        "csv"
+    }
+
+    fn serialize(&self, data: &VisData) -> Result<Vec<u8>, ExportError> {
+        let width = data.columns.len() + 1;
+        let mut csv = String::with_capacity((data.trace.len() + 1) * width * 12);
+        let nibbles = data.hex_nibbles();
+        let header: Vec<Cow<str>> = match data.repr {
+            ValueRepr::Both => {
+                column_names(&data.columns).into_iter().flat_map(|c| [Cow::from(c), Cow::from(format!("{c}_hex"))]).collect()
+            }
+            _ => column_names(&data.columns).into_iter().map(Cow::from).collect(),
+        };
+        push_csv_record(&mut csv, std::iter::once("step").chain(header.iter().map(AsRef::as_ref)));
+        for (step, row) in data.trace.iter().enumerate() {
+            let step = step.to_string();
+            let cells: Vec<Cow<str>> = row.iter().flat_map(|v| csv_cells(v, data.repr, nibbles)).collect();
+            push_csv_record(&mut csv, std::iter::once(step.as_str()).chain(cells.iter().map(AsRef::as_ref)));
+        }
+        Ok(csv.into_bytes())
+    }
+}
+
+// The CSV fields of one cell: its decimal or hex form, or both in that order.
+fn csv_cells(value: &str, repr: ValueRepr, nibbles: usize) -> impl Iterator<Item = Cow<'_, str>> {
+    let dec = (repr != ValueRepr::Hex).then_some(Cow::Borrowed(value));
+    let hex = (repr != ValueRepr::Decimal).then(|| Cow::Owned(hex_cell(value, nibbles)));
+    dec.into_iter().chain(hex)
+}
+
+// Appends one CSV line holding `fields`.
+fn push_csv_record<'a>(csv: &mut String, fields: impl Iterator<Item = &'a str>) {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            csv.push(',');
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            csv.push('"');
+            csv.push_str(&field.replace('"', "\"\""));
+            csv.push('"');
+        } else {
+            csv.push_str(field);
+        }
+    }
+    csv.push('\n');
+}
+
+// Same structure as the JSON, with named fields so it decodes to the same dictionary, except that the trace cells
+// are integers rather than strings. read_trace_msgpack decodes it back into a VisData.
+pub struct MsgpackExporter;
+
+impl TraceExporter for MsgpackExporter {
+    fn extension(&self) -> &'static str {
+        "msgpack"
+    }
+
+    fn serialize(&self, data: &VisData) -> Result<Vec<u8>, ExportError> {
+        Ok(rmp_serde::to_vec_named(data)?)
+    }
+}
+
+/// Decodes a --format msgpack export.
+pub fn read_trace_msgpack(bytes: &[u8]) -> Result<VisData, FibVisError> {
+    rmp_serde::from_slice(bytes).map_err(|e| FibVisError::Serialization(format!("trace MessagePack: {e}")))
+}
+
+// The same structure as MessagePack, integer cells included, in CBOR. Written straight into the output file
+// rather than built in memory first. read_trace_cbor decodes it back into a VisData.
+pub struct CborExporter;
+
+impl TraceExporter for CborExporter {
+    fn extension(&self) -> &'static str {
+        "cbor"
+    }
+
+    fn serialize(&self, data: &VisData) -> Result<Vec<u8>, ExportError> {
+        let mut bytes = Vec::new();
+        self.write(data, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn write(&self, data: &VisData, out: &mut dyn Write) -> Result<(), ExportError> {
+        Ok(ciborium::into_writer(data, out)?)
+    }
+}
+
+/// Decodes a --format cbor export.
+pub fn read_trace_cbor(bytes: &[u8]) -> Result<VisData, FibVisError> {
+    ciborium::from_reader(bytes).map_err(|e| FibVisError::Serialization(format!("trace CBOR: {e}")))
+}
+
+/// The JSON Schema (draft 7) of the JSON export, derived from VisData so the two cannot drift apart, with every
+/// cell representation --repr writes. The `schema` subcommand prints it.
+pub fn vis_data_schema() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(VisData)).expect("a JSON Schema is plain JSON")
+}
+
+/// Reads the JSON, MessagePack (`.msgpack`) or CBOR (`.cbor`) export at `path`, whichever schema version wrote it:
+/// older versions are upgraded in memory to SCHEMA_VERSION, versions this build does not know are an error. A
+/// `--compress` export (`trace_data.json.gz`) is decompressed first.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_vis_data(path: &Path) -> Result<VisData, FibVisError> {
+    let mut bytes = std::fs::read(path).map_err(|e| FibVisError::io(path, e))?;
+    let mut format_path = path.to_path_buf();
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        let mut plain = Vec::new();
+        GzDecoder::new(bytes.as_slice()).read_to_end(&mut plain).map_err(|e| FibVisError::io(path, e))?;
+        bytes = plain;
+        format_path.set_extension("");
+    }
+    let decode_error = |e: &dyn std::fmt::Display| FibVisError::Serialization(format!("{}: {e}", path.display()));
+    let doc: serde_json::Value = match format_path.extension().and_then(|ext| ext.to_str()) {
+        Some("msgpack") => rmp_serde::from_slice(&bytes).map_err(|e| decode_error(&e))?,
+        Some("cbor") => ciborium::from_reader(bytes.as_slice()).map_err(|e| decode_error(&e))?,
+        _ => serde_json::from_slice(&bytes).map_err(|e| decode_error(&e))?,
+    };
+    let doc = upgrade_vis_data(doc).map_err(|found| FibVisError::SchemaVersion {
+        path: path.to_path_buf(),
+        found,
+        supported: SCHEMA_VERSION,
+    })?;
+    serde_json::from_value(doc).map_err(|e| decode_error(&e))
+}
+
+/// The visualizer JSON in `json` as VisData, of any schema version as for load_vis_data. This is the import path on
+/// wasm, where the export comes as a string.
+pub fn vis_data_from_json(json: &str) -> Result<VisData, FibVisError> {
+    let decode_error = |e: serde_json::Error| FibVisError::Serialization(format!("trace JSON: {e}"));
+    let doc = serde_json::from_str(json).map_err(decode_error)?;
+    let doc = upgrade_vis_data(doc).map_err(|found| {
+        FibVisError::Serialization(format!("trace JSON: schema_version {found} is not one of 1 to {SCHEMA_VERSION}"))
+    })?;
+    serde_json::from_value(doc).map_err(decode_error)
+}
+
+/// Brings a decoded export of any known schema version up to SCHEMA_VERSION, one version at a time. Gives back
+/// the `schema_version` it found when that is not one of them.
+pub fn upgrade_vis_data(mut doc: serde_json::Value) -> Result<serde_json::Value, String> {
+    let mut version = match doc.get("schema_version") {
+        None => 1,
+        Some(v) => match v.as_u64().and_then(|v| u32::try_from(v).ok()) {
+            Some(v @ 1..=SCHEMA_VERSION) => v,
+            _ => return Err(v.to_string()),
+        },
+    };
+    // Anything but an object is left for the decoder to reject
+    let Some(map) = doc.as_object_mut() else { return Ok(doc) };
+    while version < SCHEMA_VERSION {
+        match version {
+            1 => upgrade_v1(map),
+            2 => upgrade_v2(map),
+            3 => upgrade_v3(map),
+            4 => upgrade_v4(map),
+            5 => upgrade_v5(map),
+            6 => upgrade_v6(map),
+            7 => upgrade_v7(map),
+            8 => upgrade_v8(map),
+            9 => upgrade_v9(map),
+            10 => upgrade_v10(map),
+            11 => upgrade_v11(map),
+            12 => upgrade_v12(map),
+            _ => unreachable!("no upgrade from schema version {version}"),
+        }
+        version += 1;
+        map.insert("schema_version".to_string(), version.into());
+    }
+    Ok(doc)
+}
+
+// Version 1 to 2: turns a bare field name and bare column names into the objects they became, assumes the two
+// Fibonacci columns for a two-column trace without names as the visualizer always did, and fills in every block
+// the build that wrote the export did not have yet with its default.
+fn upgrade_v1(map: &mut serde_json::Map<String, serde_json::Value>) {
+    use serde_json::Value;
+
+    if let Some(Value::String(name)) = map.get("field") {
+        let field = FieldChoice::from_str(name, true)
+            .map_or_else(|_| FieldInfo { name: name.clone(), ..FieldInfo::default() }, FieldInfo::new);
+        map.insert("field".to_string(), serde_json::to_value(field).unwrap_or_default());
+    }
+    for key in ["columns", "preprocessed_columns"] {
+        if let Some(Value::Array(columns)) = map.get_mut(key) {
+            for column in columns.iter_mut().filter(|c| c.is_string()) {
+                *column = serde_json::json!({ "name": column.take(), "description": "" });
+            }
+        }
+    }
+    let width = map.get("trace").and_then(|t| t.get(0)).and_then(Value::as_array).map_or(0, Vec::len);
+    if !map.contains_key("columns") && width == FIBONACCI_COLUMNS.len() {
+        map.insert("columns".to_string(), serde_json::to_value(&FIBONACCI_COLUMNS).unwrap_or_default());
+    }
+    map.entry("sequence").or_insert_with(|| Sequence::Fibonacci.name().into());
+
+    // What every version 1 export had is left for the decoder to miss
+    let Ok(Value::Object(defaults)) = serde_json::to_value(VisData::default()) else { return };
+    for (key, value) in defaults {
+        if !["num_steps", "final_value", "trace"].contains(&key.as_str()) {
+            map.entry(key).or_insert(value);
+        }
+    }
+}
+
+// Version 2 to 3: an export from before verification was recorded says nothing about it.
+fn upgrade_v2(map: &mut serde_json::Map<String, serde_json::Value>) {
+    map.entry("verification").or_insert(serde_json::Value::Null);
+}
+
+// Version 3 to 4: the stats of an older export do not identify the proof.
+fn upgrade_v3(map: &mut serde_json::Map<String, serde_json::Value>) {
+    if let Some(serde_json::Value::Object(stats)) = map.get_mut("stats") {
+        stats.entry("proof_digest").or_insert(serde_json::Value::Null);
+        stats.entry("proof_head").or_insert(serde_json::Value::Null);
+    }
+}
+
+// Version 4 to 5: an older export has no commitments to show.
+fn upgrade_v4(map: &mut serde_json::Map<String, serde_json::Value>) {
+    map.entry("commitments").or_insert_with(|| serde_json::Value::Array(Vec::new()));
+}
+
+// Version 5 to 6: an older export was written without its LDE.
+fn upgrade_v5(map: &mut serde_json::Map<String, serde_json::Value>) {
+    map.entry("lde").or_insert(serde_json::Value::Null);
+}
+
+// Version 6 to 7: the timings of an older export have no total, which reads as a phase that did not run.
+fn upgrade_v6(map: &mut serde_json::Map<String, serde_json::Value>) {
+    if let Some(serde_json::Value::Object(timings)) = map.get_mut("stats").and_then(|stats| stats.get_mut("timings")) {
+        timings.entry("total_ms").or_insert(serde_json::json!(0.0));
+    }
+}
+
+// Version 7 to 8: an older export did not track memory.
+fn upgrade_v7(map: &mut serde_json::Map<String, serde_json::Value>) {
+    if let Some(serde_json::Value::Object(stats)) = map.get_mut("stats") {
+        stats.entry("memory").or_insert(serde_json::Value::Null);
+    }
+}
+
+// Version 8 to 9: an older export left the selectors to the page.
+fn upgrade_v8(map: &mut serde_json::Map<String, serde_json::Value>) {
+    map.entry("selectors").or_insert(serde_json::Value::Null);
+}
+
+// Version 9 to 10: an older export had no windows, as one without --export-windows has none.
+fn upgrade_v9(map: &mut serde_json::Map<String, serde_json::Value>) {
+    map.entry("windows").or_insert(serde_json::Value::Null);
+}
+
+// Version 10 to 11: an older export leaves the column stats to be worked out from its trace.
+fn upgrade_v10(map: &mut serde_json::Map<String, serde_json::Value>) {
+    map.entry("column_stats").or_insert_with(|| serde_json::Value::Array(Vec::new()));
+}
+
+// Version 11 to 12: an older export has no keyframes, as one without --keyframes has none.
+fn upgrade_v11(map: &mut serde_json::Map<String, serde_json::Value>) {
+    map.entry("keyframes").or_insert(serde_json::Value::Null);
+}
+
+// Version 12 to 13: an older export has no heatmap, which leaves the violations to show what a trace breaks.
+fn upgrade_v12(map: &mut serde_json::Map<String, serde_json::Value>) {
+    map.entry("heatmap").or_insert(serde_json::Value::Null);
+}
+
+/// Number of trace rows above which a pretty-printed JSON export gets slow to write and too big for the visualizer
+/// to load, and the binary suggests --format ndjson instead.
+pub const JSON_ROW_LIMIT: usize = 1 << 18;
+
+// Newline-delimited JSON for traces too large for one document: a header object with what is needed to read the
+// rows, then one `{"step":i,"values":[...]}` object per row, written row by row so a reader can process the
+// trace as it streams in.
+pub struct NdjsonExporter;
+
+// The first line of an NDJSON export.
+#[derive(Serialize)]
+struct NdjsonHeader<'a> {
+    schema_version: u32,
+    num_steps: usize,
+    sequence: &'a str,
+    final_value: u64,
+    columns: &'a [ColumnMeta],
+    field: &'a FieldInfo,
+    repr: ValueRepr,
+}
+
+// Every other line.
+#[derive(Serialize)]
+struct NdjsonRow<'a> {
+    step: usize,
+    values: Vec<Cell<'a>>,
+}
+
+impl TraceExporter for NdjsonExporter {
+    fn extension(&self) -> &'static str {
+        "ndjson"
+    }
+
+    fn serialize(&self, data: &VisData) -> Result<Vec<u8>, ExportError> {
+        let mut bytes = Vec::new();
+        self.write(data, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn write(&self, data: &VisData, out: &mut dyn Write) -> Result<(), ExportError> {
+        let header = NdjsonHeader {
+            schema_version: data.schema_version,
+            num_steps: data.num_steps,
+            sequence: &data.sequence,
+            final_value: data.final_value,
+            columns: &data.columns,
+            field: &data.field,
+            repr: data.repr,
+        };
+        serde_json::to_writer(&mut *out, &header)?;
+        out.write_all(b"\n")?;
+        let nibbles = data.hex_nibbles();
+        for (step, row) in data.trace.iter().enumerate() {
+            let values = row.iter().map(|v| Cell::with_repr(v, data.repr, nibbles)).collect();
+            serde_json::to_writer(&mut *out, &NdjsonRow { step, values })?;
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// One trace cell as its own record, the "long" form of the trace a d3 data join binds one element per cell to.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CellRecord {
+    pub step: usize,
+    pub column: String,       // the column's name from the column metadata, e.g. `a`
+    pub value: String,        // in decimal
+    pub violates: Vec<usize>, // indices in `constraints` of the violated constraints that read the cell, ascending
+}
+
+/// The trace of `data` as one CellRecord per cell, row by row, with the violations it records joined in: every
+/// cell a violation reads lists that violation's constraint once, even when it is violated on two rows reading
+/// it. Without constraint evaluations no cell violates anything.
+pub fn cell_records(data: &VisData) -> impl Iterator<Item = CellRecord> + '_ {
+    let mut violated: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for violation in &data.violations {
+        for &cell in &violation.cells {
+            violated.entry(cell).or_default().push(violation.constraint);
+        }
+    }
+    for constraints in violated.values_mut() {
+        constraints.sort_unstable();
+        constraints.dedup();
+    }
+    let names = column_names(&data.columns);
+    let cells = data.trace.iter().enumerate();
+    let cells = cells.flat_map(|(step, row)| row.iter().enumerate().map(move |(column, value)| (step, column, value)));
+    cells.map(move |(step, column, value)| CellRecord {
+        step,
+        column: names.get(column).map_or_else(|| column.to_string(), |name| name.to_string()),
+        value: value.clone(),
+        violates: violated.get(&(step, column)).cloned().unwrap_or_default(),
+    })
+}
+
+// The cell records of cell_records as one JSON array, for d3.json.
+pub struct CellsExporter;
+
+impl TraceExporter for CellsExporter {
+    fn extension(&self) -> &'static str {
+        "cells.json"
+    }
+
+    fn serialize(&self, data: &VisData) -> Result<Vec<u8>, ExportError> {
+        let mut bytes = Vec::new();
+        self.write(data, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn write(&self, data: &VisData, out: &mut dyn Write) -> Result<(), ExportError> {
+        out.write_all(b"[")?;
+        for (i, record) in cell_records(data).enumerate() {
+            out.write_all(if i == 0 { b"\n" } else { b",\n" })?;
+            serde_json::to_writer(&mut *out, &record)?;
+        }
+        Ok(out.write_all(b"\n]\n")?)
+    }
+}
+
+// The same records one per line, for traces too large for one array.
+pub struct CellsNdjsonExporter;
+
+impl TraceExporter for CellsNdjsonExporter {
+    fn extension(&self) -> &'static str {
+        "cells.ndjson"
+    }
+
+    fn serialize(&self, data: &VisData) -> Result<Vec<u8>, ExportError> {
+        let mut bytes = Vec::new();
+        self.write(data, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn write(&self, data: &VisData, out: &mut dyn Write) -> Result<(), ExportError> {
+        for record in cell_records(data) {
+            serde_json::to_writer(&mut *out, &record)?;
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// Largest trace --format html writes. Every row becomes a table row of the page, which gets slow to open well
+/// before the JSON inside it gets large.
+pub const HTML_MAX_ROWS: usize = 1 << 14;
+
+// Placeholder in the template that the JSON replaces.
+const HTML_TRACE_DATA: &str = "/*TRACE_DATA*/";
+
+// A single page that shows the trace without a server or the visualizer next to it: web/trace_template.html
+// with the JSON export inlined into a <script> tag, drawn as a table and a chart of the last column.
+pub struct HtmlExporter;
+
+impl TraceExporter for HtmlExporter {
+    fn extension(&self) -> &'static str {
+        "html"
+    }
+
+    fn serialize(&self, data: &VisData) -> Result<Vec<u8>, ExportError> {
+        if data.trace.len() > HTML_MAX_ROWS {
+            return Err(ExportError::TooLarge { format: "html", rows: data.trace.len(), limit: HTML_MAX_ROWS });
+        }
+        let template = include_str!("web/trace_template.html");
+        let json = script_safe_json(&serde_json::to_string(data)?);
+        Ok(template.replacen(HTML_TRACE_DATA, &json, 1).into_bytes())
+    }
+}
+
+// JSON that can sit inside a <script> element: `<` (which would let `</script>` or `<!--` end or derail the
+// element), `>` and `&` cannot occur outside strings, so escaping them there keeps the JSON equivalent. U+2028
+// and U+2029 are escaped too, older JavaScript does not allow them in string literals.
+fn script_safe_json(json: &str) -> String {
+    let mut safe = String::with_capacity(json.len());
+    for c in json.chars() {
+        match c {
+            '<' => safe.push_str("\\u003c"),
+            '>' => safe.push_str("\\u003e"),
+            '&' => safe.push_str("\\u0026"),
+            '\u{2028}' => safe.push_str("\\u2028"),
+            '\u{2029}' => safe.push_str("\\u2029"),
+            c => safe.push(c),
+        }
+    }
+    safe
+}
+
+// The JSON export as a script for pages that cannot fetch it, opened from file:// for one: loaded with a
+// <script> tag, it sets `window.TRACE_DATA` to the same document, escaped as for HtmlExporter.
+pub struct JsExporter;
+
+impl TraceExporter for JsExporter {
+    fn extension(&self) -> &'static str {
+        "js"
+    }
+
+    fn serialize(&self, data: &VisData) -> Result<Vec<u8>, ExportError> {
+        Ok(format!("window.TRACE_DATA = {};\n", script_safe_json(&json_document(data)?)).into_bytes())
+    }
+}
+
+// The same document as the default export of an ES module, for bundlers and `import data from './trace_data.js'`.
+pub struct JsModuleExporter;
+
+impl TraceExporter for JsModuleExporter {
+    fn extension(&self) -> &'static str {
+        "js"
+    }
+
+    fn serialize(&self, data: &VisData) -> Result<Vec<u8>, ExportError> {
+        Ok(format!("export default {};\n", script_safe_json(&json_document(data)?)).into_bytes())
+    }
+}
+
+// A Markdown document to paste into an issue or notes: the run parameters, the proof stats if there are any and
+// the trace as a table, elided like trace_table. Cells --tamper overwrote are bold.
+pub struct MarkdownExporter;
+
+impl TraceExporter for MarkdownExporter {
+    fn extension(&self) -> &'static str {
+        "md"
+    }
+
+    fn serialize(&self, data: &VisData) -> Result<Vec<u8>, ExportError> {
+        Ok(markdown(data).into_bytes())
+    }
+}
+
+fn markdown(vis: &VisData) -> String {
+    use std::fmt::Write as _;
+
+    let mut md = format!("# {} trace\n\n| Parameter | Value |\n| --- | --- |\n", vis.sequence);
+    let fri = &vis.fri;
+    let seed = String::from(vis.seed.clone());
+    let parameters = [
+        ("Steps", vis.num_steps.to_string()),
+        ("Final value", vis.final_value.to_string()),
+        ("Public values", vis.public_values.join(", ")),
+        ("Field", format!("{} (p = {})", vis.field.name, vis.field.modulus)),
+        ("Hash", vis.hash.clone()),
+        (
+            "FRI",
+            format!(
+                "log_blowup {}, {} queries, {} proof-of-work bits, log_final_poly_len {}",
+                fri.log_blowup, fri.num_queries, fri.proof_of_work_bits, fri.log_final_poly_len
+            ),
+        ),
+        ("Seed", if seed.is_empty() { "none".to_string() } else { format!("`{seed}`") }),
+    ];
+    for (name, value) in parameters {
+        let _ = writeln!(md, "| {name} | {} |", markdown_cell(&value));
+    }
+
+    md.push_str("\n## Proof\n\n");
+    match &vis.stats {
+        Some(stats) => {
+            let t = &stats.timings;
+            md.push_str("| Proof size | Trace generation | Export | Proving | Verification | Total |\n");
+            md.push_str("| ---: | ---: | ---: | ---: | ---: | ---: |\n");
+            let _ = writeln!(
+                md,
+                "| {} bytes | {:.1} ms | {:.1} ms | {:.1} ms | {:.1} ms | {:.1} ms |",
+                stats.proof_bytes, t.trace_gen_ms, t.export_ms, t.prove_ms, t.verify_ms, t.total_ms
+            );
+        }
+        None => md.push_str("Not proven.\n"),
+    }
+
+    md.push_str("\n## Trace\n\n| step |");
+    for column in &vis.columns {
+        let _ = write!(md, " {} |", markdown_cell(&column.name));
+    }
+    md.push_str("\n| ---: |");
+    md.push_str(&" ---: |".repeat(vis.columns.len()));
+    md.push('\n');
+    let shown = head_and_tail(vis.trace.len(), TABLE_MAX_ROWS);
+    let tampered: HashSet<(usize, usize)> = vis.tampered.iter().copied().collect();
+    for (i, &row) in shown.iter().enumerate() {
+        if i > 0 && shown[i - 1] + 1 != row {
+            let (omitted, empty) = (row - shown[i - 1] - 1, " |".repeat(vis.columns.len().saturating_sub(1)));
+            let _ = writeln!(md, "| … | {omitted} rows omitted |{empty}");
+        }
+        let _ = write!(md, "| {row} |");
+        for (col, value) in vis.trace[row].iter().enumerate() {
+            if tampered.contains(&(row, col)) {
+                let _ = write!(md, " **{value}** |");
+            } else {
+                let _ = write!(md, " {value} |");
+            }
+        }
+        md.push('\n');
+    }
+
+    if !vis.columns.is_empty() {
+        md.push('\n');
+        for column in &vis.columns {
+            let _ = writeln!(md, "- `{}`: {}", column.name, markdown_cell(&column.description));
+        }
+    }
+    md
+}
+
+// `text` for a Markdown table cell, where a `|` would end the cell and a line break the row.
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+/// First bytes of a --format bin export.
+pub const TRACE_BIN_MAGIC: [u8; 4] = *b"P3TR";
+/// Layout version of a --format bin export, bumped whenever the header changes.
+pub const TRACE_BIN_VERSION: u16 = 1;
+/// Length of the header in front of the cells. A multiple of 4, so a Uint32Array can view the cells in place.
+pub const TRACE_BIN_HEADER_LEN: usize = 20;
+
+// The trace alone, for traces too large for any of the structured formats: a header of
+//
+//     magic "P3TR" | version: u16 | field id: u8 | 0: u8 | num_steps: u32 | num_rows: u32 | width: u32
+//
+// followed by the canonical u32 of every cell in row-major order, all little-endian. The field id is
+// FieldChoice::id; num_steps can be below num_rows for a padded trace. write_trace_bin adds the column names in
+// a sidecar file, read_trace_bin parses it back.
+pub struct BinExporter;
+
+impl TraceExporter for BinExporter {
+    fn extension(&self) -> &'static str {
+        "bin"
+    }
+
+    fn serialize(&self, data: &VisData) -> Result<Vec<u8>, ExportError> {
+        let mut bytes = Vec::new();
+        self.write(data, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn write(&self, data: &VisData, out: &mut dyn Write) -> Result<(), ExportError> {
+        let field = FieldChoice::value_variants().iter().copied().find(|field| field.name() == data.field.name);
+        let field = field.ok_or_else(|| ExportError::Bin(format!("unknown field `{}`", data.field.name)))?;
+        out.write_all(&trace_bin_header(field, data.num_steps, data.trace.len(), data.columns.len())?)?;
+        for (row, values) in data.trace.iter().enumerate() {
+            for value in values {
+                let not_a_cell = || ExportError::Bin(format!("row {row}: `{value}` is not a 32-bit field element"));
+                let cell: u32 = value.parse().map_err(|_| not_a_cell())?;
+                out.write_all(&cell.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `trace` as a --format bin export, the cells taken straight from the field elements. Gives the same bytes as
+/// BinExporter on the export of `trace`.
+pub fn trace_bin<F: PrimeField32>(
+    trace: &RowMajorMatrix<F>,
+    num_steps: usize,
+    field: FieldChoice,
+) -> Result<Vec<u8>, ExportError> {
+    let mut bytes = trace_bin_header(field, num_steps, trace.height(), trace.width())?;
+    bytes.reserve(4 * trace.values.len());
+    for value in &trace.values {
+        bytes.extend_from_slice(&value.as_canonical_u32().to_le_bytes());
+    }
+    Ok(bytes)
+}
+
+fn trace_bin_header(field: FieldChoice, num_steps: usize, num_rows: usize, width: usize) -> Result<Vec<u8>, ExportError> {
+    let word = |n: usize, what: &str| {
+        u32::try_from(n).map_err(|_| ExportError::Bin(format!("{what} {n} does not fit in 32 bits")))
+    };
+    let mut header = Vec::with_capacity(TRACE_BIN_HEADER_LEN);
+    header.extend_from_slice(&TRACE_BIN_MAGIC);
+    header.extend_from_slice(&TRACE_BIN_VERSION.to_le_bytes());
+    header.extend_from_slice(&[field.id(), 0]);
+    header.extend_from_slice(&word(num_steps, "num_steps")?.to_le_bytes());
+    header.extend_from_slice(&word(num_rows, "the row count")?.to_le_bytes());
+    header.extend_from_slice(&word(width, "the width")?.to_le_bytes());
+    Ok(header)
+}
+
+// The `.meta.json` sidecar of a --format bin export.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Serialize)]
+struct TraceBinMeta<'a> {
+    format_version: u16,
+    num_steps: usize,
+    num_rows: usize,
+    width: usize,
+    sequence: &'a str,
+    columns: &'a [ColumnMeta],
+    field: &'a FieldInfo,
+}
+
+/// A --format bin export, parsed by read_trace_bin.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceBin {
+    pub field: FieldChoice,
+    pub num_steps: usize,
+    pub width: usize,
+    pub values: Vec<u32>, // canonical cell values, row by row
+}
+
+impl TraceBin {
+    pub fn height(&self) -> usize {
+        if self.width == 0 { 0 } else { self.values.len() / self.width }
+    }
+
+    /// The trace as a matrix over `F`, which should be the field the export was made in.
+    pub fn to_matrix<F: PrimeField32>(&self) -> RowMajorMatrix<F> {
+        RowMajorMatrix::new(self.values.iter().map(|&v| F::from_u32(v)).collect(), self.width)
+    }
+}
+
+/// Parses a --format bin export, checking its header against the length of the data.
+pub fn read_trace_bin(bytes: &[u8]) -> Result<TraceBin, FibVisError> {
+    let invalid = |reason: String| FibVisError::Serialization(format!("binary trace: {reason}"));
+    if bytes.len() < TRACE_BIN_HEADER_LEN || bytes[..4] != TRACE_BIN_MAGIC {
+        return Err(invalid("not a --format bin export".to_string()));
+    }
+    let word = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize;
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if version != TRACE_BIN_VERSION {
+        return Err(invalid(format!("layout version {version}, this build reads version {TRACE_BIN_VERSION}")));
+    }
+    let field = FieldChoice::from_id(bytes[6]).ok_or_else(|| invalid(format!("unknown field id {}", bytes[6])))?;
+    let (num_steps, num_rows, width) = (word(8), word(12), word(16));
+    let cells = &bytes[TRACE_BIN_HEADER_LEN..];
+    if num_rows.checked_mul(width).and_then(|n| n.checked_mul(4)) != Some(cells.len()) {
+        return Err(invalid(format!("{} bytes of cells for a {num_rows}x{width} trace", cells.len())));
+    }
+    let values: Vec<u32> = cells.chunks_exact(4).map(|c| u32::from_le_bytes(c.try_into().unwrap())).collect();
+    if let Some(value) = values.iter().find(|&&v| v >= field.modulus()) {
+        return Err(invalid(format!("{value} is not a {} element", field.name())));
+    }
+    Ok(TraceBin { field, num_steps, width, values })
+}
+
+/// Rows render_svg draws by default; longer traces show their first and last rows with the rest elided.
+pub const SVG_MAX_ROWS: usize = 32;
+
+const SVG_ROW_HEIGHT: usize = 22;
+const SVG_CELL_WIDTH: usize = 110;
+const SVG_MARGIN: usize = 20;
+
+/// Draws `vis` as an SVG for slides, see svg_string, and writes it to `path`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn render_svg(vis: &VisData, path: &Path, max_rows: usize) -> Result<(), FibVisError> {
+    let svg = svg_string(vis, max_rows);
+    write_atomically(path, |out| Ok(out.write_all(svg.as_bytes())?))
+}
+
+/// The trace of `vis` as an SVG table: a header with the column names, one `<g class="row">` per drawn row and
+/// an arrow from each row to the next where the AIR has transition constraints. At most `max_rows` rows are
+/// drawn, the first and the last ones, with a marker for the rows left out in between. Cells a violated
+/// constraint reads are drawn in red.
+pub fn svg_string(vis: &VisData, max_rows: usize) -> String {
+    use std::fmt::Write as _;
+
+    let height = vis.trace.len();
+    let shown = head_and_tail(height, max_rows);
+    let violated: HashSet<(usize, usize)> = vis.violations.iter().flat_map(|v| v.cells.iter().copied()).collect();
+    let transitions: Vec<&str> = vis
+        .constraints
+        .iter()
+        .filter(|c| c.kind == ConstraintKind::Transition)
+        .map(|c| c.expression.as_str())
+        .collect();
+
+    // One line per drawn row, plus the header and the elision marker
+    let lines = shown.len() + 1 + usize::from(shown.len() < height);
+    let width = 2 * SVG_MARGIN + SVG_CELL_WIDTH * (vis.columns.len() + 1) + 40;
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{}" font-family="monospace" font-size="13">"#,
+        2 * SVG_MARGIN + SVG_ROW_HEIGHT * lines
+    );
+    svg.push_str(concat!(
+        r#"<defs><marker id="arrow" markerWidth="8" markerHeight="8" refX="6" refY="4" orient="auto">"#,
+        r##"<path d="M0,0 L8,4 L0,8 z" fill="#4CAF50"/></marker></defs>"##,
+        "\n"
+    ));
+    let _ = writeln!(svg, r#"<title>{} trace, {} steps</title>"#, xml_escape(&vis.sequence), vis.num_steps);
+
+    let cell_x = |col: usize| SVG_MARGIN + SVG_CELL_WIDTH * (col + 1);
+    let baseline = |line: usize| SVG_MARGIN + SVG_ROW_HEIGHT * line + 15;
+    let _ = write!(svg, r#"<g class="header" font-weight="bold"><text x="{SVG_MARGIN}" y="{}">row</text>"#, baseline(0));
+    for (col, meta) in vis.columns.iter().enumerate() {
+        let (name, description) = (xml_escape(&meta.name), xml_escape(&meta.description));
+        let _ = write!(svg, r#"<text x="{}" y="{}">{name}<title>{description}</title></text>"#, cell_x(col), baseline(0));
+    }
+    svg.push_str("</g>\n");
+
+    let mut line = 1;
+    let arrow_x = cell_x(vis.columns.len());
+    for (i, &row) in shown.iter().enumerate() {
+        if i > 0 && shown[i - 1] + 1 != row {
+            let (omitted, y) = (row - shown[i - 1] - 1, baseline(line));
+            let _ = writeln!(
+                svg,
+                r##"<text class="elided" x="{SVG_MARGIN}" y="{y}" fill="#999">… {omitted} rows omitted …</text>"##
+            );
+            line += 1;
+        }
+        let y = baseline(line);
+        let _ = write!(svg, r##"<g class="row"><text x="{SVG_MARGIN}" y="{y}" fill="#666">{row}</text>"##);
+        for (col, value) in vis.trace[row].iter().enumerate() {
+            let color = if violated.contains(&(row, col)) { r##" fill="#b00020" font-weight="bold""## } else { "" };
+            let _ = write!(svg, r#"<text x="{}" y="{y}"{color}>{}</text>"#, cell_x(col), xml_escape(value));
+        }
+        svg.push_str("</g>\n");
+        // The transition constraints tie this row to the next one, if that is drawn right below
+        if !transitions.is_empty() && shown.get(i + 1) == Some(&(row + 1)) {
+            let (x, y1, y2, bend) = (arrow_x, y - 4, y - 4 + SVG_ROW_HEIGHT, arrow_x + 24);
+            let title = xml_escape(&transitions.join("; "));
+            let _ = write!(svg, r#"<path class="transition" d="M{x},{y1} C{bend},{y1} {bend},{y2} {x},{y2}" fill="none" "#);
+            let _ = writeln!(svg, r##"stroke="#4CAF50" marker-end="url(#arrow)"><title>{title}</title></path>"##);
+        }
+        line += 1;
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// `text` with the characters XML reserves replaced by entities.
+fn xml_escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Rows the terminal table shows by default, see trace_table.
+pub const TABLE_MAX_ROWS: usize = 20;
+
+/// The trace of `vis` as an aligned text table: a `step` column and one column per trace column, values
+/// right-justified. Of a trace longer than `max_rows` only the first and the last rows are shown, with a line
+/// saying how many are left out. Columns that do not fit into `max_width` characters are left out too, and
+/// overlong values are cut short with `…`. Values are written in `vis.repr`, `13 (0x0000000d)` for both.
+pub fn trace_table(vis: &VisData, max_rows: usize, max_width: usize) -> String {
+    const GAP: &str = "  ";
+    const CELL_MAX: usize = 24;
+
+    let height = vis.trace.len();
+    let shown = head_and_tail(height, max_rows);
+    let clip = |text: &str| -> String {
+        if text.chars().count() <= CELL_MAX {
+            return text.to_string();
+        }
+        text.chars().take(CELL_MAX - 1).chain(['…']).collect()
+    };
+    let mut header = vec!["step".to_string()];
+    header.extend(vis.columns.iter().map(|c| clip(&c.name)));
+    let nibbles = vis.hex_nibbles();
+    let cell = |v: &str| clip(&Cell::with_repr(v, vis.repr, nibbles).to_string());
+    let rows: Vec<Vec<String>> = shown
+        .iter()
+        .map(|&row| std::iter::once(row.to_string()).chain(vis.trace[row].iter().map(|v| cell(v))).collect())
+        .collect();
+    let widths: Vec<usize> = (0..header.len())
+        .map(|col| rows.iter().map(|r| r[col].chars().count()).chain([header[col].chars().count()]).max().unwrap_or(0))
+        .collect();
+
+    // As many columns as fit, leaving room for the note about the rest
+    let more = |hidden: usize| format!("{GAP}… {hidden} more columns");
+    let mut fitting = header.len();
+    while fitting > 1 {
+        let used: usize = widths[..fitting].iter().sum::<usize>() + GAP.len() * (fitting - 1);
+        let note = if fitting < header.len() { more(header.len() - fitting).chars().count() } else { 0 };
+        if used + note <= max_width {
+            break;
+        }
+        fitting -= 1;
+    }
+
+    let line = |cells: &[String]| {
+        let padded: Vec<String> = cells[..fitting].iter().zip(&widths).map(|(cell, &w)| format!("{cell:>w$}")).collect();
+        let mut line = padded.join(GAP);
+        if fitting < header.len() {
+            line.push_str(&more(header.len() - fitting));
+        }
+        line
+    };
+    let mut lines = vec![line(&header), line(&widths.iter().map(|&w| "-".repeat(w)).collect::<Vec<_>>())];
+    for (i, cells) in rows.iter().enumerate() {
+        if i > 0 && shown[i - 1] + 1 != shown[i] {
+            lines.push(format!("… {} rows omitted …", shown[i] - shown[i - 1] - 1));
+        }
+        lines.push(line(cells));
+    }
+    lines.iter().map(|line| format!("{line}\n")).collect()
+}
+
+// The rows of a `height`-row trace a view of at most `max_rows` rows shows: all of them, or the first and the
+// last ones.
+fn head_and_tail(height: usize, max_rows: usize) -> Vec<usize> {
+    if height <= max_rows {
+        return (0..height).collect();
+    }
+    let head = max_rows.div_ceil(2);
+    (0..head).chain(height - (max_rows - head)..height).collect()
+}
+
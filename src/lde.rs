@@ -0,0 +1,167 @@
+//! The low-degree extension of the trace columns (`--export-lde`), for the visualizer to show the blowup step:
+//! every column's interpolating polynomial evaluated over a domain 2^log_blowup times larger than the trace's,
+//! with the FFTs the PCSs are built on.
+//!
+//! Mersenne31 goes through the circle PCS's own extrapolation, onto the standard circle domain its commitment is
+//! over. Standard circle domains of different sizes have no point in common, so there no LDE row is a trace row.
+//! BabyBear and KoalaBear are extended over the two-adic subgroup of that size, which contains the trace's
+//! subgroup: every 2^log_blowup-th LDE row is a trace row. Their PCS commits to the same polynomials over a coset
+//! of this subgroup instead, shifted off the trace's points.
+//!
+//! selector_lde evaluates uni-stark's row selectors over the same points, the polynomials the constraints are
+//! multiplied by: on the trace's domain they are nonzero exactly on the rows a constraint applies to.
+
+#[cfg(feature = "babybear")]
+use p3_baby_bear::BabyBear;
+use p3_circle::{CircleDomain, CircleEvaluations};
+use p3_commit::PolynomialSpace;
+#[cfg(any(feature = "babybear", feature = "koalabear"))]
+use p3_dft::{Radix2DitParallel, TwoAdicSubgroupDft};
+#[cfg(any(feature = "babybear", feature = "koalabear"))]
+use p3_field::TwoAdicField;
+use p3_field::{PrimeCharacteristicRing, PrimeField32};
+#[cfg(feature = "koalabear")]
+use p3_koala_bear::KoalaBear;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_mersenne_31::Mersenne31;
+use p3_util::log2_strict_usize;
+
+use crate::error::FibVisError;
+use crate::export::Lde;
+use crate::stark_config::{require_backend, FieldChoice, HashChoice};
+
+/// The LDE of `trace` over `field` at `log_blowup`. The trace is read as canonical values, so it can come from any
+/// field type with the same elements. Its height has to be a power of two, as for proving.
+pub fn trace_lde<F: PrimeField32>(
+    field: FieldChoice,
+    trace: &RowMajorMatrix<F>,
+    log_blowup: usize,
+) -> Result<Lde, FibVisError> {
+    let height = trace.height();
+    if !height.is_power_of_two() {
+        return Err(FibVisError::Config(format!("the LDE needs a power-of-two trace height, not {height}")));
+    }
+    let (rows, trace_rows) = match field {
+        FieldChoice::Mersenne31 => {
+            // The same domain only when there is no blowup
+            let trace_rows = if log_blowup == 0 { (0..height).map(|row| (row, row)).collect() } else { Vec::new() };
+            (decimal_rows(&circle_lde(convert::<_, Mersenne31>(trace), log_blowup)), trace_rows)
+        }
+        #[cfg(feature = "babybear")]
+        FieldChoice::BabyBear => {
+            (decimal_rows(&two_adic_lde(convert::<_, BabyBear>(trace), log_blowup)), embedded_rows(height, log_blowup))
+        }
+        #[cfg(feature = "koalabear")]
+        FieldChoice::KoalaBear => {
+            (decimal_rows(&two_adic_lde(convert::<_, KoalaBear>(trace), log_blowup)), embedded_rows(height, log_blowup))
+        }
+        #[allow(unreachable_patterns)]
+        _ => {
+            require_backend(field, HashChoice::Keccak256)?;
+            return Err(FibVisError::Config(format!("no LDE for {}", field.name())));
+        }
+    };
+    Ok(Lde { log_blowup, rows, trace_rows })
+}
+
+/// The row selectors of a trace with `height` rows over `field`, evaluated over the LDE domain of trace_lde at
+/// `log_blowup`: per LDE row one value per kind of Selectors::KINDS, in decimal, always 1 for the constraints on
+/// every row. The first- and last-row selectors are uni-stark's unnormalized Lagrange polynomials, the vanishing
+/// polynomial divided by that row's linear factor, and the transition selector is the linear factor of the last
+/// row. The circle PCS's domains have no point in common, so they are evaluated off the trace; that needs a blowup.
+pub fn selector_lde(field: FieldChoice, height: usize, log_blowup: usize) -> Result<Vec<Vec<String>>, FibVisError> {
+    if !height.is_power_of_two() || log_blowup == 0 {
+        return Err(FibVisError::Config(format!(
+            "the selectors' LDE needs a power-of-two trace height and a blowup, not {height} rows at log_blowup \
+             {log_blowup}"
+        )));
+    }
+    let log_n = log2_strict_usize(height);
+    match field {
+        FieldChoice::Mersenne31 => Ok(decimal_selectors(circle_selectors(log_n, log_blowup))),
+        #[cfg(feature = "babybear")]
+        FieldChoice::BabyBear => Ok(decimal_selectors(two_adic_selectors::<BabyBear>(log_n, log_blowup))),
+        #[cfg(feature = "koalabear")]
+        FieldChoice::KoalaBear => Ok(decimal_selectors(two_adic_selectors::<KoalaBear>(log_n, log_blowup))),
+        #[allow(unreachable_patterns)]
+        _ => {
+            require_backend(field, HashChoice::Keccak256)?;
+            Err(FibVisError::Config(format!("no selector LDE for {}", field.name())))
+        }
+    }
+}
+
+// First row, transition and last row per point, with the constant of the constraints on every row before them.
+fn decimal_selectors<F: PrimeField32>(points: Vec<[F; 3]>) -> Vec<Vec<String>> {
+    points.iter().map(|point| [F::ONE].iter().chain(point).map(|v| v.to_string()).collect()).collect()
+}
+
+// The standard circle domain 2^log_blowup times larger, walked in its natural order, where none of the points is
+// one of the trace's.
+fn circle_selectors(log_n: usize, log_blowup: usize) -> Vec<[Mersenne31; 3]> {
+    let trace_domain = CircleDomain::<Mersenne31>::standard(log_n);
+    let lde_domain = CircleDomain::<Mersenne31>::standard(log_n + log_blowup);
+    std::iter::successors(Some(lde_domain.first_point()), |&x| lde_domain.next_point(x))
+        .take(1 << (log_n + log_blowup))
+        .map(|x| {
+            let selectors = trace_domain.selectors_at_point(x);
+            [selectors.is_first_row, selectors.is_transition, selectors.is_last_row]
+        })
+        .collect()
+}
+
+// The subgroup of order 2^(log_n + log_blowup) in order of the powers of its generator, as for two_adic_lde. Every
+// 2^log_blowup-th point is the trace's, where the quotients are 0/0 and take the polynomials' values instead:
+// (x^n - 1) / (x - a) is n * a^(n - 1) at x = a.
+#[cfg(any(feature = "babybear", feature = "koalabear"))]
+fn two_adic_selectors<F: TwoAdicField>(log_n: usize, log_blowup: usize) -> Vec<[F; 3]> {
+    let n = F::from_u64(1 << log_n);
+    let generator = F::two_adic_generator(log_n + log_blowup);
+    let last = F::two_adic_generator(log_n).inverse();
+    let lagrange = |x: F, a: F| {
+        if x == a {
+            n * a.exp_u64((1 << log_n) - 1)
+        } else {
+            (x.exp_power_of_2(log_n) - F::ONE) / (x - a)
+        }
+    };
+    generator
+        .powers()
+        .take(1 << (log_n + log_blowup))
+        .map(|x| [lagrange(x, F::ONE), x - last, lagrange(x, last)])
+        .collect()
+}
+
+fn convert<F: PrimeField32, G: PrimeField32>(trace: &RowMajorMatrix<F>) -> RowMajorMatrix<G> {
+    RowMajorMatrix::new(trace.values.iter().map(|v| G::from_u32(v.as_canonical_u32())).collect(), trace.width())
+}
+
+fn decimal_rows<F: PrimeField32>(lde: &RowMajorMatrix<F>) -> Vec<Vec<String>> {
+    lde.values.chunks(lde.width()).map(|row| row.iter().map(|v| v.to_string()).collect()).collect()
+}
+
+// The columns extrapolated from the standard circle domain of the trace onto the one 2^log_blowup times larger,
+// both in natural order.
+fn circle_lde(trace: RowMajorMatrix<Mersenne31>, log_blowup: usize) -> RowMajorMatrix<Mersenne31> {
+    let log_n = log2_strict_usize(trace.height());
+    CircleEvaluations::from_natural_order(CircleDomain::standard(log_n), trace)
+        .extrapolate(CircleDomain::standard(log_n + log_blowup))
+        .to_natural_order()
+        .to_row_major_matrix()
+}
+
+// The columns evaluated over the subgroup of order height * 2^log_blowup, in order of the powers of its generator.
+#[cfg(any(feature = "babybear", feature = "koalabear"))]
+fn two_adic_lde<F: TwoAdicField>(trace: RowMajorMatrix<F>, log_blowup: usize) -> RowMajorMatrix<F>
+where
+    Radix2DitParallel<F>: Default,
+{
+    Radix2DitParallel::default().lde_batch(trace, log_blowup).to_row_major_matrix()
+}
+
+// The generator of the larger subgroup to the power 2^log_blowup generates the trace's.
+#[cfg(any(feature = "babybear", feature = "koalabear"))]
+fn embedded_rows(height: usize, log_blowup: usize) -> Vec<(usize, usize)> {
+    (0..height).map(|row| (row << log_blowup, row)).collect()
+}
@@ -0,0 +1,67 @@
+//! A Fibonacci AIR proven with Plonky3, and the trace export read by the web visualizer.
+//!
+//! The `plonky3_fibonacci` binary is a command line wrapper around these modules; tests and other binaries
+//! can generate, prove and export a trace with them directly:
+//!
+//! ```no_run
+//! use plonky3_fibonacci::{prove_fibonacci, verify_fibonacci, FibonacciParams};
+//!
+//! let params = FibonacciParams::new(64);
+//! let run = prove_fibonacci(params.clone()).unwrap();
+//! verify_fibonacci(&params, &run.proof).unwrap();
+//! println!("{} proof bytes, trace ends in {}", run.proof.len(), run.vis_data.final_value);
+//! ```
+//!
+//! The library also builds for `wasm32-unknown-unknown`, so the trace and its `VisData` can be produced in the
+//! browser next to the visualizer: there, `export::trace_json_string` replaces the file writers, and the
+//! proving API (`prove`) and the progress output are native-only. With the `wasm` feature, `wasm` wraps trace
+//! generation and the constraint check in wasm-bindgen functions for the page to call.
+
+pub mod air;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod assets;
+pub mod compare;
+pub mod constraints;
+pub mod error;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod events;
+pub mod export;
+pub mod lde;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod memory;
+pub mod merkle;
+pub mod prelude;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod profile;
+pub mod progress;
+pub mod proof_file;
+pub mod proof_inspect;
+pub mod proof_json;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod prove;
+pub mod quotient;
+pub mod registry;
+pub mod sequence;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod serve;
+pub mod stark_config;
+pub mod trace;
+pub mod transcript;
+pub mod typescript;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use air::{
+    BitDecompAir, CollatzAir, CounterAir, FibonacciAir, FibonacciWithIndexAir, IndexedFibonacciAir, LinearRecurrenceAir,
+    LucasAir, MulFibonacciAir, SequenceAir, TribonacciAir,
+};
+pub use error::{ExportError, FibVisError, VerifyFailure};
+pub use export::VisData;
+#[cfg(not(target_arch = "wasm32"))]
+pub use prove::{prove_fibonacci, verify_fibonacci, FibonacciParams, FibonacciRun, ProveError};
+pub use sequence::Sequence;
+pub use trace::{
+    generate_bit_decomp_trace, generate_collatz_trace, generate_counter_trace, generate_fibonacci_trace,
+    generate_fibonacci_with_index_trace, generate_linear_recurrence_trace, generate_lucas_trace,
+    generate_mul_fibonacci_trace, generate_padded_fibonacci_trace, generate_tribonacci_trace,
+};
@@ -1,175 +1,2462 @@
-use std::fmt::Debug;
-use std::marker::PhantomData;
-
-use p3_air::{Air, AirBuilder, BaseAir};
-use p3_field::{Field, PrimeCharacteristicRing};
+use p3_air::BaseAir;
+use p3_field::{Field, PrimeCharacteristicRing, PrimeField32};
 use p3_matrix::Matrix;
 use p3_matrix::dense::RowMajorMatrix;
 
-use p3_challenger::{HashChallenger, SerializingChallenger32};
-use p3_circle::CirclePcs;
-use p3_commit::ExtensionMmcs;
-use p3_field::extension::BinomialExtensionField;
-use p3_fri::FriConfig;
+#[cfg(feature = "babybear")]
+use p3_baby_bear::BabyBear;
+#[cfg(all(feature = "babybear", feature = "poseidon2"))]
+use p3_baby_bear::Poseidon2BabyBear;
+#[cfg(feature = "blake3")]
+use p3_blake3::Blake3;
+#[cfg(any(feature = "babybear", feature = "koalabear"))]
 use p3_keccak::Keccak256Hash;
-use p3_merkle_tree::MerkleTreeMmcs;
+#[cfg(feature = "koalabear")]
+use p3_koala_bear::KoalaBear;
+#[cfg(all(feature = "koalabear", feature = "poseidon2"))]
+use p3_koala_bear::Poseidon2KoalaBear;
 use p3_mersenne_31::Mersenne31;
-use p3_symmetric::{CompressionFunctionFromHasher, SerializingHasher};
-use p3_uni_stark::{prove, verify, StarkConfig};
+#[cfg(feature = "poseidon2")]
+use p3_mersenne_31::Poseidon2Mersenne31;
+use p3_uni_stark::{PcsProverData, Proof, StarkGenericConfig, Val};
+use num_bigint::BigUint;
 use tracing_forest::util::LevelFilter;
 use tracing_forest::ForestLayer;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{EnvFilter, Registry};
 
+use tracing::{field, info_span, Span};
+use tracing_forest::Printer;
+
+use clap::{Args, Parser, Subcommand};
+#[cfg(feature = "poseidon2")]
+use rand::rngs::StdRng;
+#[cfg(feature = "poseidon2")]
+use rand::SeedableRng;
+
+use plonky3_fibonacci::assets::{write_assets, AssetStatus};
+use plonky3_fibonacci::compare::{Comparison, RunStats};
+use plonky3_fibonacci::constraints::{
+    constraint_report, evaluate_and_find_violations, extract_constraints, find_violations, ConstraintReport, Violation,
+};
+use plonky3_fibonacci::events::{EventLog, PipelineEvents};
+use plonky3_fibonacci::export::{
+    column_names, load_vis_data, proof_digest, render_svg, trace_table, vis_data_from_matrix, vis_data_schema,
+    write_trace, write_trace_bin, write_trace_bundle, write_trace_csv, write_trace_gz, write_trace_js, write_trace_json,
+    write_trace_ndjson, ColumnMeta, FieldInfo, MemoryStats, ProofStats, Timings, TraceBundle, TraceFormat, ValueRepr,
+    Verification, JSON_ROW_LIMIT, SVG_MAX_ROWS, TABLE_MAX_ROWS,
+};
+use plonky3_fibonacci::lde::{selector_lde, trace_lde};
+use plonky3_fibonacci::memory::{self, CountingAllocator};
+use plonky3_fibonacci::merkle::{merkle_tree, write_merkle_json, MerkleTreeExport, MERKLE_MAX_ROWS};
+use plonky3_fibonacci::profile::chrome_trace;
+use plonky3_fibonacci::progress::{PhaseProgress, PROGRESS_AUTO_ROWS};
+use plonky3_fibonacci::stark_config::{parse_field_element, parse_seed, require_backend, ConfigBuilder, FieldChoice, FriParams, HashChoice, Seed};
+#[cfg(any(feature = "blake3", feature = "poseidon2"))]
+use plonky3_fibonacci::stark_config::circle_config;
+#[cfg(any(feature = "babybear", feature = "koalabear", feature = "blake3"))]
+use plonky3_fibonacci::stark_config::{byte_hash_challenger, byte_hash_mmcs};
+#[cfg(feature = "poseidon2")]
+use plonky3_fibonacci::stark_config::{poseidon2_challenger, poseidon2_mmcs, POSEIDON2_SEED};
+#[cfg(any(feature = "babybear", feature = "koalabear"))]
+use plonky3_fibonacci::stark_config::two_adic_config;
+#[cfg(feature = "babybear")]
+use plonky3_fibonacci::stark_config::BabyBearChallenge;
+#[cfg(feature = "koalabear")]
+use plonky3_fibonacci::stark_config::KoalaBearChallenge;
+use plonky3_fibonacci::proof_file::{ProofFile, ProofHeader};
+use plonky3_fibonacci::proof_inspect::{inspect_proof, ProofInspection, ProofSection};
+use plonky3_fibonacci::proof_json::{
+    commitment_roots, fri_queries, fri_rounds, pow_grinding, proof_to_json, write_proof_json, CommitPhase,
+    CommitmentRoot, ProofExport,
+};
+use plonky3_fibonacci::prove::{elapsed_ms, panic_message};
+use plonky3_fibonacci::quotient::{ood_opening, quotient_opening};
+use plonky3_fibonacci::registry::{DemoAir, DemoParams, LinearRecurrenceDemo, DEMOS};
+use plonky3_fibonacci::sequence::Sequence;
+use plonky3_fibonacci::serve::{ShutdownHandle, StaticServer, DEFAULT_MAX_STEPS, DEFAULT_PORT};
+use plonky3_fibonacci::trace::{check_final_value, check_num_steps, check_trace_shape, FIBONACCI_START};
+use plonky3_fibonacci::transcript::{
+    write_transcript_json, RecordingChallenger, RecordsTranscript, TranscriptEvent, TranscriptExport,
+};
+use plonky3_fibonacci::typescript::vis_data_typescript;
+use plonky3_fibonacci::{FibVisError, SequenceAir, VisData};
+
 
 
 //-----------------------------------------------------------
 // Importing for File Handling, Writing, and Serialization for the Visualizer
-use serde::Serialize;
-use std::fs::File;
-use std::io::Write;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::process::ExitCode;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+// Counts every allocation, so --track-memory can report the heap usage; without the flag only the byte count is kept.
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// Set by --json: stdout then carries only the final JSON report.
+static JSON_MODE: AtomicBool = AtomicBool::new(false);
+
+// Human-readable status lines, printed to stdout normally and to stderr in --json mode.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if JSON_MODE.load(Ordering::Relaxed) {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+// Errors end up in the --json report as their display string.
+fn serialize_error<S: serde::Serializer>(error: &Option<FibVisError>, serializer: S) -> Result<S::Ok, S::Error> {
+    match error {
+        Some(e) => serializer.serialize_some(&e.to_string()),
+        None => serializer.serialize_none(),
+    }
+}
+//-----------------------------------------------------------
+
+
+//-----------------------------------------------------------
+// Command line arguments. Every run parameter can also come from a TOML file (`--config run.toml`);
+// flags given on the command line override the file, and anything missing from both falls back to the
+// defaults of the original hardcoded run (8 steps, final value 21, Mersenne31 + Keccak256).
+// Running without a subcommand keeps the original behavior: export the trace, prove, and verify.
+#[derive(Parser, Debug)]
+#[command(about = "Prove a Fibonacci AIR with Plonky3 and export its trace for the visualizer")]
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    params: FibArgs,
+
+    /// Run the full pipeline once per listed num_steps, e.g. `--sweep 8,16,32`, and write sweep_summary.json
+    #[arg(long, value_delimiter = ',', conflicts_with = "sweep_pow2")]
+    sweep: Option<Vec<usize>>,
+
+    /// Like --sweep over the powers of two 2^a..=2^b, e.g. `--sweep-pow2 3..12`
+    #[arg(long, value_parser = parse_pow2_range)]
+    sweep_pow2: Option<Vec<usize>>,
+
+    /// Keep running and re-export the trace whenever the --config file changes
+    #[arg(long, requires = "config", conflicts_with_all = ["sweep", "sweep_pow2"])]
+    watch: bool,
+
+    /// In --watch mode, also prove and verify after every change
+    #[arg(long, requires = "watch")]
+    prove_on_change: bool,
+
+    /// Write the trace, proof and meta.json into a fresh <DIR>/<timestamp>-<num_steps>/ and point <DIR>/latest at it
+    #[arg(long, value_name = "DIR", num_args = 0..=1, default_missing_value = "web/runs",
+          conflicts_with_all = ["sweep", "sweep_pow2", "watch"])]
+    run_dir: Option<PathBuf>,
+
+    /// Allow --run-dir to overwrite an existing, non-empty run directory
+    #[arg(long, requires = "run_dir")]
+    force: bool,
+
+    /// Let a JSON export overwrite the index.html and viewer.js next to it, which it otherwise only writes into a
+    /// directory without an index.html
+    #[arg(long)]
+    force_assets: bool,
+
+    /// Print the demo AIRs --sequence and --recurrence select from and exit
+    #[arg(long)]
+    list_airs: bool,
+
+    /// Write TypeScript declarations of the trace export (VisData) to this file, `-` for stdout, and exit
+    #[arg(long, value_name = "FILE")]
+    emit_ts: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Generate the trace and write the visualizer JSON without running the prover
+    ExportTrace {
+        #[command(flatten)]
+        params: FibArgs,
+    },
+    /// Generate the trace, prove it, and save the proof to --proof (proof.bin)
+    Prove {
+        #[command(flatten)]
+        params: FibArgs,
+    },
+    /// Load a saved proof (--proof, proof.bin) and verify it against the AIR described by the flags
+    Verify {
+        #[command(flatten)]
+        params: FibArgs,
+    },
+    /// Load an export (JSON, MessagePack or CBOR, plain or gzipped) and print what it holds with a few rows
+    Inspect {
+        /// The export to read, e.g. web/trace_data.json
+        file: PathBuf,
+
+        /// Rows to show, the first and the last ones [default: 6]
+        #[arg(long, default_value_t = INSPECT_ROWS)]
+        rows: usize,
+    },
+    /// Load a saved proof and print how it is made up: FRI rounds, queries, opened values, commitments, the
+    /// proof-of-work witness and the bytes of each part
+    InspectProof {
+        /// The proof file, e.g. proof.bin
+        path: PathBuf,
+
+        /// Print it as JSON instead of a tree
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the JSON Schema of the trace export (web/trace_data.json), or write it to --output
+    Schema {
+        /// File to write the schema to instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Prove two or more runs and write their stats side by side, with each one's difference to the first
+    Compare {
+        /// TOML run configs, one run each, labelled by file name
+        configs: Vec<PathBuf>,
+
+        /// A run given by its flags instead, e.g. `--run "--hash poseidon2"`, labelled by them; repeatable
+        #[arg(long = "run", value_name = "FLAGS", allow_hyphen_values = true)]
+        runs: Vec<String>,
+
+        /// Where to write the comparison
+        #[arg(long, default_value = "web/comparison.json")]
+        output: PathBuf,
+    },
+    /// Serve the visualizer's directory over HTTP on localhost, for the page to fetch the exports, until Ctrl+C
+    Serve {
+        /// Directory to serve
+        #[arg(long, default_value = "web")]
+        dir: PathBuf,
+
+        /// Port to listen on, 0 for any free one
+        #[arg(long, default_value_t = DEFAULT_PORT)]
+        port: u16,
+
+        /// Export the trace with the flags below while serving, as export-trace does, streaming the run's
+        /// progress on /events
+        #[arg(long)]
+        regenerate: bool,
+
+        /// With --regenerate, also prove and verify the trace, as a run without a subcommand does
+        #[arg(long, requires = "regenerate")]
+        prove: bool,
+
+        /// The most steps a page may ask to have proven on /api/prove
+        #[arg(long, default_value_t = DEFAULT_MAX_STEPS)]
+        max_steps: usize,
+
+        #[command(flatten)]
+        params: FibArgs,
+    },
+}
+
+// Parameters shared by every subcommand. All of them are optional so that we can tell which ones
+// were given explicitly and should override the config file.
+#[derive(Args, Debug, Clone)]
+struct FibArgs {
+    /// TOML file describing the run, overridden by any flags given alongside it
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Print the effective (merged) configuration as TOML and exit
+    #[arg(long)]
+    print_config: bool,
+
+    /// Number of Fibonacci steps (trace rows); other sequences than fibonacci need a power of two [default: 8]
+    #[arg(long, value_parser = parse_num_steps)]
+    num_steps: Option<usize>,
+
+    /// Expected final value, a field element in decimal or 0x-prefixed hex, checked against the last row of the trace [default: computed from --num-steps]
+    #[arg(long, value_parser = parse_field_element)]
+    final_value: Option<u64>,
+
+    /// First row of the trace as `a0,a1`, e.g. `--initial 2,1` for the Lucas numbers, the seed of
+    /// --sequence collatz, or one value per --recurrence coefficient [default: 0,1]
+    #[arg(long, value_parser = parse_values)]
+    initial: Option<Vec<u64>>,
+
+    /// Prove the linear recurrence x(n) = c1 x(n-1) + ... + ck x(n-k) instead of a named sequence, given as
+    /// `c1,...,ck`, e.g. `--recurrence 2,1 --initial 0,1` for the Pell numbers
+    #[arg(long, value_parser = parse_values)]
+    recurrence: Option<Vec<u64>>,
+
+    /// Recurrence to prove and export [default: fibonacci]
+    #[arg(long, value_enum)]
+    sequence: Option<Sequence>,
+
+    /// Prime field the trace and proof are computed over [default: mersenne31]
+    #[arg(long, value_enum)]
+    field: Option<FieldChoice>,
+
+    /// Hash used for the Merkle commitments and the Fiat-Shamir challenger [default: keccak256]
+    #[arg(long, value_enum)]
+    hash: Option<HashChoice>,
+
+    /// Log2 of the blowup factor of the low-degree extension [default: 1]
+    #[arg(long)]
+    log_blowup: Option<usize>,
+
+    /// Number of FRI query repetitions [default: 100]
+    #[arg(long)]
+    num_queries: Option<usize>,
+
+    /// Proof-of-work bits ground before sampling the query indices [default: 16]
+    #[arg(long)]
+    pow_bits: Option<usize>,
+
+    /// Log2 of the length of the final polynomial sent in the clear [default: 1]
+    #[arg(long)]
+    log_final_poly_len: Option<usize>,
+
+    /// Path of the trace export, parent directories are created as needed. Use `-` for stdout [default: web/trace_data.json]
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Format of the trace export, which also sets the output file's extension [default: json]
+    #[arg(long, value_enum)]
+    format: Option<TraceFormat>,
+
+    /// How the JSON, NDJSON and CSV exports and the printed table write trace cells [default: decimal]
+    #[arg(long, value_enum)]
+    repr: Option<ValueRepr>,
+
+    /// Trace rows above which a JSON export suggests --format ndjson instead [default: 262144]
+    #[arg(long)]
+    json_row_limit: Option<usize>,
+
+    /// Write --format js as an ES module, `export default {...}`, instead of setting window.TRACE_DATA
+    #[arg(long)]
+    esm: bool,
+
+    /// Gzip the trace export (json, ndjson, csv, cells or cells-ndjson) and append `.gz` to its file name
+    #[arg(long)]
+    compress: bool,
+
+    /// Also draw the trace as an SVG table next to the export, e.g. web/trace_data.svg
+    #[arg(long)]
+    svg: bool,
+
+    /// Rows the SVG draws at most, the first and the last ones [default: 32]
+    #[arg(long)]
+    svg_max_rows: Option<usize>,
+
+    /// Print the trace as a table; done anyway for traces of at most 16 rows
+    #[arg(long)]
+    print_trace: bool,
+
+    /// Rows the printed table shows at most, the first and the last ones [default: 20]
+    #[arg(long)]
+    print_rows: Option<usize>,
+
+    /// Proof file written by `prove` and the default run, and read by `verify` [default: proof.bin]
+    #[arg(long, visible_alias = "save-proof")]
+    proof: Option<PathBuf>,
+
+    /// Also write the proof as JSON for the visualizer, e.g. web/proof_data.json, with the public values and the
+    /// parameters it verifies against; gzipped with --compress
+    #[arg(long, value_name = "FILE")]
+    export_proof: Option<PathBuf>,
+
+    /// FRI queries the --export-proof JSON describes under `fri_queries` and `fri_rounds` at most [default: all]
+    #[arg(long, value_name = "N")]
+    max_queries_export: Option<usize>,
+
+    /// Also write the proof's Fiat-Shamir transcript, what the challenger observed and sampled in order, as JSON
+    /// for the visualizer, e.g. web/transcript.json; gzipped with --compress
+    #[arg(long, value_name = "FILE")]
+    export_transcript: Option<PathBuf>,
+
+    /// Also write the Merkle tree of the trace commitment as JSON for the visualizer, e.g. web/merkle_tree.json:
+    /// every level's digests up to the root, for traces of at most 2^12 rows
+    #[arg(long, value_name = "FILE")]
+    export_merkle: Option<PathBuf>,
+
+    /// Add the low-degree extension of the trace columns to the export as its `lde` block: every column evaluated
+    /// over the 2^log_blowup times larger domain, with the rows it shares with the trace flagged
+    #[arg(long)]
+    export_lde: bool,
+
+    /// Add every trace row with the row after it to the export as its `windows` block, the pairs the constraints
+    /// read; the trace then appears twice more
+    #[arg(long)]
+    export_windows: bool,
+
+    /// Add the trace cut into N steps of evenly spaced rows to the export as its `keyframes` block, for the
+    /// visualizer to replay it being computed; each row is in one step only
+    #[arg(long, value_name = "N")]
+    keyframes: Option<usize>,
+
+    /// Hex bytes fed into the Fiat-Shamir challenger before proving, for reproducible transcripts [default: empty]
+    #[arg(long, value_parser = parse_seed)]
+    seed: Option<Seed>,
+
+    /// Overwrite a trace cell before proving, as `<row>:<col>=<value>`; may be given several times
+    #[arg(long, value_parser = parse_tamper)]
+    tamper: Vec<Tamper>,
+
+    /// Also write bundle.json next to the export: the valid trace, a copy tampered with --tamper (or in one cell of
+    /// the middle row) and the constraints that copy breaks. Only the valid trace is proven
+    #[arg(long)]
+    with_invalid: bool,
+
+    /// Prove the trace without first checking it against the constraints, which stops a broken trace before any
+    /// commitment with the row it breaks; for watching the prover (or the verifier) reject it instead
+    #[arg(long)]
+    skip_precheck: bool,
+
+    /// Evaluate every constraint on the (tampered) trace and report the violated ones instead of proving
+    #[arg(long)]
+    check: bool,
+
+    /// Print a single JSON report of the run to stdout, sending all human-readable output to stderr
+    #[arg(long)]
+    json: bool,
+
+    /// Print per-phase progress and timings to stderr (on by default from 2^16 steps)
+    #[arg(long)]
+    progress: bool,
+
+    /// Write a timeline of the run's phase spans to this file as Chrome trace events, for Perfetto or about://tracing
+    #[arg(long, value_name = "FILE")]
+    profile: Option<PathBuf>,
+
+    /// Track heap usage: the peak and what each phase allocated, in the export's stats and the run summary
+    #[arg(long)]
+    track_memory: bool,
+}
+
+impl FibArgs {
+    // Loads the config file (if any), applies the flags on top of it and validates the result.
+    fn resolve(&self) -> Result<RunConfig, FibVisError> {
+        let mut config = match &self.config {
+            Some(path) => RunConfig::from_file(path)?,
+            None => RunConfig::default(),
+        };
+
+        if let Some(num_steps) = self.num_steps {
+            config.num_steps = num_steps;
+        }
+        if let Some(final_value) = self.final_value {
+            config.final_value = Some(final_value);
+        }
+        if let Some(initial) = &self.initial {
+            config.initial = initial.clone();
+        }
+        if let Some(recurrence) = &self.recurrence {
+            config.recurrence = recurrence.clone();
+        }
+        if let Some(sequence) = self.sequence {
+            config.sequence = sequence;
+        }
+        if let Some(field) = self.field {
+            config.field = field;
+        }
+        if let Some(hash) = self.hash {
+            config.hash = hash;
+        }
+        if let Some(log_blowup) = self.log_blowup {
+            config.fri.log_blowup = log_blowup;
+        }
+        if let Some(num_queries) = self.num_queries {
+            config.fri.num_queries = num_queries;
+        }
+        if let Some(pow_bits) = self.pow_bits {
+            config.fri.proof_of_work_bits = pow_bits;
+        }
+        if let Some(log_final_poly_len) = self.log_final_poly_len {
+            config.fri.log_final_poly_len = log_final_poly_len;
+        }
+        if let Some(output) = &self.output {
+            config.output = output.clone();
+        }
+        if let Some(format) = self.format {
+            config.format = format;
+        }
+        if let Some(repr) = self.repr {
+            config.repr = repr;
+        }
+        if let Some(json_row_limit) = self.json_row_limit {
+            config.json_row_limit = json_row_limit;
+        }
+        if self.esm {
+            config.esm = true;
+        }
+        if self.compress {
+            config.compress = true;
+        }
+        if self.svg {
+            config.svg = true;
+        }
+        if let Some(svg_max_rows) = self.svg_max_rows {
+            config.svg_max_rows = svg_max_rows;
+        }
+        if self.print_trace {
+            config.print_trace = true;
+        }
+        if let Some(print_rows) = self.print_rows {
+            config.print_rows = print_rows;
+        }
+        if let Some(proof) = &self.proof {
+            config.proof = proof.clone();
+        }
+        if let Some(export_proof) = &self.export_proof {
+            config.export_proof = Some(export_proof.clone());
+        }
+        if let Some(max_queries_export) = self.max_queries_export {
+            config.max_queries_export = Some(max_queries_export);
+        }
+        if let Some(export_transcript) = &self.export_transcript {
+            config.export_transcript = Some(export_transcript.clone());
+        }
+        if let Some(export_merkle) = &self.export_merkle {
+            config.export_merkle = Some(export_merkle.clone());
+        }
+        if self.export_lde {
+            config.export_lde = true;
+        }
+        if self.export_windows {
+            config.export_windows = true;
+        }
+        if let Some(keyframes) = self.keyframes {
+            config.keyframes = Some(keyframes);
+        }
+        if let Some(seed) = &self.seed {
+            config.seed = seed.clone();
+        }
+        if !self.tamper.is_empty() {
+            config.tamper = self.tamper.clone();
+        }
+        if self.with_invalid {
+            config.with_invalid = true;
+        }
+        if self.skip_precheck {
+            config.skip_precheck = true;
+        }
+
+        config.validate()?;
+        if config.output != Path::new("-") {
+            config.output = config.export_path(&config.output);
+        }
+        for path in config.export_proof.iter_mut().chain(config.export_transcript.iter_mut()) {
+            if config.compress && !path.extension().is_some_and(|ext| ext == "gz") {
+                path.as_mut_os_string().push(".gz");
+            }
+        }
+        if config.final_value.is_none() {
+            let value = config.final_value();
+            status!(
+                "No final value given, using {value} (the last value of a {}-step {} trace over {})",
+                config.num_steps,
+                config.name(),
+                config.field.name()
+            );
+            config.final_value = Some(value);
+        }
+        Ok(config)
+    }
+}
+
+// Other step counts than powers of two are checked against the sequence later, see DemoAir::check_padding.
+fn parse_num_steps(s: &str) -> Result<usize, String> {
+    let n: usize = s.parse().map_err(|_| format!("`{s}` is not a valid number of steps"))?;
+    check_num_steps(n)?;
+    Ok(n)
+}
+
+// Parses comma-separated field elements such as `5,8` or `0x10,1`, for the first row of the trace and the
+// recurrence coefficients. How many are needed is checked in RunConfig::validate.
+fn parse_values(s: &str) -> Result<Vec<u64>, String> {
+    s.split(',').map(|v| parse_field_element(v.trim())).collect()
+}
+
+// Parses `a..b` into the sizes 2^a, 2^(a+1), ..., 2^b.
+fn parse_pow2_range(s: &str) -> Result<Vec<usize>, String> {
+    let (lo, hi) = s.split_once("..").ok_or_else(|| format!("expected a range like 3..12, got `{s}`"))?;
+    let lo: u32 = lo.trim().parse().map_err(|_| format!("invalid range start `{lo}`"))?;
+    let hi: u32 = hi.trim().parse().map_err(|_| format!("invalid range end `{hi}`"))?;
+    if lo > hi || hi >= usize::BITS {
+        return Err(format!("invalid exponent range {lo}..{hi}"));
+    }
+    Ok((lo..=hi).map(|k| 1usize << k).collect())
+}
+//-----------------------------------------------------------
+
+
+//-----------------------------------------------------------
+// The full description of a run, as read from a TOML file such as:
+//
+//     num_steps = 64
+//     field = "babybear"
+//     hash = "poseidon2"
+//     output = "web/trace_data.json"
+//
+//     [fri]
+//     num_queries = 50
+//
+// Missing keys take the defaults below.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+struct RunConfig {
+    num_steps: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    final_value: Option<u64>, // computed from num_steps, initial and field when left out
+    initial: Vec<u64>,        // first row of the trace, written `initial = [5, 8]`
+    sequence: Sequence,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    recurrence: Vec<u64>,     // coefficients of a linear recurrence proven instead of `sequence`
+    field: FieldChoice,
+    hash: HashChoice,
+    fri: FriParams,
+    output: PathBuf,
+    format: TraceFormat,
+    repr: ValueRepr,          // trace cells as decimal, hex or both in the text formats
+    json_row_limit: usize,    // a JSON export of more rows suggests NDJSON
+    esm: bool,                // --format js as an ES module
+    compress: bool,           // gzip the export, which then ends in .gz
+    svg: bool,                // draw the trace as an SVG next to the export
+    svg_max_rows: usize,
+    print_trace: bool,        // print the trace as a table, which tiny traces get anyway
+    print_rows: usize,
+    proof: PathBuf,
+    export_proof: Option<PathBuf>,
+    max_queries_export: Option<usize>,
+    export_transcript: Option<PathBuf>,
+    export_merkle: Option<PathBuf>,
+    export_lde: bool,         // add the trace's low-degree extension, 2^log_blowup times the trace's size
+    export_windows: bool,     // add every row's (local, next) window, twice the trace's size
+    keyframes: Option<usize>, // add the trace in this many steps for replaying, once more the trace's size
+    seed: Seed,
+    tamper: Vec<Tamper>,
+    with_invalid: bool,       // bundle the valid trace with a tampered copy, proving only the valid one
+    skip_precheck: bool,      // prove without checking the trace against the constraints first
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        Self {
+            num_steps: 8,
+            final_value: None,
+            initial: vec![u64::from(FIBONACCI_START.0), u64::from(FIBONACCI_START.1)],
+            sequence: Sequence::Fibonacci,
+            recurrence: Vec::new(),
+            field: FieldChoice::Mersenne31,
+            hash: HashChoice::Keccak256,
+            fri: FriParams::default(),
+            output: PathBuf::from("web/trace_data.json"),
+            format: TraceFormat::Json,
+            repr: ValueRepr::Decimal,
+            json_row_limit: JSON_ROW_LIMIT,
+            esm: false,
+            compress: false,
+            svg: false,
+            svg_max_rows: SVG_MAX_ROWS,
+            print_trace: false,
+            print_rows: TABLE_MAX_ROWS,
+            proof: PathBuf::from("proof.bin"),
+            export_proof: None,
+            max_queries_export: None,
+            export_transcript: None,
+            export_merkle: None,
+            export_lde: false,
+            export_windows: false,
+            keyframes: None,
+            seed: Seed::default(),
+            tamper: Vec::new(),
+            with_invalid: false,
+            skip_precheck: false,
+        }
+    }
+}
+
+impl RunConfig {
+    // TOML, or JSON with the same keys when the file ends in `.json`.
+    fn from_file(path: &Path) -> Result<Self, FibVisError> {
+        let text = std::fs::read_to_string(path).map_err(|e| FibVisError::io(path, e))?;
+        if path.extension().is_some_and(|ext| ext == "json") {
+            return serde_json::from_str(&text).map_err(|e| FibVisError::Config(format!("{}: {e}", path.display())));
+        }
+        toml::from_str(&text).map_err(|e| FibVisError::Config(format!("{}: {e}", path.display())))
+    }
+
+    // The claimed final value, falling back to the one the trace actually ends with. resolve() fills it in,
+    // the fallback only matters for configs built in code such as the per-size copies of a sweep.
+    fn final_value(&self) -> u64 {
+        self.final_value.unwrap_or_else(|| self.demo().final_value(self.field, &self.demo_params()))
+    }
 
-// Macro for implementing the Serialize and Clone traits
-#[derive(Serialize, Clone)]
+    fn is_recurrence(&self) -> bool {
+        !self.recurrence.is_empty()
+    }
 
-// VisData acts as the data container to serialize
-struct VisData {
-    num_steps: usize,           // unsigned int, number of fibonacci steps
-    trace: Vec<Vec<String>>,    // vector of vectors (matrix) of type String
+    // The registry demo to run: the linear recurrence with --recurrence, the named --sequence otherwise. The
+    // methods below go through it, so the pipeline knows no demo in particular.
+    fn demo(&self) -> &'static dyn DemoAir {
+        if self.is_recurrence() {
+            &LinearRecurrenceDemo
+        } else {
+            self.sequence.demo()
+        }
+    }
+
+    fn demo_params(&self) -> DemoParams<'_> {
+        DemoParams { num_steps: self.num_steps, initial: &self.initial, recurrence: &self.recurrence }
+    }
+
+    fn name(&self) -> &'static str {
+        self.demo().label()
+    }
+
+    fn air(&self) -> SequenceAir {
+        self.demo().air(&self.demo_params())
+    }
+
+    // The "generate trace" phase, which records the trace's height for the pipeline events
+    fn generate_trace<F: Field>(&self) -> RowMajorMatrix<F> {
+        info_span!("generate trace", rows = field::Empty).in_scope(|| {
+            let trace = self.demo().build_trace(&self.demo_params());
+            Span::current().record("rows", trace.height());
+            trace
+        })
+    }
+
+    fn columns(&self) -> Vec<ColumnMeta> {
+        self.demo().columns(&self.demo_params())
+    }
+
+    fn true_values(&self) -> Vec<Vec<BigUint>> {
+        self.demo().true_values(&self.demo_params())
+    }
+
+    // `path` with the extension of the export format, and `.gz` after it when the export is compressed.
+    fn export_path(&self, path: &Path) -> PathBuf {
+        let mut path = path.to_path_buf();
+        if path.extension().is_some_and(|ext| ext == "gz") {
+            path.set_extension("");
+        }
+        path.set_extension(self.format.exporter().extension());
+        if self.compress {
+            path.as_mut_os_string().push(".gz");
+        }
+        path
+    }
+
+    fn validate(&self) -> Result<(), FibVisError> {
+        require_backend(self.field, self.hash)?;
+        if self.svg && self.output == Path::new("-") {
+            return Err(FibVisError::Config("--svg writes next to the trace export, --output cannot be `-`".to_string()));
+        }
+        if self.export_proof.as_deref() == Some(Path::new("-")) {
+            return Err(FibVisError::Config("--export-proof needs a file path, not `-`".to_string()));
+        }
+        if self.export_transcript.as_deref() == Some(Path::new("-")) {
+            return Err(FibVisError::Config("--export-transcript needs a file path, not `-`".to_string()));
+        }
+        if self.export_merkle.as_deref() == Some(Path::new("-")) {
+            return Err(FibVisError::Config("--export-merkle needs a file path, not `-`".to_string()));
+        }
+        if self.export_merkle.is_some() && self.num_steps.next_power_of_two() > MERKLE_MAX_ROWS {
+            return Err(FibVisError::Config(format!(
+                "--export-merkle is for traces of at most {MERKLE_MAX_ROWS} rows, not {}",
+                self.num_steps.next_power_of_two()
+            )));
+        }
+        if self.with_invalid && self.output == Path::new("-") {
+            return Err(FibVisError::Config("--with-invalid writes bundle.json next to the trace export, --output cannot be `-`".to_string()));
+        }
+        let compressible = matches!(
+            self.format,
+            TraceFormat::Json | TraceFormat::Ndjson | TraceFormat::Csv | TraceFormat::Cells | TraceFormat::CellsNdjson
+        );
+        if self.compress && !compressible {
+            return Err(FibVisError::Config(format!(
+                "--compress applies to the json, ndjson, csv and cell exports, not to {}",
+                self.format.exporter().extension()
+            )));
+        }
+        if self.esm && self.format != TraceFormat::Js {
+            return Err(FibVisError::Config(format!(
+                "--esm is for --format js, not {}",
+                self.format.exporter().extension()
+            )));
+        }
+        if self.keyframes == Some(0) {
+            return Err(FibVisError::Config("--keyframes must be at least 1".to_string()));
+        }
+        check_num_steps(self.num_steps).map_err(FibVisError::Config)?;
+        for &value in &self.initial {
+            self.field.check_element(value).map_err(|e| FibVisError::Config(format!("initial: {e}")))?;
+        }
+        if self.is_recurrence() && self.sequence != Sequence::Fibonacci {
+            return Err(FibVisError::Config(format!(
+                "--recurrence replaces --sequence, leave out --sequence {}",
+                self.sequence.demo().name()
+            )));
+        }
+        self.demo().check(&self.demo_params())?;
+        if let Some(final_value) = self.final_value {
+            self.field.check_element(final_value).map_err(|e| FibVisError::Config(format!("final_value: {e}")))?;
+        }
+        self.fri.validate(self.num_steps).map_err(FibVisError::Config)?;
+        self.degree_report().check_log_blowup(self.fri.log_blowup)
+    }
+
+    // The degrees do not depend on the field, Mersenne31 is always built.
+    fn degree_report(&self) -> ConstraintReport {
+        let air = self.air();
+        constraint_report::<Mersenne31, _>(&air, air.preprocessed_width(), air.public_values::<Mersenne31>(0).len())
+    }
 }
+
 //-----------------------------------------------------------
 
 
-pub struct FibonacciAir {
-    pub num_steps: usize,
-    pub final_value: u32,
+//-----------------------------------------------------------
+// Trace tampering for the failing-proof demo. A tamper is written `row:col=value` both on the command line
+// and in the config file (`tamper = ["3:1=7"]`).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+struct Tamper {
+    row: usize,
+    col: usize,
+    value: u32,
+}
+
+fn parse_tamper(s: &str) -> Result<Tamper, String> {
+    let err = || format!("expected <row>:<col>=<value>, got `{s}`");
+    let (cell, value) = s.split_once('=').ok_or_else(err)?;
+    let (row, col) = cell.split_once(':').ok_or_else(err)?;
+    Ok(Tamper {
+        row: row.trim().parse().map_err(|_| err())?,
+        col: col.trim().parse().map_err(|_| err())?,
+        value: value.trim().parse().map_err(|_| err())?,
+    })
 }
 
+impl TryFrom<String> for Tamper {
+    type Error = String;
 
-impl<F: Field> BaseAir<F> for FibonacciAir {
-    fn width(&self) -> usize {
-        2 // For current and next Fibonacci number
+    fn try_from(s: String) -> Result<Self, String> {
+        parse_tamper(&s)
     }
 }
 
-impl<AB: AirBuilder> Air<AB> for FibonacciAir {
-    fn eval(&self, builder: &mut AB) {
-        let main = builder.main();
-        let local = main.row_slice(0).unwrap();
-        let next = main.row_slice(1).unwrap();
+impl From<Tamper> for String {
+    fn from(t: Tamper) -> String {
+        format!("{}:{}={}", t.row, t.col, t.value)
+    }
+}
 
-        // Enforce starting values
-        builder.when_first_row().assert_eq(local[0], AB::Expr::ZERO);
-        builder.when_first_row().assert_eq(local[1], AB::Expr::ONE);
+fn apply_tamper<F: Field>(trace: &mut RowMajorMatrix<F>, tamper: &[Tamper]) -> Result<(), FibVisError> {
+    for t in tamper {
+        if t.row >= trace.height() || t.col >= trace.width() {
+            return Err(FibVisError::TraceGeneration {
+                row: t.row,
+                reason: format!("cannot tamper cell ({}, {}), the trace is {}x{}", t.row, t.col, trace.height(), trace.width()),
+            });
+        }
+        let width = trace.width();
+        trace.values[t.row * width + t.col] = F::from_u32(t.value);
+    }
+    Ok(())
+}
 
-        // Enforce state transition constraints
-        builder.when_transition().assert_eq(next[0], local[1]);
-        builder.when_transition().assert_eq(next[1], local[0] + local[1]);
+// Stringify the trace and write it to `output` in the configured --format, together with the run parameters.
+fn export_trace<F: PrimeField32>(params: &RunConfig, trace: &RowMajorMatrix<F>, output: &Path) -> Result<(), FibVisError> {
+    write_export(params, &export_data(params, trace)?, output)
+}
 
-        // Constrain the final value
-        let final_value = AB::Expr::from_u32(self.final_value);
-        builder.when_last_row().assert_eq(local[1], final_value);
+// The export of `trace` with the run parameters, for export_trace or to add the proof's stats to first.
+fn export_data<F: PrimeField32>(params: &RunConfig, trace: &RowMajorMatrix<F>) -> Result<VisData, FibVisError> {
+    let air = params.air();
+    check_trace_shape(&air, trace)?;
+    let public_values = air.public_values::<F>(params.final_value());
+    let (constraint_evals, violations) = evaluate_and_find_violations(&air, trace, &public_values);
+    let vis_data = VisData {
+        sequence: params.name().to_string(),
+        recurrence: params.recurrence.clone(),
+        final_value: params.final_value(),
+        public_values: public_values.iter().map(|v| v.to_string()).collect(),
+        field: FieldInfo::new(params.field),
+        hash: params.hash.name().to_string(),
+        fri: params.fri,
+        seed: params.seed.clone(),
+        ..vis_data_from_matrix(trace, &params.columns())?
     }
+    .with_constraints(extract_constraints::<F, _>(&air, air.preprocessed_width(), public_values.len()))
+    .with_air_info::<F, _>(&air)
+    .with_constraint_evals(constraint_evals)
+    .with_violations(violations)
+    .with_heatmap()
+    .with_padding(params.num_steps)
+    .with_true_values(params.field.modulus(), params.true_values())
+    .with_repr(params.repr);
+    let vis_data = match BaseAir::<F>::preprocessed_trace(&air) {
+        Some(preprocessed) => vis_data.with_preprocessed(&preprocessed, &params.demo().preprocessed_columns())?,
+        None => vis_data,
+    };
+    let vis_data = if params.export_windows { vis_data.with_windows() } else { vis_data };
+    let vis_data = match params.keyframes {
+        Some(frames) => vis_data.with_keyframes(frames),
+        None => vis_data,
+    };
+    if params.export_lde {
+        let log_blowup = params.fri.log_blowup;
+        let selectors = selector_lde(params.field, trace.height(), log_blowup)?;
+        return Ok(vis_data.with_selectors(Some(selectors)).with_lde(trace_lde(params.field, trace, log_blowup)?));
+    }
+    Ok(vis_data.with_selectors(None))
 }
 
-pub fn generate_fibonacci_trace<F: Field>(num_steps: usize) -> RowMajorMatrix<F> {
-    let mut values = Vec::with_capacity(num_steps * 2);
-    let mut a = F::ZERO;
-    let mut b = F::ONE;
-    for _ in 0..num_steps {
-        values.push(a);
-        values.push(b);
-        let c = a + b;
-        a = b;
-        b = c;
+// The "write export" phase, which records the size of the file written for the pipeline events
+fn write_export(params: &RunConfig, vis_data: &VisData, output: &Path) -> Result<(), FibVisError> {
+    let span = info_span!("write export", path = %output.display(), bytes = field::Empty);
+    span.in_scope(|| write_export_file(params, vis_data, output))?;
+    if let Some(metadata) = std::fs::metadata(output).ok().filter(|_| output != Path::new("-")) {
+        span.record("bytes", metadata.len());
     }
-    RowMajorMatrix::new(values, 2)
+    Ok(())
 }
 
-fn main() -> Result<(), impl Debug> {
-    let env_filter = EnvFilter::builder()
-        .with_default_directive(LevelFilter::INFO.into())
-        .from_env_lossy();
+fn write_export_file(params: &RunConfig, vis_data: &VisData, output: &Path) -> Result<(), FibVisError> {
+    let tiny = vis_data.trace.len() <= PRINT_TRACE_AUTO_ROWS && output != Path::new("-");
+    if params.print_trace || tiny {
+        status!("{}", trace_table(vis_data, params.print_rows, terminal_width()).trim_end());
+    }
+    if params.svg {
+        let svg_path = svg_path(output);
+        render_svg(vis_data, &svg_path, params.svg_max_rows)?;
+        status!("Trace drawn in {}", svg_path.display());
+    }
+    if params.compress {
+        return write_trace_gz(vis_data, params.format, output);
+    }
+    if params.format == TraceFormat::Json && vis_data.trace.len() > params.json_row_limit {
+        status!(
+            "The trace has {} rows, more than the visualizer loads comfortably as one JSON document; \
+             consider --format ndjson, which is streamed row by row",
+            vis_data.trace.len()
+        );
+    }
+    match params.format {
+        TraceFormat::Json if output != Path::new("-") => write_trace_json(output, vis_data),
+        TraceFormat::Csv if output != Path::new("-") => write_trace_csv(output, vis_data),
+        TraceFormat::Ndjson if output != Path::new("-") => write_trace_ndjson(output, vis_data),
+        TraceFormat::Bin if output != Path::new("-") => write_trace_bin(output, vis_data),
+        TraceFormat::Js => write_trace_js(output, vis_data, params.esm),
+        TraceFormat::Cbor if output != Path::new("-") => {
+            write_trace(vis_data, TraceFormat::Cbor, output)?;
+            // How much the binary format saves, without writing the JSON anywhere
+            let cbor = std::fs::metadata(output).map_err(|e| FibVisError::io(output, e))?.len();
+            let json = TraceFormat::Json.exporter().encoded_len(vis_data).map_err(|e| FibVisError::export(output, e))?;
+            status!("CBOR export: {cbor} bytes, {json} as JSON ({:.0}%)", 100.0 * cbor as f64 / json.max(1) as f64);
+            Ok(())
+        }
+        format => write_trace(vis_data, format, output),
+    }
+}
+
+//-----------------------------------------------------------
+
+
+//-----------------------------------------------------------
+// On-disk proof format, see proof_file: the header records what the proof was generated for, so `verify` can
+// check it against the flags before decoding the proof.
+
+// The proof as postcard encodes it, the bytes the proof file wraps and ProofStats describes.
+fn encode_proof<SC: StarkGenericConfig>(proof: &Proof<SC>) -> Result<Vec<u8>, FibVisError> {
+    postcard::to_allocvec(proof).map_err(|e| FibVisError::Serialization(format!("proof: {e}")))
+}
+
+// What the proof of a run with `params` and `public_values` is for.
+fn proof_header<F: Field>(params: &RunConfig, public_values: &[F]) -> ProofHeader {
+    ProofHeader {
+        field: params.field,
+        hash: params.hash,
+        fri: params.fri,
+        num_steps: params.num_steps,
+        sequence: params.sequence,
+        recurrence: params.recurrence.clone(),
+        initial: params.initial.clone(),
+        final_value: params.final_value(),
+        public_values: public_values.iter().map(|v| v.to_string()).collect(),
+        seed: params.seed.clone(),
+    }
+}
+
+// Writes the postcard-encoded `proof` with its header, returns the number of bytes written.
+fn write_proof_file<F: Field>(params: &RunConfig, public_values: &[F], proof: &[u8]) -> Result<usize, FibVisError> {
+    ProofFile { header: proof_header(params, public_values), proof: proof.to_vec() }.write(&params.proof)
+}
+
+// Reads the proof at `params.proof` and checks its header against the parameters we are about to verify
+// with, so that a mismatch is reported in plain words instead of as an opaque uni-stark failure.
+// Returns the proof together with the file size.
+fn read_proof_file<SC: StarkGenericConfig>(params: &RunConfig) -> Result<(Proof<SC>, usize), FibVisError> {
+    let path = &params.proof;
+    let (file, bytes) = ProofFile::read(path)?;
+    let header = &file.header;
+
+    if header.field != params.field || header.hash != params.hash || header.fri != params.fri {
+        return Err(FibVisError::mismatch(format!(
+            "{} was proven with field {} / hash {} / {:?}, but verification is configured for field {} / hash {} / {:?}",
+            path.display(),
+            header.field.name(),
+            header.hash.name(),
+            header.fri,
+            params.field.name(),
+            params.hash.name(),
+            params.fri
+        )));
+    }
+    if header.sequence != params.sequence {
+        return Err(FibVisError::mismatch(format!(
+            "{} proves the {} sequence, but verification is configured for {}",
+            path.display(),
+            header.sequence.name(),
+            params.sequence.name()
+        )));
+    }
+    if header.recurrence != params.recurrence {
+        return Err(FibVisError::mismatch(format!(
+            "{} proves the recurrence {:?}, but verification is configured for {:?}",
+            path.display(),
+            header.recurrence,
+            params.recurrence
+        )));
+    }
+    if header.num_steps != params.num_steps || header.initial != params.initial || header.final_value != params.final_value() {
+        return Err(FibVisError::mismatch(format!(
+            "{} proves num_steps = {}, initial = {:?}, final_value = {}, not num_steps = {}, initial = {:?}, final_value = {}",
+            path.display(),
+            header.num_steps,
+            header.initial,
+            header.final_value,
+            params.num_steps,
+            params.initial,
+            params.final_value()
+        )));
+    }
+    let expected = proof_header(params, &params.air().public_values::<Val<SC>>(params.final_value())).public_values;
+    if header.public_values != expected {
+        return Err(FibVisError::mismatch(format!(
+            "{} proves the public values {:?}, but verification is configured for {:?}",
+            path.display(),
+            header.public_values,
+            expected
+        )));
+    }
+
+    if header.seed != params.seed {
+        return Err(FibVisError::mismatch(format!(
+            "{} was proven with seed `{}`, but verification is configured with seed `{}`",
+            path.display(),
+            String::from(header.seed.clone()),
+            String::from(params.seed.clone())
+        )));
+    }
+
+    check_proof_digest(params, &file.proof)?;
+
+    let proof = file.decode().map_err(|e| FibVisError::Serialization(format!("{}: {e}", path.display())))?;
+    Ok((proof, bytes))
+}
+
+// Checks the proof against the digest the trace export at `params.output` recorded, when it is there and has one,
+// so that a proof left over from another run is not verified as if it belonged to the trace on display.
+fn check_proof_digest(params: &RunConfig, proof: &[u8]) -> Result<(), FibVisError> {
+    let Some(expected) = load_vis_data(&params.output).ok().and_then(|vis| vis.stats?.proof_digest) else {
+        return Ok(());
+    };
+    let digest = proof_digest(proof);
+    if digest != expected {
+        return Err(FibVisError::mismatch(format!(
+            "{} has digest {digest}, but {} was exported with the proof of digest {expected}; \
+             prove again, or pass the --proof that belongs to it",
+            params.proof.display(),
+            params.output.display()
+        )));
+    }
+    status!("Proof digest {digest} matches {}", params.output.display());
+    Ok(())
+}
+
+// The proof as JSON for the visualizer (--export-proof), with everything `verify` checks it against. Written once
+// the proof is verified, so that the config's challenger holds the verifier's proof-of-work check.
+fn write_proof_export<SC: StarkGenericConfig>(
+    config: &SC,
+    params: &RunConfig,
+    proof: &Proof<SC>,
+    proof_bytes: usize,
+    public_values: &[Val<SC>],
+    transcript: &[TranscriptEvent],
+    path: &Path,
+) -> Result<(), FibVisError>
+where
+    SC::Challenger: RecordsTranscript,
+{
+    let encoding = |e: serde_json::Error| FibVisError::Serialization(format!("proof: {e}"));
+    let export = ProofExport {
+        field: params.field,
+        hash: params.hash,
+        fri: params.fri,
+        seed: params.seed.clone(),
+        sequence: params.sequence,
+        recurrence: params.recurrence.clone(),
+        num_steps: params.num_steps,
+        initial: params.initial.clone(),
+        final_value: params.final_value(),
+        public_values: public_values.iter().map(|v| v.to_string()).collect(),
+        proof_bytes,
+        proof: proof_to_json(proof).map_err(encoding)?,
+        fri_queries: fri_queries(proof, params.max_queries_export).map_err(encoding)?,
+        fri_rounds: fri_rounds(proof, transcript, params.max_queries_export).map_err(encoding)?,
+        pow: pow_grinding(proof, params.fri.proof_of_work_bits, transcript, config.initialise_challenger().pow_check())
+            .map_err(encoding)?,
+        ood: ood_opening(config, proof, transcript)?,
+        quotient: quotient_opening(config, &params.air(), proof, public_values, transcript)?,
+    };
+    write_proof_json(path, &export)
+}
+//-----------------------------------------------------------
+
+
+//-----------------------------------------------------------
+// The proving pipeline, written once for any StarkGenericConfig and dispatched per field from main.
+// Every action reports what it did as RunSummary entries, which main either prints as the --json document
+// or turns into an error. Errors are kept as strings since every field produces a different error type.
+enum Action {
+    Prove,
+    Verify,
+    ProveAndVerify { save_proof: bool },
+    Sweep(Vec<usize>),
+    InspectProof { file: Box<ProofFile>, file_bytes: usize, json: bool }, // prints the proof, no runs to report
+}
+
+#[derive(Serialize, Default)]
+struct RunSummary {
+    num_steps: usize,
+    final_value: u64,
+    trace_path: Option<PathBuf>,
+    invalid_trace_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bundle_path: Option<PathBuf>,
+    proof_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proof_json_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transcript_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    merkle_path: Option<PathBuf>,
+    proof_bytes: Option<usize>,
+    timings: Timings,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<MemoryStats>, // with --track-memory, filled in phase by phase
+    verified: bool,
+    tampered: bool, // the proven trace was deliberately corrupted with --tamper, so rejection is the expected outcome
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    violations: Vec<Violation>, // the constraints the proven trace breaks, found when it was rejected
+    #[serde(serialize_with = "serialize_error")]
+    error: Option<FibVisError>, // why this run failed; a sweep records it and carries on with the next size
+}
+
+fn run_with_config<SC: StarkGenericConfig>(config: SC, action: Action, params: &RunConfig) -> Result<Vec<RunSummary>, FibVisError>
+where
+    Val<SC>: PrimeField32,
+    SC::Challenger: RecordsTranscript,
+    PcsProverData<SC>: Serialize,
+{
+    let fri = &params.fri;
+    status!(
+        "FRI config: log_blowup={}, num_queries={}, pow_bits={}, log_final_poly_len={}",
+        fri.log_blowup, fri.num_queries, fri.proof_of_work_bits, fri.log_final_poly_len
+    );
+    status!("Constraints: {}", params.degree_report());
+
+    match action {
+        Action::Prove => Ok(vec![timed(|| prove_to_file(&config, params))]),
+        Action::Verify => Ok(vec![timed(|| verify_from_file(&config, params))]),
+        Action::ProveAndVerify { save_proof } => Ok(vec![timed(|| run_once(&config, params, save_proof))]),
+        Action::Sweep(sizes) => {
+            if params.output == Path::new("-") {
+                return Err(FibVisError::Config("sweep mode writes one trace file per size, --output cannot be `-`".to_string()));
+            }
+            // The same config (hasher, MMCS, challenger) is reused for every size.
+            let runs: Vec<RunSummary> = sizes.iter().map(|&n| timed(|| sweep_one(&config, params, n))).collect();
+            report_sweep_timings(&runs);
+
+            let summary_path = params.output.with_file_name("sweep_summary.json");
+            let json = serde_json::to_string_pretty(&runs).map_err(|e| FibVisError::Serialization(e.to_string()))?;
+            std::fs::write(&summary_path, json).map_err(|e| FibVisError::io(&summary_path, e))?;
+            let failed = runs.iter().filter(|r| !r.verified).count();
+            status!("Sweep of {} sizes ({failed} failed) summarized in {}", runs.len(), summary_path.display());
+            Ok(runs)
+        }
+        Action::InspectProof { file, file_bytes, json } => {
+            print_proof_inspection(&inspect_proof::<SC>(&file, file_bytes)?, json)?;
+            Ok(Vec::new())
+        }
+    }
+}
+
+fn mebibytes(bytes: i64) -> f64 {
+    bytes as f64 / (1 << 20) as f64
+}
+
+// Runs one action, recording its wall-clock time as the summary's total.
+fn timed(run: impl FnOnce() -> RunSummary) -> RunSummary {
+    let start = Instant::now();
+    let mut summary = run();
+    summary.timings.total_ms = elapsed_ms(start);
+    summary
+}
+
+// The phase timings of a sweep, one line per size, to compare how each phase grows.
+fn report_sweep_timings(runs: &[RunSummary]) {
+    status!("{:>10} {:>12} {:>12} {:>12} {:>12} {:>12}", "num_steps", "trace gen", "export", "prove", "verify", "total");
+    for run in runs {
+        let t = &run.timings;
+        status!(
+            "{:>10} {:>9.1} ms {:>9.1} ms {:>9.1} ms {:>9.1} ms {:>9.1} ms",
+            run.num_steps, t.trace_gen_ms, t.export_ms, t.prove_ms, t.verify_ms, t.total_ms
+        );
+    }
+}
+
+// Export only: no challenger or PCS is constructed, so this stays fast for large traces.
+fn export_only(params: &RunConfig) -> RunSummary {
+    match params.field {
+        FieldChoice::Mersenne31 => export_only_in::<Mersenne31>(params),
+        #[cfg(feature = "babybear")]
+        FieldChoice::BabyBear => export_only_in::<BabyBear>(params),
+        #[cfg(feature = "koalabear")]
+        FieldChoice::KoalaBear => export_only_in::<KoalaBear>(params),
+        // Disabled backends are rejected by RunConfig::validate already.
+        #[allow(unreachable_patterns)]
+        _ => RunSummary {
+            num_steps: params.num_steps,
+            error: require_backend(params.field, params.hash).err(),
+            ..Default::default()
+        },
+    }
+}
+
+fn export_only_in<F: PrimeField32>(params: &RunConfig) -> RunSummary {
+    let mut summary = RunSummary { num_steps: params.num_steps, final_value: params.final_value(), ..Default::default() };
+
+    let start = Instant::now();
+    let trace = params.generate_trace::<F>();
+    summary.timings.trace_gen_ms = elapsed_ms(start);
+
+    let start = Instant::now();
+    match info_span!("export trace").in_scope(|| export_trace(params, &trace, &params.output)) {
+        Ok(()) => {
+            status!("Valid trace exported to {}", params.output.display());
+            summary.trace_path = Some(params.output.clone());
+        }
+        Err(e) => summary.error = Some(e),
+    }
+    summary.timings.export_ms = elapsed_ms(start);
+    summary
+}
+
+fn prove_to_file<SC: StarkGenericConfig>(config: &SC, params: &RunConfig) -> RunSummary
+where
+    Val<SC>: PrimeField32,
+{
+    let mut summary = RunSummary { num_steps: params.num_steps, final_value: params.final_value(), ..Default::default() };
+    let air = params.air();
+    let public_values = air.public_values::<Val<SC>>(params.final_value());
+
+    let start = Instant::now();
+    let trace = params.generate_trace::<Val<SC>>();
+    summary.timings.trace_gen_ms = elapsed_ms(start);
+    let checked = check_trace_shape(&air, &trace)
+        .and_then(|()| check_final_value(&air, params.final_value(), &trace));
+    if let Err(e) = checked {
+        summary.error = Some(e);
+        return summary;
+    }
+
+    let start = Instant::now();
+    let proving = info_span!("proving", proof_bytes = field::Empty);
+    let proved = proving.in_scope(|| panic::catch_unwind(AssertUnwindSafe(|| air.prove(config, trace, &public_values))));
+    let proof = match proved {
+        Ok(proof) => proof,
+        Err(_) => {
+            summary.error = Some(FibVisError::Prove(format!("the prover panicked for num_steps = {}", params.num_steps)));
+            return summary;
+        }
+    };
+    summary.timings.prove_ms = elapsed_ms(start);
+
+    let encoded = encode_proof(&proof).inspect(|encoded| {
+        proving.record("proof_bytes", encoded.len());
+    });
+    drop(proving);
+    let written = encoded.and_then(|encoded| {
+        let bytes = write_proof_file(params, &public_values, &encoded)?;
+        Ok((bytes, proof_digest(&encoded)))
+    });
+    match written {
+        Ok((bytes, digest)) => {
+            status!("Proof ({bytes} bytes, digest {digest}) written to {}", params.proof.display());
+            summary.proof_path = Some(params.proof.clone());
+            summary.proof_bytes = Some(bytes);
+        }
+        Err(e) => summary.error = Some(e),
+    }
+    summary
+}
+
+fn verify_from_file<SC: StarkGenericConfig>(config: &SC, params: &RunConfig) -> RunSummary {
+    let mut summary = RunSummary { num_steps: params.num_steps, final_value: params.final_value(), ..Default::default() };
+    let air = params.air();
+    let public_values = air.public_values::<Val<SC>>(params.final_value());
+
+    let proof: Proof<SC> = match read_proof_file(params) {
+        Ok((proof, bytes)) => {
+            summary.proof_bytes = Some(bytes);
+            proof
+        }
+        Err(e) => {
+            summary.error = Some(e);
+            return summary;
+        }
+    };
+    summary.proof_path = Some(params.proof.clone());
+
+    let start = Instant::now();
+    // A proof for a different trace shape can trip assertions inside uni-stark, report that as a rejection too.
+    let result = info_span!("verification", ok = field::Empty).in_scope(|| {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| air.verify(config, &proof, &public_values)));
+        Span::current().record("ok", matches!(result, Ok(Ok(()))));
+        result
+    });
+    summary.timings.verify_ms = elapsed_ms(start);
+    match result {
+        Ok(Ok(())) => {
+            summary.verified = true;
+            status!("Proof in {} verified", params.proof.display());
+        }
+        Ok(Err(e)) => summary.error = Some(e),
+        Err(_) => summary.error = Some(FibVisError::mismatch("the proof does not match the AIR".to_string())),
+    }
+    summary
+}
+
+// One full trace/export/prove/verify run with the parameters as given. With --tamper, the valid trace is
+// still exported as usual, then the requested cells are overwritten, the corrupted trace is exported
+// next to it (trace_data_invalid.json) and that is what gets proven -- so a failure is the expected outcome.
+// With `save_proof` the proof is also written to `params.proof`. The export of the proven trace is
+// only written once proving and verifying are done, so that it can carry the proof's stats and whether it verified.
+fn run_once<SC: StarkGenericConfig>(config: &SC, params: &RunConfig, save_proof: bool) -> RunSummary
+where
+    Val<SC>: PrimeField32,
+    SC::Challenger: RecordsTranscript,
+    PcsProverData<SC>: Serialize,
+{
+    let mut summary = RunSummary {
+        num_steps: params.num_steps,
+        final_value: params.final_value(),
+        tampered: !params.tamper.is_empty() && !params.with_invalid,
+        memory: memory::is_enabled().then(MemoryStats::default),
+        ..Default::default()
+    };
+    let run_start = Instant::now();
+    memory::reset_peak();
+
+    let start = Instant::now();
+    let allocated = memory::allocated();
+    let mut trace = params.generate_trace::<Val<SC>>();
+    summary.timings.trace_gen_ms = elapsed_ms(start);
+    if let Some(heap) = &mut summary.memory {
+        heap.trace_gen_bytes = memory::allocated_since(allocated);
+    }
+    if let Err(e) = check_trace_shape(&params.air(), &trace).and_then(|()| check_final_value(&params.air(), params.final_value(), &trace)) {
+        summary.error = Some(e);
+        return summary;
+    }
+
+    let start = Instant::now();
+    let allocated = memory::allocated();
+    let valid = match info_span!("export trace").in_scope(|| export_data(params, &trace)) {
+        Ok(vis_data) => vis_data,
+        Err(e) => {
+            summary.error = Some(e);
+            return summary;
+        }
+    };
+    summary.timings.export_ms = elapsed_ms(start);
+    if let Some(heap) = &mut summary.memory {
+        heap.export_bytes = memory::allocated_since(allocated);
+    }
+
+    let (proven, proven_path) = if summary.tampered {
+        // Only the tampered trace is proven, the valid one is written right away.
+        let invalid_path = suffixed_path(&params.output, "invalid");
+        let tampered = write_export(params, &valid, &params.output)
+            .and_then(|()| apply_tamper(&mut trace, &params.tamper))
+            .and_then(|()| export_data(params, &trace))
+            .map(|invalid| invalid.with_tampered(params.tamper.iter().map(|t| (t.row, t.col)).collect()));
+        match tampered {
+            Ok(invalid) => {
+                status!("Valid trace exported to {}", params.output.display());
+                summary.trace_path = Some(params.output.clone());
+                (invalid, invalid_path)
+            }
+            Err(e) => {
+                summary.error = Some(e);
+                return summary;
+            }
+        }
+    } else {
+        (valid, params.output.clone())
+    };
+    // --with-invalid: the tampered copy only goes into the bundle, the valid trace is proven as usual
+    let invalid = match params.with_invalid.then(|| invalid_copy(params, &trace)).transpose() {
+        Ok(invalid) => invalid,
+        Err(e) => {
+            summary.error = Some(e);
+            return summary;
+        }
+    };
+
+    let proof = prove_and_verify(config, params, trace, proven.violations.clone(), save_proof, &mut summary);
+    // The export's stats are taken here, before it is written; the summary's total also covers the writing
+    summary.timings.total_ms = elapsed_ms(run_start);
+    if let Some(heap) = &mut summary.memory {
+        heap.peak_bytes = memory::peak() as u64;
+    }
+
+    let proven = match proof {
+        Some((proof, commitments)) => {
+            let stats = ProofStats { memory: summary.memory, ..ProofStats::new(&proof, summary.timings, params.fri) };
+            proven.with_stats(stats).with_commitments(commitments)
+        }
+        None => proven,
+    };
+    // Recorded whichever way it went, a panicking prover included, so the page can show a rejected trace as such
+    let proven =
+        proven.with_verification(Verification::new(summary.error.as_ref(), summary.proof_bytes, summary.timings.verify_ms));
+    let start = Instant::now();
+    if let Err(e) = write_export(params, &proven, &proven_path) {
+        // A failed proof or verification is the more telling error.
+        summary.error = summary.error.take().or(Some(e));
+        return summary;
+    }
+    summary.timings.export_ms += elapsed_ms(start);
+    if summary.tampered {
+        status!("Tampered trace exported to {}", proven_path.display());
+        summary.invalid_trace_path = Some(proven_path);
+    } else {
+        status!("Valid trace exported to {}", proven_path.display());
+        summary.trace_path = Some(proven_path);
+    }
+    if let Some(invalid) = invalid {
+        let bundle_path = params.output.with_file_name("bundle.json");
+        let bundle = TraceBundle::new(proven, invalid);
+        if let Err(e) = write_trace_bundle(&bundle_path, &bundle) {
+            summary.error = summary.error.take().or(Some(e));
+            return summary;
+        }
+        status!(
+            "Valid and invalid trace ({} cells changed, {} constraints violated) bundled in {}",
+            bundle.tamper.len(),
+            bundle.violations.len(),
+            bundle_path.display()
+        );
+        summary.bundle_path = Some(bundle_path);
+    }
+    summary
+}
+
+// The tampered copy of `trace` for --with-invalid: with the cells --tamper gives, or else with the last column of
+// the middle row one more than it should be.
+fn invalid_copy<F: PrimeField32>(params: &RunConfig, trace: &RowMajorMatrix<F>) -> Result<VisData, FibVisError> {
+    let tamper = if params.tamper.is_empty() {
+        let (row, col) = (trace.height() / 2, trace.width() - 1);
+        let old = trace.values[row * trace.width() + col].as_canonical_u32();
+        vec![Tamper { row, col, value: (old + 1) % F::ORDER_U32 }]
+    } else {
+        params.tamper.clone()
+    };
+    let mut invalid = trace.clone();
+    apply_tamper(&mut invalid, &tamper)?;
+    Ok(export_data(params, &invalid)?.with_tampered(tamper.iter().map(|t| (t.row, t.col)).collect()))
+}
+
+// The proving and verification half of run_once, recording the outcome in `summary`. `violations` are those of
+// `trace`, as its export lists them. Gives back the postcard-encoded proof and its Merkle roots, unless proving (or
+// saving the proof) failed.
+fn prove_and_verify<SC: StarkGenericConfig>(
+    config: &SC,
+    params: &RunConfig,
+    trace: RowMajorMatrix<Val<SC>>,
+    violations: Vec<Violation>,
+    save_proof: bool,
+    summary: &mut RunSummary,
+) -> Option<(Vec<u8>, Vec<CommitmentRoot>)>
+where
+    Val<SC>: PrimeField32,
+    SC::Challenger: RecordsTranscript,
+    PcsProverData<SC>: Serialize,
+{
+    let air = params.air();
+    let public_values = air.public_values::<Val<SC>>(params.final_value());
+    // Generated traces are checked in run_once, only a tampered one should break the constraints. The precheck
+    // stops it here, before any commitment, unless --skip-precheck lets the prover reject it; the violations then
+    // explain that rejection.
+    if let (false, Some(first)) = (params.skip_precheck, violations.first()) {
+        summary.error = Some(FibVisError::ConstraintViolation { count: violations.len(), first: first.to_string() });
+        report_violations(&violations);
+        summary.violations = violations;
+        return None;
+    }
+    // The prover commits to the trace it consumes, so the tree is built again from a copy beforehand.
+    if let Some(path) = &params.export_merkle {
+        let export = merkle_tree(config, trace.clone()).map(|tree| MerkleTreeExport {
+            field: params.field,
+            hash: params.hash,
+            num_steps: params.num_steps,
+            log_blowup: params.fri.log_blowup,
+            tree,
+        });
+        match export.and_then(|export| write_merkle_json(path, &export).map(|()| export)) {
+            Ok(export) => {
+                let leaves = export.tree.levels.first().map_or(0, Vec::len);
+                status!("Merkle tree ({leaves} leaves, root {}) exported to {}", export.tree.root, path.display());
+                summary.merkle_path = Some(path.clone());
+            }
+            Err(e) => {
+                summary.error = Some(e);
+                return None;
+            }
+        }
+    }
+    // Every run with this config shares its challenger's log, drop what an earlier verification recorded.
+    config.initialise_challenger().take_transcript();
+    let start = Instant::now();
+    let allocated = memory::allocated();
+    // uni-stark panics on a trace that violates the constraints, record that instead of aborting a sweep.
+    let proving = info_span!("proving", proof_bytes = field::Empty);
+    let proved = proving.in_scope(|| panic::catch_unwind(AssertUnwindSafe(|| air.prove(config, trace, &public_values))));
+    let proof = match proved {
+        Ok(proof) => proof,
+        Err(payload) => {
+            summary.error = Some(FibVisError::Prove(panic_message(payload)));
+            report_violations(&violations);
+            summary.violations = violations;
+            return None;
+        }
+    };
+    summary.timings.prove_ms = elapsed_ms(start);
+    if let Some(heap) = &mut summary.memory {
+        heap.prove_bytes = memory::allocated_since(allocated);
+    }
+    let transcript = config.initialise_challenger().take_transcript();
+    let roots = commitment_roots(&proof).map_err(|e| FibVisError::Serialization(format!("proof: {e}")));
+    let (encoded, commitments) = match encode_proof(&proof).and_then(|encoded| Ok((encoded, roots?))) {
+        Ok(proven) => proven,
+        Err(e) => {
+            summary.error = Some(e);
+            return None;
+        }
+    };
+    summary.proof_bytes = Some(encoded.len());
+    proving.record("proof_bytes", encoded.len());
+    drop(proving);
+    if save_proof {
+        match write_proof_file(params, &public_values, &encoded) {
+            Ok(bytes) => {
+                let digest = proof_digest(&encoded);
+                status!("Proof ({bytes} bytes, digest {digest}) written to {}", params.proof.display());
+                summary.proof_path = Some(params.proof.clone());
+                summary.proof_bytes = Some(bytes);
+            }
+            Err(e) => {
+                summary.error = Some(e);
+                return None;
+            }
+        }
+    }
+    if let Some(path) = &params.export_transcript {
+        let entries = transcript.len();
+        let export = TranscriptExport {
+            field: params.field,
+            hash: params.hash,
+            seed: params.seed.clone(),
+            num_steps: params.num_steps,
+            events: transcript.clone(),
+        };
+        match write_transcript_json(path, &export) {
+            Ok(()) => {
+                status!("Fiat-Shamir transcript ({entries} entries) exported to {}", path.display());
+                summary.transcript_path = Some(path.clone());
+            }
+            Err(e) => {
+                summary.error = Some(e);
+                return None;
+            }
+        }
+    }
+
+    let start = Instant::now();
+    let allocated = memory::allocated();
+    let result = info_span!("verification", ok = field::Empty).in_scope(|| {
+        let result = air.verify(config, &proof, &public_values);
+        Span::current().record("ok", result.is_ok());
+        result
+    });
+    summary.timings.verify_ms = elapsed_ms(start);
+    if let Some(heap) = &mut summary.memory {
+        heap.verify_bytes = memory::allocated_since(allocated);
+    }
+    match result {
+        Ok(()) => summary.verified = true,
+        Err(e) => {
+            summary.error = Some(e);
+            report_violations(&violations);
+            summary.violations = violations;
+        }
+    }
+    if let Some(path) = &params.export_proof {
+        match write_proof_export(config, params, &proof, encoded.len(), &public_values, &transcript, path) {
+            Ok(()) => {
+                status!("Proof exported as JSON to {}", path.display());
+                summary.proof_json_path = Some(path.clone());
+            }
+            Err(e) => {
+                summary.error.get_or_insert(e);
+                return None;
+            }
+        }
+    }
+    Some((encoded, commitments))
+}
+
+// At most this many violations are printed, the export lists all of them.
+const VIOLATIONS_SHOWN: usize = 20;
+
+// Explains a rejected trace, one line per broken (row, constraint) pair.
+fn report_violations(violations: &[Violation]) {
+    if violations.is_empty() {
+        return;
+    }
+    status!("The trace breaks {} constraint evaluations:", violations.len());
+    for violation in violations.iter().take(VIOLATIONS_SHOWN) {
+        status!("  {violation}");
+    }
+    if violations.len() > VIOLATIONS_SHOWN {
+        status!("  ... and {} more, see `violations` in the tampered trace export", violations.len() - VIOLATIONS_SHOWN);
+    }
+}
+
+// --check: evaluate the constraints on the trace, tampered if asked, without building a prover.
+fn check_only(params: &RunConfig) -> RunSummary {
+    match params.field {
+        FieldChoice::Mersenne31 => check_only_in::<Mersenne31>(params),
+        #[cfg(feature = "babybear")]
+        FieldChoice::BabyBear => check_only_in::<BabyBear>(params),
+        #[cfg(feature = "koalabear")]
+        FieldChoice::KoalaBear => check_only_in::<KoalaBear>(params),
+        // Disabled backends are rejected by RunConfig::validate already.
+        #[allow(unreachable_patterns)]
+        _ => RunSummary {
+            num_steps: params.num_steps,
+            error: require_backend(params.field, params.hash).err(),
+            ..Default::default()
+        },
+    }
+}
+
+fn check_only_in<F: PrimeField32>(params: &RunConfig) -> RunSummary {
+    let mut summary = RunSummary {
+        num_steps: params.num_steps,
+        final_value: params.final_value(),
+        tampered: !params.tamper.is_empty(),
+        ..Default::default()
+    };
+
+    let start = Instant::now();
+    let mut trace = params.generate_trace::<F>();
+    summary.timings.trace_gen_ms = elapsed_ms(start);
+    let air = params.air();
+    if let Err(e) = check_trace_shape(&air, &trace).and_then(|()| apply_tamper(&mut trace, &params.tamper)) {
+        summary.error = Some(e);
+        return summary;
+    }
+
+    let public_values = air.public_values::<F>(params.final_value());
+    summary.violations = info_span!("check constraints").in_scope(|| find_violations(&air, &trace, &public_values));
+    match summary.violations.first() {
+        Some(first) => {
+            report_violations(&summary.violations);
+            summary.error = Some(FibVisError::ConstraintViolation { count: summary.violations.len(), first: first.to_string() });
+            // The broken trace is exported with its heatmap, as the tampered trace of a proving run is
+            if params.output != Path::new("-") {
+                let path = suffixed_path(&params.output, "invalid");
+                let tampered = params.tamper.iter().map(|t| (t.row, t.col)).collect();
+                let exported = export_data(params, &trace).map(|vis| vis.with_tampered(tampered));
+                match exported.and_then(|vis| write_export(params, &vis, &path)) {
+                    Ok(()) => {
+                        status!("Violated constraints exported to {}", path.display());
+                        summary.invalid_trace_path = Some(path);
+                    }
+                    Err(e) => status!("Warning: the checked trace could not be exported: {e}"),
+                }
+            }
+        }
+        None => status!("All constraints hold on all {} rows", trace.height()),
+    }
+    summary
+}
+
+//-----------------------------------------------------------
+// Sweep mode: one full run per size, collected into sweep_summary.json.
+
+// Traces this short are printed without --print-trace.
+const PRINT_TRACE_AUTO_ROWS: usize = 16;
+
+// Width of the terminal as the shell exports it in COLUMNS, 120 otherwise.
+fn terminal_width() -> usize {
+    std::env::var("COLUMNS").ok().and_then(|columns| columns.parse().ok()).unwrap_or(120)
+}
+
+// web/trace_data.json (or .json.gz) -> web/trace_data.svg
+fn svg_path(output: &Path) -> PathBuf {
+    let mut path = output.to_path_buf();
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        path.set_extension("");
+    }
+    path.with_extension("svg")
+}
+
+// web/trace_data.json -> web/trace_data_<suffix>.json, and web/trace_data.json.gz -> web/trace_data_<suffix>.json.gz
+fn suffixed_path(path: &Path, suffix: &str) -> PathBuf {
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        let mut path = suffixed_path(&path.with_extension(""), suffix).into_os_string();
+        path.push(".gz");
+        return path.into();
+    }
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("trace_data");
+    let name = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{stem}_{suffix}.{ext}"),
+        None => format!("{stem}_{suffix}"),
+    };
+    path.with_file_name(name)
+}
+
+fn sweep_one<SC: StarkGenericConfig>(config: &SC, base: &RunConfig, num_steps: usize) -> RunSummary
+where
+    Val<SC>: PrimeField32,
+    SC::Challenger: RecordsTranscript,
+    PcsProverData<SC>: Serialize,
+{
+    let mut params = base.clone();
+    params.num_steps = num_steps;
+    params.output = suffixed_path(&base.output, &num_steps.to_string());
+    params.export_proof = base.export_proof.as_deref().map(|path| suffixed_path(path, &num_steps.to_string()));
+    params.export_transcript = base.export_transcript.as_deref().map(|path| suffixed_path(path, &num_steps.to_string()));
+    params.export_merkle = base.export_merkle.as_deref().map(|path| suffixed_path(path, &num_steps.to_string()));
+
+    if let Err(e) = params.validate() {
+        return RunSummary { num_steps, error: Some(e), ..Default::default() };
+    }
+
+    // A single --final-value can only match one size, so each run uses the value computed for its own size.
+    params.final_value = None;
+
+    run_once(config, &params, false)
+}
+//-----------------------------------------------------------
+
+// Builds the config for the chosen field/hash pair and runs the pipeline with it.
+fn dispatch(action: Action, params: &RunConfig) -> Result<Vec<RunSummary>, FibVisError> {
+    #[cfg(feature = "poseidon2")]
+    let mut rng = StdRng::seed_from_u64(POSEIDON2_SEED);
+
+    match (params.field, params.hash) {
+        (FieldChoice::Mersenne31, HashChoice::Keccak256) => {
+            let config = ConfigBuilder::new().fri(params.fri).seed(params.seed.0.clone()).build_recording()?;
+            run_with_config(config, action, params)
+        }
+        #[cfg(feature = "blake3")]
+        (FieldChoice::Mersenne31, HashChoice::Blake3) => {
+            let challenger = byte_hash_challenger::<Mersenne31, _>(Blake3 {}, &params.seed);
+            let config = circle_config(
+                byte_hash_mmcs::<Mersenne31, _>(Blake3 {}),
+                RecordingChallenger::<Mersenne31, _>::new(challenger, &params.seed),
+                &params.fri,
+            );
+            run_with_config(config, action, params)
+        }
+        #[cfg(feature = "poseidon2")]
+        (FieldChoice::Mersenne31, HashChoice::Poseidon2) => {
+            let perm = Poseidon2Mersenne31::<16>::new_from_rng_128(&mut rng);
+            let challenger = poseidon2_challenger::<Mersenne31, _>(perm.clone(), &params.seed);
+            let config = circle_config(
+                poseidon2_mmcs::<Mersenne31, _>(perm),
+                RecordingChallenger::<Mersenne31, _>::new(challenger, &params.seed),
+                &params.fri,
+            );
+            run_with_config(config, action, params)
+        }
+        #[cfg(feature = "babybear")]
+        (FieldChoice::BabyBear, HashChoice::Keccak256) => {
+            let challenger = byte_hash_challenger::<BabyBear, _>(Keccak256Hash {}, &params.seed);
+            let config = two_adic_config::<BabyBear, BabyBearChallenge, _, _>(
+                byte_hash_mmcs::<BabyBear, _>(Keccak256Hash {}),
+                RecordingChallenger::<BabyBear, _>::new(challenger, &params.seed),
+                &params.fri,
+            );
+            run_with_config(config, action, params)
+        }
+        #[cfg(all(feature = "babybear", feature = "blake3"))]
+        (FieldChoice::BabyBear, HashChoice::Blake3) => {
+            let challenger = byte_hash_challenger::<BabyBear, _>(Blake3 {}, &params.seed);
+            let config = two_adic_config::<BabyBear, BabyBearChallenge, _, _>(
+                byte_hash_mmcs::<BabyBear, _>(Blake3 {}),
+                RecordingChallenger::<BabyBear, _>::new(challenger, &params.seed),
+                &params.fri,
+            );
+            run_with_config(config, action, params)
+        }
+        #[cfg(all(feature = "babybear", feature = "poseidon2"))]
+        (FieldChoice::BabyBear, HashChoice::Poseidon2) => {
+            let perm = Poseidon2BabyBear::<16>::new_from_rng_128(&mut rng);
+            let challenger = poseidon2_challenger::<BabyBear, _>(perm.clone(), &params.seed);
+            let config = two_adic_config::<BabyBear, BabyBearChallenge, _, _>(
+                poseidon2_mmcs::<BabyBear, _>(perm),
+                RecordingChallenger::<BabyBear, _>::new(challenger, &params.seed),
+                &params.fri,
+            );
+            run_with_config(config, action, params)
+        }
+        #[cfg(feature = "koalabear")]
+        (FieldChoice::KoalaBear, HashChoice::Keccak256) => {
+            let challenger = byte_hash_challenger::<KoalaBear, _>(Keccak256Hash {}, &params.seed);
+            let config = two_adic_config::<KoalaBear, KoalaBearChallenge, _, _>(
+                byte_hash_mmcs::<KoalaBear, _>(Keccak256Hash {}),
+                RecordingChallenger::<KoalaBear, _>::new(challenger, &params.seed),
+                &params.fri,
+            );
+            run_with_config(config, action, params)
+        }
+        #[cfg(all(feature = "koalabear", feature = "blake3"))]
+        (FieldChoice::KoalaBear, HashChoice::Blake3) => {
+            let challenger = byte_hash_challenger::<KoalaBear, _>(Blake3 {}, &params.seed);
+            let config = two_adic_config::<KoalaBear, KoalaBearChallenge, _, _>(
+                byte_hash_mmcs::<KoalaBear, _>(Blake3 {}),
+                RecordingChallenger::<KoalaBear, _>::new(challenger, &params.seed),
+                &params.fri,
+            );
+            run_with_config(config, action, params)
+        }
+        #[cfg(all(feature = "koalabear", feature = "poseidon2"))]
+        (FieldChoice::KoalaBear, HashChoice::Poseidon2) => {
+            let perm = Poseidon2KoalaBear::<16>::new_from_rng_128(&mut rng);
+            let challenger = poseidon2_challenger::<KoalaBear, _>(perm.clone(), &params.seed);
+            let config = two_adic_config::<KoalaBear, KoalaBearChallenge, _, _>(
+                poseidon2_mmcs::<KoalaBear, _>(perm),
+                RecordingChallenger::<KoalaBear, _>::new(challenger, &params.seed),
+                &params.fri,
+            );
+            run_with_config(config, action, params)
+        }
+        // Disabled backends are rejected by RunConfig::validate already.
+        #[allow(unreachable_patterns)]
+        _ => {
+            require_backend(params.field, params.hash)?;
+            Err(FibVisError::Config(format!("no backend for {} with {}", params.field.name(), params.hash.name())))
+        }
+    }
+}
+
+//-----------------------------------------------------------
+
+
+//-----------------------------------------------------------
+// Watch mode: poll the config file's modification time and regenerate the trace whenever it changes.
+// Editors often write a file in several steps, so a change only counts once the file has been quiet
+// for WATCH_DEBOUNCE. Errors (including a half-edited, invalid config) are printed and the watcher keeps going.
+const WATCH_POLL: Duration = Duration::from_millis(200);
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+fn watch(args: &FibArgs, prove_on_change: bool) -> Result<(), FibVisError> {
+    let path = args.config.as_deref().ok_or_else(|| FibVisError::Config("--watch needs --config".to_string()))?;
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+
+    eprintln!("Watching {} for changes (Ctrl+C to stop)", path.display());
+    let mut last_seen = modified(path);
+    regenerate(args, prove_on_change);
+
+    loop {
+        std::thread::sleep(WATCH_POLL);
+        let current = modified(path);
+        if current == last_seen {
+            continue;
+        }
+
+        // Wait until the file stops changing before acting on it.
+        let mut settled = current;
+        loop {
+            std::thread::sleep(WATCH_DEBOUNCE);
+            let again = modified(path);
+            if again == settled {
+                break;
+            }
+            settled = again;
+        }
+        last_seen = settled;
+        regenerate(args, prove_on_change);
+    }
+}
+
+// One watch iteration, summarized on a single line.
+fn regenerate(args: &FibArgs, prove_on_change: bool) {
+    let start = Instant::now();
+    let result = args.resolve().and_then(|params| {
+        let run = if prove_on_change {
+            dispatch(Action::ProveAndVerify { save_proof: false }, &params)?.pop().unwrap_or_default()
+        } else {
+            export_only(&params)
+        };
+        match run.error {
+            Some(e) => Err(e),
+            None => Ok((params, run.verified)),
+        }
+    });
+
+    match result {
+        Ok((params, verified)) => {
+            let proof = if prove_on_change { if verified { ", verified" } else { ", NOT verified" } } else { "" };
+            eprintln!(
+                "[watch] num_steps={} field={} hash={} -> {} ({:.1} ms{proof})",
+                params.num_steps,
+                params.field.name(),
+                params.hash.name(),
+                params.output.display(),
+                elapsed_ms(start)
+            );
+        }
+        Err(e) => eprintln!("[watch] error: {e}"),
+    }
+}
+//-----------------------------------------------------------
+
+
+//-----------------------------------------------------------
+// Run directories. Each --run-dir run gets its own <root>/<timestamp>-<num_steps>/ holding trace_data.json,
+// proof.bin and meta.json, so runs can be compared side by side instead of overwriting web/trace_data.json.
+// <root>/latest always points at the newest one: a symlink where the platform has them, a latest.json
+// pointer file elsewhere. The default root web/runs is inside the directory the visualizer is served from.
+fn create_run_dir(root: &Path, num_steps: usize, force: bool) -> Result<PathBuf, FibVisError> {
+    let name = format!("{}-{num_steps}", chrono::Utc::now().format("%Y%m%dT%H%M%SZ"));
+    let dir = root.join(name);
+    let non_empty = std::fs::read_dir(&dir).is_ok_and(|mut entries| entries.next().is_some());
+    if non_empty && !force {
+        return Err(FibVisError::Config(format!("{} already exists and is not empty, pass --force to overwrite it", dir.display())));
+    }
+    std::fs::create_dir_all(&dir).map_err(|e| FibVisError::io(&dir, e))?;
+    Ok(dir)
+}
+
+fn write_run_meta(dir: &Path, report: &JsonReport) -> Result<(), FibVisError> {
+    let path = dir.join("meta.json");
+    let json = serde_json::to_string_pretty(report).map_err(|e| FibVisError::Serialization(e.to_string()))?;
+    std::fs::write(&path, json).map_err(|e| FibVisError::io(&path, e))
+}
+
+#[cfg(unix)]
+fn update_latest(root: &Path, dir: &Path) -> Result<(), FibVisError> {
+    let link = root.join("latest");
+    if link.symlink_metadata().is_ok() {
+        std::fs::remove_file(&link).map_err(|e| FibVisError::io(&link, e))?;
+    }
+    // Relative target, so the runs directory can be moved or served as a whole.
+    let target = dir.file_name().map(PathBuf::from).unwrap_or_else(|| dir.to_path_buf());
+    std::os::unix::fs::symlink(target, &link).map_err(|e| FibVisError::io(&link, e))
+}
+
+#[cfg(not(unix))]
+fn update_latest(root: &Path, dir: &Path) -> Result<(), FibVisError> {
+    let name = dir.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let pointer = serde_json::json!({ "run": name, "trace": format!("{name}/trace_data.json") });
+    let path = root.join("latest.json");
+    std::fs::write(&path, pointer.to_string()).map_err(|e| FibVisError::io(&path, e))
+}
+//-----------------------------------------------------------
+
+
+//-----------------------------------------------------------
+// Compare mode: every run proven and verified like a run without a subcommand, and their stats written to one
+// comparison.json against the first run. Runs that would write the same trace or proof file get `_run<i>` in
+// its name, so each one keeps its own.
+
+// One run of `compare`: the flags of --run, or `--config <file>`.
+#[derive(Parser, Debug)]
+#[command(no_binary_name = true)]
+struct CompareRun {
+    #[command(flatten)]
+    params: FibArgs,
+}
+
+fn compare(configs: &[PathBuf], flags: &[String], output: &Path) -> Result<(), FibVisError> {
+    let from_files = configs.iter().map(|path| {
+        let label = path.file_stem().map_or_else(|| path.display().to_string(), |s| s.to_string_lossy().into_owned());
+        (label, vec!["--config".to_string(), path.display().to_string()])
+    });
+    let from_flags = flags.iter().map(|f| (f.clone(), f.split_whitespace().map(str::to_string).collect()));
+    let mut runs = Vec::new();
+    for (label, args) in from_files.chain(from_flags) {
+        let run = CompareRun::try_parse_from(&args).map_err(|e| FibVisError::Config(format!("{label}: {e}")))?;
+        runs.push((label, run.params.resolve()?));
+    }
+    if runs.len() < 2 {
+        return Err(FibVisError::Config(format!("compare needs at least two runs, got {}", runs.len())));
+    }
+    let labels: HashSet<&str> = runs.iter().map(|(label, _)| label.as_str()).collect();
+    if labels.len() < runs.len() {
+        return Err(FibVisError::Config("compare needs a different config file or set of flags per run".to_string()));
+    }
+    let shared = |path: fn(&RunConfig) -> &PathBuf| {
+        let paths: HashSet<&PathBuf> = runs.iter().map(|(_, params)| path(params)).collect();
+        paths.len() < runs.len()
+    };
+    let (shared_output, shared_proof) = (shared(|p| &p.output), shared(|p| &p.proof));
+    for (i, (_, params)) in runs.iter_mut().enumerate() {
+        if shared_output && params.output != Path::new("-") {
+            params.output = suffixed_path(&params.output, &format!("run{}", i + 1));
+        }
+        if shared_proof {
+            params.proof = suffixed_path(&params.proof, &format!("run{}", i + 1));
+        }
+    }
+
+    let mut stats = Vec::with_capacity(runs.len());
+    for (label, params) in &runs {
+        status!("Run {label}");
+        let summary = match dispatch(Action::ProveAndVerify { save_proof: true }, params) {
+            Ok(mut summaries) => summaries.pop().unwrap_or_default(),
+            Err(e) => RunSummary { num_steps: params.num_steps, error: Some(e), ..Default::default() },
+        };
+        stats.push(RunStats {
+            label: label.clone(),
+            num_steps: params.num_steps,
+            sequence: params.demo().label().to_string(),
+            field: params.field,
+            hash: params.hash,
+            fri: params.fri,
+            trace_path: summary.trace_path,
+            proof_path: summary.proof_path,
+            proof_bytes: summary.proof_bytes,
+            timings: summary.timings,
+            verified: summary.verified,
+            error: summary.error.map(|e| e.to_string()),
+        });
+    }
+
+    let comparison = Comparison::new(stats);
+    status!("{:<24} {:>10} {:>12} {:>12} {:>12}", "run", "num_steps", "proof bytes", "prove", "verify");
+    for run in &comparison.runs {
+        let bytes = run.proof_bytes.map_or_else(|| "-".to_string(), |bytes| bytes.to_string());
+        let t = &run.timings;
+        status!("{:<24} {:>10} {bytes:>12} {:>9.1} ms {:>9.1} ms", run.label, run.num_steps, t.prove_ms, t.verify_ms);
+    }
+    let json = serde_json::to_string_pretty(&comparison).map_err(|e| FibVisError::Serialization(e.to_string()))?;
+    if let Some(parent) = output.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| FibVisError::io(parent, e))?;
+    }
+    std::fs::write(output, json + "\n").map_err(|e| FibVisError::io(output, e))?;
+    let failed = comparison.runs.iter().filter(|run| !run.verified).count();
+    status!("Comparison of {} runs ({failed} failed) written to {}", comparison.runs.len(), output.display());
+    Ok(())
+}
+//-----------------------------------------------------------
+
+
+// The single document printed to stdout in --json mode, and saved as meta.json of a --run-dir run.
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    config: &'a RunConfig,
+    runs: &'a [RunSummary],
+}
+
+fn main() -> ExitCode {
+    // Usage errors exit with 1 like configuration errors, --help and --version with 0.
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            return if e.use_stderr() { ExitCode::from(1) } else { ExitCode::SUCCESS };
+        }
+    };
+
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("FibVisError: {e}");
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+// Rows `inspect` shows by default.
+const INSPECT_ROWS: usize = 6;
+
+// `inspect`: the shape of an export and a few of its rows.
+fn inspect(path: &Path, rows: usize) -> Result<(), FibVisError> {
+    let vis = VisData::from_path(path)?;
+    let width = vis.trace.first().map_or(vis.columns.len(), Vec::len);
+    let field = if vis.field.name.is_empty() { "an unknown field" } else { vis.field.name.as_str() };
+    println!("{}: schema version {}, {} over {field}", path.display(), vis.schema_version, vis.sequence);
+    println!("  num_steps: {}", vis.num_steps);
+    println!("  width:     {width}");
+    println!("  rows:      {}", vis.trace.len());
+    println!("  columns:   {}", column_names(&vis.columns).join(", "));
+    if let Some(stats) = &vis.stats {
+        println!("  proof:     {} bytes", stats.proof_bytes);
+    }
+    println!();
+    print!("{}", trace_table(&vis, rows, terminal_width()));
+    Ok(())
+}
+
+// `inspect-proof`: the proof at `path`, decoded for the config its header names. The header has everything
+// dispatch picks the config by, which then prints the FRI config and the constraints as a run does.
+fn inspect_proof_file(path: &Path, json: bool) -> Result<(), FibVisError> {
+    JSON_MODE.store(json, Ordering::Relaxed);
+    let (file, file_bytes) = ProofFile::read(path)?;
+    let header = &file.header;
+    let params = RunConfig {
+        num_steps: header.num_steps,
+        final_value: Some(header.final_value),
+        initial: header.initial.clone(),
+        sequence: header.sequence,
+        recurrence: header.recurrence.clone(),
+        field: header.field,
+        hash: header.hash,
+        fri: header.fri,
+        proof: path.to_path_buf(),
+        seed: header.seed.clone(),
+        ..RunConfig::default()
+    };
+    dispatch(Action::InspectProof { file: Box::new(file), file_bytes, json }, &params)?;
+    Ok(())
+}
 
-    Registry::default()
-        .with(env_filter)
-        .with(ForestLayer::default())
-        .init();
+// The inspection as a tree, or as JSON with --json.
+fn print_proof_inspection(inspection: &ProofInspection, json: bool) -> Result<(), FibVisError> {
+    if json {
+        let json = serde_json::to_string_pretty(inspection).map_err(|e| FibVisError::Serialization(e.to_string()))?;
+        println!("{json}");
+        return Ok(());
+    }
+    let header = &inspection.header;
+    println!(
+        "Proof of {} steps of {} over {} with {}, {} bytes",
+        header.num_steps,
+        header.sequence.name(),
+        header.field.name(),
+        header.hash.name(),
+        inspection.file_bytes
+    );
+    println!("  commit-phase rounds: {}", inspection.commit_phase_rounds);
+    println!("  queries:             {}", inspection.num_queries);
+    println!("  opened values:");
+    for opened in &inspection.opened_values {
+        println!("    {:<17} {} field elements", opened.name, opened.values);
+    }
+    println!("  commitments:");
+    for commitment in &inspection.commitments {
+        let name = match commitment.phase {
+            CommitPhase::FriCommitPhase => format!("{} {}", commitment.phase.name(), commitment.round),
+            phase => phase.name().to_string(),
+        };
+        println!("    {name:<18} {}", commitment.root);
+    }
+    println!("  pow witness:         {} ({} bits)", inspection.pow.witness, inspection.pow.bits);
+    println!("  bytes:");
+    print_proof_section(&inspection.sections, 2);
+    Ok(())
+}
 
-    type Val = Mersenne31;
-    type Challenge = BinomialExtensionField<Val, 3>;
+// `section` and its sections below it, `depth` levels in, with the byte counts in one column.
+fn print_proof_section(section: &ProofSection, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let width = 32usize.saturating_sub(indent.len());
+    println!("{indent}{:<width$} {:>8}", section.name, section.bytes);
+    for child in &section.sections {
+        print_proof_section(child, depth + 1);
+    }
+}
 
-    type ByteHash = Keccak256Hash;
-    type FieldHash = SerializingHasher<ByteHash>;
-    let byte_hash = ByteHash {};
-    let field_hash = FieldHash::new(Keccak256Hash {});
+// `schema`: the JSON Schema of the export, which needs no run parameters.
+fn write_schema(output: Option<&Path>) -> Result<(), FibVisError> {
+    let mut schema = serde_json::to_string_pretty(&vis_data_schema())
+        .map_err(|e| FibVisError::Serialization(format!("JSON Schema: {e}")))?;
+    schema.push('\n');
+    match output {
+        None => print!("{schema}"),
+        Some(path) => std::fs::write(path, schema).map_err(|e| FibVisError::io(path, e))?,
+    }
+    Ok(())
+}
 
-    type MyCompress = CompressionFunctionFromHasher<ByteHash, 2, 32>;
-    let compress = MyCompress::new(byte_hash);
+// `serve`: the directory over HTTP until Ctrl+C.
+fn serve(dir: &Path, port: u16, max_steps: usize) -> Result<(), FibVisError> {
+    spawn_server(dir, port, max_steps, None)?.wait()
+}
 
-    type ValMmcs = MerkleTreeMmcs<Val, u8, FieldHash, MyCompress, 32>;
-    let val_mmcs = ValMmcs::new(field_hash, compress);
+// A StaticServer answering on a thread of its own, while the run goes on in main's.
+struct ServerThread {
+    thread: std::thread::JoinHandle<()>,
+    shutdown: ShutdownHandle,
+}
 
-    type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
-    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+// Serves `dir` until Ctrl+C, with the progress in `events` on /events. A second Ctrl+C also ends a run that is
+// still going.
+fn spawn_server(dir: &Path, port: u16, max_steps: usize, events: Option<EventLog>) -> Result<ServerThread, FibVisError> {
+    let mut server = StaticServer::bind(dir, port)?.with_max_steps(max_steps);
+    if let Some(log) = events {
+        server = server.with_events(log);
+    }
+    let shutdown = server.shutdown_handle();
+    let (on_ctrlc, stopped) = (shutdown.clone(), AtomicBool::new(false));
+    ctrlc::set_handler(move || {
+        if stopped.swap(true, Ordering::Relaxed) {
+            std::process::exit(130);
+        }
+        on_ctrlc.shutdown();
+    })
+    .map_err(|e| FibVisError::Config(format!("cannot stop the server on Ctrl+C: {e}")))?;
+    status!("Serving {} at {} (Ctrl+C to stop)", dir.display(), server.url());
+    Ok(ServerThread { thread: std::thread::spawn(move || server.run()), shutdown })
+}
+
+impl ServerThread {
+    // Until Ctrl+C.
+    fn wait(self) -> Result<(), FibVisError> {
+        let _ = self.thread.join();
+        status!("Server stopped");
+        Ok(())
+    }
+
+    fn stop(self) {
+        self.shutdown.shutdown();
+        let _ = self.thread.join();
+    }
+}
+
+// --emit-ts: the export's TypeScript declarations, generated from the same schema.
+fn write_typescript(path: &Path) -> Result<(), FibVisError> {
+    let ts = vis_data_typescript();
+    if path == Path::new("-") {
+        print!("{ts}");
+        return Ok(());
+    }
+    std::fs::write(path, ts).map_err(|e| FibVisError::io(path, e))
+}
+
+// Writes the minimal visualizer next to a plain JSON export or a --format js script, for a first run outside `src/` to leave a page to
+// serve. Files that are there already, the full visualizer in src/web/ among them, are kept unless `force`.
+fn scaffold_viewer(params: &RunConfig, force: bool) -> Result<(), FibVisError> {
+    let output = &params.output;
+    let Some(trace_file) = output.file_name().and_then(|name| name.to_str()) else { return Ok(()) };
+    let readable = params.format == TraceFormat::Json || (params.format == TraceFormat::Js && !params.esm);
+    if !readable || params.compress || output == Path::new("-") {
+        return Ok(());
+    }
+    let dir = output.parent().unwrap_or(Path::new(""));
+    for (path, status) in write_assets(dir, trace_file, force)? {
+        if status == AssetStatus::Written {
+            status!("Wrote {}, the visualizer for {trace_file}", path.display());
+        }
+    }
+    Ok(())
+}
 
-    type Challenger = SerializingChallenger32<Val, HashChallenger<u8, ByteHash, 32>>;
+fn run(cli: Cli) -> Result<(), FibVisError> {
+    if cli.list_airs {
+        for demo in DEMOS.iter() {
+            println!("{:<18} {}", demo.name(), demo.description());
+        }
+        return Ok(());
+    }
+    if let Some(path) = &cli.emit_ts {
+        return write_typescript(path);
+    }
 
-    let fri_config = FriConfig {
-        log_blowup: 1,
-        num_queries: 100,
-        proof_of_work_bits: 16,
-        mmcs: challenge_mmcs,
-        log_final_poly_len: 1,
+    let mut serve_after = None;
+    let (action, args) = match cli.command {
+        Some(Command::ExportTrace { params }) => (None, params),
+        Some(Command::Serve { dir, port, regenerate: false, max_steps, .. }) => return serve(&dir, port, max_steps),
+        Some(Command::Serve { dir, port, regenerate: true, prove, max_steps, params }) => {
+            serve_after = Some((dir, port, max_steps));
+            (prove.then_some(Action::ProveAndVerify { save_proof: true }), params)
+        }
+        Some(Command::Prove { params }) => (Some(Action::Prove), params),
+        Some(Command::Verify { params }) => (Some(Action::Verify), params),
+        Some(Command::Schema { output }) => return write_schema(output.as_deref()),
+        Some(Command::Inspect { file, rows }) => return inspect(&file, rows),
+        Some(Command::InspectProof { path, json }) => return inspect_proof_file(&path, json),
+        Some(Command::Compare { configs, runs, output }) => return compare(&configs, &runs, &output),
+        // No subcommand: the original all-in-one run, or a sweep over several sizes.
+        None => match cli.sweep.or(cli.sweep_pow2) {
+            Some(sizes) => (Some(Action::Sweep(sizes)), cli.params),
+            None => (Some(Action::ProveAndVerify { save_proof: true }), cli.params),
+        },
     };
+    JSON_MODE.store(args.json, Ordering::Relaxed);
 
-    type Pcs = CirclePcs<Val, ValMmcs, ChallengeMmcs>;
-    let pcs = Pcs {
-        mmcs: val_mmcs,
-        fri_config,
-        _phantom: PhantomData,
+    let mut params = args.resolve()?;
+    // prove saves a proof of the generated trace, a tampered one is what a full run or --check would explain
+    if matches!(action, Some(Action::Prove)) && !args.check && !params.tamper.is_empty() {
+        return Err(FibVisError::Config(
+            "prove does not apply --tamper, run without a subcommand to watch a tampered trace fail".to_string(),
+        ));
+    }
+    if args.track_memory {
+        memory::enable();
+    }
+    let progress = args.progress || params.num_steps >= PROGRESS_AUTO_ROWS;
+    plonky3_fibonacci::progress::set_enabled(progress);
+
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    // Dropped at the end of main, after the last span, which completes the --profile file.
+    let (profile, _profile_guard) = match &args.profile {
+        Some(path) => {
+            let (layer, guard) = chrome_trace(path)?;
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
     };
+    // What serve --regenerate streams on /events
+    let events = serve_after.is_some().then(EventLog::default);
+    // In --json mode stdout is reserved for the report, so the span tree goes to stderr instead.
+    if args.json {
+        Registry::default()
+            .with(env_filter)
+            .with(ForestLayer::from(Printer::new().writer(io::stderr)))
+            .with(progress.then_some(PhaseProgress))
+            .with(profile)
+            .with(events.clone().map(PipelineEvents::new))
+            .init();
+    } else {
+        Registry::default()
+            .with(env_filter)
+            .with(ForestLayer::default())
+            .with(progress.then_some(PhaseProgress))
+            .with(profile)
+            .with(events.clone().map(PipelineEvents::new))
+            .init();
+    }
 
-    type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
-    let challenger = Challenger::from_hasher(vec![], byte_hash);
-    let config = MyConfig::new(pcs, challenger);
+    if args.print_config {
+        print!("{}", toml::to_string_pretty(&params).map_err(|e| FibVisError::Serialization(e.to_string()))?);
+        return Ok(());
+    }
+    if args.json && params.output == Path::new("-") {
+        return Err(FibVisError::Config("--json already uses stdout for the report, pick a file for --output".to_string()));
+    }
 
-    let num_steps = 8; // Choose the number of Fibonacci steps in powers of 2^n
-    let final_value = 21; // Expected result of final Fibonacci value
-    let air = FibonacciAir { num_steps, final_value };
-    let trace = generate_fibonacci_trace::<Val>(num_steps);
-    
+    if cli.watch {
+        return watch(&args, cli.prove_on_change);
+    }
+
+    let run_dir = match &cli.run_dir {
+        Some(root) => {
+            let dir = create_run_dir(root, params.num_steps, cli.force)?;
+            params.output = params.export_path(&dir.join("trace_data"));
+            params.proof = dir.join("proof.bin");
+            Some(dir)
+        }
+        None => None,
+    };
 
-    //-----------------------------------------------------------
-    // Create a mutable trace matrix (Vec<Vec<String>>). We populate it by iterating through Seong's trace variable (line 141) with 
-    // the p3_matrix::Matrix method signature for height (returns number of rows).
+    if run_dir.is_none() {
+        scaffold_viewer(&params, cli.force_assets)?;
+    }
+
+    // Up before the run, so the page can follow it
+    let server = match serve_after {
+        Some((dir, port, max_steps)) => Some(spawn_server(&dir, port, max_steps, events)?),
+        None => None,
+    };
 
-    let mut trace_matrix = Vec::new();
+    let is_sweep = matches!(action, Some(Action::Sweep(_)));
+    let runs = match action {
+        _ if args.check => vec![timed(|| check_only(&params))],
+        Some(action) => dispatch(action, &params)?,
+        None => vec![timed(|| export_only(&params))],
+    };
 
-    for i in 0..trace.height() {
-        let row = trace
-                        .row_slice(i)           // Accesses row i of the trace matrix, returning Some(&[F]) if exists, else None. 
-                        .unwrap()               // Returns the slice. Will panic if i is out of bounds.
-                        .iter()                 // Creates an iterator (pointer) over the elements of a specific row.
-                        .map(|v| v.to_string()) // For every element v produced/pointed by the iterator, convert it from Mersenne31 to String.
-                        .collect();             // Collects iterator, allocated memory on the heap, and pushes the strings into a Vec<String>.
-        trace_matrix.push(row); // Appends row (Vec<String>) to the end of trace_matrix (Vec<Vec<String>>).
+    if let (Some(dir), Some(root)) = (&run_dir, &cli.run_dir) {
+        write_run_meta(dir, &JsonReport { config: &params, runs: &runs })?;
+        update_latest(root, dir)?;
+        status!("Run saved in {}", dir.display());
     }
-    
-    // Create an immutable instance of the VisData struct to Export
-    let vis_data = VisData{num_steps, trace: trace_matrix};
 
-    // Export trace
-    let json_valid = serde_json::to_string_pretty(&vis_data).unwrap(); // Convert vis_data into a JSON formatted string. to_string_pretty() provides indentation and newlines.
-    std::fs::create_dir_all("web").expect("Failed to create web directory"); // Create the web/ directory if it does not already exist. 
-    let mut file_valid = File::create("web/trace_data.json").expect("Failed to create web/trace_data.json");
-    file_valid.write_all(json_valid.as_bytes()).unwrap();
-    println!("Valid trace exported to web/trace_data.json");
+    if args.json {
+        let report = JsonReport { config: &params, runs: &runs };
+        println!("{}", serde_json::to_string_pretty(&report).map_err(|e| FibVisError::Serialization(e.to_string()))?);
+        return server.map_or(Ok(()), ServerThread::wait);
+    }
 
-    //-----------------------------------------------------------
+    for (run, heap) in runs.iter().filter_map(|run| Some((run, run.memory?))) {
+        status!(
+            "Peak memory for {} steps: {:.1} MiB (trace generation {:+.1} MiB, export {:+.1} MiB, proving {:+.1} MiB, \
+             verification {:+.1} MiB)",
+            run.num_steps,
+            mebibytes(heap.peak_bytes as i64),
+            mebibytes(heap.trace_gen_bytes),
+            mebibytes(heap.export_bytes),
+            mebibytes(heap.prove_bytes),
+            mebibytes(heap.verify_bytes)
+        );
+    }
 
+    // A tampered trace is supposed to be rejected, so for those runs proving/verification failures are the
+    // demo working as intended rather than an error of the program.
+    for run in &runs {
+        if !run.tampered {
+            continue;
+        }
+        match &run.error {
+            Some(e @ (FibVisError::Prove(_) | FibVisError::Verify(_) | FibVisError::ConstraintViolation { .. })) => {
+                status!("Tampered trace rejected as expected: {e}")
+            }
+            None if run.verified => status!("Warning: the tampered trace still verified, did --tamper change any value?"),
+            _ => {}
+        }
+    }
 
-    let proof = prove(&config, &air, trace, &vec![]);
-    verify(&config, &air, &proof, &vec![])
+    // A sweep records failing sizes in its summary, any other command fails with the error.
+    let unexpected = |r: &RunSummary| {
+        !(r.tampered
+            && matches!(r.error, Some(FibVisError::Prove(_) | FibVisError::Verify(_) | FibVisError::ConstraintViolation { .. })))
+    };
+    match runs.into_iter().filter(unexpected).find_map(|r| r.error) {
+        Some(error) if !is_sweep => {
+            if let Some(server) = server {
+                server.stop();
+            }
+            Err(error)
+        }
+        _ => server.map_or(Ok(()), ServerThread::wait),
+    }
 }
@@ -0,0 +1,93 @@
+//! Heap usage for `--track-memory`: CountingAllocator wraps the system allocator and counts the bytes allocated,
+//! so a run can report how much memory it peaked at and how much each phase kept. A binary opts in by installing
+//! it as its `#[global_allocator]`; without that every count stays zero.
+//!
+//! The allocated bytes are always counted, one relaxed atomic add per allocation, so they are exact whenever
+//! tracking is turned on. The peak, the more expensive compare-and-swap, is only kept while it is on.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+static TRACKING: AtomicBool = AtomicBool::new(false);
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator, counting what it hands out, see the module documentation.
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn allocated(size: usize) {
+        let now = ALLOCATED.fetch_add(size, Ordering::Relaxed) + size;
+        if TRACKING.load(Ordering::Relaxed) {
+            PEAK.fetch_max(now, Ordering::Relaxed);
+        }
+    }
+
+    fn freed(size: usize) {
+        ALLOCATED.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+// SAFETY: every call is passed on to System unchanged; the counters never touch the memory.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::freed(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            Self::freed(layout.size());
+            Self::allocated(new_size);
+        }
+        new
+    }
+}
+
+/// Starts keeping the peak, from what is allocated now.
+pub fn enable() {
+    TRACKING.store(true, Ordering::Relaxed);
+    reset_peak();
+}
+
+/// Whether the peak is being kept, see enable.
+pub fn is_enabled() -> bool {
+    TRACKING.load(Ordering::Relaxed)
+}
+
+/// Bytes currently allocated through CountingAllocator.
+pub fn allocated() -> usize {
+    ALLOCATED.load(Ordering::Relaxed)
+}
+
+/// The change in allocated bytes since `before`, an earlier allocated(): negative when more was freed.
+pub fn allocated_since(before: usize) -> i64 {
+    allocated() as i64 - before as i64
+}
+
+/// The most bytes allocated at once since tracking was enabled or the peak last reset.
+pub fn peak() -> usize {
+    PEAK.load(Ordering::Relaxed)
+}
+
+/// Starts the peak over from what is allocated now, to measure one run of several.
+pub fn reset_peak() {
+    PEAK.store(allocated(), Ordering::Relaxed);
+}
@@ -0,0 +1,83 @@
+//! The Merkle tree of the trace commitment (`--export-merkle`), for the visualizer to draw it whole: the trace is
+//! committed again the way the prover commits it, through the config's PCS and so with its MMCS, leaf hash and
+//! compression, and every level of the tree is read from the PCS's prover data through its serde form. The PCS
+//! commits to the trace's low-degree extension, so the leaves are its 2^log_blowup times as many rows, in the order
+//! the PCS puts them in; the root is the trace commitment in the proof.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use p3_commit::Pcs;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_uni_stark::{PcsProverData, StarkGenericConfig, Val};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::FibVisError;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::export::write_atomically;
+use crate::proof_json::{find_key, root_hex};
+use crate::stark_config::{FieldChoice, HashChoice};
+
+/// The most trace rows the Merkle tree is exported for. Every node is listed, so the file grows with the LDE.
+pub const MERKLE_MAX_ROWS: usize = 1 << 12;
+
+/// What `--export-merkle` writes: the tree of the trace commitment and what it was made with.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MerkleTreeExport {
+    pub field: FieldChoice,
+    pub hash: HashChoice,
+    pub num_steps: usize,
+    pub log_blowup: usize,
+    #[serde(flatten)]
+    pub tree: TraceTree,
+}
+
+/// A Merkle tree's digests, hex-encoded like the roots of proof_json::commitment_roots.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TraceTree {
+    pub root: String,             // the trace commitment, as commitment_roots lists it
+    pub levels: Vec<Vec<String>>, // the digests level by level, from the leaves' up to the root alone
+}
+
+/// The Merkle tree the prover commits `trace` to with `config`. The trace can have at most MERKLE_MAX_ROWS rows, a
+/// power of two of them as for proving.
+pub fn merkle_tree<SC: StarkGenericConfig>(
+    config: &SC,
+    trace: RowMajorMatrix<Val<SC>>,
+) -> Result<TraceTree, FibVisError>
+where
+    PcsProverData<SC>: Serialize,
+{
+    let height = trace.height();
+    if !height.is_power_of_two() || height > MERKLE_MAX_ROWS {
+        return Err(FibVisError::Config(format!(
+            "the Merkle tree is exported for a power-of-two trace height of at most {MERKLE_MAX_ROWS}, not {height}"
+        )));
+    }
+    let pcs = config.pcs();
+    let domain = pcs.natural_domain_for_degree(height);
+    let (commitment, data) = pcs.commit(vec![(domain, trace)]);
+
+    let encoding = |e: serde_json::Error| FibVisError::Serialization(format!("Merkle tree: {e}"));
+    let root = root_hex(&serde_json::to_value(&commitment).map_err(encoding)?);
+    let data = serde_json::to_value(&data).map_err(encoding)?;
+    let Some(Value::Array(layers)) = find_key(&data, "digest_layers") else {
+        return Err(FibVisError::Serialization("Merkle tree: the prover data has no digest layers".to_string()));
+    };
+    let levels = layers
+        .iter()
+        .map(|layer| layer.as_array().map_or_else(Vec::new, |digests| digests.iter().map(root_hex).collect()))
+        .collect();
+    Ok(TraceTree { root, levels })
+}
+
+/// Writes `export` to `path` as compact JSON. Written atomically, parent directories created.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_merkle_json(path: &Path, export: &MerkleTreeExport) -> Result<(), FibVisError> {
+    write_atomically(path, |out| {
+        serde_json::to_writer(out, export)?;
+        Ok(())
+    })
+}
@@ -0,0 +1,31 @@
+//! The types a prove-and-export program needs, in one import:
+//!
+//! ```no_run
+//! use plonky3_fibonacci::prelude::*;
+//! ```
+//!
+//! Besides this crate's own items it re-exports the Plonky3 types they are used with, so a downstream crate
+//! does not have to depend on the individual p3 crates to get started.
+
+pub use crate::air::{
+    BitDecompAir, CollatzAir, CounterAir, FibonacciAir, FibonacciWithIndexAir, IndexedFibonacciAir, LinearRecurrenceAir,
+    LucasAir, MulFibonacciAir, SequenceAir, TribonacciAir,
+};
+pub use crate::error::FibVisError;
+pub use crate::export::{trace_json_string, vis_data_from_matrix, ColumnMeta, VisData, FIBONACCI_COLUMNS};
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::export::write_trace_json;
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::prove::{prove_fibonacci, verify_fibonacci, FibonacciParams, FibonacciRun};
+pub use crate::stark_config::{ConfigBuilder, FieldChoice, FriParams, HashChoice, M31KeccakConfig, Seed};
+pub use crate::registry::{DemoAir, DemoParams};
+pub use crate::sequence::Sequence;
+pub use crate::trace::{
+    fibonacci_final_value, generate_bit_decomp_trace, generate_collatz_trace, generate_counter_trace,
+    generate_fibonacci_trace, generate_fibonacci_with_index_trace, generate_linear_recurrence_trace, generate_lucas_trace, generate_mul_fibonacci_trace,
+    generate_padded_fibonacci_trace, generate_tribonacci_trace, FIBONACCI_START,
+};
+
+pub use p3_matrix::dense::RowMajorMatrix;
+pub use p3_mersenne_31::Mersenne31;
+pub use p3_uni_stark::{prove, verify};
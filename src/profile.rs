@@ -0,0 +1,121 @@
+//! The `--profile` timeline: a tracing layer that writes every span, as it is entered and left, as a begin and an
+//! end event of the Chrome trace-event format, which Perfetto and about://tracing open as nested slices per
+//! thread. The spans are the phases PhaseProgress reports, ours and uni-stark's own.
+//!
+//! Events go straight to the file as they happen, one per line, and the guard closes the JSON array when it is
+//! dropped. The format lets the closing bracket be missing, so the file of a run that failed midway still loads.
+
+use std::cell::Cell;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Instant;
+
+use serde_json::{json, Value};
+use tracing::{span, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::error::FibVisError;
+
+/// Tracing layer writing the spans it sees to a Chrome trace file, see chrome_trace.
+pub struct ChromeTraceLayer {
+    out: Arc<Mutex<Output>>,
+    start: Instant,
+}
+
+/// Closes the Chrome trace file once dropped; keep it until the last span has ended.
+pub struct ChromeTraceGuard {
+    out: Arc<Mutex<Output>>,
+}
+
+struct Output {
+    file: File,    // unbuffered, so every event is in the file once written
+    events: usize, // written so far, to know whether the next one needs a comma
+    closed: bool,  // the array is closed, anything after it would break the file
+}
+
+// Threads are numbered in the order they first enter a span; the trace names each one the way std does.
+static NEXT_THREAD: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// A layer writing the timeline of every span to `path` (parent directories created), and the guard that
+/// finishes the file.
+pub fn chrome_trace(path: &Path) -> Result<(ChromeTraceLayer, ChromeTraceGuard), FibVisError> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| FibVisError::io(parent, e))?;
+    }
+    let mut file = File::create(path).map_err(|e| FibVisError::io(path, e))?;
+    file.write_all(b"[\n").map_err(|e| FibVisError::io(path, e))?;
+    let out = Arc::new(Mutex::new(Output { file, events: 0, closed: false }));
+    Ok((ChromeTraceLayer { out: out.clone(), start: Instant::now() }, ChromeTraceGuard { out }))
+}
+
+impl Output {
+    // A failed write loses that event, not the run.
+    fn write(&mut self, event: &Value) {
+        if self.closed {
+            return;
+        }
+        let separator = if self.events == 0 { "" } else { ",\n" };
+        let _ = self.file.write_all(format!("{separator}{event}").as_bytes());
+        self.events += 1;
+    }
+}
+
+impl ChromeTraceLayer {
+    // A span that panicked while the lock was held leaves the file as it was, so it is still usable.
+    fn out(&self) -> MutexGuard<'_, Output> {
+        self.out.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn slice(&self, phase: &str, name: &str, category: &str) {
+        let pid = std::process::id();
+        let mut out = self.out();
+        // Taken under the lock, so the file is in time order across threads
+        let ts = self.start.elapsed().as_secs_f64() * 1e6;
+        let tid = THREAD.with(|thread| match thread.get() {
+            Some(tid) => tid,
+            None => {
+                let tid = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
+                thread.set(Some(tid));
+                let current = std::thread::current();
+                let thread_name = current.name().map_or_else(|| format!("thread {tid}"), str::to_string);
+                let args = json!({"name": thread_name});
+                out.write(&json!({"name": "thread_name", "ph": "M", "pid": pid, "tid": tid, "args": args}));
+                tid
+            }
+        });
+        out.write(&json!({"name": name, "cat": category, "ph": phase, "ts": ts, "pid": pid, "tid": tid}));
+    }
+}
+
+impl<S> Layer<S> for ChromeTraceLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            self.slice("B", span.name(), span.metadata().target());
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            self.slice("E", span.name(), span.metadata().target());
+        }
+    }
+}
+
+impl Drop for ChromeTraceGuard {
+    fn drop(&mut self) {
+        let mut out = self.out.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = out.file.write_all(b"\n]\n");
+        out.closed = true;
+    }
+}
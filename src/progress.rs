@@ -0,0 +1,90 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+#[cfg(not(target_arch = "wasm32"))]
+use indicatif::{ProgressBar, ProgressStyle};
+#[cfg(not(target_arch = "wasm32"))]
+use tracing::{span, Subscriber};
+#[cfg(not(target_arch = "wasm32"))]
+use tracing_subscriber::layer::{Context, Layer};
+#[cfg(not(target_arch = "wasm32"))]
+use tracing_subscriber::registry::LookupSpan;
+
+// Progress reporting for large traces. When enabled (--progress, or automatically from PROGRESS_AUTO_ROWS rows)
+// every phase span prints a line to stderr when it starts and when it finishes. The spans are the ones we
+// put around trace generation, export, proving and verification, plus the commitment/quotient/FRI spans
+// uni-stark opens inside `prove`, so the same phases also show up in the tracing-forest tree. The loops
+// under our control (trace generation and string conversion) additionally get a progress bar.
+static PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// Turns the progress bars of trace generation and export on or off (off by default).
+pub fn set_enabled(enabled: bool) {
+    PROGRESS.store(enabled, Ordering::Relaxed);
+}
+
+/// Trace height from which the binary enables progress reporting without --progress.
+pub const PROGRESS_AUTO_ROWS: usize = 1 << 16;
+pub(crate) const PROGRESS_CHUNK: usize = 1 << 12; // rows between progress bar updates
+#[cfg(not(target_arch = "wasm32"))]
+const PROGRESS_MAX_DEPTH: usize = 2; // our phases are depth 0, uni-stark's own phases sit below them
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn row_progress(rows: usize, label: &'static str) -> ProgressBar {
+    if !PROGRESS.load(Ordering::Relaxed) {
+        return ProgressBar::hidden();
+    }
+    let style = ProgressStyle::with_template("{msg:>14} [{bar:40}] {pos}/{len} ({elapsed})")
+        .unwrap_or_else(|_| ProgressStyle::default_bar())
+        .progress_chars("=> ");
+    ProgressBar::new(rows as u64).with_style(style).with_message(label)
+}
+
+// There is no terminal to draw on in the browser, so on wasm the loops get a bar that does nothing.
+#[cfg(target_arch = "wasm32")]
+pub(crate) struct HiddenBar;
+
+#[cfg(target_arch = "wasm32")]
+impl HiddenBar {
+    pub(crate) fn set_position(&self, _pos: u64) {}
+
+    pub(crate) fn finish_and_clear(&self) {}
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn row_progress(_rows: usize, _label: &'static str) -> HiddenBar {
+    HiddenBar
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct PhaseStart(Instant);
+
+/// Tracing layer printing every phase span (down to uni-stark's own phases) with its elapsed time to stderr.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct PhaseProgress;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<S> Layer<S> for PhaseProgress
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let depth = span.scope().count() - 1;
+        if depth <= PROGRESS_MAX_DEPTH {
+            eprintln!("{:indent$}> {}", "", span.name(), indent = depth * 2);
+        }
+        span.extensions_mut().insert(PhaseStart(Instant::now()));
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else { return };
+        let depth = span.scope().count() - 1;
+        if depth > PROGRESS_MAX_DEPTH {
+            return;
+        }
+        if let Some(PhaseStart(start)) = span.extensions().get::<PhaseStart>() {
+            eprintln!("{:indent$}< {} ({:.1?})", "", span.name(), start.elapsed(), indent = depth * 2);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! The proof file `prove` writes and `verify` reads (proof.bin): a header recording what the proof was generated
+//! for, then the postcard-encoded uni-stark proof as an opaque body. The header comes first and is decoded on its
+//! own, so it can always be read back and checked, even when the body belongs to a different field or hash, and
+//! the body is only decoded once its length and Keccak-256 digest match the ones the header gives. A truncated or
+//! corrupted file is therefore a Serialization error, never a panic.
+//!
+//! The layout, all postcard: the 4 bytes of PROOF_MAGIC, the format version as a u16, the ProofHeader, the body's
+//! length and digest, then the body itself.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::Write;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use p3_uni_stark::{Proof, StarkGenericConfig};
+use serde::{Deserialize, Serialize};
+
+use crate::error::FibVisError;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::export::write_atomically;
+use crate::export::proof_digest;
+use crate::sequence::Sequence;
+use crate::stark_config::{FieldChoice, FriParams, HashChoice, Seed};
+
+/// The first bytes of every proof file.
+pub const PROOF_MAGIC: [u8; 4] = *b"FIBP";
+/// Version of the layout, which is only read back by a build of the same version. 2: the header records the
+/// challenger seed, 3: the initial values, 4: the sequence, 5: the recurrence, 6: the public values, with the
+/// header decoded before the body and the body's length and digest checked.
+pub const PROOF_FORMAT_VERSION: u16 = 6;
+
+/// What a proof was generated for, everything `verify` compares with its own parameters before it decodes the proof.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProofHeader {
+    pub field: FieldChoice,
+    pub hash: HashChoice,
+    pub fri: FriParams,
+    pub num_steps: usize,
+    pub sequence: Sequence,
+    pub recurrence: Vec<u64>,       // the coefficients of a --recurrence trace, empty otherwise
+    pub initial: Vec<u64>,
+    pub final_value: u64,
+    pub public_values: Vec<String>, // what the proof is checked against, in decimal as the export lists them
+    pub seed: Seed,
+}
+
+/// A proof file read back or about to be written: its header and the postcard-encoded proof.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofFile {
+    pub header: ProofHeader,
+    pub proof: Vec<u8>,
+}
+
+// The body's length and digest, between the header and the body.
+#[derive(Serialize, Deserialize)]
+struct BodySeal {
+    len: usize,
+    digest: String,
+}
+
+impl ProofFile {
+    /// The file's bytes, see the module docs for the layout.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, FibVisError> {
+        let encode = |e: postcard::Error| FibVisError::Serialization(format!("proof file: {e}"));
+        let mut bytes = postcard::to_allocvec(&(PROOF_MAGIC, PROOF_FORMAT_VERSION)).map_err(encode)?;
+        bytes.extend(postcard::to_allocvec(&self.header).map_err(encode)?);
+        let seal = BodySeal { len: self.proof.len(), digest: proof_digest(&self.proof) };
+        bytes.extend(postcard::to_allocvec(&seal).map_err(encode)?);
+        bytes.extend_from_slice(&self.proof);
+        Ok(bytes)
+    }
+
+    /// Reads the bytes to_bytes wrote, checking the magic, the format version and the body against its seal. The
+    /// proof itself stays encoded, see decode.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FibVisError> {
+        let error = FibVisError::Serialization;
+        let corrupted = |e: postcard::Error| error(format!("corrupted header: {e}"));
+        if !bytes.starts_with(&PROOF_MAGIC) {
+            return Err(error("not a proof file written by `prove`".to_string()));
+        }
+        let ((_, version), rest) = postcard::take_from_bytes::<([u8; 4], u16)>(bytes).map_err(corrupted)?;
+        if version != PROOF_FORMAT_VERSION {
+            return Err(error(format!("uses proof format version {version}, this build reads {PROOF_FORMAT_VERSION}")));
+        }
+        let (header, rest) = postcard::take_from_bytes::<ProofHeader>(rest).map_err(corrupted)?;
+        let (seal, body) = postcard::take_from_bytes::<BodySeal>(rest).map_err(corrupted)?;
+        if body.len() != seal.len {
+            return Err(error(format!("truncated or padded: the proof is {} bytes, not {}", body.len(), seal.len)));
+        }
+        if proof_digest(body) != seal.digest {
+            return Err(error(format!("corrupted: the proof does not match the digest {} in its header", seal.digest)));
+        }
+        Ok(Self { header, proof: body.to_vec() })
+    }
+
+    /// Writes the file to `path`, atomically and with its parent directories, and gives back its size in bytes.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write(&self, path: &Path) -> Result<usize, FibVisError> {
+        let bytes = self.to_bytes()?;
+        write_atomically(path, |out: &mut dyn Write| Ok(out.write_all(&bytes)?))?;
+        Ok(bytes.len())
+    }
+
+    /// Reads the file at `path`, see from_bytes, and gives back its size in bytes with it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read(path: &Path) -> Result<(Self, usize), FibVisError> {
+        let bytes = std::fs::read(path).map_err(|e| FibVisError::io(path, e))?;
+        let file = Self::from_bytes(&bytes).map_err(|e| match e {
+            FibVisError::Serialization(reason) => FibVisError::Serialization(format!("{}: {reason}", path.display())),
+            e => e,
+        })?;
+        Ok((file, bytes.len()))
+    }
+
+    /// The proof, for the config the header names. Its bytes are the ones `prove` wrote, but a proof for another
+    /// config does not decode or fails verification.
+    pub fn decode<SC: StarkGenericConfig>(&self) -> Result<Proof<SC>, FibVisError> {
+        postcard::from_bytes(&self.proof).map_err(|e| FibVisError::Serialization(format!("corrupted proof: {e}")))
+    }
+}
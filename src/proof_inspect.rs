@@ -0,0 +1,314 @@
+//! What a saved proof holds, for `inspect-proof`: its header, the FRI rounds and queries, the opened values, the
+//! Merkle roots and the proof-of-work witness, and where its bytes go. The sizes are postcard's, the encoding of
+//! the proof file's body: every struct of the proof's serde form is broken down into its fields, each with the
+//! bytes it is encoded in, while a list or a value is one section and its elements are not listed one by one.
+
+use std::fmt;
+
+use p3_uni_stark::{Proof, StarkGenericConfig};
+use serde::ser;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::FibVisError;
+use crate::proof_file::{ProofFile, ProofHeader};
+use crate::proof_json::{
+    collect_numbers, commitment_roots, find_key, pow_grinding, CommitPhase, CommitmentRoot, PowGrinding,
+};
+
+/// One part of a proof's encoding: a field of its serde form and the bytes postcard encodes it in.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProofSection {
+    pub name: String,
+    pub bytes: usize,
+    pub sections: Vec<ProofSection>, // a struct's fields in order, but the empty ones; none for a list or a value
+}
+
+/// One of the proof's opened values, e.g. `trace_local`, the trace's row at the out-of-domain point.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OpenedValues {
+    pub name: String,
+    pub values: usize, // base field elements, an extension element counting once per coefficient
+}
+
+/// A proof file taken apart, see inspect_proof.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProofInspection {
+    pub header: ProofHeader,
+    pub file_bytes: usize,
+    pub commit_phase_rounds: usize,       // FRI folding rounds, one commitment each
+    pub num_queries: usize,
+    pub opened_values: Vec<OpenedValues>,
+    pub commitments: Vec<CommitmentRoot>, // in protocol order, see commitment_roots
+    pub pow: PowGrinding,
+    pub sections: ProofSection,           // the proof, the file's body, with its bytes broken down
+}
+
+/// Decodes the proof in `file`, which is `file_bytes` long on disk, for the config its header names, and takes it
+/// apart. Like `verify`, it fails on a proof for another config.
+pub fn inspect_proof<SC: StarkGenericConfig>(
+    file: &ProofFile,
+    file_bytes: usize,
+) -> Result<ProofInspection, FibVisError> {
+    let proof: Proof<SC> = file.decode()?;
+    let encoding = |e: serde_json::Error| FibVisError::Serialization(format!("proof: {e}"));
+    let json = serde_json::to_value(&proof).map_err(encoding)?;
+
+    let opened_values = match &json["opened_values"] {
+        Value::Object(openings) => openings
+            .iter()
+            .filter(|(_, values)| !values.is_null())
+            .map(|(name, values)| {
+                let mut numbers = Vec::new();
+                collect_numbers(values, &mut numbers);
+                OpenedValues { name: name.clone(), values: numbers.len() }
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let num_queries = match find_key(&json["opening_proof"], "query_proofs") {
+        Some(Value::Array(queries)) => queries.len(),
+        _ => 0,
+    };
+    let commitments = commitment_roots(&proof).map_err(encoding)?;
+    Ok(ProofInspection {
+        header: file.header.clone(),
+        file_bytes,
+        commit_phase_rounds: commitments.iter().filter(|c| c.phase == CommitPhase::FriCommitPhase).count(),
+        num_queries,
+        opened_values,
+        commitments,
+        pow: pow_grinding(&proof, file.header.fri.proof_of_work_bits, &[], None).map_err(encoding)?,
+        sections: proof_sections(&proof)?,
+    })
+}
+
+/// `proof` as postcard encodes it, broken down by the fields of its structs. The sections of a struct add up to
+/// its bytes; an enum or an Option spends a byte or so more on which one it is.
+pub fn proof_sections<P: Serialize>(proof: &P) -> Result<ProofSection, FibVisError> {
+    section("proof", proof).map_err(|e| FibVisError::Serialization(format!("proof: {e}")))
+}
+
+// The section `name` for `value`, with its bytes and its fields.
+fn section<T: Serialize + ?Sized>(name: &str, value: &T) -> Result<ProofSection, SizeError> {
+    let bytes = postcard::to_allocvec(value).map_err(|e| SizeError(e.to_string()))?.len();
+    Ok(ProofSection { name: name.to_string(), bytes, sections: value.serialize(Sizer)? })
+}
+
+// A serializer that turns a value into the sections of its fields, see section. It is not human readable, as
+// postcard is not, for types that serialize differently for the two.
+struct Sizer;
+
+#[derive(Debug)]
+struct SizeError(String);
+
+impl fmt::Display for SizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for SizeError {}
+
+impl ser::Error for SizeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        SizeError(msg.to_string())
+    }
+}
+
+// Values without fields of their own.
+macro_rules! no_sections {
+    ($($method:ident($ty:ty)),* $(,)?) => {
+        $(fn $method(self, _: $ty) -> Result<Self::Ok, SizeError> {
+            Ok(Vec::new())
+        })*
+    };
+}
+
+impl ser::Serializer for Sizer {
+    type Ok = Vec<ProofSection>;
+    type Error = SizeError;
+    type SerializeSeq = Whole;
+    type SerializeTuple = Whole;
+    type SerializeTupleStruct = Whole;
+    type SerializeTupleVariant = Whole;
+    type SerializeMap = Whole;
+    type SerializeStruct = Fields;
+    type SerializeStructVariant = Fields;
+
+    no_sections!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_unit_struct(&'static str),
+    );
+
+    fn serialize_none(self) -> Result<Self::Ok, SizeError> {
+        Ok(Vec::new())
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, SizeError> {
+        Ok(Vec::new())
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, _: u32, _: &'static str) -> Result<Self::Ok, SizeError> {
+        Ok(Vec::new())
+    }
+
+    // An Option, a newtype and a newtype variant have the fields of what they wrap.
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, SizeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, SizeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, SizeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Whole, SizeError> {
+        Ok(Whole)
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Whole, SizeError> {
+        Ok(Whole)
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Whole, SizeError> {
+        Ok(Whole)
+    }
+
+    fn serialize_tuple_variant(self, _: &'static str, _: u32, _: &'static str, _: usize) -> Result<Whole, SizeError> {
+        Ok(Whole)
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Whole, SizeError> {
+        Ok(Whole)
+    }
+
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<Fields, SizeError> {
+        Ok(Fields(Vec::with_capacity(len)))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        len: usize,
+    ) -> Result<Fields, SizeError> {
+        Ok(Fields(Vec::with_capacity(len)))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+// A list, tuple or map, whose elements are not sections of their own.
+struct Whole;
+
+macro_rules! whole {
+    ($($serialize:ident::$method:ident),* $(,)?) => {
+        $(impl ser::$serialize for Whole {
+            type Ok = Vec<ProofSection>;
+            type Error = SizeError;
+
+            fn $method<T: Serialize + ?Sized>(&mut self, _: &T) -> Result<(), SizeError> {
+                Ok(())
+            }
+
+            fn end(self) -> Result<Self::Ok, SizeError> {
+                Ok(Vec::new())
+            }
+        })*
+    };
+}
+
+whole!(
+    SerializeSeq::serialize_element,
+    SerializeTuple::serialize_element,
+    SerializeTupleStruct::serialize_field,
+    SerializeTupleVariant::serialize_field,
+);
+
+impl ser::SerializeMap for Whole {
+    type Ok = Vec<ProofSection>;
+    type Error = SizeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, _: &T) -> Result<(), SizeError> {
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, _: &T) -> Result<(), SizeError> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, SizeError> {
+        Ok(Vec::new())
+    }
+}
+
+// A struct's fields, each a section. A field encoded in no bytes at all, such as a PhantomData, is left out.
+struct Fields(Vec<ProofSection>);
+
+impl Fields {
+    fn push<T: Serialize + ?Sized>(&mut self, name: &str, value: &T) -> Result<(), SizeError> {
+        let section = section(name, value)?;
+        if section.bytes > 0 {
+            self.0.push(section);
+        }
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for Fields {
+    type Ok = Vec<ProofSection>;
+    type Error = SizeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), SizeError> {
+        self.push(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, SizeError> {
+        Ok(self.0)
+    }
+}
+
+impl ser::SerializeStructVariant for Fields {
+    type Ok = Vec<ProofSection>;
+    type Error = SizeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), SizeError> {
+        self.push(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, SizeError> {
+        Ok(self.0)
+    }
+}
@@ -0,0 +1,412 @@
+//! A proof as JSON, for the visualizer to show its structure next to the trace (`--export-proof`). uni-stark's
+//! Proof serializes with serde; this writes it through serde_json with every byte array (the Merkle roots and
+//! paths) as one hex string instead of an array of numbers, and reads it back the same way. The same serde form
+//! gives the Merkle roots the trace export lists under `commitments`, see commitment_roots.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::{Read, Write};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+#[cfg(not(target_arch = "wasm32"))]
+use flate2::read::GzDecoder;
+#[cfg(not(target_arch = "wasm32"))]
+use flate2::write::GzEncoder;
+#[cfg(not(target_arch = "wasm32"))]
+use flate2::Compression;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::FibVisError;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::export::write_atomically;
+use crate::sequence::Sequence;
+use crate::stark_config::{FieldChoice, FriParams, HashChoice, Seed};
+use crate::transcript::{TranscriptEvent, TranscriptLabel};
+
+/// Byte arrays of at least this many bytes are written as a hex string. Shorter runs of small numbers are left
+/// alone, they are as likely to be a few field elements.
+pub const HEX_MIN_BYTES: usize = 8;
+
+/// What `--export-proof` writes: the proof together with what it takes to verify it again, the parameters its AIR
+/// and STARK config are built from and the public values it was checked against.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ProofExport {
+    pub field: FieldChoice,
+    pub hash: HashChoice,
+    pub fri: FriParams,
+    pub seed: Seed,                 // the challenger seed, as hex
+    pub sequence: Sequence,
+    pub recurrence: Vec<u64>,       // coefficients of a linear recurrence proven instead of `sequence`
+    pub num_steps: usize,
+    pub initial: Vec<u64>,
+    pub final_value: u64,
+    pub public_values: Vec<String>, // as the verifier takes them, decimal field elements
+    pub proof_bytes: usize,         // size of the same proof postcard-encoded, as `prove` writes it
+    pub proof: Value,               // the Proof itself, see proof_to_json
+    pub fri_queries: Vec<FriQuery>, // what each FRI query opens, see fri_queries; possibly only the first few
+    pub fri_rounds: Vec<FriRound>,  // the same openings per folding round, with its beta, see fri_rounds
+    pub pow: PowGrinding,           // the proof-of-work step before the queries, see pow_grinding
+    pub ood: OodOpening,            // the trace opened at zeta, see quotient::ood_opening
+    pub quotient: QuotientOpening,  // the quotient chunks opened at zeta, see quotient::quotient_opening
+}
+
+impl ProofExport {
+    /// Decodes the proof back into uni-stark's Proof for the config it was made with.
+    pub fn decode<P: DeserializeOwned>(&self) -> Result<P, FibVisError> {
+        proof_from_json(self.proof.clone()).map_err(|e| FibVisError::Serialization(format!("proof: {e}")))
+    }
+}
+
+/// `proof` as a JSON value, with every array of at least HEX_MIN_BYTES bytes as a `0x...` hex string.
+pub fn proof_to_json<P: Serialize>(proof: &P) -> Result<Value, serde_json::Error> {
+    let mut json = serde_json::to_value(proof)?;
+    hex_encode(&mut json);
+    Ok(json)
+}
+
+/// The inverse of proof_to_json. The proof has no strings of its own, so every string is one of its byte arrays.
+pub fn proof_from_json<P: DeserializeOwned>(mut json: Value) -> Result<P, serde_json::Error> {
+    hex_decode(&mut json);
+    serde_json::from_value(json)
+}
+
+// Replaces every byte array in `json` with its hex string.
+fn hex_encode(json: &mut Value) {
+    if let Some(bytes) = byte_array(json) {
+        *json = Value::String(format!("0x{}", bytes.iter().map(|b| format!("{b:02x}")).collect::<String>()));
+        return;
+    }
+    match json {
+        Value::Array(items) => items.iter_mut().for_each(hex_encode),
+        Value::Object(fields) => fields.values_mut().for_each(hex_encode),
+        _ => {}
+    }
+}
+
+// The bytes of an array of at least HEX_MIN_BYTES numbers that all fit in a byte.
+fn byte_array(json: &Value) -> Option<Vec<u8>> {
+    let items = json.as_array().filter(|items| items.len() >= HEX_MIN_BYTES)?;
+    items.iter().map(|item| item.as_u64().and_then(|b| u8::try_from(b).ok())).collect()
+}
+
+// Turns the hex strings hex_encode wrote back into arrays of numbers. Anything else is left for serde to reject.
+fn hex_decode(json: &mut Value) {
+    match json {
+        Value::String(s) => {
+            let Some(hex) = s.strip_prefix("0x").filter(|hex| hex.len() % 2 == 0) else { return };
+            let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok().map(Value::from);
+            let bytes: Option<Vec<Value>> = (0..hex.len()).step_by(2).map(byte).collect();
+            if let Some(bytes) = bytes {
+                *json = Value::Array(bytes);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(hex_decode),
+        Value::Object(fields) => fields.values_mut().for_each(hex_decode),
+        _ => {}
+    }
+}
+
+/// The step of the protocol a Merkle root commits to, in the order the prover gets there: the trace, the quotient
+/// polynomial's chunks once the constraints are combined, then FRI. The circle PCS (Mersenne31) first folds the
+/// opened polynomials from the circle onto the line and commits that layer, then every FRI folding round commits
+/// the folded evaluations.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitPhase {
+    Trace,
+    QuotientChunks,
+    FriFirstLayer,
+    FriCommitPhase,
+}
+
+impl CommitPhase {
+    /// Name recorded in the exported JSON.
+    pub fn name(self) -> &'static str {
+        match self {
+            CommitPhase::Trace => "trace",
+            CommitPhase::QuotientChunks => "quotient_chunks",
+            CommitPhase::FriFirstLayer => "fri_first_layer",
+            CommitPhase::FriCommitPhase => "fri_commit_phase",
+        }
+    }
+}
+
+/// One Merkle root of a proof, see commitment_roots.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct CommitmentRoot {
+    pub phase: CommitPhase,
+    pub round: usize, // the FRI folding round for FriCommitPhase, 0 for the other phases
+    pub root: String, // as hex, two digits per byte, or eight per element for a hash over field elements (Poseidon2)
+}
+
+/// The Merkle roots `proof` commits to, in protocol order: the trace, the quotient chunks, the circle PCS's first
+/// layer and one root per FRI folding round. Read from the proof's serde form, which names them the same whichever
+/// field and hash the proof was made with.
+pub fn commitment_roots<P: Serialize>(proof: &P) -> Result<Vec<CommitmentRoot>, serde_json::Error> {
+    let json = serde_json::to_value(proof)?;
+    let mut roots = Vec::new();
+    let mut push = |phase, round, commitment: &Value| {
+        roots.push(CommitmentRoot { phase, round, root: root_hex(commitment) });
+    };
+    for (key, phase) in [("trace", CommitPhase::Trace), ("quotient_chunks", CommitPhase::QuotientChunks)] {
+        if let Some(commitment) = json["commitments"].get(key) {
+            push(phase, 0, commitment);
+        }
+    }
+    let opening = &json["opening_proof"];
+    if let Some(commitment) = find_key(opening, "first_layer_commitment") {
+        push(CommitPhase::FriFirstLayer, 0, commitment);
+    }
+    if let Some(Value::Array(commits)) = find_key(opening, "commit_phase_commits") {
+        for (round, commitment) in commits.iter().enumerate() {
+            push(CommitPhase::FriCommitPhase, round, commitment);
+        }
+    }
+    Ok(roots)
+}
+
+// The first value under `key` in `json`, depth first.
+pub(crate) fn find_key<'a>(json: &'a Value, key: &str) -> Option<&'a Value> {
+    match json {
+        Value::Object(fields) => fields.get(key).or_else(|| fields.values().find_map(|v| find_key(v, key))),
+        Value::Array(items) => items.iter().find_map(|v| find_key(v, key)),
+        _ => None,
+    }
+}
+
+// The numbers of a serialized hash, in order, as one hex string.
+pub(crate) fn root_hex(commitment: &Value) -> String {
+    let mut words = Vec::new();
+    collect_numbers(commitment, &mut words);
+    let digits = if words.iter().all(|&w| w <= 0xff) { 2 } else { 8 };
+    words.iter().map(|w| format!("{w:0digits$x}")).collect()
+}
+
+// Every number in `json`, depth first, skipping what is not one (such as the null of a PhantomData).
+pub(crate) fn collect_numbers(json: &Value, out: &mut Vec<u64>) {
+    match json {
+        Value::Number(n) => out.extend(n.as_u64()),
+        Value::Array(items) => items.iter().for_each(|item| collect_numbers(item, out)),
+        Value::Object(fields) => fields.values().for_each(|field| collect_numbers(field, out)),
+        _ => {}
+    }
+}
+
+/// What one FRI query opens, for the query-phase visualization. The proof does not carry the index the query
+/// sampled: the verifier draws it again from the Fiat-Shamir transcript, so only its position is known here.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FriQuery {
+    pub query: usize,                      // its position among the proof's queries
+    pub input_openings: Vec<QueryOpening>, // the opened row of each committed batch: the trace, the quotient chunks
+    pub rounds: Vec<QueryOpening>,         // per FRI folding round: the sibling of the folded value
+}
+
+/// Leaf values opened from a Merkle tree, with the path that authenticates them.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct QueryOpening {
+    pub values: Vec<String>, // the leaf's field elements in decimal, extension elements by their coefficients
+    pub path_len: usize,     // sibling digests on the path to the root, the height of the tree
+}
+
+/// The FRI queries of `proof`, the first `max` of them if given, in the order the proof holds them. Each folding
+/// round halves the tree, so the path lengths of a query's rounds go down by one per round.
+pub fn fri_queries<P: Serialize>(proof: &P, max: Option<usize>) -> Result<Vec<FriQuery>, serde_json::Error> {
+    let json = serde_json::to_value(proof)?;
+    let Some(Value::Array(queries)) = find_key(&json["opening_proof"], "query_proofs") else { return Ok(Vec::new()) };
+    let queries = queries.iter().take(max.unwrap_or(usize::MAX)).enumerate().map(|(query, query_proof)| {
+        let mut input_openings = Vec::new();
+        collect_openings(&query_proof["input_proof"], &mut input_openings);
+        let rounds = match &query_proof["commit_phase_openings"] {
+            Value::Array(steps) => {
+                steps.iter().map(|step| opening(&step["sibling_value"], &step["opening_proof"])).collect()
+            }
+            _ => Vec::new(),
+        };
+        FriQuery { query, input_openings, rounds }
+    });
+    Ok(queries.collect())
+}
+
+// Every batch opening (`opened_values` with its `opening_proof`) under `json`, depth first.
+fn collect_openings(json: &Value, out: &mut Vec<QueryOpening>) {
+    match json {
+        Value::Object(fields) if fields.contains_key("opened_values") && fields.contains_key("opening_proof") => {
+            out.push(opening(&fields["opened_values"], &fields["opening_proof"]));
+        }
+        Value::Object(fields) => fields.values().for_each(|field| collect_openings(field, out)),
+        Value::Array(items) => items.iter().for_each(|item| collect_openings(item, out)),
+        _ => {}
+    }
+}
+
+// The opened `values` and the length of the Merkle `path`, a list of digests.
+fn opening(values: &Value, path: &Value) -> QueryOpening {
+    let mut numbers = Vec::new();
+    collect_numbers(values, &mut numbers);
+    QueryOpening {
+        values: numbers.iter().map(u64::to_string).collect(),
+        path_len: path.as_array().map_or(0, Vec::len),
+    }
+}
+
+/// One FRI folding round, for the step-by-step FRI animation. The round folds a codeword of `domain_size` values
+/// in half: the values at positions 2i and 2i + 1, e0 and e1 at the points x and -x, give the next round's value
+/// at position i, f'(x^2) = f_even(x^2) + beta * f_odd(x^2) = (e0 + e1) / 2 + beta * (e0 - e1) / (2x). For the
+/// two-adic PCSs (BabyBear, KoalaBear) x is the generator of the subgroup of order domain_size to the power
+/// of i with its log2(domain_size) - 1 bits reversed; the circle PCS (Mersenne31) folds over its line domain.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FriRound {
+    pub round: usize,
+    pub beta: Option<Vec<String>>,      // the folding challenge by its coefficients, when recorded with the transcript
+    pub domain_size: usize,             // values in the codeword this round commits to
+    pub openings: Vec<FriRoundOpening>, // per query, the value the proof opens next to the one it folds
+}
+
+/// What one query opens in one FRI folding round: the query's own value is the one the round before folded to,
+/// the proof only carries its sibling.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FriRoundOpening {
+    pub query: usize,               // its position among the proof's queries
+    pub index: Option<usize>,       // the query's position in the codeword, when recorded; its sibling's is index ^ 1
+    pub sibling_value: Vec<String>, // by its coefficients
+}
+
+/// The FRI folding rounds of `proof`, with the openings of its first `max` queries if given. The betas and the
+/// sampled query indices are not in the proof, they are read from `transcript`, the prover's or the verifier's (see
+/// RecordsTranscript): a round's beta is drawn right after its commitment, and a query's index loses one bit per
+/// round. The circle PCS samples one bit more for the fold it does before the first round, and draws its beta too.
+pub fn fri_rounds<P: Serialize>(
+    proof: &P,
+    transcript: &[TranscriptEvent],
+    max: Option<usize>,
+) -> Result<Vec<FriRound>, serde_json::Error> {
+    let json = serde_json::to_value(proof)?;
+    let opening = &json["opening_proof"];
+    let num_rounds = match find_key(opening, "commit_phase_commits") {
+        Some(Value::Array(commits)) => commits.len(),
+        _ => 0,
+    };
+    let extra_bits = usize::from(find_key(opening, "first_layer_commitment").is_some());
+    let recorded = |label: TranscriptLabel| transcript.iter().filter(move |event| event.label == label);
+    let betas: Vec<&TranscriptEvent> = recorded(TranscriptLabel::FriBeta).collect();
+    let betas = betas.get(betas.len().saturating_sub(num_rounds)..).filter(|betas| betas.len() == num_rounds);
+    let indices: Vec<Option<usize>> =
+        recorded(TranscriptLabel::QueryIndex).map(|event| event.values.first()?.parse().ok()).collect();
+
+    // Every query's path in a round has the same length, the height of the round's tree of sibling pairs
+    let queries = fri_queries(proof, None)?;
+    let rounds = (0..num_rounds).map(|round| {
+        let path_len = queries.first().and_then(|query| query.rounds.get(round)).map_or(0, |opening| opening.path_len);
+        let openings = queries.iter().take(max.unwrap_or(usize::MAX)).filter_map(|query| {
+            let sibling = query.rounds.get(round)?;
+            Some(FriRoundOpening {
+                query: query.query,
+                index: indices.get(query.query).copied().flatten().map(|index| index >> (extra_bits + round)),
+                sibling_value: sibling.values.clone(),
+            })
+        });
+        FriRound {
+            round,
+            beta: betas.map(|betas| betas[round].values.clone()),
+            domain_size: 2 << path_len,
+            openings: openings.collect(),
+        }
+    });
+    Ok(rounds.collect())
+}
+
+/// The proof-of-work step of FRI: before drawing the query indices the prover grinds for a witness that, observed
+/// by the challenger, makes its next `bits` sampled bits zero, so every query costs it about 2^bits hashes to redo.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PowGrinding {
+    pub bits: usize,
+    pub witness: String,       // the field element in decimal
+    pub attempts: Option<u64>, // the candidates tried until it, when it was ground with the transcript recorded
+    pub check: Option<bool>,   // the verifier's check of the witness, when the verifier's transcript was recorded
+}
+
+/// The proof-of-work step of `proof`, ground for `bits` bits. RecordingChallenger tries the candidates counting up
+/// from zero, so a witness ground with it, as `transcript` tells, took its value plus one attempts. `check` is the
+/// verifier's verdict on the witness, see RecordsTranscript::pow_check.
+pub fn pow_grinding<P: Serialize>(
+    proof: &P,
+    bits: usize,
+    transcript: &[TranscriptEvent],
+    check: Option<bool>,
+) -> Result<PowGrinding, serde_json::Error> {
+    let json = serde_json::to_value(proof)?;
+    let mut numbers = Vec::new();
+    if let Some(witness) = find_key(&json["opening_proof"], "pow_witness") {
+        collect_numbers(witness, &mut numbers);
+    }
+    let witness = numbers.first().copied().unwrap_or_default();
+    let ground = transcript
+        .iter()
+        .any(|event| event.label == TranscriptLabel::PowWitness && event.values == [witness.to_string()]);
+    Ok(PowGrinding { bits, witness: witness.to_string(), attempts: ground.then_some(witness + 1), check })
+}
+
+/// The trace as the proof opens it at the out-of-domain point zeta, for the DEEP step: what the prover claims every
+/// column's polynomial is at zeta and at the next row's point after it. Extension elements, by their coefficients in
+/// decimal.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OodOpening {
+    pub zeta: Option<Vec<String>>,            // the point, when it was recorded with the transcript
+    pub zeta_next: Option<Vec<String>>,       // zeta shifted by one row of the trace domain
+    pub trace_local: Vec<Vec<String>>,        // per main trace column, its value at zeta
+    pub trace_next: Vec<Vec<String>>,         // ... and at zeta_next
+    pub preprocessed_local: Vec<Vec<String>>, // the same for the preprocessed trace, empty without one
+    pub preprocessed_next: Vec<Vec<String>>,
+}
+
+/// The quotient polynomial's chunks as the proof opens them at the out-of-domain point zeta.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct QuotientOpening {
+    pub num_chunks: usize,
+    pub root: String,                 // the chunks' commitment, as commitment_roots lists it
+    pub chunks: Vec<Vec<String>>,     // per chunk, the values of its columns at zeta by their coefficients
+    pub check: Option<QuotientCheck>, // the verifier's check at zeta, when alpha and zeta were recorded
+}
+
+/// Both sides of the check `quotient * vanishing == folded_constraints` the verifier makes at zeta. Extension
+/// elements, by their coefficients in decimal.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct QuotientCheck {
+    pub alpha: Vec<String>,              // the challenge the constraints are folded with
+    pub zeta: Vec<String>,
+    pub quotient: Vec<String>,           // Q(zeta), the chunks recombined
+    pub vanishing: Vec<String>,          // Z(zeta), the trace domain's vanishing polynomial
+    pub folded_constraints: Vec<String>, // the constraints at zeta, folded with powers of alpha
+}
+
+/// Writes `export` to `path` as compact JSON, gzipped when the path ends in `.gz`. Written atomically, parent
+/// directories created.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_proof_json(path: &Path, export: &ProofExport) -> Result<(), FibVisError> {
+    let compress = path.extension().is_some_and(|ext| ext == "gz");
+    write_atomically(path, |out| {
+        if compress {
+            let mut gz = GzEncoder::new(out, Compression::default());
+            serde_json::to_writer(&mut gz, export)?;
+            gz.finish()?.flush()?;
+        } else {
+            serde_json::to_writer(out, export)?;
+        }
+        Ok(())
+    })
+}
+
+/// Reads a proof written by write_proof_json, plain or gzipped.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_proof_json(path: &Path) -> Result<ProofExport, FibVisError> {
+    let mut bytes = std::fs::read(path).map_err(|e| FibVisError::io(path, e))?;
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        let mut plain = Vec::new();
+        GzDecoder::new(bytes.as_slice()).read_to_end(&mut plain).map_err(|e| FibVisError::io(path, e))?;
+        bytes = plain;
+    }
+    serde_json::from_slice(&bytes).map_err(|e| FibVisError::Serialization(format!("{}: {e}", path.display())))
+}
@@ -0,0 +1,210 @@
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Instant;
+
+use p3_air::BaseAir;
+use p3_field::PrimeCharacteristicRing;
+use p3_matrix::Matrix;
+use p3_mersenne_31::Mersenne31;
+use p3_uni_stark::Proof;
+
+use crate::constraints::{constraint_report, evaluate_and_find_violations, extract_constraints};
+use crate::error::FibVisError;
+use crate::export::{vis_data_from_matrix, FieldInfo, MemoryStats, ProofStats, Timings, VisData};
+use crate::memory;
+use crate::proof_json::commitment_roots;
+use crate::stark_config::{ConfigBuilder, FieldChoice, FriParams, HashChoice, M31KeccakConfig, Seed};
+use crate::sequence::{check_initial, check_padding, Sequence};
+use crate::trace::{check_final_value, check_num_steps, check_trace_shape, FIBONACCI_START};
+
+/// Errors returned by prove_fibonacci and verify_fibonacci.
+pub type ProveError = FibVisError;
+
+/// What to prove with prove_fibonacci. The defaults are the original demo: 8 steps, final value 21.
+#[derive(Debug, Clone)]
+pub struct FibonacciParams {
+    pub num_steps: usize,
+    /// Claimed last value of the trace; computed from `num_steps` when `None`.
+    pub final_value: Option<u64>,
+    /// First row of the trace, (0, 1) for the canonical sequence.
+    pub initial: (u32, u32),
+    /// Recurrence to prove; `initial` only applies to Sequence::Fibonacci. Sequence::Collatz takes a single seed
+    /// and is proven through CollatzAir directly.
+    pub sequence: Sequence,
+    pub fri: FriParams,
+    pub seed: Seed,
+    /// Cells overwritten after the trace is generated, as (row, column, value), to see a trace rejected: by the
+    /// constraint precheck before proving, or with `skip_precheck` by the prover, which panics on it in debug
+    /// builds, and by the verifier otherwise.
+    pub tamper: Vec<(usize, usize, u32)>,
+    /// Prove the trace without checking it against the constraints first, to watch the prover fail on a broken one.
+    pub skip_precheck: bool,
+}
+
+impl Default for FibonacciParams {
+    fn default() -> Self {
+        Self {
+            num_steps: 8,
+            final_value: None,
+            initial: FIBONACCI_START,
+            sequence: Sequence::Fibonacci,
+            fri: FriParams::default(),
+            seed: Seed::default(),
+            tamper: Vec::new(),
+            skip_precheck: false,
+        }
+    }
+}
+
+impl FibonacciParams {
+    /// Parameters for a trace of `num_steps` rows, with the final value computed and default FRI settings.
+    pub fn new(num_steps: usize) -> Self {
+        Self { num_steps, ..Self::default() }
+    }
+
+    // The first row as the slice the Sequence methods take.
+    fn initial(&self) -> [u64; 2] {
+        [self.initial.0.into(), self.initial.1.into()]
+    }
+
+    fn final_value(&self) -> u64 {
+        self.final_value.unwrap_or_else(|| self.sequence.final_value(FieldChoice::Mersenne31, self.num_steps, &self.initial()))
+    }
+
+    // Checks the parameters and builds the config both sides use.
+    fn config(&self) -> Result<M31KeccakConfig, FibVisError> {
+        check_num_steps(self.num_steps).map_err(FibVisError::Config)?;
+        check_initial(self.sequence, self.num_steps, &self.initial())?;
+        check_padding(self.sequence, self.num_steps)?;
+        if let Some(final_value) = self.final_value {
+            FieldChoice::Mersenne31.check_element(final_value).map_err(|e| FibVisError::Config(format!("final_value: {e}")))?;
+        }
+        let air = self.sequence.air(self.num_steps, &self.initial());
+        let num_public_values = air.public_values::<Mersenne31>(0).len();
+        constraint_report::<Mersenne31, _>(&air, air.preprocessed_width(), num_public_values)
+            .check_log_blowup(self.fri.log_blowup)?;
+        ConfigBuilder::new().fri(self.fri).seed(self.seed.0.clone()).num_steps(self.num_steps).build()
+    }
+}
+
+/// Result of prove_fibonacci: the postcard-encoded proof, the visualizer data of the proven trace, and how
+/// long each phase took. The visualizer data carries the same timings and the proof size as its stats; the proof
+/// is verified separately, so their verify_ms is zero. With memory tracking enabled (memory::enable) the stats also
+/// carry the run's heap usage.
+#[derive(Clone)]
+pub struct FibonacciRun {
+    pub proof: Vec<u8>,
+    pub vis_data: VisData,
+    pub timings: Timings,
+}
+
+/// Milliseconds since `start`, as recorded in Timings.
+pub fn elapsed_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+/// Best-effort text of a caught panic. In debug builds uni-stark checks the constraints before proving and
+/// panics naming the offending row, which is exactly what a tamper demo wants to show.
+pub fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "the prover panicked".to_string()
+    }
+}
+
+// The high-level API for embedding the prover: the same Mersenne31/Keccak256/circle PCS stack the binary
+// uses by default, with no file IO. The proof is only meaningful to verify_fibonacci called with the same
+// params (FRI settings and seed included).
+
+/// Generates, proves and stringifies a Fibonacci trace.
+pub fn prove_fibonacci(params: FibonacciParams) -> Result<FibonacciRun, ProveError> {
+    let run_start = Instant::now();
+    memory::reset_peak();
+    let config = params.config()?;
+    let final_value = params.final_value();
+    let mut timings = Timings::default();
+    let mut heap = MemoryStats::default();
+
+    let start = Instant::now();
+    let allocated = memory::allocated();
+    let mut trace = params.sequence.generate_trace::<Mersenne31>(params.num_steps, &params.initial());
+    for &(row, column, value) in &params.tamper {
+        let width = trace.width();
+        if row >= trace.height() || column >= width {
+            return Err(FibVisError::TraceGeneration {
+                row,
+                reason: format!("cannot tamper cell ({row}, {column}), the trace is {}x{}", trace.height(), width),
+            });
+        }
+        trace.values[row * width + column] = Mersenne31::from_u32(value);
+    }
+    timings.trace_gen_ms = elapsed_ms(start);
+    heap.trace_gen_bytes = memory::allocated_since(allocated);
+
+    // Checked once for both the export and the prover, the trace does not change in between.
+    let air = params.sequence.air(params.num_steps, &params.initial());
+    check_trace_shape(&air, &trace)?;
+    check_final_value(&air, final_value, &trace)?;
+    let public_values = air.public_values::<Mersenne31>(final_value);
+    // One pass over the trace gives the export its evaluations and the precheck its violations
+    let (constraint_evals, violations) = evaluate_and_find_violations(&air, &trace, &public_values);
+    if let (false, Some(first)) = (params.skip_precheck, violations.first()) {
+        return Err(FibVisError::ConstraintViolation { count: violations.len(), first: first.to_string() });
+    }
+    let start = Instant::now();
+    let allocated = memory::allocated();
+    let vis_data = VisData {
+        sequence: params.sequence.name().to_string(),
+        final_value,
+        public_values: public_values.iter().map(|v| v.to_string()).collect(),
+        field: FieldInfo::new(FieldChoice::Mersenne31),
+        hash: HashChoice::Keccak256.name().to_string(),
+        fri: params.fri,
+        seed: params.seed.clone(),
+        ..vis_data_from_matrix(&trace, &params.sequence.columns(params.num_steps))?
+    }
+    .with_constraints(extract_constraints::<Mersenne31, _>(&air, air.preprocessed_width(), public_values.len()))
+    .with_air_info::<Mersenne31, _>(&air)
+    .with_constraint_evals(constraint_evals)
+    .with_violations(violations)
+    .with_padding(params.num_steps)
+    .with_selectors(None)
+    .with_true_values(FieldChoice::Mersenne31.modulus(), params.sequence.true_values(params.num_steps, &params.initial()))
+    .with_tampered(params.tamper.iter().map(|&(row, column, _)| (row, column)).collect());
+    let vis_data = match BaseAir::<Mersenne31>::preprocessed_trace(&air) {
+        Some(preprocessed) => vis_data.with_preprocessed(&preprocessed, &params.sequence.preprocessed_columns())?,
+        None => vis_data,
+    };
+    timings.export_ms = elapsed_ms(start);
+    heap.export_bytes = memory::allocated_since(allocated);
+
+    let start = Instant::now();
+    let allocated = memory::allocated();
+    let proof = panic::catch_unwind(AssertUnwindSafe(|| air.prove(&config, trace, &public_values)))
+        .map_err(|payload| FibVisError::Prove(panic_message(payload)))?;
+    timings.prove_ms = elapsed_ms(start);
+    heap.prove_bytes = memory::allocated_since(allocated);
+
+    let commitments = commitment_roots(&proof).map_err(|e| FibVisError::Serialization(format!("proof: {e}")))?;
+    let proof = postcard::to_allocvec(&proof).map_err(|e| FibVisError::Serialization(format!("proof: {e}")))?;
+    timings.total_ms = elapsed_ms(run_start);
+    let stats = ProofStats {
+        memory: memory::is_enabled().then(|| MemoryStats { peak_bytes: memory::peak() as u64, ..heap }),
+        ..ProofStats::new(&proof, timings, params.fri)
+    };
+    let vis_data = vis_data.with_stats(stats).with_commitments(commitments);
+    Ok(FibonacciRun { proof, vis_data, timings })
+}
+
+/// Verifies proof bytes returned by prove_fibonacci against the AIR described by `params`.
+pub fn verify_fibonacci(params: &FibonacciParams, proof: &[u8]) -> Result<(), ProveError> {
+    let config = params.config()?;
+    let proof: Proof<M31KeccakConfig> =
+        postcard::from_bytes(proof).map_err(|e| FibVisError::Serialization(format!("proof: {e}")))?;
+    let air = params.sequence.air(params.num_steps, &params.initial());
+    let public_values = air.public_values::<Mersenne31>(params.final_value());
+    air.verify(&config, &proof, &public_values)
+}
@@ -0,0 +1,162 @@
+//! The proof at the out-of-domain point zeta, for the visualizer to show uni-stark's last steps: the trace values
+//! opened there for the DEEP step (the `ood` block of `--export-proof`), and the quotient polynomial (the
+//! `quotient` block). The prover splits the quotient into chunks over disjoint parts of its domain and commits them
+//! under one root; the proof opens every chunk at zeta, and the verifier recombines them into Q(zeta) and compares
+//! Q(zeta) * Z(zeta), Z the trace domain's vanishing polynomial, with the constraints folded with alpha at zeta.
+//! The openings are in the proof; alpha and zeta are not, the verifier draws them from the Fiat-Shamir transcript,
+//! so they are read from the recorded one.
+use p3_air::Air;
+use p3_commit::{Pcs, PolynomialSpace};
+use p3_field::{BasedVectorSpace, Field, PrimeCharacteristicRing};
+use p3_uni_stark::{StarkGenericConfig, SymbolicAirBuilder, Val};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::constraints::fold_constraints_at;
+use crate::error::FibVisError;
+use crate::proof_json::{root_hex, OodOpening, QuotientCheck, QuotientOpening};
+use crate::transcript::{TranscriptEvent, TranscriptLabel};
+
+// The part of uni-stark's OpenedValues read here, from the proof's serde form.
+#[derive(Deserialize)]
+struct OpenedValues<EF> {
+    trace_local: Vec<EF>,
+    trace_next: Vec<EF>,
+    #[serde(default)]
+    preprocessed_local: Option<Vec<EF>>,
+    #[serde(default)]
+    preprocessed_next: Option<Vec<EF>>,
+    quotient_chunks: Vec<Vec<EF>>, // per chunk, its base field columns' values at zeta
+}
+
+/// The ood block of `proof`, made with `config`. `transcript` is the prover's, see RecordsTranscript; the points
+/// are left out when it has no zeta.
+pub fn ood_opening<SC: StarkGenericConfig, P: Serialize>(
+    config: &SC,
+    proof: &P,
+    transcript: &[TranscriptEvent],
+) -> Result<OodOpening, FibVisError> {
+    let (_, opened, degree_bits) = read_proof::<SC, P>(proof)?;
+    let zeta = challenge::<SC>(transcript, TranscriptLabel::Zeta);
+    let zeta_next = zeta.and_then(|zeta| config.pcs().natural_domain_for_degree(1 << degree_bits).next_point(zeta));
+    let columns = |values: &[SC::Challenge]| -> Vec<Vec<String>> { values.iter().map(coefficients::<SC>).collect() };
+    Ok(OodOpening {
+        zeta: zeta.as_ref().map(coefficients::<SC>),
+        zeta_next: zeta_next.as_ref().map(coefficients::<SC>),
+        trace_local: columns(&opened.trace_local),
+        trace_next: columns(&opened.trace_next),
+        preprocessed_local: opened.preprocessed_local.as_deref().map_or_else(Vec::new, columns),
+        preprocessed_next: opened.preprocessed_next.as_deref().map_or_else(Vec::new, columns),
+    })
+}
+
+/// The quotient block of `proof`, made with `config` for `air` against `public_values`. `transcript` is the
+/// prover's, see RecordsTranscript; without an alpha and a zeta in it the block has no check.
+pub fn quotient_opening<SC, A, P>(
+    config: &SC,
+    air: &A,
+    proof: &P,
+    public_values: &[Val<SC>],
+    transcript: &[TranscriptEvent],
+) -> Result<QuotientOpening, FibVisError>
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<Val<SC>>>,
+    P: Serialize,
+{
+    let (json, opened, degree_bits) = read_proof::<SC, P>(proof)?;
+    let alpha = challenge::<SC>(transcript, TranscriptLabel::Alpha);
+    let zeta = challenge::<SC>(transcript, TranscriptLabel::Zeta);
+    let check = alpha.zip(zeta).map(|(alpha, zeta)| {
+        recombine(config, air, &opened, degree_bits, public_values, alpha, zeta)
+    });
+    Ok(QuotientOpening {
+        num_chunks: opened.quotient_chunks.len(),
+        root: root_hex(&json["commitments"]["quotient_chunks"]),
+        chunks: opened.quotient_chunks.iter().map(|chunk| chunk.iter().flat_map(coefficients::<SC>).collect()).collect(),
+        check,
+    })
+}
+
+// The proof's serde form, its opened values and its degree_bits, the log2 of the trace height.
+fn read_proof<SC: StarkGenericConfig, P: Serialize>(
+    proof: &P,
+) -> Result<(Value, OpenedValues<SC::Challenge>, usize), FibVisError> {
+    let encoding = |e: serde_json::Error| FibVisError::Serialization(format!("proof: {e}"));
+    let json = serde_json::to_value(proof).map_err(encoding)?;
+    let opened = serde_json::from_value(json["opened_values"].clone()).map_err(encoding)?;
+    let degree_bits = json["degree_bits"]
+        .as_u64()
+        .ok_or_else(|| FibVisError::Serialization("proof: no degree_bits".to_string()))?;
+    Ok((json, opened, degree_bits as usize))
+}
+
+// Q(zeta) from the chunks and the constraints folded at zeta, computed the way uni-stark's verifier does before it
+// compares them.
+fn recombine<SC: StarkGenericConfig, A: Air<SymbolicAirBuilder<Val<SC>>>>(
+    config: &SC,
+    air: &A,
+    opened: &OpenedValues<SC::Challenge>,
+    degree_bits: usize,
+    public_values: &[Val<SC>],
+    alpha: SC::Challenge,
+    zeta: SC::Challenge,
+) -> QuotientCheck {
+    let trace_domain = config.pcs().natural_domain_for_degree(1 << degree_bits);
+    let num_chunks = opened.quotient_chunks.len();
+    let chunk_domains = trace_domain.create_disjoint_domain(num_chunks << degree_bits).split_domains(num_chunks);
+    // Each chunk is weighted by the other parts' vanishing polynomials, scaled to one at its own first point, and
+    // is made of one base field column per coefficient of the extension.
+    let quotient = chunk_domains
+        .iter()
+        .zip(&opened.quotient_chunks)
+        .enumerate()
+        .map(|(i, (domain, chunk))| {
+            let first = SC::Challenge::from(domain.first_point());
+            let weight = chunk_domains
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, other)| other.vanishing_poly_at_point(zeta) * other.vanishing_poly_at_point(first).inverse())
+                .product::<SC::Challenge>();
+            let value = chunk
+                .iter()
+                .enumerate()
+                .map(|(e, &c)| basis_element::<SC>(e) * c)
+                .sum::<SC::Challenge>();
+            weight * value
+        })
+        .sum::<SC::Challenge>();
+
+    let selectors = trace_domain.selectors_at_point(zeta);
+    let preprocessed = opened
+        .preprocessed_local
+        .as_deref()
+        .zip(opened.preprocessed_next.as_deref())
+        .map(|(local, next)| [local, next]);
+    let main = [opened.trace_local.as_slice(), opened.trace_next.as_slice()];
+    let folded = fold_constraints_at(air, main, preprocessed, public_values, &selectors, alpha);
+    QuotientCheck {
+        alpha: coefficients::<SC>(&alpha),
+        zeta: coefficients::<SC>(&zeta),
+        quotient: coefficients::<SC>(&quotient),
+        vanishing: coefficients::<SC>(&selectors.inv_vanishing.inverse()),
+        folded_constraints: coefficients::<SC>(&folded),
+    }
+}
+
+// The first challenge with `label` in `transcript`, read back from its coefficients.
+fn challenge<SC: StarkGenericConfig>(transcript: &[TranscriptEvent], label: TranscriptLabel) -> Option<SC::Challenge> {
+    let event = transcript.iter().find(|event| event.label == label)?;
+    let coefficients: Vec<Val<SC>> =
+        event.values.iter().map(|v| v.parse().ok().map(<Val<SC>>::from_u64)).collect::<Option<_>>()?;
+    <SC::Challenge as BasedVectorSpace<Val<SC>>>::from_basis_coefficients_slice(&coefficients)
+}
+
+fn basis_element<SC: StarkGenericConfig>(i: usize) -> SC::Challenge {
+    <SC::Challenge as BasedVectorSpace<Val<SC>>>::ith_basis_element(i).expect("a chunk has one column per coefficient")
+}
+
+fn coefficients<SC: StarkGenericConfig>(x: &SC::Challenge) -> Vec<String> {
+    <SC::Challenge as BasedVectorSpace<Val<SC>>>::as_basis_coefficients_slice(x).iter().map(ToString::to_string).collect()
+}
@@ -0,0 +1,534 @@
+use num_bigint::BigUint;
+use p3_field::Field;
+use p3_matrix::dense::RowMajorMatrix;
+
+use crate::air::{
+    BitDecompAir, CollatzAir, CounterAir, FibonacciAir, FibonacciWithIndexAir, IndexedFibonacciAir,
+    LinearRecurrenceAir, LucasAir, MulFibonacciAir, SequenceAir, TribonacciAir,
+};
+use crate::error::FibVisError;
+use crate::export::{
+    linear_recurrence_columns, ColumnMeta, BIT_DECOMP_COLUMNS, COLLATZ_COLUMNS, COUNTER_COLUMNS, FIBONACCI_COLUMNS,
+    FIBONACCI_WITH_INDEX_COLUMNS, INDEX_COLUMNS, PADDED_FIBONACCI_COLUMNS, TRIBONACCI_COLUMNS,
+};
+use crate::stark_config::FieldChoice;
+use crate::trace::{
+    bit_decomp_final_value, bit_decomp_true_values, collatz_final_value, collatz_orbit_len, collatz_true_values,
+    counter_final_value, counter_true_values, fibonacci_final_value, fibonacci_true_values,
+    fibonacci_with_index_true_values, linear_recurrence_final_value, linear_recurrence_true_values,
+    mul_fibonacci_final_value, mul_fibonacci_true_values, tribonacci_final_value, tribonacci_true_values,
+    COLLATZ_MAX_ROWS, FIBONACCI_START, LUCAS_START, TRUE_VALUES_MAX_ROWS,
+};
+
+/// What a demo is built from: the trace length, the first row (or seed) given with --initial and, for the
+/// linear recurrence, the coefficients given with --recurrence.
+#[derive(Clone, Copy, Debug)]
+pub struct DemoParams<'a> {
+    pub num_steps: usize,
+    pub initial: &'a [u64],
+    pub recurrence: &'a [u64],
+}
+
+/// A demo AIR the pipeline can export, prove and verify by name. Each one builds its AIR as a SequenceAir
+/// variant, since uni-stark proves one concrete AIR type, and knows everything else about its trace: the column
+/// names, the final value, the exact values for the export and which --initial values it takes.
+///
+/// Adding a demo means an AIR with its SequenceAir variant, an implementation of this trait and an entry in
+/// DEMOS; get() then finds it by name and --list-airs shows it.
+pub trait DemoAir: Sync {
+    /// Name the demo is looked up by, which is also its --sequence value, e.g. `mul-fibonacci`.
+    fn name(&self) -> &'static str;
+
+    /// Name recorded in the export, e.g. `MulFibonacci`.
+    fn label(&self) -> &'static str;
+
+    /// One line for --list-airs.
+    fn description(&self) -> &'static str;
+
+    /// The trace's columns with their names and what each one holds, one per column of build_trace.
+    fn columns(&self, params: &DemoParams) -> Vec<ColumnMeta>;
+
+    /// The columns of the AIR's preprocessed trace, none for an AIR without one.
+    fn preprocessed_columns(&self) -> Vec<ColumnMeta> {
+        Vec::new()
+    }
+
+    /// The AIR for `params`, checked with check_initial first.
+    fn air(&self, params: &DemoParams) -> SequenceAir;
+
+    /// The value the trace ends with over `field`.
+    fn final_value(&self, field: FieldChoice, params: &DemoParams) -> u64;
+
+    /// The exact values of the first TRUE_VALUES_MAX_ROWS rows of the trace, for the export.
+    fn true_values(&self, params: &DemoParams) -> Vec<Vec<BigUint>>;
+
+    /// Whether the AIR has a padded form for traces whose height is not a power of two, see FibonacciAir.
+    fn pads(&self) -> bool {
+        false
+    }
+
+    /// Checks the --initial values. By default a demo fixes its own first row in the AIR and only takes the
+    /// default pair, meaning none was given. Whether the values are below the field's prime is up to the caller.
+    fn check_initial(&self, params: &DemoParams) -> Result<(), FibVisError> {
+        if params.initial != [u64::from(FIBONACCI_START.0), u64::from(FIBONACCI_START.1)] {
+            return Err(FibVisError::Config(format!(
+                "--initial only applies to the fibonacci, fibonacci-with-index, indexed-fibonacci and collatz sequences and --recurrence, {} fixes its first row in the AIR",
+                self.label()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl dyn DemoAir {
+    /// The trace the demo's AIR accepts. Generic over the field, so it lives on the trait object rather than in
+    /// the trait, and generated by the AIR from the parameters it was built with.
+    pub fn build_trace<F: Field>(&self, params: &DemoParams) -> RowMajorMatrix<F> {
+        self.air(params).generate_trace()
+    }
+
+    /// Rejects a step count that would need a padded trace when the AIR has no padded form.
+    pub fn check_padding(&self, num_steps: usize) -> Result<(), FibVisError> {
+        if !num_steps.is_power_of_two() && !self.pads() {
+            return Err(FibVisError::Config(format!(
+                "num_steps = {num_steps} is not a power of two, which only the fibonacci sequence can pad its trace to; \
+                 {} needs a power of two",
+                self.label()
+            )));
+        }
+        Ok(())
+    }
+
+    /// check_initial and check_padding.
+    pub fn check(&self, params: &DemoParams) -> Result<(), FibVisError> {
+        self.check_initial(params)?;
+        self.check_padding(params.num_steps)
+    }
+}
+
+/// Every demo, in the order --list-airs prints them.
+pub static DEMOS: [&dyn DemoAir; 10] = [
+    &FibonacciDemo,
+    &FibonacciWithIndexDemo,
+    &IndexedFibonacciDemo,
+    &LucasDemo,
+    &TribonacciDemo,
+    &MulFibonacciDemo,
+    &CollatzDemo,
+    &CounterDemo,
+    &BitDecompDemo,
+    &LinearRecurrenceDemo,
+];
+
+/// The demo called `name`, see DemoAir::name.
+pub fn get(name: &str) -> Option<&'static dyn DemoAir> {
+    DEMOS.iter().copied().find(|demo| demo.name() == name)
+}
+
+// The rows of the export's true values.
+fn true_value_rows(params: &DemoParams) -> usize {
+    params.num_steps.min(TRUE_VALUES_MAX_ROWS)
+}
+
+// The first row of a Fibonacci trace, which check_initial has made sure is two values that fit in 32 bits.
+fn pair(initial: &[u64]) -> (u32, u32) {
+    (initial[0] as u32, initial[1] as u32)
+}
+
+/// 0, 1, 1, 2, 3, ... or any other start pair, padded when num_steps is not a power of two.
+pub struct FibonacciDemo;
+
+impl DemoAir for FibonacciDemo {
+    fn name(&self) -> &'static str {
+        "fibonacci"
+    }
+
+    fn label(&self) -> &'static str {
+        "Fibonacci"
+    }
+
+    fn description(&self) -> &'static str {
+        "0, 1, 1, 2, 3, ... or any other start pair given with --initial; pads any --num-steps"
+    }
+
+    fn columns(&self, params: &DemoParams) -> Vec<ColumnMeta> {
+        if params.num_steps.is_power_of_two() { FIBONACCI_COLUMNS.to_vec() } else { PADDED_FIBONACCI_COLUMNS.to_vec() }
+    }
+
+    fn air(&self, params: &DemoParams) -> SequenceAir {
+        SequenceAir::Fibonacci(match params.num_steps.is_power_of_two() {
+            true => FibonacciAir::with_initial(params.num_steps, pair(params.initial)),
+            false => FibonacciAir::padded(params.num_steps, pair(params.initial)),
+        })
+    }
+
+    fn final_value(&self, field: FieldChoice, params: &DemoParams) -> u64 {
+        fibonacci_final_value(field, params.num_steps, pair(params.initial))
+    }
+
+    fn true_values(&self, params: &DemoParams) -> Vec<Vec<BigUint>> {
+        fibonacci_true_values(true_value_rows(params), pair(params.initial))
+    }
+
+    fn pads(&self) -> bool {
+        true
+    }
+
+    fn check_initial(&self, params: &DemoParams) -> Result<(), FibVisError> {
+        check_pair(self, params)
+    }
+}
+
+/// The Fibonacci sequence next to a committed step-index column that the AIR constrains to count the rows.
+pub struct FibonacciWithIndexDemo;
+
+impl DemoAir for FibonacciWithIndexDemo {
+    fn name(&self) -> &'static str {
+        "fibonacci-with-index"
+    }
+
+    fn label(&self) -> &'static str {
+        "FibonacciWithIndex"
+    }
+
+    fn description(&self) -> &'static str {
+        "the Fibonacci sequence after a step-index column i = 0, 1, 2, ... that the AIR constrains to count the rows"
+    }
+
+    fn columns(&self, _: &DemoParams) -> Vec<ColumnMeta> {
+        FIBONACCI_WITH_INDEX_COLUMNS.to_vec()
+    }
+
+    fn air(&self, params: &DemoParams) -> SequenceAir {
+        SequenceAir::FibonacciWithIndex(FibonacciWithIndexAir::new(params.num_steps, pair(params.initial)))
+    }
+
+    fn final_value(&self, field: FieldChoice, params: &DemoParams) -> u64 {
+        fibonacci_final_value(field, params.num_steps, pair(params.initial))
+    }
+
+    fn true_values(&self, params: &DemoParams) -> Vec<Vec<BigUint>> {
+        fibonacci_with_index_true_values(true_value_rows(params), pair(params.initial))
+    }
+
+    fn check_initial(&self, params: &DemoParams) -> Result<(), FibVisError> {
+        check_pair(self, params)
+    }
+}
+
+/// The Fibonacci sequence with its step index in a preprocessed column the verifier fixes, see IndexedFibonacciAir.
+pub struct IndexedFibonacciDemo;
+
+impl DemoAir for IndexedFibonacciDemo {
+    fn name(&self) -> &'static str {
+        "indexed-fibonacci"
+    }
+
+    fn label(&self) -> &'static str {
+        "IndexedFibonacci"
+    }
+
+    fn description(&self) -> &'static str {
+        "the Fibonacci sequence next to a preprocessed step column 0, 1, 2, ... that the verifier fixes, not the prover"
+    }
+
+    fn columns(&self, _: &DemoParams) -> Vec<ColumnMeta> {
+        FIBONACCI_COLUMNS.to_vec()
+    }
+
+    fn preprocessed_columns(&self) -> Vec<ColumnMeta> {
+        INDEX_COLUMNS.to_vec()
+    }
+
+    fn air(&self, params: &DemoParams) -> SequenceAir {
+        SequenceAir::Indexed(IndexedFibonacciAir::new(params.num_steps, pair(params.initial)))
+    }
+
+    fn final_value(&self, field: FieldChoice, params: &DemoParams) -> u64 {
+        fibonacci_final_value(field, params.num_steps, pair(params.initial))
+    }
+
+    fn true_values(&self, params: &DemoParams) -> Vec<Vec<BigUint>> {
+        fibonacci_true_values(true_value_rows(params), pair(params.initial))
+    }
+
+    fn check_initial(&self, params: &DemoParams) -> Result<(), FibVisError> {
+        check_pair(self, params)
+    }
+}
+
+// --initial of the Fibonacci demos: the two values of the first row, each fitting in 32 bits.
+fn check_pair(demo: &dyn DemoAir, params: &DemoParams) -> Result<(), FibVisError> {
+    if params.initial.len() != 2 {
+        return Err(FibVisError::Config(format!(
+            "--initial takes the two values of the first row for the {} sequence, got {}",
+            demo.name(),
+            params.initial.len()
+        )));
+    }
+    if params.initial.iter().any(|&v| v > u64::from(u32::MAX)) {
+        return Err(FibVisError::Config(format!("--initial values of the {} sequence must fit in 32 bits", demo.name())));
+    }
+    Ok(())
+}
+
+/// 2, 1, 3, 4, 7, ...
+pub struct LucasDemo;
+
+impl DemoAir for LucasDemo {
+    fn name(&self) -> &'static str {
+        "lucas"
+    }
+
+    fn label(&self) -> &'static str {
+        "Lucas"
+    }
+
+    fn description(&self) -> &'static str {
+        "2, 1, 3, 4, 7, ...: the Fibonacci transition with the first row fixed by the AIR"
+    }
+
+    fn columns(&self, _: &DemoParams) -> Vec<ColumnMeta> {
+        FIBONACCI_COLUMNS.to_vec()
+    }
+
+    fn air(&self, params: &DemoParams) -> SequenceAir {
+        SequenceAir::Lucas(LucasAir::new(params.num_steps))
+    }
+
+    fn final_value(&self, field: FieldChoice, params: &DemoParams) -> u64 {
+        fibonacci_final_value(field, params.num_steps, LUCAS_START)
+    }
+
+    fn true_values(&self, params: &DemoParams) -> Vec<Vec<BigUint>> {
+        fibonacci_true_values(true_value_rows(params), LUCAS_START)
+    }
+}
+
+/// 0, 0, 1, 1, 2, 4, 7, ... in a three-column trace.
+pub struct TribonacciDemo;
+
+impl DemoAir for TribonacciDemo {
+    fn name(&self) -> &'static str {
+        "tribonacci"
+    }
+
+    fn label(&self) -> &'static str {
+        "Tribonacci"
+    }
+
+    fn description(&self) -> &'static str {
+        "0, 0, 1, 1, 2, 4, 7, ... in a three-column trace"
+    }
+
+    fn columns(&self, _: &DemoParams) -> Vec<ColumnMeta> {
+        TRIBONACCI_COLUMNS.to_vec()
+    }
+
+    fn air(&self, params: &DemoParams) -> SequenceAir {
+        SequenceAir::Tribonacci(TribonacciAir::new(params.num_steps))
+    }
+
+    fn final_value(&self, field: FieldChoice, params: &DemoParams) -> u64 {
+        tribonacci_final_value(field, params.num_steps)
+    }
+
+    fn true_values(&self, params: &DemoParams) -> Vec<Vec<BigUint>> {
+        tribonacci_true_values(true_value_rows(params))
+    }
+}
+
+/// 1, 2, 2, 4, 8, 32, ...: each value the product of the previous two.
+pub struct MulFibonacciDemo;
+
+impl DemoAir for MulFibonacciDemo {
+    fn name(&self) -> &'static str {
+        "mul-fibonacci"
+    }
+
+    fn label(&self) -> &'static str {
+        "MulFibonacci"
+    }
+
+    fn description(&self) -> &'static str {
+        "1, 2, 2, 4, 8, 32, ...: each value the product of the previous two, a degree-2 transition"
+    }
+
+    fn columns(&self, _: &DemoParams) -> Vec<ColumnMeta> {
+        FIBONACCI_COLUMNS.to_vec()
+    }
+
+    fn air(&self, params: &DemoParams) -> SequenceAir {
+        SequenceAir::MulFibonacci(MulFibonacciAir::new(params.num_steps))
+    }
+
+    fn final_value(&self, field: FieldChoice, params: &DemoParams) -> u64 {
+        mul_fibonacci_final_value(field, params.num_steps)
+    }
+
+    // The product recurrence stops earlier, see mul_fibonacci_true_values.
+    fn true_values(&self, params: &DemoParams) -> Vec<Vec<BigUint>> {
+        mul_fibonacci_true_values(true_value_rows(params))
+    }
+}
+
+/// The Collatz orbit of a seed, with a parity witness column.
+pub struct CollatzDemo;
+
+impl DemoAir for CollatzDemo {
+    fn name(&self) -> &'static str {
+        "collatz"
+    }
+
+    fn label(&self) -> &'static str {
+        "Collatz"
+    }
+
+    fn description(&self) -> &'static str {
+        "the Collatz orbit of the seed given with --initial, e.g. 27, 82, 41, 124, ..., with a parity witness column"
+    }
+
+    fn columns(&self, _: &DemoParams) -> Vec<ColumnMeta> {
+        COLLATZ_COLUMNS.to_vec()
+    }
+
+    fn air(&self, params: &DemoParams) -> SequenceAir {
+        SequenceAir::Collatz(CollatzAir::new(params.num_steps, params.initial[0]))
+    }
+
+    fn final_value(&self, field: FieldChoice, params: &DemoParams) -> u64 {
+        collatz_final_value(field, params.num_steps, params.initial[0])
+    }
+
+    fn true_values(&self, params: &DemoParams) -> Vec<Vec<BigUint>> {
+        collatz_true_values(true_value_rows(params), params.initial[0])
+    }
+
+    // The seed's orbit has to reach 1 within the trace.
+    fn check_initial(&self, params: &DemoParams) -> Result<(), FibVisError> {
+        match params.initial {
+            [seed] if *seed >= 1 => match collatz_orbit_len(*seed, COLLATZ_MAX_ROWS) {
+                Some(len) if len <= params.num_steps => Ok(()),
+                Some(len) => Err(FibVisError::Config(format!(
+                    "the Collatz orbit of {seed} takes {len} rows to reach 1, use --num-steps {}",
+                    len.next_power_of_two()
+                ))),
+                None => Err(FibVisError::Config(format!(
+                    "the Collatz orbit of {seed} does not reach 1 within {COLLATZ_MAX_ROWS} rows without overflowing 64 bits"
+                ))),
+            },
+            _ => Err(FibVisError::Config("the collatz sequence takes its seed as --initial, e.g. --initial 27".to_string())),
+        }
+    }
+}
+
+/// 0, 1, 2, 3, ... in a single column.
+pub struct CounterDemo;
+
+impl DemoAir for CounterDemo {
+    fn name(&self) -> &'static str {
+        "counter"
+    }
+
+    fn label(&self) -> &'static str {
+        "Counter"
+    }
+
+    fn description(&self) -> &'static str {
+        "0, 1, 2, 3, ...: a single column that grows by 1 every row"
+    }
+
+    fn columns(&self, _: &DemoParams) -> Vec<ColumnMeta> {
+        COUNTER_COLUMNS.to_vec()
+    }
+
+    fn air(&self, params: &DemoParams) -> SequenceAir {
+        SequenceAir::Counter(CounterAir::new(params.num_steps))
+    }
+
+    fn final_value(&self, field: FieldChoice, params: &DemoParams) -> u64 {
+        counter_final_value(field, params.num_steps)
+    }
+
+    fn true_values(&self, params: &DemoParams) -> Vec<Vec<BigUint>> {
+        counter_true_values(true_value_rows(params))
+    }
+}
+
+/// The row index modulo 256 next to its 8 bits.
+pub struct BitDecompDemo;
+
+impl DemoAir for BitDecompDemo {
+    fn name(&self) -> &'static str {
+        "bit-decomp"
+    }
+
+    fn label(&self) -> &'static str {
+        "BitDecomp"
+    }
+
+    fn description(&self) -> &'static str {
+        "the row index modulo 256 next to its 8 bits, a range check by bit decomposition"
+    }
+
+    fn columns(&self, _: &DemoParams) -> Vec<ColumnMeta> {
+        BIT_DECOMP_COLUMNS.to_vec()
+    }
+
+    fn air(&self, params: &DemoParams) -> SequenceAir {
+        SequenceAir::BitDecomp(BitDecompAir::new(params.num_steps))
+    }
+
+    fn final_value(&self, _: FieldChoice, params: &DemoParams) -> u64 {
+        bit_decomp_final_value(params.num_steps)
+    }
+
+    fn true_values(&self, params: &DemoParams) -> Vec<Vec<BigUint>> {
+        bit_decomp_true_values(true_value_rows(params))
+    }
+}
+
+/// x(n) = c1 x(n-1) + ... + ck x(n-k) for the coefficients given with --recurrence.
+pub struct LinearRecurrenceDemo;
+
+impl DemoAir for LinearRecurrenceDemo {
+    fn name(&self) -> &'static str {
+        "linear-recurrence"
+    }
+
+    fn label(&self) -> &'static str {
+        "LinearRecurrence"
+    }
+
+    fn description(&self) -> &'static str {
+        "x(n) = c1 x(n-1) + ... + ck x(n-k), selected with --recurrence c1,...,ck and one --initial value per coefficient"
+    }
+
+    fn columns(&self, params: &DemoParams) -> Vec<ColumnMeta> {
+        linear_recurrence_columns(params.recurrence.len())
+    }
+
+    fn air(&self, params: &DemoParams) -> SequenceAir {
+        SequenceAir::Linear(LinearRecurrenceAir::new(params.num_steps, params.recurrence.to_vec(), params.initial.to_vec()))
+    }
+
+    fn final_value(&self, field: FieldChoice, params: &DemoParams) -> u64 {
+        linear_recurrence_final_value(field, params.num_steps, params.recurrence, params.initial)
+    }
+
+    fn true_values(&self, params: &DemoParams) -> Vec<Vec<BigUint>> {
+        linear_recurrence_true_values(true_value_rows(params), params.recurrence, params.initial)
+    }
+
+    fn check_initial(&self, params: &DemoParams) -> Result<(), FibVisError> {
+        if params.initial.len() != params.recurrence.len() {
+            return Err(FibVisError::Config(format!(
+                "a recurrence with {} coefficients needs {} initial values, got {}",
+                params.recurrence.len(),
+                params.recurrence.len(),
+                params.initial.len()
+            )));
+        }
+        Ok(())
+    }
+}
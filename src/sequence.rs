@@ -0,0 +1,117 @@
+use clap::ValueEnum;
+use num_bigint::BigUint;
+use p3_field::Field;
+use p3_matrix::dense::RowMajorMatrix;
+use serde::{Deserialize, Serialize};
+
+use crate::air::SequenceAir;
+use crate::error::FibVisError;
+use crate::export::ColumnMeta;
+use crate::registry::{
+    BitDecompDemo, CollatzDemo, CounterDemo, DemoAir, DemoParams, FibonacciDemo, FibonacciWithIndexDemo,
+    IndexedFibonacciDemo, LucasDemo, MulFibonacciDemo, TribonacciDemo,
+};
+use crate::stark_config::FieldChoice;
+
+/// The sequences selectable with --sequence, as they are written in configs and proof files. Each one is a demo
+/// of the registry, which knows its AIR, trace and final value; the export records its name so the visualizer
+/// can label the trace.
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum Sequence {
+    /// 0, 1, 1, 2, 3, ... or any other start pair given with --initial
+    #[default]
+    Fibonacci,
+    /// 2, 1, 3, 4, 7, ...
+    Lucas,
+    /// 0, 0, 1, 1, 2, 4, 7, ... in a three-column trace
+    Tribonacci,
+    /// 1, 2, 2, 4, 8, 32, ...: each value the product of the previous two, a degree-2 transition
+    MulFibonacci,
+    /// The Collatz orbit of the seed given with --initial, e.g. 27, 82, 41, 124, ..., with a parity witness column
+    Collatz,
+    /// 0, 1, 2, 3, ...: a single column that grows by 1 every row
+    Counter,
+    /// The row index modulo 256 next to its 8 bits, a range check by bit decomposition
+    BitDecomp,
+    /// The Fibonacci sequence after a step-index column i = 0, 1, 2, ... that the AIR constrains to count the rows
+    FibonacciWithIndex,
+    /// The Fibonacci sequence next to a preprocessed step column 0, 1, 2, ... that the verifier fixes, not the prover
+    IndexedFibonacci,
+}
+
+impl Sequence {
+    /// The registry entry behind this sequence.
+    pub fn demo(self) -> &'static dyn DemoAir {
+        match self {
+            Sequence::Fibonacci => &FibonacciDemo,
+            Sequence::Lucas => &LucasDemo,
+            Sequence::Tribonacci => &TribonacciDemo,
+            Sequence::MulFibonacci => &MulFibonacciDemo,
+            Sequence::Collatz => &CollatzDemo,
+            Sequence::Counter => &CounterDemo,
+            Sequence::BitDecomp => &BitDecompDemo,
+            Sequence::FibonacciWithIndex => &FibonacciWithIndexDemo,
+            Sequence::IndexedFibonacci => &IndexedFibonacciDemo,
+        }
+    }
+
+    /// Name recorded in the export.
+    pub fn name(self) -> &'static str {
+        self.demo().label()
+    }
+
+    /// Whether a `num_steps`-row trace is padded to a power of two, see FibonacciAir. Only the Fibonacci AIR has a
+    /// padded form.
+    pub fn is_padded(self, num_steps: usize) -> bool {
+        self.demo().pads() && !num_steps.is_power_of_two()
+    }
+
+    /// The columns of a `num_steps`-row trace, see DemoAir::columns.
+    pub fn columns(self, num_steps: usize) -> Vec<ColumnMeta> {
+        self.demo().columns(&params(num_steps, &[]))
+    }
+
+    /// The columns of the AIR's preprocessed trace, see DemoAir::preprocessed_columns.
+    pub fn preprocessed_columns(self) -> Vec<ColumnMeta> {
+        self.demo().preprocessed_columns()
+    }
+
+    /// The AIR of a `num_steps`-row trace. `initial` is the first row for the Fibonacci sequences and the seed for
+    /// Collatz, the other sequences fix their own, see check_initial.
+    pub fn air(self, num_steps: usize, initial: &[u64]) -> SequenceAir {
+        self.demo().air(&params(num_steps, initial))
+    }
+
+    /// The trace the AIR expects.
+    pub fn generate_trace<F: Field>(self, num_steps: usize, initial: &[u64]) -> RowMajorMatrix<F> {
+        self.demo().build_trace(&params(num_steps, initial))
+    }
+
+    /// The value the trace ends with over `field`.
+    pub fn final_value(self, field: FieldChoice, num_steps: usize, initial: &[u64]) -> u64 {
+        self.demo().final_value(field, &params(num_steps, initial))
+    }
+
+    /// The exact values of the first TRUE_VALUES_MAX_ROWS rows of the trace, for the export.
+    pub fn true_values(self, num_steps: usize, initial: &[u64]) -> Vec<Vec<BigUint>> {
+        self.demo().true_values(&params(num_steps, initial))
+    }
+}
+
+// A named sequence takes no recurrence coefficients.
+fn params(num_steps: usize, initial: &[u64]) -> DemoParams<'_> {
+    DemoParams { num_steps, initial, recurrence: &[] }
+}
+
+/// Rejects a step count that would need a padded trace for a sequence whose AIR has no padded form.
+pub fn check_padding(sequence: Sequence, num_steps: usize) -> Result<(), FibVisError> {
+    sequence.demo().check_padding(num_steps)
+}
+
+/// Checks the values given with --initial against what `sequence` takes: a start pair for the Fibonacci sequences,
+/// a seed for Collatz whose orbit reaches 1 within `num_steps` rows, and nothing but the default for the sequences
+/// that fix their own first row, see DemoAir::check_initial.
+pub fn check_initial(sequence: Sequence, num_steps: usize, initial: &[u64]) -> Result<(), FibVisError> {
+    sequence.demo().check_initial(&params(num_steps, initial))
+}
@@ -0,0 +1,537 @@
+//! The `serve` subcommand: a static file server for the visualizer's directory, since browsers do not let a page
+//! opened from a file:// URL fetch trace_data.json next to it. It listens on localhost only and answers GET and
+//! HEAD with the files under its root, one request at a time, which is plenty for one page and its exports.
+//!
+//! The exports are rewritten by every run while the page stays open, so they are sent with `Cache-Control:
+//! no-cache` and a reload always fetches the current ones. A page does not even have to reload: it can open a
+//! WebSocket on LIVE_PATH, and whenever LIVE_FILE is written again, by this server's --regenerate or any other
+//! run, every open socket gets a LiveUpdate. The file's modification time is polled like --watch polls the
+//! config, and a burst of writes is one update once the file has been quiet for LIVE_DEBOUNCE.
+//!
+//! A server given the EventLog of the pipeline it runs alongside, as `serve --regenerate` does, also streams its
+//! progress as server-sent events on EVENTS_PATH: every PipelineEvent as one `data:` line of JSON, the current
+//! run's history first. Other servers answer 404 there.
+//!
+//! The page can also ask for a proof itself: a POST of a ProveRequest to PROVE_PATH proves it with prove_fibonacci
+//! on a worker thread and answers 202 with the ApiRun to poll at RUNS_PATH/{id}. One run proves at a time, a
+//! request while it does is answered 409, and none is taken for more than `max_steps` steps, so that a page cannot
+//! exhaust the host's memory. A verified run's export is written to LIVE_FILE, which updates the open pages.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, ReadWrite, Request, Response, Server};
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+
+use crate::error::FibVisError;
+use crate::events::EventLog;
+use crate::export::{write_trace_json, Timings, Verification};
+use crate::prove::{elapsed_ms, prove_fibonacci, verify_fibonacci, FibonacciParams};
+
+/// The port `serve` listens on unless --port says otherwise.
+pub const DEFAULT_PORT: u16 = 8000;
+
+/// Where the page opens its WebSocket for live updates.
+pub const LIVE_PATH: &str = "/ws";
+
+/// The export under the root whose changes are pushed, the one the page loads by default.
+pub const LIVE_FILE: &str = "trace_data.json";
+
+/// Where the pipeline's progress is streamed, see the module documentation.
+pub const EVENTS_PATH: &str = "/events";
+
+/// How long an event stream may stay silent before it gets a comment line, which finds clients that went away.
+pub const EVENTS_KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// Where a proof is asked for, see the module documentation.
+pub const PROVE_PATH: &str = "/api/prove";
+
+/// Where the runs asked for are polled, by their id below it.
+pub const RUNS_PATH: &str = "/api/runs";
+
+/// The most steps a run asked for over HTTP may have unless the server is given another bound.
+pub const DEFAULT_MAX_STEPS: usize = 1 << 16;
+
+// A ProveRequest is a few numbers, anything much bigger is not one
+const PROVE_REQUEST_MAX_BYTES: u64 = 1 << 16;
+
+/// Exports of at most this many bytes come inline with their update, bigger ones are for the page to fetch.
+pub const LIVE_INLINE_MAX_BYTES: u64 = 1 << 20;
+
+/// How often the live file's modification time is checked.
+pub const LIVE_POLL: Duration = Duration::from_millis(200);
+
+/// How long the live file has to stay unchanged before its update goes out.
+pub const LIVE_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// What a live socket is sent when the export changed, as a JSON text message tagged with its `type`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveUpdate {
+    /// The new export itself, a small one that reads as JSON.
+    Update { path: String, data: serde_json::Value },
+    /// The export at `path` changed, fetch it again.
+    Reload { path: String },
+}
+
+/// What a POST to PROVE_PATH asks for, as JSON.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ProveRequest {
+    pub num_steps: usize,
+    #[serde(default)]
+    pub final_value: Option<u64>, // computed from num_steps when missing
+    #[serde(default)]
+    pub tamper: Vec<TamperRequest>, // cells to overwrite before proving, for a trace that is rejected
+}
+
+/// One cell a ProveRequest overwrites, as FibonacciParams::tamper.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct TamperRequest {
+    pub row: usize,
+    pub column: usize,
+    pub value: u32,
+}
+
+/// Where a run asked for over HTTP is at.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Proving,
+    Done,   // proven and verified
+    Failed, // rejected, or an error on the way, see the run's error
+}
+
+/// A run asked for over HTTP, as the POST answers it and RUNS_PATH/{id} reports it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ApiRun {
+    pub id: usize,
+    pub status: RunStatus,
+    pub request: ProveRequest,
+    pub verified: bool,
+    pub final_value: Option<u64>, // the trace's, once generated
+    pub proof_bytes: Option<usize>,
+    pub timings: Option<Timings>,
+    pub error: Option<String>,
+}
+
+/// A server for the files under one directory, see the module documentation.
+pub struct StaticServer {
+    server: Arc<Server>,
+    root: PathBuf,
+    addr: SocketAddr,
+    live: Arc<Live>,
+    stop: Arc<AtomicBool>,
+    events: Option<EventLog>,
+    api: Arc<Api>,
+}
+
+/// Stops a StaticServer from another thread, e.g. a Ctrl+C handler: run returns once the request it is answering,
+/// if any, is done.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    server: Arc<Server>,
+    stop: Arc<AtomicBool>,
+}
+
+// The live file and the sockets its updates go to.
+struct Live {
+    file: PathBuf,
+    clients: Mutex<Vec<WebSocket<Box<dyn ReadWrite + Send>>>>,
+}
+
+// The runs asked for over HTTP, numbered from 1 in the order they were, and where the verified ones are exported.
+struct Api {
+    export: PathBuf,
+    max_steps: usize,
+    runs: Mutex<Vec<ApiRun>>,
+}
+
+impl StaticServer {
+    /// Listens on `port` of localhost for the files under `root`; port 0 takes any free one, see addr.
+    pub fn bind(root: &Path, port: u16) -> Result<Self, FibVisError> {
+        if !root.is_dir() {
+            return Err(FibVisError::Config(format!("{} is not a directory to serve", root.display())));
+        }
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        let listener = TcpListener::bind(addr).map_err(|e| match e.kind() {
+            io::ErrorKind::AddrInUse => FibVisError::Config(format!(
+                "port {port} is already in use, pick another with --port (0 for any free one)"
+            )),
+            _ => FibVisError::io(Path::new(&addr.to_string()), e),
+        })?;
+        let addr = listener.local_addr().map_err(|e| FibVisError::io(Path::new(&addr.to_string()), e))?;
+        let server = Server::from_listener(listener, None).map_err(|e| FibVisError::Config(format!("{addr}: {e}")))?;
+        let live = Arc::new(Live { file: root.join(LIVE_FILE), clients: Mutex::new(Vec::new()) });
+        let stop = Arc::new(AtomicBool::new(false));
+        let runs = Mutex::new(Vec::new());
+        let api = Arc::new(Api { export: live.file.clone(), max_steps: DEFAULT_MAX_STEPS, runs });
+        Ok(Self { server: Arc::new(server), root: root.to_path_buf(), addr, live, stop, events: None, api })
+    }
+
+    /// Takes runs of at most `max_steps` steps on PROVE_PATH, instead of DEFAULT_MAX_STEPS.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.api = Arc::new(Api { export: self.api.export.clone(), max_steps, runs: Mutex::new(Vec::new()) });
+        self
+    }
+
+    /// Streams the events of `log` on EVENTS_PATH.
+    pub fn with_events(mut self, log: EventLog) -> Self {
+        self.events = Some(log);
+        self
+    }
+
+    /// The address the server listens on, with the port it got.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The URL of the root directory, which serves its index.html.
+    pub fn url(&self) -> String {
+        format!("http://{}/", self.addr)
+    }
+
+    /// A handle to stop run with.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle { server: self.server.clone(), stop: self.stop.clone() }
+    }
+
+    /// Answers requests, and watches the live file on a thread of its own, until shut down.
+    pub fn run(&self) {
+        let watcher = {
+            let (live, stop) = (self.live.clone(), self.stop.clone());
+            std::thread::spawn(move || live.watch(&stop))
+        };
+        for request in self.server.incoming_requests() {
+            let url = request.url().to_string();
+            let status = self.respond(request);
+            tracing::debug!("{url}: {status}");
+        }
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(log) = &self.events {
+            log.close();
+        }
+        let _ = watcher.join();
+    }
+
+    // Answers one request and gives back its status code. A client that went away is nothing to report.
+    fn respond(&self, request: Request) -> u16 {
+        let route = request.url().split('?').next().unwrap_or_default().to_string();
+        if route == PROVE_PATH {
+            return Api::prove(&self.api, request);
+        }
+        if let Some(id) = route.strip_prefix(RUNS_PATH).and_then(|rest| rest.strip_prefix('/')) {
+            return self.api.report(request, id);
+        }
+        if !matches!(request.method(), Method::Get | Method::Head) {
+            let _ = request.respond(Response::from_string("only GET and HEAD are served").with_status_code(405));
+            return 405;
+        }
+        if route == LIVE_PATH {
+            return self.live.accept(request);
+        }
+        if route == EVENTS_PATH {
+            return self.stream_events(request);
+        }
+        let Some(path) = self.file_path(request.url()) else {
+            let _ = request.respond(Response::from_string("not found").with_status_code(404));
+            return 404;
+        };
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                let status = if e.kind() == io::ErrorKind::NotFound { 404 } else { 500 };
+                let _ = request.respond(Response::from_string(e.to_string()).with_status_code(status));
+                return status;
+            }
+        };
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default().to_ascii_lowercase();
+        let mut response = Response::from_file(file).with_header(header("Content-Type", content_type(&extension)));
+        if is_export(&extension) {
+            response = response.with_header(header("Cache-Control", "no-cache"));
+        }
+        let _ = request.respond(response);
+        200
+    }
+
+    // Hands the connection of `request` to a thread of its own, which writes the events of the log to it until the
+    // log is closed or the client goes away.
+    fn stream_events(&self, request: Request) -> u16 {
+        let Some(log) = &self.events else {
+            let _ = request.respond(Response::from_string("no pipeline runs under this server").with_status_code(404));
+            return 404;
+        };
+        let mut subscription = log.subscribe();
+        let mut out = request.into_writer();
+        std::thread::spawn(move || {
+            let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\
+                        Connection: close\r\n\r\n";
+            let mut send = |text: &str| out.write_all(text.as_bytes()).and_then(|()| out.flush());
+            if send(head).is_err() {
+                return;
+            }
+            loop {
+                let text = match subscription.next_timeout(EVENTS_KEEPALIVE) {
+                    Some(event) => match serde_json::to_string(&event) {
+                        Ok(json) => format!("data: {json}\n\n"),
+                        Err(_) => continue,
+                    },
+                    None if subscription.is_closed() => return,
+                    None => ": keepalive\n\n".to_string(),
+                };
+                if send(&text).is_err() {
+                    return;
+                }
+            }
+        });
+        200
+    }
+
+    // The file under the root that `url` names, its index.html for a directory. Only plain names are followed,
+    // so None for a URL that would leave the root.
+    fn file_path(&self, url: &str) -> Option<PathBuf> {
+        let relative = Path::new(url.split(['?', '#']).next().unwrap_or_default().trim_start_matches('/'));
+        if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            return None;
+        }
+        let path = self.root.join(relative);
+        Some(if path.is_dir() { path.join("index.html") } else { path })
+    }
+}
+
+impl ShutdownHandle {
+    /// Has StaticServer::run return.
+    pub fn shutdown(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.server.unblock();
+    }
+}
+
+impl Live {
+    // Completes the WebSocket handshake of `request` and keeps the socket for the next update. The sockets are only
+    // written to, a client that closed its end fails the next write and is dropped then.
+    fn accept(&self, request: Request) -> u16 {
+        let key = request.headers().iter().find(|h| h.field.equiv("Sec-WebSocket-Key")).map(|h| h.value.to_string());
+        let Some(key) = key else {
+            let _ = request.respond(Response::from_string("expected a WebSocket handshake").with_status_code(400));
+            return 400;
+        };
+        // tiny_http adds the Upgrade and Connection headers itself
+        let accept = derive_accept_key(key.as_bytes());
+        let response = Response::empty(101).with_header(header("Sec-WebSocket-Accept", &accept));
+        let stream = request.upgrade("websocket", response);
+        let socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+        self.clients.lock().unwrap_or_else(PoisonError::into_inner).push(socket);
+        101
+    }
+
+    // Polls the live file until `stop`, sending one update per burst of writes.
+    fn watch(&self, stop: &AtomicBool) {
+        let modified = || -> Option<SystemTime> { std::fs::metadata(&self.file).and_then(|m| m.modified()).ok() };
+        let mut last_seen = modified();
+        while !stop.load(Ordering::Relaxed) {
+            std::thread::sleep(LIVE_POLL);
+            let current = modified();
+            if current == last_seen {
+                continue;
+            }
+            let mut settled = current;
+            loop {
+                std::thread::sleep(LIVE_DEBOUNCE);
+                let again = modified();
+                if again == settled {
+                    break;
+                }
+                settled = again;
+            }
+            last_seen = settled;
+            self.broadcast(&self.update());
+        }
+    }
+
+    // The update for the live file as it is now: inline when it is small and reads as JSON, a reload otherwise.
+    fn update(&self) -> LiveUpdate {
+        let path = LIVE_FILE.to_string();
+        let small = std::fs::metadata(&self.file).is_ok_and(|m| m.len() <= LIVE_INLINE_MAX_BYTES);
+        let data = if small {
+            std::fs::read(&self.file).ok().and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        } else {
+            None
+        };
+        match data {
+            Some(data) => LiveUpdate::Update { path, data },
+            None => LiveUpdate::Reload { path },
+        }
+    }
+
+    // Sends `update` to every socket, dropping the ones that can no longer be written to.
+    fn broadcast(&self, update: &LiveUpdate) {
+        let Ok(text) = serde_json::to_string(update) else { return };
+        let mut clients = self.clients.lock().unwrap_or_else(PoisonError::into_inner);
+        clients.retain_mut(|socket| socket.send(Message::text(text.clone())).is_ok());
+        tracing::debug!("{LIVE_FILE} changed, {} live clients updated", clients.len());
+    }
+}
+
+impl Api {
+    // Starts the run `request` asks for on a worker thread, unless it is not one this server takes or another run
+    // is still proving.
+    fn prove(api: &Arc<Self>, mut request: Request) -> u16 {
+        if request.method() != &Method::Post {
+            return respond_error(request, 405, format!("{PROVE_PATH} takes a POST of the run to prove"));
+        }
+        // A page from another origin cannot send JSON without asking first, and it is not told yes
+        let json = request.headers().iter().any(|h| {
+            let mime = h.value.as_str().split(';').next().unwrap_or_default();
+            h.field.equiv("Content-Type") && mime.trim().eq_ignore_ascii_case("application/json")
+        });
+        if !json {
+            return respond_error(request, 415, "the run to prove is sent as application/json".to_string());
+        }
+        let mut body = Vec::new();
+        if let Err(e) = request.as_reader().take(PROVE_REQUEST_MAX_BYTES).read_to_end(&mut body) {
+            return respond_error(request, 400, e.to_string());
+        }
+        let asked: ProveRequest = match serde_json::from_slice(&body) {
+            Ok(asked) => asked,
+            Err(e) => return respond_error(request, 400, format!("not a run to prove: {e}")),
+        };
+        if asked.num_steps > api.max_steps {
+            let (steps, max) = (asked.num_steps, api.max_steps);
+            let reason = format!("{steps} steps are more than this server proves, at most {max}");
+            return respond_error(request, 400, reason);
+        }
+
+        let run = {
+            let mut runs = api.runs();
+            if let Some(busy) = runs.iter().find(|run| run.status == RunStatus::Proving) {
+                let reason = format!("run {} is still proving, ask again once it is done", busy.id);
+                return respond_error(request, 409, reason);
+            }
+            let run = ApiRun {
+                id: runs.len() + 1,
+                status: RunStatus::Proving,
+                request: asked,
+                verified: false,
+                final_value: None,
+                proof_bytes: None,
+                timings: None,
+                error: None,
+            };
+            runs.push(run.clone());
+            run
+        };
+        let worker = api.clone();
+        let (id, asked) = (run.id, run.request.clone());
+        std::thread::spawn(move || worker.finish(id, &asked));
+        let location = format!("{RUNS_PATH}/{id}");
+        let _ = request.respond(json_response(202, &run).with_header(header("Location", &location)));
+        202
+    }
+
+    // Proves and verifies what run `id` asked for, and records how that went.
+    fn finish(&self, id: usize, asked: &ProveRequest) {
+        let params = FibonacciParams {
+            final_value: asked.final_value,
+            tamper: asked.tamper.iter().map(|t| (t.row, t.column, t.value)).collect(),
+            ..FibonacciParams::new(asked.num_steps)
+        };
+        let outcome = prove_fibonacci(params.clone()).and_then(|proven| {
+            let start = Instant::now();
+            verify_fibonacci(&params, &proven.proof)?;
+            let timings = Timings { verify_ms: elapsed_ms(start), ..proven.timings };
+            let (final_value, proof_bytes) = (proven.vis_data.final_value, proven.proof.len());
+            let verification = Verification::new(None, Some(proof_bytes), timings.verify_ms);
+            write_trace_json(&self.export, &proven.vis_data.with_verification(verification))?;
+            Ok((final_value, proof_bytes, timings))
+        });
+        if let Some(run) = self.runs().get_mut(id - 1) {
+            match outcome {
+                Ok((final_value, proof_bytes, timings)) => {
+                    run.status = RunStatus::Done;
+                    run.verified = true;
+                    run.final_value = Some(final_value);
+                    run.proof_bytes = Some(proof_bytes);
+                    run.timings = Some(timings);
+                }
+                Err(e) => {
+                    run.status = RunStatus::Failed;
+                    run.error = Some(e.to_string());
+                }
+            }
+        }
+    }
+
+    // Answers with run `id` as it is now.
+    fn report(&self, request: Request, id: &str) -> u16 {
+        if !matches!(request.method(), Method::Get | Method::Head) {
+            return respond_error(request, 405, "runs are polled with GET".to_string());
+        }
+        let run = id.parse::<usize>().ok().and_then(|id| self.runs().get(id.checked_sub(1)?).cloned());
+        match run {
+            Some(run) => {
+                let _ = request.respond(json_response(200, &run));
+                200
+            }
+            None => respond_error(request, 404, format!("no run {id}")),
+        }
+    }
+
+    // A worker that panicked leaves the runs as they were.
+    fn runs(&self) -> MutexGuard<'_, Vec<ApiRun>> {
+        self.runs.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// `value` as a JSON response with `status`, never cached: a run changes while it is polled.
+fn json_response<T: Serialize>(status: u16, value: &T) -> Response<io::Cursor<Vec<u8>>> {
+    let body = serde_json::to_vec(value).unwrap_or_default();
+    Response::from_data(body)
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"))
+        .with_header(header("Cache-Control", "no-cache"))
+}
+
+// Answers `request` with `status` and `{"error": reason}`, and gives back the status.
+fn respond_error(request: Request, status: u16, reason: String) -> u16 {
+    let _ = request.respond(json_response(status, &serde_json::json!({ "error": reason })));
+    status
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("header names and values here are ASCII")
+}
+
+// The MIME type of a file by its extension, for the files the visualizer and the exports come in.
+fn content_type(extension: &str) -> &'static str {
+    match extension {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "json" => "application/json",
+        "ndjson" => "application/x-ndjson",
+        "csv" => "text/csv; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "wasm" => "application/wasm",
+        "gz" => "application/gzip",
+        "msgpack" => "application/msgpack",
+        "cbor" => "application/cbor",
+        "txt" | "ts" | "md" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+// Whether a file of this extension is one the binary writes, and so may have changed since the page last got it.
+fn is_export(extension: &str) -> bool {
+    matches!(extension, "json" | "ndjson" | "csv" | "svg" | "gz" | "msgpack" | "cbor" | "bin" | "js")
+}
@@ -0,0 +1,477 @@
+use std::marker::PhantomData;
+
+use clap::ValueEnum;
+#[cfg(feature = "babybear")]
+use p3_baby_bear::BabyBear;
+#[cfg(feature = "poseidon2")]
+use p3_challenger::{CanObserve, DuplexChallenger};
+use p3_challenger::{HashChallenger, SerializingChallenger32};
+use p3_circle::CirclePcs;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+#[cfg(feature = "poseidon2")]
+use p3_field::{Field, PrimeCharacteristicRing};
+use p3_field::PrimeField32;
+use p3_fri::{FriConfig, TwoAdicFriPcs};
+use p3_keccak::Keccak256Hash;
+#[cfg(feature = "koalabear")]
+use p3_koala_bear::KoalaBear;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_mersenne_31::Mersenne31;
+use p3_symmetric::{CompressionFunctionFromHasher, CryptographicHasher, SerializingHasher};
+#[cfg(feature = "poseidon2")]
+use p3_symmetric::{CryptographicPermutation, PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::StarkConfig;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::FibVisError;
+use crate::transcript::RecordingChallenger;
+
+// STARK configuration. Mersenne31 pairs with the circle PCS, BabyBear and KoalaBear (which have large
+// two-adic subgroups) with the two-adic FRI PCS. Independently of the field, the Merkle commitments and
+// the Fiat-Shamir challenger are built from Keccak256, Blake3 (both hashing serialized bytes) or
+// Poseidon2 (hashing field elements natively).
+
+/// Prime field the trace and proof are computed over.
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldChoice {
+    Mersenne31,
+    #[value(name = "babybear")]
+    BabyBear,
+    #[value(name = "koalabear")]
+    KoalaBear,
+}
+
+impl FieldChoice {
+    /// Name recorded in the exported JSON.
+    pub fn name(self) -> &'static str {
+        match self {
+            FieldChoice::Mersenne31 => "Mersenne31",
+            FieldChoice::BabyBear => "BabyBear",
+            FieldChoice::KoalaBear => "KoalaBear",
+        }
+    }
+
+    /// Identifier of the field in a --format bin export.
+    pub fn id(self) -> u8 {
+        match self {
+            FieldChoice::Mersenne31 => 0,
+            FieldChoice::BabyBear => 1,
+            FieldChoice::KoalaBear => 2,
+        }
+    }
+
+    /// The field with the given id, see id.
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::value_variants().iter().copied().find(|field| field.id() == id)
+    }
+
+    /// Cargo feature the field is gated behind, if any.
+    pub fn feature(self) -> Option<&'static str> {
+        match self {
+            FieldChoice::Mersenne31 => None,
+            FieldChoice::BabyBear => Some("babybear"),
+            FieldChoice::KoalaBear => Some("koalabear"),
+        }
+    }
+
+    /// Whether this binary was built with the field's backend.
+    pub fn is_enabled(self) -> bool {
+        match self {
+            FieldChoice::Mersenne31 => true,
+            FieldChoice::BabyBear => cfg!(feature = "babybear"),
+            FieldChoice::KoalaBear => cfg!(feature = "koalabear"),
+        }
+    }
+
+    /// The field's prime, for arithmetic that has to match the field without needing its backend.
+    pub fn modulus(self) -> u32 {
+        match self {
+            FieldChoice::Mersenne31 => (1 << 31) - 1,
+            FieldChoice::BabyBear => 15 * (1 << 27) + 1,
+            FieldChoice::KoalaBear => (1 << 31) - (1 << 24) + 1,
+        }
+    }
+
+    /// Bit length of the field's prime.
+    pub fn bits(self) -> usize {
+        (u32::BITS - self.modulus().leading_zeros()) as usize
+    }
+
+    /// Degree of the extension field the verifier's challenges are drawn from, see M31Challenge.
+    pub fn challenge_degree(self) -> usize {
+        match self {
+            FieldChoice::Mersenne31 => M31_CHALLENGE_DEGREE,
+            FieldChoice::BabyBear | FieldChoice::KoalaBear => TWO_ADIC_CHALLENGE_DEGREE,
+        }
+    }
+
+    /// Rejects a value that is not a canonical element of the field, i.e. not below its prime. Converting it would
+    /// silently reduce it to some other value.
+    pub fn check_element(self, value: u64) -> Result<(), String> {
+        if value >= u64::from(self.modulus()) {
+            return Err(format!(
+                "{value} is not a {} element, it must be below the prime {}",
+                self.name(),
+                self.modulus()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Parses a field element given in decimal or as `0x`-prefixed hex. Anything that does not fit in 64 bits is an
+/// error; whether the value is below the prime of the chosen field is checked by FieldChoice::check_element.
+pub fn parse_field_element(s: &str) -> Result<u64, String> {
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|e| match e.kind() {
+        std::num::IntErrorKind::PosOverflow => format!("`{s}` does not fit in 64 bits"),
+        _ => format!("`{s}` is not a decimal or 0x-prefixed hex number"),
+    })
+}
+
+/// Hash behind the Merkle commitments and the Fiat-Shamir challenger.
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HashChoice {
+    Keccak256,
+    Poseidon2,
+    Blake3,
+}
+
+impl HashChoice {
+    /// Name recorded in the exported JSON.
+    pub fn name(self) -> &'static str {
+        match self {
+            HashChoice::Keccak256 => "Keccak256",
+            HashChoice::Poseidon2 => "Poseidon2",
+            HashChoice::Blake3 => "Blake3",
+        }
+    }
+
+    /// Cargo feature the hash is gated behind, if any.
+    pub fn feature(self) -> Option<&'static str> {
+        match self {
+            HashChoice::Keccak256 => None,
+            HashChoice::Poseidon2 => Some("poseidon2"),
+            HashChoice::Blake3 => Some("blake3"),
+        }
+    }
+
+    /// Whether this binary was built with the hash's backend.
+    pub fn is_enabled(self) -> bool {
+        match self {
+            HashChoice::Keccak256 => true,
+            HashChoice::Poseidon2 => cfg!(feature = "poseidon2"),
+            HashChoice::Blake3 => cfg!(feature = "blake3"),
+        }
+    }
+}
+
+/// Fails with FibVisError::MissingFeature when the field or hash was left out of this build.
+pub fn require_backend(field: FieldChoice, hash: HashChoice) -> Result<(), FibVisError> {
+    if let (false, Some(feature)) = (field.is_enabled(), field.feature()) {
+        return Err(FibVisError::MissingFeature { feature, requested: format!("--field {feature}") });
+    }
+    if let (false, Some(feature)) = (hash.is_enabled(), hash.feature()) {
+        return Err(FibVisError::MissingFeature { feature, requested: format!("--hash {feature}") });
+    }
+    Ok(())
+}
+
+/// FRI parameters, defaulting to the values the demo always used. They are echoed into the exported
+/// JSON so a run can be reproduced from its artifact.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FriParams {
+    pub log_blowup: usize,
+    pub num_queries: usize,
+    pub proof_of_work_bits: usize,
+    pub log_final_poly_len: usize,
+}
+
+impl Default for FriParams {
+    fn default() -> Self {
+        Self {
+            log_blowup: 1,
+            num_queries: 100,
+            proof_of_work_bits: 16,
+            log_final_poly_len: 1,
+        }
+    }
+}
+
+impl FriParams {
+    /// Rejects combinations the prover cannot work with before any setup happens. A trace of `num_steps` rows is
+    /// padded to the next power of two if need be.
+    pub fn validate(&self, num_steps: usize) -> Result<(), String> {
+        self.check_ranges()?;
+        let log_trace_len = num_steps.next_power_of_two().ilog2() as usize;
+        if self.log_final_poly_len > log_trace_len {
+            return Err(format!(
+                "final polynomial length 2^{} exceeds the trace length 2^{log_trace_len} (LDE size 2^{})",
+                self.log_final_poly_len,
+                log_trace_len + self.log_blowup
+            ));
+        }
+        Ok(())
+    }
+
+    /// The checks that do not depend on the trace length.
+    pub fn check_ranges(&self) -> Result<(), String> {
+        if self.log_blowup == 0 {
+            return Err("log_blowup must be at least 1, the Fibonacci constraints have degree 2".to_string());
+        }
+        if self.num_queries == 0 {
+            return Err("num_queries must be at least 1".to_string());
+        }
+        if self.proof_of_work_bits > 30 {
+            return Err(format!("pow_bits = {} is infeasible to grind, use at most 30", self.proof_of_work_bits));
+        }
+        Ok(())
+    }
+
+    /// FRI folding rounds, each committing one Merkle root, in the proof of a trace of `num_steps` rows over
+    /// `field`. FRI folds the LDE of 2^(log n + log_blowup) rows in half per round until 2^log_final_poly_len
+    /// coefficients (times the blowup) are left; the circle PCS (Mersenne31) does the first fold itself.
+    pub fn commit_rounds(&self, field: FieldChoice, num_steps: usize) -> usize {
+        let log_trace_len = num_steps.next_power_of_two().ilog2() as usize;
+        let circle_fold = usize::from(field == FieldChoice::Mersenne31);
+        log_trace_len.saturating_sub(self.log_final_poly_len + circle_fold)
+    }
+}
+
+/// Challenger seed, written as hex (`seed = "f1b0"`) on the command line, in config files and in the
+/// exported JSON. The empty seed is what the demo used before the flag existed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Seed(pub Vec<u8>);
+
+/// Parses a hex seed, with or without a `0x` prefix.
+pub fn parse_seed(s: &str) -> Result<Seed, String> {
+    let hex = s.strip_prefix("0x").unwrap_or(s);
+    if hex.len() % 2 != 0 {
+        return Err(format!("seed `{s}` must have an even number of hex digits"));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2).unwrap_or("-"), 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map(Seed)
+        .map_err(|_| format!("seed `{s}` is not valid hex"))
+}
+
+impl TryFrom<String> for Seed {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, String> {
+        parse_seed(&s)
+    }
+}
+
+impl From<Seed> for String {
+    fn from(seed: Seed) -> String {
+        seed.0.iter().map(|b| format!("{b:02x}")).collect()
+    }
+}
+
+// Degrees of the extension fields the challenges are drawn from, so that the export can record them.
+pub const M31_CHALLENGE_DEGREE: usize = 3;
+pub const TWO_ADIC_CHALLENGE_DEGREE: usize = 4;
+
+pub type M31Challenge = BinomialExtensionField<Mersenne31, M31_CHALLENGE_DEGREE>;
+#[cfg(feature = "babybear")]
+pub type BabyBearChallenge = BinomialExtensionField<BabyBear, TWO_ADIC_CHALLENGE_DEGREE>;
+#[cfg(feature = "koalabear")]
+pub type KoalaBearChallenge = BinomialExtensionField<KoalaBear, TWO_ADIC_CHALLENGE_DEGREE>;
+
+// Byte-oriented hashes (Keccak256, Blake3): field elements are serialized to bytes before hashing.
+pub type ByteHashMmcs<F, H> = MerkleTreeMmcs<F, u8, SerializingHasher<H>, CompressionFunctionFromHasher<H, 2, 32>, 32>;
+pub type ByteHashChallenger<F, H> = SerializingChallenger32<F, HashChallenger<u8, H, 32>>;
+
+// Poseidon2 over the field itself, using a width-16 permutation with 8-element digests.
+#[cfg(feature = "poseidon2")]
+pub type Poseidon2Mmcs<F, P> = MerkleTreeMmcs<
+    <F as Field>::Packing,
+    <F as Field>::Packing,
+    PaddingFreeSponge<P, 16, 8, 8>,
+    TruncatedPermutation<P, 2, 8, 16>,
+    8,
+>;
+#[cfg(feature = "poseidon2")]
+pub type Poseidon2Challenger<F, P> = DuplexChallenger<F, P, 16, 8>;
+
+pub type CircleConfig<M, C> = StarkConfig<CirclePcs<Mersenne31, M, ExtensionMmcs<Mersenne31, M31Challenge, M>>, M31Challenge, C>;
+pub type TwoAdicConfig<F, EF, M, C> = StarkConfig<TwoAdicFriPcs<F, Radix2DitParallel<F>, M, ExtensionMmcs<F, EF, M>>, EF, C>;
+
+/// The demo's original stack: Mersenne31 with the circle PCS, Keccak256 Merkle commitments and challenger.
+pub type M31KeccakConfig = CircleConfig<ByteHashMmcs<Mersenne31, Keccak256Hash>, ByteHashChallenger<Mersenne31, Keccak256Hash>>;
+
+/// M31KeccakConfig with its challenger recording the transcript, see ConfigBuilder::build_recording. It makes the
+/// same proofs but for the proof-of-work witness, which it grinds for in order, and M31KeccakConfig verifies them.
+pub type M31KeccakRecordingConfig = CircleConfig<
+    ByteHashMmcs<Mersenne31, Keccak256Hash>,
+    RecordingChallenger<Mersenne31, ByteHashChallenger<Mersenne31, Keccak256Hash>>,
+>;
+
+// Seed for the Poseidon2 round constants. It is fixed so that a proof written by `prove` can be checked
+// by a later `verify` run, which has to rebuild exactly the same permutation.
+#[cfg(feature = "poseidon2")]
+pub const POSEIDON2_SEED: u64 = 1;
+
+// Prover and verifier must construct the config identically, otherwise the verifier's transcript
+// diverges from the prover's.
+pub fn build_fri_config<M>(mmcs: M, fri: &FriParams) -> FriConfig<M> {
+    FriConfig {
+        log_blowup: fri.log_blowup,
+        num_queries: fri.num_queries,
+        proof_of_work_bits: fri.proof_of_work_bits,
+        mmcs,
+        log_final_poly_len: fri.log_final_poly_len,
+    }
+}
+
+pub fn byte_hash_mmcs<F, H: Copy>(hash: H) -> ByteHashMmcs<F, H> {
+    MerkleTreeMmcs::new(SerializingHasher::new(hash), CompressionFunctionFromHasher::new(hash))
+}
+
+// The seed is the challenger's initial input, so two runs with the same seed and parameters produce the
+// same transcript and byte-identical proofs, while different seeds give domain-separated transcripts.
+pub fn byte_hash_challenger<F: PrimeField32, H: CryptographicHasher<u8, [u8; 32]>>(hash: H, seed: &Seed) -> ByteHashChallenger<F, H> {
+    SerializingChallenger32::from_hasher(seed.0.clone(), hash)
+}
+
+#[cfg(feature = "poseidon2")]
+pub fn poseidon2_mmcs<F: Field, P: Clone>(perm: P) -> Poseidon2Mmcs<F, P> {
+    MerkleTreeMmcs::new(PaddingFreeSponge::new(perm.clone()), TruncatedPermutation::new(perm))
+}
+
+// A duplex challenger works on field elements, so the seed is observed one byte per element.
+#[cfg(feature = "poseidon2")]
+pub fn poseidon2_challenger<F: Field, P: CryptographicPermutation<[F; 16]>>(perm: P, seed: &Seed) -> Poseidon2Challenger<F, P> {
+    let mut challenger = DuplexChallenger::new(perm);
+    for &byte in &seed.0 {
+        challenger.observe(F::from_u8(byte));
+    }
+    challenger
+}
+
+pub fn circle_config<M: Clone, C>(val_mmcs: M, challenger: C, fri: &FriParams) -> CircleConfig<M, C> {
+    let challenge_mmcs = ExtensionMmcs::new(val_mmcs.clone());
+    let pcs = CirclePcs {
+        mmcs: val_mmcs,
+        fri_config: build_fri_config(challenge_mmcs, fri),
+        _phantom: PhantomData,
+    };
+    StarkConfig::new(pcs, challenger)
+}
+
+/// Builder for the M31KeccakConfig stack (Keccak256 MMCS, circle PCS with FRI, seeded challenger), with
+/// defaults matching the original hardcoded demo. The config owns its challenger, so the result can be
+/// passed straight to `prove` and `verify`.
+///
+/// ```no_run
+/// use plonky3_fibonacci::stark_config::ConfigBuilder;
+///
+/// let config = ConfigBuilder::new().num_queries(50).pow_bits(8).seed(*b"demo").build().unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConfigBuilder {
+    fri: FriParams,
+    seed: Seed,
+    num_steps: Option<usize>,
+}
+
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All FRI parameters at once, e.g. from a run config.
+    pub fn fri(mut self, fri: FriParams) -> Self {
+        self.fri = fri;
+        self
+    }
+
+    pub fn log_blowup(mut self, log_blowup: usize) -> Self {
+        self.fri.log_blowup = log_blowup;
+        self
+    }
+
+    pub fn num_queries(mut self, num_queries: usize) -> Self {
+        self.fri.num_queries = num_queries;
+        self
+    }
+
+    pub fn pow_bits(mut self, pow_bits: usize) -> Self {
+        self.fri.proof_of_work_bits = pow_bits;
+        self
+    }
+
+    pub fn log_final_poly_len(mut self, log_final_poly_len: usize) -> Self {
+        self.fri.log_final_poly_len = log_final_poly_len;
+        self
+    }
+
+    /// Bytes the challenger absorbs before anything else.
+    pub fn seed(mut self, seed: impl Into<Vec<u8>>) -> Self {
+        self.seed = Seed(seed.into());
+        self
+    }
+
+    /// The trace length the config will be used for, which lets build() also check the final polynomial
+    /// length against it.
+    pub fn num_steps(mut self, num_steps: usize) -> Self {
+        self.num_steps = Some(num_steps);
+        self
+    }
+
+    pub fn fri_params(&self) -> FriParams {
+        self.fri
+    }
+
+    /// Validates the parameters and constructs the config.
+    pub fn build(&self) -> Result<M31KeccakConfig, FibVisError> {
+        self.check()?;
+        Ok(circle_config(
+            byte_hash_mmcs::<Mersenne31, _>(Keccak256Hash {}),
+            byte_hash_challenger::<Mersenne31, _>(Keccak256Hash {}, &self.seed),
+            &self.fri,
+        ))
+    }
+
+    /// Like build, with a challenger that records the Fiat-Shamir transcript of every proof made or verified with
+    /// the config, see transcript::RecordsTranscript.
+    pub fn build_recording(&self) -> Result<M31KeccakRecordingConfig, FibVisError> {
+        self.check()?;
+        let challenger = byte_hash_challenger::<Mersenne31, _>(Keccak256Hash {}, &self.seed);
+        Ok(circle_config(
+            byte_hash_mmcs::<Mersenne31, _>(Keccak256Hash {}),
+            RecordingChallenger::new(challenger, &self.seed),
+            &self.fri,
+        ))
+    }
+
+    fn check(&self) -> Result<(), FibVisError> {
+        match self.num_steps {
+            Some(num_steps) => self.fri.validate(num_steps),
+            None => self.fri.check_ranges(),
+        }
+        .map_err(FibVisError::Config)
+    }
+}
+
+pub fn two_adic_config<F, EF, M: Clone, C>(val_mmcs: M, challenger: C, fri: &FriParams) -> TwoAdicConfig<F, EF, M, C>
+where
+    Radix2DitParallel<F>: Default,
+{
+    let challenge_mmcs = ExtensionMmcs::new(val_mmcs.clone());
+    let pcs = TwoAdicFriPcs::new(Radix2DitParallel::default(), val_mmcs, build_fri_config(challenge_mmcs, fri));
+    StarkConfig::new(pcs, challenger)
+}
@@ -0,0 +1,430 @@
+use p3_air::BaseAir;
+use p3_field::{Field, PrimeCharacteristicRing, PrimeField64};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use num_bigint::BigUint;
+
+use crate::air::{StepCount, BIT_DECOMP_BITS};
+use crate::error::FibVisError;
+use crate::progress::{row_progress, PROGRESS_CHUNK};
+use crate::stark_config::FieldChoice;
+
+/// The transition constraints need two rows. uni-stark also requires the trace height to be a power of two, other
+/// step counts need a padded trace, see check_padding.
+pub fn check_num_steps(n: usize) -> Result<(), String> {
+    if n < 2 {
+        return Err(format!("num_steps must be at least 2 (got {n})"));
+    }
+    Ok(())
+}
+
+/// First row of the canonical Fibonacci sequence, F(0) and F(1).
+pub const FIBONACCI_START: (u32, u32) = (0, 1);
+
+/// First row of the Lucas numbers, L(0) and L(1).
+pub const LUCAS_START: (u32, u32) = (2, 1);
+
+/// The trace FibonacciAir expects: `num_steps` rows of (a, b), the first one being `start` and every next one
+/// (b, a + b), computed in `F`. With FIBONACCI_START this is a = F(i), b = F(i+1).
+pub fn generate_fibonacci_trace<F: Field>(num_steps: usize, start: (u32, u32)) -> RowMajorMatrix<F> {
+    let bar = row_progress(num_steps, "trace rows");
+    let mut values = Vec::with_capacity(num_steps * 2);
+    let mut a = F::from_u32(start.0);
+    let mut b = F::from_u32(start.1);
+    for i in 0..num_steps {
+        values.push(a);
+        values.push(b);
+        let c = a + b;
+        a = b;
+        b = c;
+        if i % PROGRESS_CHUNK == 0 {
+            bar.set_position(i as u64);
+        }
+    }
+    bar.finish_and_clear();
+    RowMajorMatrix::new(values, 2)
+}
+
+/// The trace FibonacciWithIndexAir expects: the rows of generate_fibonacci_trace with the row index in front.
+pub fn generate_fibonacci_with_index_trace<F: Field>(num_steps: usize, start: (u32, u32)) -> RowMajorMatrix<F> {
+    let fibonacci = generate_fibonacci_trace::<F>(num_steps, start);
+    let mut values = Vec::with_capacity(num_steps * 3);
+    for (i, row) in fibonacci.values.chunks_exact(2).enumerate() {
+        values.extend([F::from_usize(i), row[0], row[1]]);
+    }
+    RowMajorMatrix::new(values, 3)
+}
+
+/// The trace a padded FibonacciAir expects: the rows of generate_fibonacci_trace with is_real = 1 appended, followed
+/// by rows of zeros up to the next power of two, and every row's index after is_real.
+pub fn generate_padded_fibonacci_trace<F: Field>(num_steps: usize, start: (u32, u32)) -> RowMajorMatrix<F> {
+    let real = generate_fibonacci_trace::<F>(num_steps, start);
+    let height = num_steps.next_power_of_two();
+    let mut values = Vec::with_capacity(height * 4);
+    for row in 0..height {
+        let (a, b, is_real) = match real.values.get(row * 2..row * 2 + 2) {
+            Some(&[a, b]) => (a, b, F::ONE),
+            _ => (F::ZERO, F::ZERO, F::ZERO),
+        };
+        values.extend([a, b, is_real, F::from_usize(row)]);
+    }
+    RowMajorMatrix::new(values, 4)
+}
+
+/// The trace LucasAir expects: generate_fibonacci_trace started from LUCAS_START.
+pub fn generate_lucas_trace<F: Field>(num_steps: usize) -> RowMajorMatrix<F> {
+    generate_fibonacci_trace(num_steps, LUCAS_START)
+}
+
+/// First row of the Tribonacci numbers, T(0), T(1) and T(2).
+pub const TRIBONACCI_START: [u32; 3] = [0, 0, 1];
+
+/// The trace TribonacciAir expects: `num_steps` rows of (a, b, c) with a = T(i), b = T(i+1), c = T(i+2), every next
+/// row being (b, c, a + b + c), computed in `F`.
+pub fn generate_tribonacci_trace<F: Field>(num_steps: usize) -> RowMajorMatrix<F> {
+    let bar = row_progress(num_steps, "trace rows");
+    let mut values = Vec::with_capacity(num_steps * 3);
+    let [mut a, mut b, mut c] = TRIBONACCI_START.map(F::from_u32);
+    for i in 0..num_steps {
+        values.extend([a, b, c]);
+        let d = a + b + c;
+        a = b;
+        b = c;
+        c = d;
+        if i % PROGRESS_CHUNK == 0 {
+            bar.set_position(i as u64);
+        }
+    }
+    bar.finish_and_clear();
+    RowMajorMatrix::new(values, 3)
+}
+
+/// First row of the product recurrence.
+pub const MUL_FIBONACCI_START: (u32, u32) = (1, 2);
+
+/// The trace MulFibonacciAir expects: `num_steps` rows of (a, b), starting from MUL_FIBONACCI_START with every next
+/// row (b, a * b), computed in `F`.
+pub fn generate_mul_fibonacci_trace<F: Field>(num_steps: usize) -> RowMajorMatrix<F> {
+    let bar = row_progress(num_steps, "trace rows");
+    let mut values = Vec::with_capacity(num_steps * 2);
+    let mut a = F::from_u32(MUL_FIBONACCI_START.0);
+    let mut b = F::from_u32(MUL_FIBONACCI_START.1);
+    for i in 0..num_steps {
+        values.push(a);
+        values.push(b);
+        let c = a * b;
+        a = b;
+        b = c;
+        if i % PROGRESS_CHUNK == 0 {
+            bar.set_position(i as u64);
+        }
+    }
+    bar.finish_and_clear();
+    RowMajorMatrix::new(values, 2)
+}
+
+/// The trace LinearRecurrenceAir expects: `num_steps` rows of k = coeffs.len() consecutive values, the first one
+/// being `initial` and every next one the window shifted by one, ending in c(1) x(n-1) + ... + c(k) x(n-k).
+pub fn generate_linear_recurrence_trace<F: Field>(num_steps: usize, coeffs: &[u64], initial: &[u64]) -> RowMajorMatrix<F> {
+    let k = coeffs.len();
+    let bar = row_progress(num_steps, "trace rows");
+    let coeffs: Vec<F> = coeffs.iter().map(|&c| F::from_u64(c)).collect();
+    let mut window: Vec<F> = initial.iter().map(|&v| F::from_u64(v)).collect();
+    let mut values = Vec::with_capacity(num_steps * k);
+    for i in 0..num_steps {
+        values.extend_from_slice(&window);
+        let next = coeffs.iter().zip(window.iter().rev()).map(|(&c, &x)| c * x).sum();
+        window.rotate_left(1);
+        window[k - 1] = next;
+        if i % PROGRESS_CHUNK == 0 {
+            bar.set_position(i as u64);
+        }
+    }
+    bar.finish_and_clear();
+    RowMajorMatrix::new(values, k)
+}
+
+/// One Collatz step of `n`, None once it no longer fits in a u64.
+fn collatz_step(n: u64) -> Option<u64> {
+    if n % 2 == 0 {
+        Some(n / 2)
+    } else {
+        n.checked_mul(3)?.checked_add(1)
+    }
+}
+
+/// The first `num_steps` values of the Collatz orbit of `seed` as exact integers, repeating 1 once they reach it
+/// rather than going around the 1, 4, 2 cycle. None if a value overflows a u64 on the way.
+pub fn collatz_orbit(num_steps: usize, seed: u64) -> Option<Vec<u64>> {
+    let mut orbit = Vec::with_capacity(num_steps);
+    let mut n = seed;
+    for i in 0..num_steps {
+        orbit.push(n);
+        if i + 1 < num_steps && n != 1 {
+            n = collatz_step(n)?;
+        }
+    }
+    Some(orbit)
+}
+
+/// Longest orbit check_initial looks for, far beyond any trace this demo proves.
+pub const COLLATZ_MAX_ROWS: usize = 1 << 20;
+
+/// Number of values of the orbit of `seed` up to and including the first 1, None if it overflows a u64 or has
+/// not reached 1 within `limit` values.
+pub fn collatz_orbit_len(seed: u64, limit: usize) -> Option<usize> {
+    let mut n = seed;
+    for len in 1..=limit {
+        if n == 1 {
+            return Some(len);
+        }
+        n = collatz_step(n)?;
+    }
+    None
+}
+
+/// The trace CollatzAir expects: `num_steps` rows of (value, parity, is_real) along the orbit of `seed`, see
+/// collatz_orbit. The rows from its first 1 on are (1, 0, 0). The caller has checked that the orbit fits in a u64
+/// (see check_initial).
+pub fn generate_collatz_trace<F: Field>(num_steps: usize, seed: u64) -> RowMajorMatrix<F> {
+    let orbit = collatz_orbit(num_steps, seed).expect("the Collatz orbit overflows a u64");
+    let values = orbit.iter().flat_map(|&n| collatz_row(n).map(F::from_u64)).collect();
+    RowMajorMatrix::new(values, 3)
+}
+
+// The (value, parity, is_real) row of generate_collatz_trace holding `n`.
+fn collatz_row(n: u64) -> [u64; 3] {
+    if n == 1 { [1, 0, 0] } else { [n, n % 2, 1] }
+}
+
+/// The trace CounterAir expects: `num_steps` rows holding 0, 1, 2, ....
+pub fn generate_counter_trace<F: Field>(num_steps: usize) -> RowMajorMatrix<F> {
+    RowMajorMatrix::new((0..num_steps).map(F::from_usize).collect(), 1)
+}
+
+/// The value in row `i` of generate_bit_decomp_trace.
+fn bit_decomp_value(i: usize) -> u64 {
+    (i % (1 << BIT_DECOMP_BITS)) as u64
+}
+
+/// The trace BitDecompAir expects: `num_steps` rows of the row index modulo 2^8 followed by its bits, least
+/// significant first.
+pub fn generate_bit_decomp_trace<F: Field>(num_steps: usize) -> RowMajorMatrix<F> {
+    let values = (0..num_steps)
+        .flat_map(|i| {
+            let value = bit_decomp_value(i);
+            std::iter::once(F::from_u64(value)).chain((0..BIT_DECOMP_BITS).map(move |j| F::from_u64((value >> j) & 1)))
+        })
+        .collect();
+    RowMajorMatrix::new(values, 1 + BIT_DECOMP_BITS)
+}
+
+/// Number of leading rows whose true values go into the export. The numbers grow by about 0.7 bits per row, so
+/// past this their decimal strings would dominate both the export time and the file size.
+pub const TRUE_VALUES_MAX_ROWS: usize = 1 << 12;
+
+/// The rows of generate_fibonacci_trace as exact integers, without reduction into any field.
+pub fn fibonacci_true_values(num_steps: usize, start: (u32, u32)) -> Vec<Vec<BigUint>> {
+    let mut rows = Vec::with_capacity(num_steps);
+    let mut a = BigUint::from(start.0);
+    let mut b = BigUint::from(start.1);
+    for _ in 0..num_steps {
+        let c = &a + &b;
+        rows.push(vec![a, b.clone()]);
+        a = b;
+        b = c;
+    }
+    rows
+}
+
+/// The exact values of the first `num_steps` rows of a FibonacciWithIndexAir trace: fibonacci_true_values with
+/// the row index in front.
+pub fn fibonacci_with_index_true_values(num_steps: usize, start: (u32, u32)) -> Vec<Vec<BigUint>> {
+    let mut rows = fibonacci_true_values(num_steps, start);
+    for (i, row) in rows.iter_mut().enumerate() {
+        row.insert(0, BigUint::from(i));
+    }
+    rows
+}
+
+/// Size above which the true values of the product recurrence and of linear recurrences are no longer exported.
+/// The former squares roughly every row and the latter can have coefficients of any size, so unlike the named
+/// additive sequences they would blow past any row limit within a few dozen rows.
+pub const TRUE_VALUE_MAX_BITS: u64 = 1 << 12;
+
+/// The leading rows of generate_mul_fibonacci_trace as exact integers, up to the first one holding a value wider
+/// than TRUE_VALUE_MAX_BITS.
+pub fn mul_fibonacci_true_values(num_steps: usize) -> Vec<Vec<BigUint>> {
+    let mut rows = Vec::new();
+    let mut a = BigUint::from(MUL_FIBONACCI_START.0);
+    let mut b = BigUint::from(MUL_FIBONACCI_START.1);
+    for _ in 0..num_steps {
+        if b.bits() > TRUE_VALUE_MAX_BITS {
+            break;
+        }
+        let c = &a * &b;
+        rows.push(vec![a, b.clone()]);
+        a = b;
+        b = c;
+    }
+    rows
+}
+
+/// The leading rows of generate_linear_recurrence_trace as exact integers, up to the first one holding a value
+/// wider than TRUE_VALUE_MAX_BITS.
+pub fn linear_recurrence_true_values(num_steps: usize, coeffs: &[u64], initial: &[u64]) -> Vec<Vec<BigUint>> {
+    let k = coeffs.len();
+    let mut rows = Vec::new();
+    let mut window: Vec<BigUint> = initial.iter().map(|&v| BigUint::from(v)).collect();
+    for _ in 0..num_steps {
+        if window[k - 1].bits() > TRUE_VALUE_MAX_BITS {
+            break;
+        }
+        let next = coeffs.iter().zip(window.iter().rev()).map(|(&c, x)| x * c).sum();
+        rows.push(window.clone());
+        window.rotate_left(1);
+        window[k - 1] = next;
+    }
+    rows
+}
+
+/// The rows of generate_collatz_trace as exact integers.
+pub fn collatz_true_values(num_steps: usize, seed: u64) -> Vec<Vec<BigUint>> {
+    let orbit = collatz_orbit(num_steps, seed).unwrap_or_default();
+    orbit.iter().map(|&n| collatz_row(n).map(BigUint::from).to_vec()).collect()
+}
+
+/// The rows of generate_counter_trace as exact integers.
+pub fn counter_true_values(num_steps: usize) -> Vec<Vec<BigUint>> {
+    (0..num_steps).map(|i| vec![BigUint::from(i)]).collect()
+}
+
+/// The rows of generate_bit_decomp_trace as exact integers.
+pub fn bit_decomp_true_values(num_steps: usize) -> Vec<Vec<BigUint>> {
+    (0..num_steps)
+        .map(|i| {
+            let value = bit_decomp_value(i);
+            std::iter::once(value).chain((0..BIT_DECOMP_BITS).map(|j| (value >> j) & 1)).map(BigUint::from).collect()
+        })
+        .collect()
+}
+
+/// The rows of generate_tribonacci_trace as exact integers.
+pub fn tribonacci_true_values(num_steps: usize) -> Vec<Vec<BigUint>> {
+    let mut rows = Vec::with_capacity(num_steps);
+    let [mut a, mut b, mut c] = TRIBONACCI_START.map(BigUint::from);
+    for _ in 0..num_steps {
+        let d = &a + &b + &c;
+        rows.push(vec![a, b.clone(), c.clone()]);
+        a = b;
+        b = c;
+        c = d;
+    }
+    rows
+}
+
+/// First row containing a value that does not fit below `modulus`, i.e. where the field trace starts to differ
+/// from the true sequence. The rows after it need not all be reduced: a Collatz orbit that peaks above `modulus`
+/// comes back down below it.
+pub fn first_reduced_row(modulus: u32, true_values: &[Vec<BigUint>]) -> Option<usize> {
+    let p = BigUint::from(modulus);
+    true_values.iter().position(|row| row.iter().any(|v| *v >= p))
+}
+
+/// The value in the last row of generate_fibonacci_trace over `field`, computed with the same recurrence
+/// reduced modulo the field's prime, without materializing the trace. Works for every field whether or not
+/// its backend was built.
+pub fn fibonacci_final_value(field: FieldChoice, num_steps: usize, start: (u32, u32)) -> u64 {
+    let p = u64::from(field.modulus());
+    let mut a = u64::from(start.0) % p;
+    let mut b = u64::from(start.1) % p;
+    for _ in 1..num_steps {
+        let c = (a + b) % p;
+        a = b;
+        b = c;
+    }
+    b
+}
+
+/// The value in the last row of generate_tribonacci_trace over `field`, see fibonacci_final_value.
+pub fn tribonacci_final_value(field: FieldChoice, num_steps: usize) -> u64 {
+    let p = u64::from(field.modulus());
+    let [mut a, mut b, mut c] = TRIBONACCI_START.map(u64::from);
+    for _ in 1..num_steps {
+        let d = (a + b + c) % p;
+        a = b;
+        b = c;
+        c = d;
+    }
+    c
+}
+
+/// The value in the last row of generate_mul_fibonacci_trace over `field`, see fibonacci_final_value.
+pub fn mul_fibonacci_final_value(field: FieldChoice, num_steps: usize) -> u64 {
+    let p = u64::from(field.modulus());
+    let (mut a, mut b) = (u64::from(MUL_FIBONACCI_START.0), u64::from(MUL_FIBONACCI_START.1));
+    for _ in 1..num_steps {
+        let c = a * b % p; // both below p < 2^32, so the product fits in a u64
+        a = b;
+        b = c;
+    }
+    b
+}
+
+/// The value in the last row of generate_linear_recurrence_trace over `field`, see fibonacci_final_value.
+pub fn linear_recurrence_final_value(field: FieldChoice, num_steps: usize, coeffs: &[u64], initial: &[u64]) -> u64 {
+    let p = u64::from(field.modulus());
+    let k = coeffs.len();
+    let coeffs: Vec<u64> = coeffs.iter().map(|c| c % p).collect();
+    let mut window: Vec<u64> = initial.iter().map(|v| v % p).collect();
+    for _ in 1..num_steps {
+        // every term is below p^2 < 2^64, so reducing after each addition keeps the sum in range
+        let next = coeffs.iter().zip(window.iter().rev()).fold(0, |acc, (c, x)| (acc + c * x % p) % p);
+        window.rotate_left(1);
+        window[k - 1] = next;
+    }
+    window[k - 1]
+}
+
+/// The value in the last row of generate_collatz_trace over `field`.
+pub fn collatz_final_value(field: FieldChoice, num_steps: usize, seed: u64) -> u64 {
+    let orbit = collatz_orbit(num_steps, seed).expect("the Collatz orbit overflows a u64");
+    orbit[num_steps - 1] % u64::from(field.modulus())
+}
+
+/// The value in the last row of generate_counter_trace over `field`.
+pub fn counter_final_value(field: FieldChoice, num_steps: usize) -> u64 {
+    (num_steps as u64 - 1) % u64::from(field.modulus())
+}
+
+/// The value in the last row of generate_bit_decomp_trace, the same in every field.
+pub fn bit_decomp_final_value(num_steps: usize) -> u64 {
+    bit_decomp_value(num_steps - 1)
+}
+
+/// Checks that `trace` has the width and height `air` declares. A mismatch would otherwise only show up as a panic
+/// somewhere inside uni-stark, so this runs before exporting and before proving.
+pub fn check_trace_shape<F: Field, A: BaseAir<F> + StepCount>(air: &A, trace: &RowMajorMatrix<F>) -> Result<(), FibVisError> {
+    let expected_width = air.width();
+    if trace.width() != expected_width || trace.height() != air.height() {
+        return Err(FibVisError::ShapeMismatch {
+            expected_width,
+            expected_height: air.height(),
+            width: trace.width(),
+            height: trace.height(),
+        });
+    }
+    Ok(())
+}
+
+/// Checks the claimed `expected` value against the cell of `trace` that `air` constrains it to, in the last real row.
+/// A wrong final value would only surface as an opaque failure deep inside uni-stark, so this runs before proving,
+/// after check_trace_shape.
+pub fn check_final_value<F: PrimeField64, A: StepCount>(air: &A, expected: u64, trace: &RowMajorMatrix<F>) -> Result<(), FibVisError> {
+    let row = air.num_steps() - 1;
+    let actual = trace.values[row * trace.width() + air.final_column()].as_canonical_u64();
+    if actual != expected {
+        return Err(FibVisError::FinalValueMismatch { expected, actual, row });
+    }
+    Ok(())
+}
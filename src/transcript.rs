@@ -0,0 +1,265 @@
+//! The Fiat-Shamir transcript of a proof, for the visualizer to replay (`--export-transcript`): every value the
+//! challenger absorbed and every challenge it drew, in order. RecordingChallenger wraps the challenger a config is
+//! built with and logs what passes through it. Most of the calls are made by uni-stark and the PCS rather than by
+//! this crate, so the protocol step of an entry is told from its position: the first commitment observed is the
+//! trace's, the first challenge drawn after it is alpha, and so on.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::Write;
+use std::marker::PhantomData;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+#[cfg(not(target_arch = "wasm32"))]
+use flate2::write::GzEncoder;
+#[cfg(not(target_arch = "wasm32"))]
+use flate2::Compression;
+use p3_challenger::{CanObserve, CanSample, CanSampleBits, FieldChallenger, GrindingChallenger};
+use p3_field::{BasedVectorSpace, Field, PrimeCharacteristicRing};
+use p3_symmetric::Hash;
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::FibVisError;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::export::write_atomically;
+use crate::proof_json::{collect_numbers, root_hex};
+use crate::stark_config::{FieldChoice, HashChoice, Seed};
+
+/// Whether the challenger absorbed a value or gave one out.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptOp {
+    Observe,
+    Sample,
+}
+
+/// The protocol step an entry belongs to, as far as its position tells.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptLabel {
+    Seed,               // the bytes the challenger starts from
+    TraceCommitment,
+    QuotientCommitment,
+    FriCommitment,      // the Merkle root of a FRI layer, the circle PCS's first layer included
+    Alpha,              // the challenge combining the constraints
+    Zeta,               // the out-of-domain point the trace is opened at
+    FriBeta,            // the folding challenge of a FRI layer
+    PowWitness,         // the proof-of-work witness found by grinding
+    QueryIndex,         // the position a FRI query opens
+    Observed,           // any other value absorbed: the public values, the final polynomial
+    Sampled,            // any other challenge drawn
+}
+
+/// One step of the transcript. Field elements are in decimal, an extension element by its coefficients, a
+/// commitment as one hex string. Values observed one after the other with no label of their own are one entry.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptEvent {
+    pub op: TranscriptOp,
+    pub label: TranscriptLabel,
+    pub values: Vec<String>,
+}
+
+/// What `--export-transcript` writes: the transcript of the proof and what it was made with.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TranscriptExport {
+    pub field: FieldChoice,
+    pub hash: HashChoice,
+    pub seed: Seed,
+    pub num_steps: usize,
+    pub events: Vec<TranscriptEvent>,
+}
+
+/// A challenger that logs everything passing through `C`, which does the actual hashing. Clones share the log, so
+/// the transcript of a prover or verifier run can be taken from the config's challenger afterwards, see
+/// RecordsTranscript. `F` is the field the challenger observes.
+#[derive(Clone, Debug)]
+pub struct RecordingChallenger<F, C> {
+    inner: C,
+    log: Arc<Mutex<Log>>,
+    _field: PhantomData<F>,
+}
+
+#[derive(Debug)]
+struct Log {
+    seed: TranscriptEvent,
+    events: Vec<TranscriptEvent>,
+    commitments: usize,      // commitments observed so far
+    sampled: bool,           // whether a challenge was drawn since the last commitment
+    pow_check: Option<bool>, // whether the last proof-of-work witness checked passed
+}
+
+impl Log {
+    fn new(seed: TranscriptEvent) -> Self {
+        Self { events: vec![seed.clone()], seed, commitments: 0, sampled: false, pow_check: None }
+    }
+
+    fn push(&mut self, op: TranscriptOp, label: TranscriptLabel, values: Vec<String>) {
+        match self.events.last_mut() {
+            Some(last) if label == TranscriptLabel::Observed && last.label == TranscriptLabel::Observed => {
+                last.values.extend(values)
+            }
+            _ => self.events.push(TranscriptEvent { op, label, values }),
+        }
+    }
+
+    fn commitment(&mut self, root: String) {
+        let label = match self.commitments {
+            0 => TranscriptLabel::TraceCommitment,
+            1 => TranscriptLabel::QuotientCommitment,
+            _ => TranscriptLabel::FriCommitment,
+        };
+        self.commitments += 1;
+        self.sampled = false;
+        self.push(TranscriptOp::Observe, label, vec![root]);
+    }
+
+    fn challenge(&mut self, values: Vec<String>) {
+        let label = match (self.sampled, self.commitments) {
+            (true, _) | (false, 0) => TranscriptLabel::Sampled,
+            (false, 1) => TranscriptLabel::Alpha,
+            (false, 2) => TranscriptLabel::Zeta,
+            (false, _) => TranscriptLabel::FriBeta,
+        };
+        self.sampled = true;
+        self.push(TranscriptOp::Sample, label, values);
+    }
+}
+
+impl<F, C> RecordingChallenger<F, C> {
+    /// Wraps `inner`, which was seeded with `seed`; the seed is the transcript's first entry.
+    pub fn new(inner: C, seed: &Seed) -> Self {
+        let values = if seed.0.is_empty() { Vec::new() } else { vec![String::from(seed.clone())] };
+        let seed = TranscriptEvent { op: TranscriptOp::Observe, label: TranscriptLabel::Seed, values };
+        Self { inner, log: Arc::new(Mutex::new(Log::new(seed))), _field: PhantomData }
+    }
+
+    // The log survives a panicking prover, which is caught and reported like any other failure.
+    fn log(&self) -> MutexGuard<'_, Log> {
+        self.log.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A challenger whose transcript can be taken, implemented by RecordingChallenger.
+pub trait RecordsTranscript {
+    /// Everything recorded since the challenger was built or the transcript last taken, which starts it over.
+    fn take_transcript(&self) -> Vec<TranscriptEvent>;
+
+    /// Whether the last proof-of-work witness checked since then passed, the verifier's check after verifying.
+    fn pow_check(&self) -> Option<bool>;
+}
+
+impl<F, C> RecordsTranscript for RecordingChallenger<F, C> {
+    fn take_transcript(&self) -> Vec<TranscriptEvent> {
+        let mut log = self.log();
+        let seed = log.seed.clone();
+        std::mem::replace(&mut *log, Log::new(seed)).events
+    }
+
+    fn pow_check(&self) -> Option<bool> {
+        self.log().pow_check
+    }
+}
+
+// The numbers `value` serializes to, in decimal.
+fn numbers<T: Serialize>(value: &T) -> Vec<String> {
+    let mut numbers = Vec::new();
+    collect_numbers(&serde_json::to_value(value).unwrap_or_default(), &mut numbers);
+    numbers.iter().map(u64::to_string).collect()
+}
+
+impl<F: Field, C: CanObserve<F>> CanObserve<F> for RecordingChallenger<F, C> {
+    fn observe(&mut self, value: F) {
+        self.log().push(TranscriptOp::Observe, TranscriptLabel::Observed, vec![value.to_string()]);
+        self.inner.observe(value);
+    }
+}
+
+// Commitments are Merkle roots over `F`, hashed as bytes (Keccak256, Blake3) or as field elements (Poseidon2).
+impl<F, C, W, const N: usize> CanObserve<Hash<F, W, N>> for RecordingChallenger<F, C>
+where
+    C: CanObserve<Hash<F, W, N>>,
+    Hash<F, W, N>: Serialize,
+{
+    fn observe(&mut self, value: Hash<F, W, N>) {
+        self.log().commitment(root_hex(&serde_json::to_value(&value).unwrap_or_default()));
+        self.inner.observe(value);
+    }
+}
+
+impl<F, C: CanSample<T>, T: Serialize> CanSample<T> for RecordingChallenger<F, C> {
+    fn sample(&mut self) -> T {
+        let value = self.inner.sample();
+        self.log().challenge(numbers(&value));
+        value
+    }
+}
+
+impl<F, C: CanSampleBits<usize>> CanSampleBits<usize> for RecordingChallenger<F, C> {
+    fn sample_bits(&mut self, bits: usize) -> usize {
+        let index = self.inner.sample_bits(bits);
+        self.log().push(TranscriptOp::Sample, TranscriptLabel::QueryIndex, vec![index.to_string()]);
+        index
+    }
+}
+
+impl<F: Field, C: FieldChallenger<F>> FieldChallenger<F> for RecordingChallenger<F, C> {
+    fn observe_algebra_element<A: BasedVectorSpace<F>>(&mut self, element: A) {
+        let values = element.as_basis_coefficients_slice().iter().map(F::to_string).collect();
+        self.log().push(TranscriptOp::Observe, TranscriptLabel::Observed, values);
+        self.inner.observe_algebra_element(element);
+    }
+
+    // One entry for the element, not one per coefficient.
+    fn sample_algebra_element<A: BasedVectorSpace<F>>(&mut self) -> A {
+        let element: A = self.inner.sample_algebra_element();
+        self.log().challenge(element.as_basis_coefficients_slice().iter().map(F::to_string).collect());
+        element
+    }
+}
+
+// The inner challenger checks the witness itself, only the witness is recorded, the same way on the prover's side
+// as on the verifier's. Grinding tries the candidates one by one counting up from zero, instead of in parallel as
+// the inner challenger would, so the witness is the smallest that passes and the attempts it took are its value
+// plus one, see proof_json::pow_grinding. The expected 2^bits attempts stay quick at the default 16 bits.
+impl<F: Field, C: GrindingChallenger<Witness = F>> GrindingChallenger for RecordingChallenger<F, C> {
+    type Witness = F;
+
+    fn grind(&mut self, bits: usize) -> F {
+        let witness = (0..=u64::from(u32::MAX))
+            .map(F::from_u64)
+            .find(|&candidate| self.inner.clone().check_witness(bits, candidate))
+            .expect("a witness for at most 30 bits among 2^32 candidates");
+        let passed = self.inner.check_witness(bits, witness);
+        let mut log = self.log();
+        log.push(TranscriptOp::Observe, TranscriptLabel::PowWitness, vec![witness.to_string()]);
+        log.pow_check = Some(passed);
+        witness
+    }
+
+    fn check_witness(&mut self, bits: usize, witness: F) -> bool {
+        let passed = self.inner.check_witness(bits, witness);
+        let mut log = self.log();
+        log.push(TranscriptOp::Observe, TranscriptLabel::PowWitness, vec![witness.to_string()]);
+        log.pow_check = Some(passed);
+        passed
+    }
+}
+
+/// Writes `export` to `path` as compact JSON, gzipped when the path ends in `.gz`. Written atomically, parent
+/// directories created.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_transcript_json(path: &Path, export: &TranscriptExport) -> Result<(), FibVisError> {
+    let compress = path.extension().is_some_and(|ext| ext == "gz");
+    write_atomically(path, |out| {
+        if compress {
+            let mut gz = GzEncoder::new(out, Compression::default());
+            serde_json::to_writer(&mut gz, export)?;
+            gz.finish()?.flush()?;
+        } else {
+            serde_json::to_writer(out, export)?;
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,112 @@
+//! TypeScript declarations of the JSON export, generated from its JSON Schema (export::vis_data_schema) so the
+//! visualizer's types follow VisData the same way the schema does.
+
+use serde_json::Value;
+
+use crate::export::vis_data_schema;
+
+/// The declarations `--emit-ts` writes: an interface per struct and a type per enum, `VisData` first and the rest
+/// by name. A field the export may leave out is optional (`?`), one it writes as `null` is `| null`, and every
+/// trace cell is a `Cell`, whichever --repr wrote it.
+pub fn vis_data_typescript() -> String {
+    let schema = vis_data_schema();
+    let mut ts = String::from("// Generated from the JSON Schema of VisData by `plonky3_fibonacci --emit-ts`, do not edit.\n");
+    declaration(&mut ts, "VisData", &schema);
+    if let Some(Value::Object(definitions)) = schema.get("definitions") {
+        let mut names: Vec<&String> = definitions.keys().collect();
+        names.sort();
+        for name in names {
+            ts.push('\n');
+            declaration(&mut ts, name, &definitions[name.as_str()]);
+        }
+    }
+    ts
+}
+
+// `export interface` for an object schema, `export type` for anything else, under its description.
+fn declaration(ts: &mut String, name: &str, schema: &Value) {
+    if let Some(description) = schema.get("description").and_then(Value::as_str) {
+        ts.push_str(&format!("/** {} */\n", description.replace("\n\n", "\n *\n * ")));
+    }
+    match object_fields(schema) {
+        Some(fields) => {
+            ts.push_str(&format!("export interface {name} {{\n"));
+            for field in fields {
+                ts.push_str(&format!("    {field};\n"));
+            }
+            ts.push_str("}\n");
+        }
+        None => ts.push_str(&format!("export type {name} = {};\n", ts_type(schema))),
+    }
+}
+
+// The `name: type` members of an object schema, in the order of its properties. None if it has none.
+fn object_fields(schema: &Value) -> Option<Vec<String>> {
+    let properties = schema.get("properties")?.as_object()?;
+    let required: Vec<&str> = match schema.get("required") {
+        Some(Value::Array(required)) => required.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    let field = |(name, property): (&String, &Value)| {
+        let optional = if required.contains(&name.as_str()) { "" } else { "?" };
+        format!("{name}{optional}: {}", ts_type(property))
+    };
+    Some(properties.iter().map(field).collect())
+}
+
+// The TypeScript type a schema describes. Integers are `number`: every integer in the export is a count or a
+// field element, far below 2^53.
+fn ts_type(schema: &Value) -> String {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        return reference.rsplit('/').next().unwrap_or(reference).to_string();
+    }
+    if let Some(Value::Array(all)) = schema.get("allOf") {
+        if let [only] = all.as_slice() {
+            return ts_type(only);
+        }
+    }
+    if let Some(Value::Array(variants)) = schema.get("anyOf").or_else(|| schema.get("oneOf")) {
+        return union(variants.iter().map(ts_type));
+    }
+    if let Some(Value::Array(values)) = schema.get("enum") {
+        return union(values.iter().map(Value::to_string));
+    }
+    if let Some(fields) = object_fields(schema) {
+        return format!("{{ {} }}", fields.join("; "));
+    }
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => return "unknown".to_string(),
+    };
+    union(types.into_iter().map(|t| match t {
+        "integer" | "number" => "number".to_string(),
+        "string" | "boolean" | "null" => t.to_string(),
+        "array" => array_type(schema),
+        "object" => "Record<string, unknown>".to_string(),
+        _ => "unknown".to_string(),
+    }))
+}
+
+// `T[]`, or `[A, B]` for a tuple such as a (row, column) pair.
+fn array_type(schema: &Value) -> String {
+    match schema.get("items") {
+        Some(Value::Array(items)) => format!("[{}]", items.iter().map(ts_type).collect::<Vec<_>>().join(", ")),
+        Some(items) => {
+            let item = ts_type(items);
+            if item.contains(" | ") { format!("({item})[]") } else { format!("{item}[]") }
+        }
+        None => "unknown[]".to_string(),
+    }
+}
+
+// `A | B`, each type once.
+fn union(types: impl Iterator<Item = String>) -> String {
+    let mut distinct: Vec<String> = Vec::new();
+    for t in types {
+        if !distinct.contains(&t) {
+            distinct.push(t);
+        }
+    }
+    distinct.join(" | ")
+}
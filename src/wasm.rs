@@ -0,0 +1,142 @@
+//! The visualizer's functions for the browser (`--features wasm`, built with wasm-pack): the page generates and
+//! checks a trace itself, for instant feedback while its parameters change, and verifies the proofs the native
+//! binary makes, so that it shows a verification of its own. Everything here works in memory, with the same VisData
+//! an export-trace run writes, so it pulls in no file IO or tracing setup.
+//!
+//! Verifying builds the default Mersenne31/Keccak256 config with the circle PCS, which runs single-threaded
+//! without the parallel feature of Plonky3's maybe-rayon, as it is here, and draws no randomness: the challenges
+//! all come from the Keccak challenger.
+//!
+//! fibonacci_trace_json, check_trace_json and verify_proof_json are the functions behind the bindings, with Rust
+//! types for native callers and tests.
+
+use p3_field::{PrimeCharacteristicRing, PrimeField32};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_mersenne_31::Mersenne31;
+use p3_uni_stark::Proof;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::constraints::{evaluate_constraints, extract_constraints, find_violations, Violation};
+use crate::error::FibVisError;
+use crate::export::{trace_json_string, vis_data_from_json, vis_data_from_matrix, FieldInfo, VisData};
+use crate::proof_json::ProofExport;
+use crate::sequence::{check_initial, Sequence};
+use crate::stark_config::{parse_field_element, ConfigBuilder, FieldChoice, HashChoice, M31KeccakConfig};
+use crate::trace::{check_num_steps, check_trace_shape};
+
+/// What check_trace finds: whether every constraint holds on the trace, and each one that does not where.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TraceCheck {
+    pub ok: bool,
+    pub violations: Vec<Violation>,
+}
+
+/// The visualizer JSON of the Fibonacci trace of `num_steps` rows starting with (a0, a1), over Mersenne31 as
+/// generate_fibonacci_trace builds it: what export-trace writes for it, the trace padded to a power of two like
+/// for proving, its constraints and their evaluations. Throws an Error for parameters the AIR does not take.
+#[wasm_bindgen]
+pub fn generate_trace_json(num_steps: u32, a0: u32, a1: u32) -> Result<String, JsError> {
+    fibonacci_trace_json(num_steps as usize, (a0, a1)).map_err(js_error)
+}
+
+/// The constraints the Fibonacci trace in the visualizer JSON `json` violates, as a TraceCheck object. Throws an
+/// Error for JSON that is not such a trace.
+#[wasm_bindgen]
+pub fn check_trace(json: &str) -> Result<JsValue, JsError> {
+    let check = check_trace_json(json).map_err(js_error)?;
+    serde_wasm_bindgen::to_value(&check).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Whether the proof in `proof_json`, an --export-proof file, shows that the trace of `num_steps` rows of its
+/// sequence ends in `final_value`, with the FRI parameters and seed the file records. False for any proof that
+/// does not verify or does not decode, and for the proofs of other fields or hashes. A proof of the wrong shape
+/// can make uni-stark panic, which on wasm32 is thrown as a RuntimeError.
+#[wasm_bindgen]
+pub fn verify_proof(proof_json: &str, num_steps: u32, final_value: u32) -> bool {
+    verify_proof_json(proof_json, num_steps as usize, final_value.into()).is_ok()
+}
+
+/// generate_trace_json for native callers.
+pub fn fibonacci_trace_json(num_steps: usize, start: (u32, u32)) -> Result<String, FibVisError> {
+    let sequence = Sequence::Fibonacci;
+    let initial = [u64::from(start.0), u64::from(start.1)];
+    check_num_steps(num_steps).map_err(FibVisError::Config)?;
+    check_initial(sequence, num_steps, &initial)?;
+
+    let air = sequence.air(num_steps, &initial);
+    let trace = sequence.generate_trace::<Mersenne31>(num_steps, &initial);
+    let final_value = sequence.final_value(FieldChoice::Mersenne31, num_steps, &initial);
+    let public_values = air.public_values::<Mersenne31>(final_value);
+    let vis_data = VisData {
+        sequence: sequence.name().to_string(),
+        final_value,
+        public_values: public_values.iter().map(|v| v.to_string()).collect(),
+        field: FieldInfo::new(FieldChoice::Mersenne31),
+        hash: HashChoice::Keccak256.name().to_string(),
+        ..vis_data_from_matrix(&trace, &sequence.columns(num_steps))?
+    }
+    .with_constraints(extract_constraints::<Mersenne31, _>(&air, air.preprocessed_width(), public_values.len()))
+    .with_air_info::<Mersenne31, _>(&air)
+    .with_constraint_evals(evaluate_constraints(&air, &trace, &public_values))
+    .with_padding(num_steps)
+    .with_selectors(None)
+    .with_true_values(FieldChoice::Mersenne31.modulus(), sequence.true_values(num_steps, &initial));
+    trace_json_string(&vis_data)
+}
+
+/// check_trace for native callers. The AIR is the one of the export's num_steps and public values, so a trace
+/// edited after it was exported, its first row included, shows the constraints it breaks.
+pub fn check_trace_json(json: &str) -> Result<TraceCheck, FibVisError> {
+    let vis = vis_data_from_json(json)?;
+    let sequence = Sequence::Fibonacci;
+    if vis.sequence != sequence.name() {
+        return Err(FibVisError::Config(format!("check_trace reads Fibonacci traces, not {}", vis.sequence)));
+    }
+    let element = |value: &str| {
+        let parsed = parse_field_element(value).ok().filter(|&v| v < u64::from(Mersenne31::ORDER_U32));
+        parsed.ok_or_else(|| FibVisError::Serialization(format!("trace JSON: {value} is not a Mersenne31 element")))
+    };
+    let width = vis.trace.first().map_or(0, Vec::len);
+    if width == 0 || vis.trace.iter().any(|row| row.len() != width) {
+        let reason = "trace JSON: the trace rows are empty or of different widths";
+        return Err(FibVisError::Serialization(reason.to_string()));
+    }
+    let values = vis.trace.iter().flatten().map(|cell| Ok(Mersenne31::from_u64(element(cell)?)));
+    let values = values.collect::<Result<Vec<_>, FibVisError>>()?;
+    let trace = RowMajorMatrix::new(values, width);
+
+    let initial = match vis.public_values.get(..2) {
+        Some([a, b]) => [element(a)?, element(b)?],
+        _ => return Err(FibVisError::Serialization("trace JSON: no initial values in public_values".to_string())),
+    };
+    let air = sequence.air(vis.num_steps, &initial);
+    // The constraints index into the rows, so a trace of another shape is an error rather than a panic
+    check_trace_shape(&air, &trace)?;
+    let violations = find_violations(&air, &trace, &air.public_values::<Mersenne31>(vis.final_value));
+    Ok(TraceCheck { ok: violations.is_empty(), violations })
+}
+
+/// verify_proof for native callers, with the reason a proof is not accepted.
+pub fn verify_proof_json(proof_json: &str, num_steps: usize, final_value: u64) -> Result<(), FibVisError> {
+    let export: ProofExport =
+        serde_json::from_str(proof_json).map_err(|e| FibVisError::Serialization(format!("proof JSON: {e}")))?;
+    check_num_steps(num_steps).map_err(FibVisError::Config)?;
+    let default_config = export.field == FieldChoice::Mersenne31 && export.hash == HashChoice::Keccak256;
+    if !default_config || !export.recurrence.is_empty() {
+        return Err(FibVisError::Config(format!(
+            "verify_proof takes proofs of the named sequences over Mersenne31 with Keccak256, not {} with {}",
+            export.field.name(),
+            export.hash.name()
+        )));
+    }
+    check_initial(export.sequence, num_steps, &export.initial)?;
+    let config = ConfigBuilder::new().fri(export.fri).seed(export.seed.0.clone()).num_steps(num_steps).build()?;
+    let air = export.sequence.air(num_steps, &export.initial);
+    let proof: Proof<M31KeccakConfig> = export.decode()?;
+    air.verify(&config, &proof, &air.public_values::<Mersenne31>(final_value))
+}
+
+fn js_error(e: FibVisError) -> JsError {
+    JsError::new(&e.to_string())
+}
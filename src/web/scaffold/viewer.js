@@ -0,0 +1,85 @@
+// The minimal visualizer the binary writes next to its trace export: fetches the export named by the script tag's
+// data-trace attribute, or takes window.TRACE_DATA when a --format js script set it, and shows it as a table and a
+// chart of the sequence's growth. The full visualizer with the
+// constraints, the proof and live updates is src/web/index.html.
+const traceFile = document.currentScript.dataset.trace || 'trace_data.json';
+const rowLimit = 2000;
+
+const text = (tag, content, cls) => {
+    const el = document.createElement(tag);
+    el.textContent = content;
+    if (cls) el.className = cls;
+    return el;
+};
+
+function render(data) {
+    const columns = (data.columns || []).map(c => typeof c === 'string' ? c : c.name);
+    const padding = data.padding || [];
+    document.getElementById('title').textContent = `AIR trace - Plonky3 ${data.sequence || 'Fibonacci'}`;
+    const field = data.field && data.field.name ? `${data.field.name} (p = ${data.field.modulus})` : data.field;
+    document.getElementById('info').textContent =
+        `${data.num_steps} steps over ${field}, final value ${data.final_value}, hash ${data.hash} (${traceFile})`;
+
+    // Cells some violated constraint reads, see VisData::violations
+    const violated = new Set((data.violations || []).flatMap(v => v.cells).map(([row, col]) => `${row}:${col}`));
+    const head = document.getElementById('head');
+    head.appendChild(text('th', 'Row'));
+    columns.forEach(c => head.appendChild(text('th', c)));
+    const body = document.getElementById('body');
+    data.trace.slice(0, rowLimit).forEach((row, i) => {
+        const tr = document.createElement('tr');
+        if (padding[i]) tr.className = 'padding';
+        tr.appendChild(text('td', i));
+        row.forEach((value, j) => tr.appendChild(text('td', value, violated.has(`${i}:${j}`) ? 'violated' : '')));
+        body.appendChild(tr);
+    });
+    if (data.trace.length > rowLimit) {
+        const tr = document.createElement('tr');
+        tr.appendChild(text('td', `... ${data.trace.length - rowLimit} more rows`));
+        body.appendChild(tr);
+    }
+    chart(data, columns, padding);
+}
+
+// The last column over the real rows, on a log scale as the number of digits grows linearly. The exact values
+// where the export has them, so the curve keeps growing after the field trace wraps around.
+function chart(data, columns, padding) {
+    const svg = document.getElementById('chart');
+    const exact = data.true_values || [];
+    // Exact values have more digits than a double holds
+    const log10 = s => s.length > 15 ? s.length - 15 + Math.log10(Number(s.slice(0, 15))) : Math.log10(1 + Number(s));
+    const values = data.trace
+        .filter((_, i) => !padding[i])
+        .map((row, i) => {
+            const value = String((exact[i] || row)[row.length - 1]);
+            return /^[0-9]+$/.test(value) ? log10(value) : 0;
+        });
+    const max = Math.max(1, ...values);
+    const x = i => 40 + (1120 * i) / Math.max(1, values.length - 1);
+    const y = v => 220 - (200 * v) / max;
+    const line = document.createElementNS('http://www.w3.org/2000/svg', 'polyline');
+    line.setAttribute('points', values.map((v, i) => `${x(i)},${y(v)}`).join(' '));
+    line.setAttribute('fill', 'none');
+    line.setAttribute('stroke', '#4CAF50');
+    line.setAttribute('stroke-width', '2');
+    svg.appendChild(line);
+    const label = document.createElementNS('http://www.w3.org/2000/svg', 'text');
+    label.setAttribute('x', '40');
+    label.setAttribute('y', '16');
+    label.textContent = `log10 of ${columns[columns.length - 1] || 'the last column'} per step, up to ${max.toFixed(1)}`;
+    svg.appendChild(label);
+}
+
+const loaded = window.TRACE_DATA
+    ? Promise.resolve(window.TRACE_DATA)
+    : fetch(traceFile, { cache: 'no-store' }).then(response => {
+        if (!response.ok) throw new Error(`${traceFile}: ${response.status} ${response.statusText}`);
+        return response.json();
+    });
+loaded
+    .then(render)
+    .catch(e => {
+        const info = document.getElementById('info');
+        info.textContent = `Could not load the trace: ${e.message}. Serve this directory, e.g. with \`cargo run -- serve\`.`;
+        info.className = 'info error';
+    });
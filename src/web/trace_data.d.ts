@@ -0,0 +1,205 @@
+// Generated from the JSON Schema of VisData by `plonky3_fibonacci --emit-ts`, do not edit.
+/** VisData acts as the data container to serialize, the schema web/index.html reads. */
+export interface VisData {
+    schema_version: number;
+    num_steps: number;
+    sequence: string;
+    recurrence: number[];
+    final_value: number;
+    public_values: string[];
+    field: FieldInfo;
+    hash: string;
+    fri: FriParams;
+    seed: string;
+    columns: ColumnMeta[];
+    air_info: AirInfo;
+    constraints: ConstraintDesc[];
+    degree_report: ConstraintReport;
+    trace: Cell[][];
+    constraint_evals: string[][];
+    violations: Violation[];
+    preprocessed_columns: ColumnMeta[];
+    preprocessed: Cell[][];
+    true_values: string[][];
+    reduced: boolean[];
+    padding: boolean[];
+    first_reduced_row?: number | null;
+    column_stats: ColumnStats[];
+    stats?: ProofStats | null;
+    verification?: Verification | null;
+    commitments: CommitmentRoot[];
+    lde?: Lde | null;
+    selectors?: Selectors | null;
+    windows?: RowWindow[] | null;
+    keyframes?: Keyframe[] | null;
+    heatmap?: Heatmap | null;
+    tampered: [number, number][];
+    repr: ValueRepr;
+}
+
+/** The shape of the AIR behind an export, see VisData::with_air_info. */
+export interface AirInfo {
+    sequence: string;
+    width: number;
+    columns: string[];
+    final_value: number;
+    num_constraints: number;
+    boundary_constraints: number;
+    transition_constraints: number;
+    every_row_constraints: number;
+}
+
+export type Cell = number | string | { dec: string; hex: string };
+
+/** A trace column as the export describes it: the name the visualizer labels it with and what it holds. */
+export interface ColumnMeta {
+    name: string;
+    description: string;
+}
+
+/** One trace column at a glance, over the real rows (see VisData::with_padding), for the visualizer to mark where its values start to wrap around without redoing the exact arithmetic. */
+export interface ColumnStats {
+    name: string;
+    min: string;
+    max: string;
+    increasing: boolean;
+    first_wrap_row?: number | null;
+}
+
+/** The step of the protocol a Merkle root commits to, in the order the prover gets there: the trace, the quotient polynomial's chunks once the constraints are combined, then FRI. The circle PCS (Mersenne31) first folds the opened polynomials from the circle onto the line and commits that layer, then every FRI folding round commits the folded evaluations. */
+export type CommitPhase = "trace" | "quotient_chunks" | "fri_first_layer" | "fri_commit_phase";
+
+/** One Merkle root of a proof, see commitment_roots. */
+export interface CommitmentRoot {
+    phase: CommitPhase;
+    round: number;
+    root: string;
+}
+
+/** One constraint of an AIR as uni-stark sees it, recorded by evaluating the AIR against its symbolic builder, so the export lists exactly what the prover enforces. */
+export interface ConstraintDesc {
+    kind: ConstraintKind;
+    expression: string;
+    degree: number;
+    tree: ConstraintExpr;
+}
+
+/** A constraint expression over trace cells, public values and constants. */
+export type ConstraintExpr = { op: "cell"; trace: TraceKind; offset: number; column: number } | { op: "public"; index: number } | { op: "constant"; value: string } | { op: "selector"; kind: ConstraintKind } | { op: "add"; x: ConstraintExpr; y: ConstraintExpr } | { op: "sub"; x: ConstraintExpr; y: ConstraintExpr } | { op: "mul"; x: ConstraintExpr; y: ConstraintExpr } | { op: "neg"; x: ConstraintExpr };
+
+/** Which rows a constraint applies to. */
+export type ConstraintKind = "every_row" | "first_row" | "transition" | "last_row";
+
+/** The degrees that decide how large the quotient polynomial gets, see constraint_report. */
+export interface ConstraintReport {
+    degrees: number[];
+    max_degree: number;
+    log_quotient_degree: number;
+    quotient_degree: number;
+    min_log_blowup: number;
+}
+
+/** The prime field behind an export, so the visualizer can say what the values are reduced modulo. */
+export interface FieldInfo {
+    name: string;
+    modulus: string;
+    bits: number;
+    extension_degree: number;
+}
+
+/** FRI parameters, defaulting to the values the demo always used. They are echoed into the exported JSON so a run can be reproduced from its artifact. */
+export interface FriParams {
+    log_blowup?: number;
+    num_queries?: number;
+    proof_of_work_bits?: number;
+    log_final_poly_len?: number;
+}
+
+/** constraint_evals as a matrix to plot, trace rows by constraints, see heatmap. Every row has an entry per constraint: the canonical value of the evaluation, 0 where the constraint holds and null on the rows it does not apply to, so a first- or last-row constraint has a number on one row only. */
+export interface Heatmap {
+    constraint_names: string[];
+    rows: number;
+    values: (number | null)[][];
+}
+
+/** One step of animating the trace being computed, see keyframes: the rows it reveals, from `row` on, and the constraints that fix them. Each row is in one keyframe only, so they add about the trace's size to the export. */
+export interface Keyframe {
+    row: number;
+    rows: string[][];
+    constraints: number[];
+}
+
+/** The low-degree extension of the trace, see lde::trace_lde: each trace column's polynomial evaluated over a domain 2^log_blowup times larger than the trace's, written separately from the trace itself. */
+export interface Lde {
+    log_blowup: number;
+    rows: string[][];
+    trace_rows: [number, number][];
+}
+
+/** Heap usage of a run with --track-memory, see memory::CountingAllocator: its peak, and per phase the change in allocated bytes, which is negative for a phase that freed more than it allocated. */
+export interface MemoryStats {
+    peak_bytes: number;
+    trace_gen_bytes: number;
+    export_bytes: number;
+    prove_bytes: number;
+    verify_bytes: number;
+}
+
+/** How big the proof of an exported trace is and how long it took, see VisData::with_stats. */
+export interface ProofStats {
+    proof_bytes: number;
+    proof_digest?: string | null;
+    proof_head?: string | null;
+    timings: Timings;
+    fri: FriParams;
+    memory?: MemoryStats | null;
+}
+
+/** One row of the trace with the row after it, as uni-stark's builder hands them to the constraints on that row: `main().row_slice(0)` and `row_slice(1)`. The trace wraps around, so the last row's next row is the first one. */
+export interface RowWindow {
+    local: string[];
+    next: string[];
+    terminal: boolean;
+}
+
+/** The row selectors uni-stark multiplies the constraints by, one column per ConstraintKind in the order of Selectors::KINDS, named like the `kind` of the constraints they gate. */
+export interface Selectors {
+    kinds: ConstraintKind[];
+    rows: number[][];
+    lde_rows?: string[][] | null;
+}
+
+/** Wall-clock milliseconds per phase, zero for phases that did not run. */
+export interface Timings {
+    trace_gen_ms: number;
+    export_ms: number;
+    prove_ms: number;
+    verify_ms: number;
+    total_ms: number;
+}
+
+/** Which trace a cell belongs to. */
+export type TraceKind = "main" | "preprocessed";
+
+/** How the text formats (JSON, NDJSON, CSV and the terminal table) write trace cells, see --repr. */
+export type ValueRepr = "decimal" | "hex" | "both";
+
+/** Whether the proof of an exported trace was accepted, see VisData::with_verification. The frontend reads it to tell a verified trace from a rejected one. */
+export interface Verification {
+    verified: boolean;
+    error?: string | null;
+    proof_bytes?: number | null;
+    verify_ms: number;
+}
+
+/** A constraint that does not hold on some row of a trace, see find_violations. */
+export interface Violation {
+    row: number;
+    constraint: number;
+    kind: ConstraintKind;
+    expression: string;
+    value: string;
+    expected?: string | null;
+    actual?: string | null;
+    cells: [number, number][];
+}
@@ -0,0 +1,105 @@
+// Runs of the binary itself, for what only main does: the commands, their flags and the exit codes. Every test
+// runs it in its own temporary directory.
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use serde_json::Value;
+
+// The binary run in `dir` with `args`
+fn run_in(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_plonky3_fibonacci"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .expect("could not run the binary")
+}
+
+// The (row, kind) of every violation in the tampered export of an 8-step run with `--tamper <tamper>`, and what
+// the export says about its proof.
+fn tampered_run(tamper: &str) -> (Vec<(u64, String)>, Value) {
+    let dir = tempfile::tempdir().unwrap();
+    let args = ["--num-steps", "8", "--output", "trace_data.json", "--proof", "proof.bin", "--tamper", tamper];
+    let output = run_in(dir.path(), &args);
+    // Rejecting the tampered trace is the run working, so it succeeds
+    assert!(output.status.success(), "--tamper {tamper}: {}", String::from_utf8_lossy(&output.stderr));
+
+    let read = |name: &str| -> Value {
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join(name)).unwrap()).unwrap()
+    };
+    assert_eq!(read("trace_data.json")["violations"], serde_json::json!([]), "the honest trace is exported as is");
+    let invalid = read("trace_data_invalid.json");
+    let violations = invalid["violations"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| (v["row"].as_u64().unwrap(), v["kind"].as_str().unwrap().to_string()))
+        .collect();
+    (violations, invalid["verification"].clone())
+}
+
+#[test]
+fn tampered_first_row_is_rejected_by_the_first_row_constraint() {
+    let (violations, verification) = tampered_run("0:0=5");
+    // a0 = 5 also breaks the first sum
+    assert_eq!(violations, [(0, "first_row".to_string()), (0, "transition".to_string())]);
+    assert_eq!(verification["verified"], false);
+    assert!(verification["error"].as_str().unwrap().contains("row 0: first-row constraint"), "{verification}");
+}
+
+#[test]
+fn tampered_middle_row_is_rejected_by_the_transitions_around_it() {
+    let (violations, verification) = tampered_run("3:1=7");
+    let transition = |row| (row, "transition".to_string());
+    assert_eq!(violations, [transition(2), transition(3), transition(3)]);
+    assert_eq!(verification["verified"], false);
+    assert!(verification["error"].as_str().unwrap().contains("row 2: transition constraint"), "{verification}");
+}
+
+#[test]
+fn tampered_last_row_is_rejected_by_the_final_value_constraint() {
+    let (violations, verification) = tampered_run("7:1=22");
+    assert_eq!(violations, [(6, "transition".to_string()), (7, "last_row".to_string())]);
+    assert_eq!(verification["verified"], false);
+    assert!(verification["error"].as_str().unwrap().contains("2 constraint evaluations"), "{verification}");
+}
+
+#[test]
+fn prove_rejects_tamper() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = run_in(dir.path(), &["prove", "--num-steps", "8", "--proof", "proof.bin", "--tamper", "3:1=7"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--tamper"));
+    assert!(!dir.path().join("proof.bin").exists(), "no proof is written");
+}
+
+#[test]
+fn unwritable_export_exits_with_the_io_exit_code() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("web"), "not a directory").unwrap();
+    // As CSV, which no viewer is scaffolded for, so it is the export itself that fails
+    let args = ["export-trace", "--num-steps", "8", "--format", "csv", "--output", "web/trace.csv"];
+    let output = run_in(dir.path(), &args);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("FibVisError: could not export the trace to web"));
+}
+
+#[test]
+fn saved_proof_verifies_and_is_rejected_for_other_parameters_or_when_cut_short() {
+    let dir = tempfile::tempdir().unwrap();
+    let files = ["--output", "trace_data.json", "--proof", "proof.bin"];
+    let proved = run_in(dir.path(), &[&["prove", "--num-steps", "8"][..], &files[..]].concat());
+    assert!(proved.status.success(), "prove: {}", String::from_utf8_lossy(&proved.stderr));
+    let verified = run_in(dir.path(), &[&["verify", "--num-steps", "8"][..], &files[..]].concat());
+    assert!(verified.status.success(), "verify: {}", String::from_utf8_lossy(&verified.stderr));
+
+    // The header records the 8 steps, a claim of 16 is a verification failure
+    let other = run_in(dir.path(), &["verify", "--num-steps", "16", "--output", "other.json", "--proof", "proof.bin"]);
+    assert_eq!(other.status.code(), Some(2));
+
+    // A file cut short fails to decode
+    let proof = std::fs::read(dir.path().join("proof.bin")).unwrap();
+    std::fs::write(dir.path().join("proof.bin"), &proof[..proof.len() - 10]).unwrap();
+    let truncated = run_in(dir.path(), &[&["verify", "--num-steps", "8"][..], &files[..]].concat());
+    assert_eq!(truncated.status.code(), Some(4));
+}
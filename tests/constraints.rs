@@ -0,0 +1,320 @@
+// The constraints the export lists, read back from the AIRs through uni-stark's symbolic builder.
+
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::{Field, PrimeCharacteristicRing};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_mersenne_31::Mersenne31;
+use plonky3_fibonacci::air::NUM_PUBLIC_VALUES;
+use plonky3_fibonacci::constraints::{
+    check_trace, constraint_report, evaluate_and_find_violations, evaluate_constraints, extract_constraints,
+    find_violations, ConstraintKind, Violation,
+};
+use plonky3_fibonacci::export::heatmap;
+use plonky3_fibonacci::trace::FIBONACCI_START;
+use plonky3_fibonacci::{
+    generate_fibonacci_trace, generate_padded_fibonacci_trace, FibVisError, FibonacciAir, MulFibonacciAir,
+};
+
+#[test]
+fn fibonacci_air_has_five_constraints() {
+    let constraints = extract_constraints::<Mersenne31, _>(&FibonacciAir::new(8), 0, NUM_PUBLIC_VALUES);
+    let kinds: Vec<ConstraintKind> = constraints.iter().map(|c| c.kind).collect();
+    use ConstraintKind::{FirstRow, LastRow, Transition};
+    assert_eq!(kinds, [FirstRow, FirstRow, Transition, Transition, LastRow]);
+
+    let expressions: Vec<&str> = constraints.iter().map(|c| c.expression.as_str()).collect();
+    assert_eq!(
+        expressions,
+        ["local[0] - public[0]", "local[1] - public[1]", "next[0] - local[1]", "next[1] - (local[0] + local[1])", "local[1] - public[2]"]
+    );
+    assert!(constraints.iter().all(|c| c.degree == 1));
+}
+
+#[test]
+fn padded_fibonacci_keeps_the_extra_condition_out_of_the_selector() {
+    let constraints = extract_constraints::<Mersenne31, _>(&FibonacciAir::padded(10, FIBONACCI_START), 0, NUM_PUBLIC_VALUES);
+    let shift = constraints.iter().find(|c| c.expression == "next[2] * (next[0] - local[1])").unwrap();
+    assert_eq!((shift.kind, shift.degree), (ConstraintKind::Transition, 2));
+    let boolean = constraints.iter().find(|c| c.expression == "local[2] * (local[2] - 1)").unwrap();
+    assert_eq!(boolean.kind, ConstraintKind::EveryRow);
+}
+
+#[test]
+fn padded_fibonacci_binds_the_final_value_to_row_num_steps_minus_one() {
+    let air = FibonacciAir::padded(10, FIBONACCI_START);
+    let honest = generate_padded_fibonacci_trace::<Mersenne31>(10, FIBONACCI_START);
+    assert!(find_violations(&air, &honest, &air.public_values::<Mersenne31>(55)).is_empty());
+
+    // Nine real rows ending in 34 satisfy the sequence's constraints but not the row counter's
+    let short = generate_padded_fibonacci_trace::<Mersenne31>(9, FIBONACCI_START);
+    let violations = find_violations(&air, &short, &air.public_values::<Mersenne31>(34));
+    assert_eq!(violations.len(), 1, "{violations:?}");
+    assert_eq!((violations[0].row, violations[0].kind), (8, ConstraintKind::Transition));
+    assert!(violations[0].expression.contains("local[3]"), "{}", violations[0].expression);
+}
+
+#[test]
+fn product_constraint_has_degree_two() {
+    let constraints = extract_constraints::<Mersenne31, _>(&MulFibonacciAir::new(8), 0, 1);
+    let transition = constraints.iter().find(|c| c.expression == "next[1] - local[0] * local[1]").unwrap();
+    assert_eq!((transition.kind, transition.degree), (ConstraintKind::Transition, 2));
+}
+
+// A one-column AIR whose transition raises the current value to `exponent`, for constraints of any degree.
+struct PowerAir {
+    exponent: usize,
+}
+
+impl<F: Field> BaseAir<F> for PowerAir {
+    fn width(&self) -> usize {
+        1
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for PowerAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0).unwrap()[0], main.row_slice(1).unwrap()[0]);
+        let power = (1..self.exponent).fold(local.into(), |acc: AB::Expr, _| acc * local);
+        builder.when_transition().assert_eq(next, power);
+    }
+}
+
+#[test]
+fn fibonacci_report_needs_the_smallest_blowup() {
+    let report = constraint_report::<Mersenne31, _>(&FibonacciAir::new(8), 0, NUM_PUBLIC_VALUES);
+    // Degree 1 in the columns; the first- and last-row selectors add one
+    assert_eq!(report.degrees, [2, 2, 1, 1, 2]);
+    assert_eq!((report.max_degree, report.quotient_degree, report.min_log_blowup), (2, 1, 1));
+    report.check_log_blowup(1).unwrap();
+}
+
+#[test]
+fn degree_three_air_still_fits_the_default_blowup() {
+    let report = constraint_report::<Mersenne31, _>(&PowerAir { exponent: 3 }, 0, 0);
+    assert_eq!(report.degrees, [3]);
+    assert_eq!((report.max_degree, report.log_quotient_degree, report.quotient_degree), (3, 1, 2));
+    report.check_log_blowup(1).unwrap();
+}
+
+#[test]
+fn higher_degree_is_rejected_before_proving() {
+    let report = constraint_report::<Mersenne31, _>(&PowerAir { exponent: 5 }, 0, 0);
+    assert_eq!((report.max_degree, report.quotient_degree, report.min_log_blowup), (5, 4, 2));
+    assert!(matches!(report.check_log_blowup(1), Err(FibVisError::Config(_))));
+    report.check_log_blowup(2).unwrap();
+}
+
+#[test]
+fn valid_trace_evaluates_to_zero_where_constraints_apply() {
+    let air = FibonacciAir::new(8);
+    let trace = generate_fibonacci_trace::<Mersenne31>(8, FIBONACCI_START);
+    let evals = evaluate_constraints(&air, &trace, &air.public_values::<Mersenne31>(21));
+    assert_eq!(evals.len(), 8);
+    assert_eq!(evals[0], ["0", "0", "0", "0", ""]);
+    for row in &evals[1..7] {
+        assert_eq!(row, &["", "", "0", "0", ""]);
+    }
+    assert_eq!(evals[7], ["", "", "", "", "0"]);
+}
+
+#[test]
+fn tampered_cell_breaks_exactly_the_constraints_reading_it() {
+    let air = FibonacciAir::new(8);
+    let mut trace = generate_fibonacci_trace::<Mersenne31>(8, FIBONACCI_START);
+    trace.values[3 * 2 + 1] = Mersenne31::from_u32(4); // row 3 is (2, 3)
+    let evals = evaluate_constraints(&air, &trace, &air.public_values::<Mersenne31>(21));
+
+    let broken: Vec<(usize, usize, &str)> = evals
+        .iter()
+        .enumerate()
+        .flat_map(|(row, r)| r.iter().enumerate().map(move |(i, v)| (row, i, v.as_str())))
+        .filter(|(_, _, v)| !v.is_empty() && *v != "0")
+        .collect();
+    // Row 2's sum now misses by one, and row 3 both passes on the wrong b and sums it
+    assert_eq!(broken, [(2, 3, "1"), (3, 2, "2147483646"), (3, 3, "2147483646")]);
+}
+
+#[test]
+fn heatmap_is_the_evaluations_as_a_rectangle_of_numbers() {
+    let air = FibonacciAir::new(8);
+    let constraints = extract_constraints::<Mersenne31, _>(&air, 0, NUM_PUBLIC_VALUES);
+    let mut trace = generate_fibonacci_trace::<Mersenne31>(8, FIBONACCI_START);
+    let public_values = air.public_values::<Mersenne31>(21);
+    // Nothing to plot on an honest trace
+    assert_eq!(heatmap(&constraints, &evaluate_constraints(&air, &trace, &public_values)), None);
+
+    trace.values[3 * 2 + 1] = Mersenne31::from_u32(4); // row 3 is (2, 3)
+    let map = heatmap(&constraints, &evaluate_constraints(&air, &trace, &public_values)).unwrap();
+    assert_eq!(map.constraint_names[2], "transition: next[0] - local[1]");
+    assert_eq!(map.constraint_names[4], "last-row: local[1] - public[2]");
+    assert_eq!(map.rows, 8);
+    assert_eq!(map.values.len(), 8);
+    assert!(map.values.iter().all(|row| row.len() == constraints.len()));
+    // The boundary constraints have a number on their one row, null elsewhere
+    assert_eq!(map.values[0], [Some(0), Some(0), Some(0), Some(0), None]);
+    assert_eq!(map.values[7], [None, None, None, None, Some(0)]);
+    assert_eq!(map.values[2], [None, None, Some(0), Some(1), None]);
+    assert_eq!(map.values[3], [None, None, Some(2147483646), Some(2147483646), None]);
+
+    // Numbers, not strings, in the JSON
+    let json = serde_json::to_value(&map).unwrap();
+    assert_eq!(json["values"][3][2], serde_json::json!(2147483646));
+    assert!(json["values"][3][0].is_null());
+}
+
+type Tampered = (FibonacciAir, RowMajorMatrix<Mersenne31>, Vec<Mersenne31>);
+
+// The 8-step Fibonacci trace (0, 1), (1, 1), ..., (13, 21) with column `col` of `row` set to `value`, its AIR and
+// the public values claiming 21.
+fn tampered_fibonacci(row: usize, col: usize, value: u32) -> Tampered {
+    let air = FibonacciAir::new(8);
+    let mut trace = generate_fibonacci_trace::<Mersenne31>(8, FIBONACCI_START);
+    trace.values[row * 2 + col] = Mersenne31::from_u32(value);
+    let public_values = air.public_values::<Mersenne31>(21);
+    (air, trace, public_values)
+}
+
+// The violations of the tampered trace, see tampered_fibonacci.
+fn fibonacci_violations(row: usize, col: usize, value: u32) -> Vec<Violation> {
+    let (air, trace, public_values) = tampered_fibonacci(row, col, value);
+    find_violations(&air, &trace, &public_values)
+}
+
+#[test]
+fn valid_trace_has_no_violations() {
+    let air = FibonacciAir::new(8);
+    let trace = generate_fibonacci_trace::<Mersenne31>(8, FIBONACCI_START);
+    assert!(find_violations(&air, &trace, &air.public_values::<Mersenne31>(21)).is_empty());
+}
+
+#[test]
+fn one_pass_gives_the_evaluations_and_the_violations() {
+    let (air, trace, public_values) = tampered_fibonacci(3, 1, 7);
+    let (evals, violations) = evaluate_and_find_violations(&air, &trace, &public_values);
+    assert_eq!(evals, evaluate_constraints(&air, &trace, &public_values));
+    assert_eq!(violations, find_violations(&air, &trace, &public_values));
+    assert_eq!(violations.len(), 3);
+}
+
+#[test]
+fn first_row_violation_names_the_initial_value() {
+    let violations = fibonacci_violations(0, 0, 5);
+    let rows: Vec<(usize, usize)> = violations.iter().map(|v| (v.row, v.constraint)).collect();
+    // a0 = 5 breaks the first-row constraint and the first sum
+    assert_eq!(rows, [(0, 0), (0, 3)]);
+    assert_eq!(violations[0].kind, ConstraintKind::FirstRow);
+    assert_eq!(violations[0].to_string(), "row 0: first-row constraint local[0] = public[0] violated (expected 0, got 5)");
+    assert_eq!(violations[0].cells, [(0, 0)]);
+}
+
+#[test]
+fn mid_trace_violation_reports_both_sides_of_the_transition() {
+    let violations = fibonacci_violations(3, 1, 4);
+    let messages: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "row 2: transition constraint next[1] = local[0] + local[1] violated (expected 3, got 4)",
+            "row 3: transition constraint next[0] = local[1] violated (expected 4, got 3)",
+            "row 3: transition constraint next[1] = local[0] + local[1] violated (expected 6, got 5)",
+        ]
+    );
+    // The tampered cell is row 3's b, which both rows' transitions read
+    assert_eq!(violations[0].cells, [(2, 0), (2, 1), (3, 1)]);
+    assert_eq!(violations[1].cells, [(3, 1), (4, 0)]);
+}
+
+#[test]
+fn last_row_violation_names_the_final_value() {
+    let violations = fibonacci_violations(7, 1, 22);
+    let last = violations.last().unwrap();
+    assert_eq!((last.row, last.kind), (7, ConstraintKind::LastRow));
+    assert_eq!(last.to_string(), "row 7: last-row constraint local[1] = public[2] violated (expected 21, got 22)");
+    assert_eq!((violations[0].row, violations[0].kind), (6, ConstraintKind::Transition));
+    assert_eq!(violations.len(), 2);
+}
+
+// A one-column AIR whose every value must be 0 or 1, the one constraint applying to every row.
+struct BooleanAir;
+
+impl<F: Field> BaseAir<F> for BooleanAir {
+    fn width(&self) -> usize {
+        1
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for BooleanAir {
+    fn eval(&self, builder: &mut AB) {
+        let local = builder.main().row_slice(0).unwrap()[0];
+        builder.assert_bool(local);
+    }
+}
+
+// What check_trace says about the tampered trace, see tampered_fibonacci.
+fn fibonacci_precheck(row: usize, col: usize, value: u32) -> String {
+    let (air, trace, public_values) = tampered_fibonacci(row, col, value);
+    check_trace(&air, &trace, &public_values).unwrap_err().to_string()
+}
+
+#[test]
+fn precheck_passes_a_valid_trace() {
+    let air = FibonacciAir::new(8);
+    let trace = generate_fibonacci_trace::<Mersenne31>(8, FIBONACCI_START);
+    check_trace(&air, &trace, &air.public_values::<Mersenne31>(21)).unwrap();
+    check_trace(&BooleanAir, &RowMajorMatrix::new([0, 1, 1, 0].map(Mersenne31::from_u32).to_vec(), 1), &[]).unwrap();
+}
+
+#[test]
+fn precheck_names_a_broken_first_row() {
+    assert_eq!(
+        fibonacci_precheck(0, 0, 5),
+        "2 constraint evaluations on the trace are nonzero, the first one at row 0: first-row constraint \
+         local[0] = public[0] violated (expected 0, got 5)"
+    );
+}
+
+#[test]
+fn precheck_names_a_broken_transition() {
+    assert_eq!(
+        fibonacci_precheck(3, 1, 7),
+        "3 constraint evaluations on the trace are nonzero, the first one at row 2: transition constraint \
+         next[1] = local[0] + local[1] violated (expected 3, got 7)"
+    );
+}
+
+#[test]
+fn precheck_names_a_wrong_final_value_claim() {
+    let air = FibonacciAir::new(8);
+    let trace = generate_fibonacci_trace::<Mersenne31>(8, FIBONACCI_START);
+    let error = check_trace(&air, &trace, &air.public_values::<Mersenne31>(34)).unwrap_err();
+    assert!(matches!(error, FibVisError::ConstraintViolation { count: 1, .. }), "{error}");
+    assert_eq!(
+        error.to_string(),
+        "1 constraint evaluation on the trace is nonzero, the first one at row 7: last-row constraint \
+         local[1] = public[2] violated (expected 34, got 21)"
+    );
+}
+
+#[test]
+fn precheck_names_a_broken_every_row_constraint() {
+    let trace = RowMajorMatrix::new([0, 1, 2, 1].map(Mersenne31::from_u32).to_vec(), 1);
+    assert_eq!(
+        check_trace(&BooleanAir, &trace, &[]).unwrap_err().to_string(),
+        "1 constraint evaluation on the trace is nonzero, the first one at row 2: every-row constraint \
+         local[0] * (local[0] - 1) = 0 violated (evaluates to 2)"
+    );
+}
+
+#[test]
+fn precheck_rejects_a_trace_of_another_shape() {
+    let air = FibonacciAir::new(8);
+    let public_values = air.public_values::<Mersenne31>(21);
+    let narrow = RowMajorMatrix::new(vec![Mersenne31::ONE; 8], 1);
+    let error = check_trace(&air, &narrow, &public_values).unwrap_err();
+    assert!(
+        matches!(error, FibVisError::ShapeMismatch { expected_width: 2, expected_height: 8, width: 1, height: 8 }),
+        "{error}"
+    );
+}
@@ -0,0 +1,2495 @@
+// End-to-end checks of the library: prove, verify and export small traces the way the binary does. Every test
+// writes into its own temporary directory, so the suite runs in parallel.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Instant;
+
+use p3_air::BaseAir;
+use p3_field::{BasedVectorSpace, Field, PrimeCharacteristicRing, PrimeField32};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_mersenne_31::Mersenne31;
+use p3_uni_stark::{get_symbolic_constraints, prove, verify, Proof, StarkGenericConfig};
+use plonky3_fibonacci::assets::{
+    index_html, write_assets, AssetStatus, INDEX_HTML, TRACE_FILE, TRACE_SCRIPT, VIEWER_JS,
+};
+use plonky3_fibonacci::compare::{Comparison, RunStats};
+use plonky3_fibonacci::events::{EventLog, PipelineEvent, PipelineEvents};
+use plonky3_fibonacci::export::{
+    cell_records, column_names, linear_recurrence_columns, load_vis_data, proof_digest, read_trace_bin, read_trace_cbor,
+    read_trace_msgpack, render_svg, replay_keyframes, trace_bin, trace_table, vis_data_from_matrix, vis_data_schema,
+    write_trace, write_trace_bin, write_trace_bundle, write_trace_csv, write_trace_gz, write_trace_js, write_trace_json,
+    write_trace_ndjson,
+    CellRecord, ColumnMeta, FieldInfo, Lde, ProofStats, Selectors, Timings, TraceBundle, TraceFormat, ValueRepr, Verification, VisData,
+    FIBONACCI_COLUMNS, FIBONACCI_WITH_INDEX_COLUMNS, HTML_MAX_ROWS, INDEX_COLUMNS, PROOF_HEAD_BYTES, SCHEMA_VERSION,
+    TRACE_BIN_HEADER_LEN,
+};
+use plonky3_fibonacci::lde::{selector_lde, trace_lde};
+use plonky3_fibonacci::memory::{self, CountingAllocator};
+use plonky3_fibonacci::merkle::{merkle_tree, MERKLE_MAX_ROWS};
+use plonky3_fibonacci::profile::chrome_trace;
+use plonky3_fibonacci::proof_file::{ProofFile, ProofHeader, PROOF_FORMAT_VERSION, PROOF_MAGIC};
+use plonky3_fibonacci::proof_inspect::{inspect_proof, ProofInspection};
+use plonky3_fibonacci::proof_json::{
+    commitment_roots, fri_queries, fri_rounds, pow_grinding, proof_from_json, proof_to_json, read_proof_json,
+    write_proof_json, CommitPhase, FriQuery, ProofExport,
+};
+use plonky3_fibonacci::prove::{elapsed_ms, panic_message};
+use plonky3_fibonacci::quotient::{ood_opening, quotient_opening};
+use plonky3_fibonacci::sequence::check_initial;
+use plonky3_fibonacci::serve::{
+    ApiRun, LiveUpdate, RunStatus, StaticServer, EVENTS_PATH, LIVE_DEBOUNCE, LIVE_FILE, LIVE_PATH, LIVE_POLL, PROVE_PATH,
+};
+use plonky3_fibonacci::stark_config::{
+    parse_field_element, ConfigBuilder, FieldChoice, FriParams, HashChoice, M31KeccakConfig, M31KeccakRecordingConfig, Seed,
+    M31_CHALLENGE_DEGREE,
+};
+use plonky3_fibonacci::trace::{collatz_final_value, fibonacci_final_value, linear_recurrence_final_value, FIBONACCI_START};
+use plonky3_fibonacci::transcript::{RecordsTranscript, TranscriptEvent, TranscriptLabel, TranscriptOp};
+use plonky3_fibonacci::typescript::vis_data_typescript;
+use plonky3_fibonacci::constraints::{extract_constraints, find_violations, ConstraintKind};
+use plonky3_fibonacci::{
+    generate_bit_decomp_trace, generate_collatz_trace, generate_fibonacci_trace, generate_fibonacci_with_index_trace,
+    generate_linear_recurrence_trace, generate_mul_fibonacci_trace, prove_fibonacci, verify_fibonacci, BitDecompAir,
+    CollatzAir, ExportError, FibVisError, FibonacciAir, FibonacciParams, FibonacciWithIndexAir, IndexedFibonacciAir,
+    LinearRecurrenceAir, MulFibonacciAir, Sequence,
+};
+use serde_json::Value;
+use tracing::{field, info_span, Dispatch, Span};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Registry;
+
+// As in the binary, so --track-memory's counts can be checked
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn prove_and_verify_round_trip() {
+    for num_steps in [8, 16] {
+        let params = FibonacciParams::new(num_steps);
+        let run = prove_fibonacci(params.clone()).unwrap();
+        verify_fibonacci(&params, &run.proof).unwrap();
+        assert_eq!(run.vis_data.num_steps, num_steps);
+    }
+}
+
+#[test]
+fn default_params_prove_the_original_demo() {
+    let run = prove_fibonacci(FibonacciParams::default()).unwrap();
+    assert_eq!(run.vis_data.num_steps, 8);
+    assert_eq!(run.vis_data.final_value, 21);
+}
+
+#[test]
+fn wrong_final_value_fails_verification() {
+    let params = FibonacciParams::new(8);
+    let run = prove_fibonacci(params.clone()).unwrap();
+
+    let wrong = FibonacciParams { final_value: Some(22), ..params };
+    let err = verify_fibonacci(&wrong, &run.proof).unwrap_err();
+    assert!(matches!(err, FibVisError::Verify(_)), "unexpected error: {err}");
+}
+
+#[test]
+fn proof_only_verifies_against_its_public_values() {
+    let num_steps = 8;
+    let config = ConfigBuilder::new().num_steps(num_steps).build().unwrap();
+    let air = FibonacciAir::new(num_steps);
+    let public_values = air.public_values::<Mersenne31>(21);
+
+    let proof = prove(&config, &air, generate_fibonacci_trace::<Mersenne31>(num_steps, FIBONACCI_START), &public_values);
+    verify(&config, &air, &proof, &public_values).unwrap();
+    for i in 0..public_values.len() {
+        let mut other = public_values.clone();
+        other[i] += Mersenne31::ONE;
+        assert!(verify(&config, &air, &proof, &other).is_err(), "accepted with public value {i} changed");
+    }
+}
+
+#[test]
+fn wrong_final_value_is_caught_before_proving() {
+    let params = FibonacciParams { final_value: Some(22), ..FibonacciParams::new(8) };
+    let err = prove_fibonacci(params).err().unwrap();
+    assert!(matches!(err, FibVisError::FinalValueMismatch { expected: 22, actual: 21, row: 7 }), "unexpected error: {err}");
+}
+
+#[test]
+fn tampered_trace_is_stopped_before_proving_unless_the_precheck_is_skipped() {
+    let params = FibonacciParams { tamper: vec![(3, 1, 7)], ..FibonacciParams::new(8) };
+    let err = prove_fibonacci(params.clone()).err().unwrap();
+    assert!(matches!(err, FibVisError::ConstraintViolation { count: 3, .. }), "unexpected error: {err}");
+    assert!(err.to_string().contains("row 2: transition constraint"), "{err}");
+
+    // Then the prover panics on it (debug builds) or its proof does not verify
+    let skipped = FibonacciParams { skip_precheck: true, ..params };
+    let rejected = match prove_fibonacci(skipped.clone()) {
+        Ok(run) => {
+            // The export names the violations the precheck would have stopped it on
+            assert_eq!(run.vis_data.violations.len(), 3);
+            verify_fibonacci(&skipped, &run.proof).unwrap_err()
+        }
+        Err(e) => e,
+    };
+    assert!(matches!(rejected, FibVisError::Prove(_) | FibVisError::Verify(_)), "unexpected error: {rejected}");
+}
+
+#[test]
+fn json_export_matches_the_trace() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("web").join("trace_data.json");
+    let num_steps = 16;
+
+    let run = prove_fibonacci(FibonacciParams::new(num_steps)).unwrap();
+    write_trace_json(&path, &run.vis_data).unwrap();
+
+    let json: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(json["num_steps"], num_steps);
+    assert_eq!(json["final_value"], 987);
+    assert_eq!(json["public_values"], serde_json::json!(["0", "1", "987"]));
+    assert_eq!(json["field"], serde_json::json!({"name": "Mersenne31", "modulus": "2147483647", "bits": 31, "extension_degree": 3}));
+    assert_eq!(
+        json["columns"],
+        serde_json::json!([
+            {"name": "a", "description": "current Fibonacci number"},
+            {"name": "b", "description": "next Fibonacci number"},
+        ])
+    );
+
+    let rows = json["trace"].as_array().unwrap();
+    assert_eq!(rows.len(), num_steps);
+    let trace = generate_fibonacci_trace::<Mersenne31>(num_steps, FIBONACCI_START);
+    for (i, row) in rows.iter().enumerate() {
+        let expected: Vec<String> =
+            trace.row_slice(i).unwrap().iter().map(|v| v.as_canonical_u32().to_string()).collect();
+        let actual: Vec<&str> = row.as_array().unwrap().iter().map(|v| v.as_str().unwrap()).collect();
+        assert_eq!(actual, expected, "row {i}");
+    }
+}
+
+#[test]
+fn air_info_schema_is_pinned() {
+    let run = prove_fibonacci(FibonacciParams::new(8)).unwrap();
+    let json = serde_json::to_value(&run.vis_data).unwrap();
+    assert_eq!(
+        json["air_info"],
+        serde_json::json!({
+            "sequence": "Fibonacci",
+            "width": 2,
+            "columns": ["a", "b"],
+            "final_value": 21,
+            "num_constraints": 5,
+            "boundary_constraints": 3,
+            "transition_constraints": 2,
+            "every_row_constraints": 0,
+        })
+    );
+
+    // The bit decomposition's range check holds on every row, its only boundary constraint is the final value
+    let params = FibonacciParams { sequence: Sequence::BitDecomp, ..FibonacciParams::new(8) };
+    let json = serde_json::to_value(&prove_fibonacci(params).unwrap().vis_data).unwrap();
+    assert_eq!(
+        json["air_info"],
+        serde_json::json!({
+            "sequence": "BitDecomp",
+            "width": 9,
+            "columns": ["value", "bit0", "bit1", "bit2", "bit3", "bit4", "bit5", "bit6", "bit7"],
+            "final_value": 7,
+            "num_constraints": 10,
+            "boundary_constraints": 1,
+            "transition_constraints": 0,
+            "every_row_constraints": 9,
+        })
+    );
+}
+
+#[test]
+fn custom_initial_values_prove_and_verify() {
+    // Lucas numbers: 2, 1, 3, 4, 7, 11, 18, 29, 47
+    let params = FibonacciParams { initial: (2, 1), ..FibonacciParams::new(8) };
+    let run = prove_fibonacci(params.clone()).unwrap();
+    verify_fibonacci(&params, &run.proof).unwrap();
+    assert_eq!(run.vis_data.final_value, 47);
+
+    let canonical = FibonacciParams { final_value: Some(47), ..FibonacciParams::new(8) };
+    assert!(verify_fibonacci(&canonical, &run.proof).is_err(), "accepted with the default start pair");
+}
+
+#[test]
+fn exported_trace_starts_with_the_initial_values() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("trace_data.json");
+
+    let run = prove_fibonacci(FibonacciParams { initial: (5, 8), ..FibonacciParams::new(8) }).unwrap();
+    write_trace_json(&path, &run.vis_data).unwrap();
+
+    let json: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(json["trace"][0], serde_json::json!(["5", "8"]));
+    assert_eq!(json["trace"][1], serde_json::json!(["8", "13"]));
+    assert_eq!(json["public_values"][0], "5");
+    assert_eq!(json["public_values"][1], "8");
+}
+
+#[test]
+fn lucas_proves_and_verifies() {
+    let params = FibonacciParams { sequence: Sequence::Lucas, final_value: Some(47), ..FibonacciParams::new(8) };
+    let run = prove_fibonacci(params.clone()).unwrap();
+    verify_fibonacci(&params, &run.proof).unwrap();
+    assert_eq!(run.vis_data.sequence, "Lucas");
+    assert_eq!(run.vis_data.trace[0], ["2", "1"]);
+}
+
+#[test]
+fn fibonacci_final_value_fails_against_lucas() {
+    let lucas = FibonacciParams { sequence: Sequence::Lucas, ..FibonacciParams::new(8) };
+    let run = prove_fibonacci(lucas.clone()).unwrap();
+
+    let fibonacci_final = FibonacciParams { final_value: Some(21), ..lucas.clone() };
+    assert!(matches!(verify_fibonacci(&fibonacci_final, &run.proof), Err(FibVisError::Verify(_))));
+    assert!(matches!(prove_fibonacci(fibonacci_final).err(), Some(FibVisError::FinalValueMismatch { actual: 47, .. })));
+}
+
+#[test]
+fn lucas_rejects_a_custom_start_pair() {
+    let params = FibonacciParams { sequence: Sequence::Lucas, initial: (5, 8), ..FibonacciParams::new(8) };
+    assert!(matches!(prove_fibonacci(params).err(), Some(FibVisError::Config(_))));
+}
+
+#[test]
+fn tribonacci_proves_verifies_and_exports_three_columns() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("trace_data.json");
+    let params = FibonacciParams { sequence: Sequence::Tribonacci, ..FibonacciParams::new(8) };
+
+    let run = prove_fibonacci(params.clone()).unwrap();
+    verify_fibonacci(&params, &run.proof).unwrap();
+    write_trace_json(&path, &run.vis_data).unwrap();
+
+    let json: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(json["sequence"], "Tribonacci");
+    assert_eq!(json["final_value"], 44);
+    let names: Vec<&str> = json["columns"].as_array().unwrap().iter().map(|c| c["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["a", "b", "c"]);
+    assert_eq!(json["columns"][2]["description"], "the Tribonacci number after that");
+    assert_eq!(json["trace"][0], serde_json::json!(["0", "0", "1"]));
+    assert_eq!(json["trace"][7], serde_json::json!(["13", "24", "44"]));
+    assert!(json["trace"].as_array().unwrap().iter().all(|row| row.as_array().unwrap().len() == 3));
+}
+
+#[test]
+fn mul_fibonacci_proves_and_verifies() {
+    let params = FibonacciParams { sequence: Sequence::MulFibonacci, ..FibonacciParams::new(8) };
+    let run = prove_fibonacci(params.clone()).unwrap();
+    verify_fibonacci(&params, &run.proof).unwrap();
+    assert_eq!(run.vis_data.final_value, 2097152);
+    assert_eq!(run.vis_data.trace[6], ["256", "8192"]);
+    let degrees: Vec<usize> = run.vis_data.constraints.iter().map(|c| c.degree).collect();
+    assert_eq!(degrees.iter().max(), Some(&2));
+}
+
+#[test]
+fn tampered_mul_fibonacci_cell_fails() {
+    let num_steps = 8;
+    let config = ConfigBuilder::new().num_steps(num_steps).build().unwrap();
+    let air = MulFibonacciAir::new(num_steps);
+    let public_values = air.public_values::<Mersenne31>(2097152);
+    let mut trace = generate_mul_fibonacci_trace::<Mersenne31>(num_steps);
+    // Row 3 is (4, 8); make b = 9 so the product constraint into row 4 no longer holds.
+    trace.values[3 * 2 + 1] = Mersenne31::from_u32(9);
+
+    // uni-stark checks the constraints before proving in debug builds and panics there; otherwise the proof
+    // is produced and the verifier has to reject it.
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let proof = prove(&config, &air, trace, &public_values);
+        verify(&config, &air, &proof, &public_values)
+    }));
+    assert!(!matches!(result, Ok(Ok(()))), "a tampered trace verified");
+}
+
+#[test]
+fn padded_num_steps_prove_and_verify() {
+    for (num_steps, last_real_row) in [(10, ["34", "55", "1", "9"]), (13, ["144", "233", "1", "12"])] {
+        let params = FibonacciParams::new(num_steps);
+        let run = prove_fibonacci(params.clone()).unwrap();
+        verify_fibonacci(&params, &run.proof).unwrap();
+
+        let vis = &run.vis_data;
+        assert_eq!(vis.num_steps, num_steps);
+        assert_eq!(column_names(&vis.columns), ["a", "b", "is_real", "row"]);
+        assert_eq!(vis.trace.len(), 16);
+        assert_eq!(vis.trace[num_steps - 1], last_real_row);
+        assert_eq!(vis.trace[num_steps], ["0", "0", "0", &num_steps.to_string()]);
+        assert_eq!(vis.padding.iter().position(|&padding| padding), Some(num_steps));
+        assert!(vis.padding[num_steps..].iter().all(|&padding| padding));
+    }
+}
+
+#[test]
+fn padded_trace_rejects_a_wrong_final_value() {
+    let params = FibonacciParams::new(10);
+    let run = prove_fibonacci(params.clone()).unwrap();
+    let wrong = FibonacciParams { final_value: Some(89), ..params };
+    assert!(matches!(verify_fibonacci(&wrong, &run.proof), Err(FibVisError::Verify(_))));
+}
+
+#[test]
+fn only_fibonacci_pads_its_trace() {
+    let params = FibonacciParams { sequence: Sequence::Tribonacci, ..FibonacciParams::new(10) };
+    assert!(matches!(prove_fibonacci(params).err(), Some(FibVisError::Config(_))));
+}
+
+#[test]
+fn final_value_round_trips_as_a_31_bit_field_element() {
+    // 64 steps over Mersenne31 end in 1640641543, which needs all 31 bits.
+    let final_value = parse_field_element("0x61ca3407").unwrap();
+    assert_eq!(final_value, 1640641543);
+    assert_eq!(parse_field_element("1640641543"), Ok(final_value));
+
+    let params = FibonacciParams { final_value: Some(final_value), ..FibonacciParams::new(64) };
+    let run = prove_fibonacci(params.clone()).unwrap();
+    verify_fibonacci(&params, &run.proof).unwrap();
+    assert_eq!(run.vis_data.final_value, final_value);
+    assert_eq!(run.vis_data.public_values[2], "1640641543");
+}
+
+#[test]
+fn out_of_range_final_value_is_rejected() {
+    assert!(parse_field_element("18446744073709551616").unwrap_err().contains("64 bits"));
+    assert!(parse_field_element("0x1_0000").is_err());
+    assert!(FieldChoice::Mersenne31.check_element((1 << 31) - 1).is_err());
+    assert!(FieldChoice::Mersenne31.check_element((1 << 31) - 2).is_ok());
+
+    // Reducing 2^31 + 20 modulo the prime would give 21, the right final value for 8 steps.
+    let params = FibonacciParams { final_value: Some((1 << 31) + 20), ..FibonacciParams::new(8) };
+    assert!(matches!(prove_fibonacci(params).err(), Some(FibVisError::Config(_))));
+}
+
+#[test]
+fn linear_recurrence_with_fibonacci_coefficients_matches_fibonacci_air() {
+    let num_steps = 16;
+    let config = ConfigBuilder::new().num_steps(num_steps).build().unwrap();
+    let fibonacci = FibonacciAir::new(num_steps);
+    let linear = LinearRecurrenceAir::new(num_steps, vec![1, 1], vec![0, 1]);
+
+    let trace = generate_linear_recurrence_trace::<Mersenne31>(num_steps, &linear.coeffs, &linear.initial);
+    assert_eq!(trace.values, generate_fibonacci_trace::<Mersenne31>(num_steps, FIBONACCI_START).values);
+    let final_value = linear_recurrence_final_value(FieldChoice::Mersenne31, num_steps, &linear.coeffs, &linear.initial);
+    assert_eq!(final_value, 987);
+    let public_values = linear.public_values::<Mersenne31>(final_value);
+    assert_eq!(public_values, fibonacci.public_values::<Mersenne31>(final_value));
+
+    // A proof for either AIR verifies against the other one, they build the same constraints
+    let proof = prove(&config, &linear, trace.clone(), &public_values);
+    verify(&config, &fibonacci, &proof, &public_values).unwrap();
+    let proof = prove(&config, &fibonacci, trace, &public_values);
+    verify(&config, &linear, &proof, &public_values).unwrap();
+}
+
+#[test]
+fn pell_numbers_prove_and_verify_as_a_linear_recurrence() {
+    let num_steps = 8;
+    let config = ConfigBuilder::new().num_steps(num_steps).build().unwrap();
+    let air = LinearRecurrenceAir::new(num_steps, vec![2, 1], vec![0, 1]);
+    let trace = generate_linear_recurrence_trace::<Mersenne31>(num_steps, &air.coeffs, &air.initial);
+    assert_eq!(trace.row_slice(7).unwrap().iter().map(|v| v.as_canonical_u32()).collect::<Vec<_>>(), [169, 408]);
+
+    let public_values = air.public_values::<Mersenne31>(408);
+    let proof = prove(&config, &air, trace, &public_values);
+    verify(&config, &air, &proof, &public_values).unwrap();
+    assert!(verify(&config, &air, &proof, &air.public_values::<Mersenne31>(407)).is_err());
+}
+
+#[test]
+fn collatz_orbit_of_27_proves_and_verifies() {
+    let num_steps = 128;
+    let config = ConfigBuilder::new().num_steps(num_steps).build().unwrap();
+    let air = CollatzAir::new(num_steps, 27);
+    let trace = generate_collatz_trace::<Mersenne31>(num_steps, 27);
+    let row = |i: usize| trace.row_slice(i).unwrap().iter().map(|v| v.as_canonical_u32()).collect::<Vec<_>>();
+    assert_eq!((row(0), row(1), row(110)), (vec![27, 1, 1], vec![82, 0, 1], vec![2, 0, 1]));
+
+    // The orbit reaches 1 at row 111 and repeats it with is_real off for the remaining rows
+    assert!((111..num_steps).all(|i| row(i) == [1, 0, 0]));
+    let final_value = collatz_final_value(FieldChoice::Mersenne31, num_steps, 27);
+    assert_eq!(final_value, 1);
+    let public_values = air.public_values::<Mersenne31>(final_value);
+    let proof = prove(&config, &air, trace, &public_values);
+    verify(&config, &air, &proof, &public_values).unwrap();
+    assert!(verify(&config, &air, &proof, &air.public_values::<Mersenne31>(4)).is_err());
+}
+
+#[test]
+fn collatz_needs_enough_rows_for_the_orbit() {
+    assert!(check_initial(Sequence::Collatz, 128, &[27]).is_ok());
+    assert!(matches!(check_initial(Sequence::Collatz, 64, &[27]), Err(FibVisError::Config(_))));
+    assert!(matches!(check_initial(Sequence::Collatz, 64, &[0]), Err(FibVisError::Config(_))));
+    assert_eq!(column_names(&Sequence::Collatz.columns(64)), ["value", "parity", "is_real"]);
+}
+
+#[test]
+fn counter_and_bit_decomposition_prove_and_verify() {
+    let params = FibonacciParams { sequence: Sequence::Counter, ..FibonacciParams::new(16) };
+    let run = prove_fibonacci(params.clone()).unwrap();
+    verify_fibonacci(&params, &run.proof).unwrap();
+    assert_eq!(run.vis_data.final_value, 15);
+    assert_eq!(column_names(&run.vis_data.columns), ["count"]);
+
+    let params = FibonacciParams { sequence: Sequence::BitDecomp, ..FibonacciParams::new(512) };
+    let run = prove_fibonacci(params.clone()).unwrap();
+    verify_fibonacci(&params, &run.proof).unwrap();
+    assert_eq!(run.vis_data.final_value, 255);
+    assert_eq!(run.vis_data.trace[300], ["44", "0", "0", "1", "1", "0", "1", "0", "0"]);
+}
+
+#[test]
+fn tampered_bit_fails() {
+    let num_steps = 16;
+    let config = ConfigBuilder::new().num_steps(num_steps).build().unwrap();
+    let air = BitDecompAir::new(num_steps);
+    let public_values = air.public_values::<Mersenne31>(15);
+    let mut trace = generate_bit_decomp_trace::<Mersenne31>(num_steps);
+    // Row 5 is 5 = 0b101; clearing bit0 leaves bits that recompose to 4.
+    trace.values[5 * 9 + 1] = Mersenne31::ZERO;
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let proof = prove(&config, &air, trace, &public_values);
+        verify(&config, &air, &proof, &public_values)
+    }));
+    assert!(!matches!(result, Ok(Ok(()))), "a tampered trace verified");
+}
+
+#[test]
+fn indexed_fibonacci_exports_its_preprocessed_column() {
+    let num_steps = 8;
+    let air = IndexedFibonacciAir::new(num_steps, FIBONACCI_START);
+    let preprocessed = BaseAir::<Mersenne31>::preprocessed_trace(&air).unwrap();
+    assert_eq!(preprocessed.values, (0..8).map(Mersenne31::from_u32).collect::<Vec<_>>());
+
+    // The Fibonacci constraints plus the one on the last row's index
+    assert_eq!(get_symbolic_constraints::<Mersenne31, _>(&air, 1, 3).len(), 6);
+
+    let trace = generate_fibonacci_trace::<Mersenne31>(num_steps, FIBONACCI_START);
+    let vis = vis_data_from_matrix(&trace, &FIBONACCI_COLUMNS).unwrap().with_preprocessed(&preprocessed, &INDEX_COLUMNS).unwrap();
+    assert_eq!(column_names(&vis.preprocessed_columns), ["step"]);
+    assert_eq!(vis.preprocessed[7], ["7"]);
+    assert!(vis_data_from_matrix(&trace, &FIBONACCI_COLUMNS).unwrap().with_preprocessed(&preprocessed, &FIBONACCI_COLUMNS).is_err());
+}
+
+#[test]
+fn indexed_fibonacci_proves_and_verifies_against_its_preprocessed_commitment() {
+    let num_steps = 8;
+    let config = ConfigBuilder::new().num_steps(num_steps).build().unwrap();
+    let air = Sequence::IndexedFibonacci.air(num_steps, &[0, 1]);
+    let public_values = air.public_values::<Mersenne31>(21);
+    let proof = air.prove(&config, air.generate_trace::<Mersenne31>(), &public_values);
+    air.verify(&config, &proof, &public_values).unwrap();
+    assert!(air.verify(&config, &proof, &air.public_values::<Mersenne31>(34)).is_err());
+
+    // The same through prove_fibonacci, whose export carries the preprocessed column next to the committed ones
+    let params = FibonacciParams { sequence: Sequence::IndexedFibonacci, ..FibonacciParams::new(num_steps) };
+    let run = prove_fibonacci(params.clone()).unwrap();
+    verify_fibonacci(&params, &run.proof).unwrap();
+    assert_eq!(column_names(&run.vis_data.preprocessed_columns), ["step"]);
+    assert_eq!(run.vis_data.preprocessed[7], ["7"]);
+    assert_eq!(run.vis_data.final_value, 21);
+}
+
+#[test]
+fn fibonacci_with_index_proves_verifies_and_exports_its_index() {
+    let params = FibonacciParams { sequence: Sequence::FibonacciWithIndex, ..FibonacciParams::new(8) };
+    let run = prove_fibonacci(params.clone()).unwrap();
+    verify_fibonacci(&params, &run.proof).unwrap();
+    assert_eq!(column_names(&run.vis_data.columns), ["i", "a", "b"]);
+    assert_eq!(run.vis_data.final_value, 21);
+    assert_eq!(run.vis_data.trace[0], ["0", "0", "1"]);
+    assert_eq!(run.vis_data.trace[7], ["7", "13", "21"]);
+    // The Fibonacci constraints plus the index on the first row, on every transition and on the last row
+    assert_eq!(run.vis_data.constraints.len(), 8);
+}
+
+#[test]
+fn tampered_index_fails_with_valid_sequence_columns() {
+    let num_steps = 8;
+    let config = ConfigBuilder::new().num_steps(num_steps).build().unwrap();
+    let air = FibonacciWithIndexAir::new(num_steps, FIBONACCI_START);
+    let public_values = air.public_values::<Mersenne31>(21);
+
+    // Row 3's index made 4 breaks the transitions into and out of it; every index shifted by one still counts,
+    // but no longer starts at 0 or ends at num_steps - 1. Columns a and b stay untouched in both.
+    let tampered: [&dyn Fn(&mut Vec<Mersenne31>); 2] = [
+        &|values: &mut Vec<Mersenne31>| values[3 * 3] = Mersenne31::from_u32(4),
+        &|values: &mut Vec<Mersenne31>| (0..num_steps).for_each(|row| values[row * 3] += Mersenne31::ONE),
+    ];
+    for tamper in tampered {
+        let mut trace = generate_fibonacci_with_index_trace::<Mersenne31>(num_steps, FIBONACCI_START);
+        tamper(&mut trace.values);
+        let violations = find_violations(&air, &trace, &public_values);
+        assert!(!violations.is_empty());
+        assert!(violations.iter().all(|v| v.cells.iter().any(|&(_, col)| col == 0)));
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let proof = prove(&config, &air, trace, &public_values);
+            verify(&config, &air, &proof, &public_values)
+        }));
+        assert!(!matches!(result, Ok(Ok(()))), "a trace with a tampered index verified");
+    }
+}
+
+#[test]
+fn column_metadata_must_match_the_trace_width() {
+    let trace = generate_fibonacci_trace::<Mersenne31>(8, FIBONACCI_START);
+    let result = vis_data_from_matrix(&trace, &[ColumnMeta::new("a", "current Fibonacci number")]);
+    assert!(matches!(result, Err(FibVisError::Columns { width: 2, names: 1 })));
+
+    let pell = linear_recurrence_columns(2);
+    assert_eq!(column_names(&pell), ["a", "b"]);
+    assert_eq!(pell[1].description, "x(i+1)");
+    assert_eq!(column_names(&linear_recurrence_columns(30))[29], "x29");
+}
+
+#[test]
+fn matrix_with_no_rows_or_other_column_names_converts_or_is_rejected() {
+    // No rows is an empty trace, the columns are still described
+    let empty = RowMajorMatrix::new(Vec::<Mersenne31>::new(), 2);
+    let vis = vis_data_from_matrix(&empty, &FIBONACCI_COLUMNS).unwrap();
+    assert_eq!((vis.num_steps, vis.trace.len(), vis.schema_version), (0, 0, SCHEMA_VERSION));
+    assert_eq!(column_names(&vis.columns), ["a", "b"]);
+
+    // More names than columns is as wrong as fewer, with or without rows
+    let three = [FIBONACCI_COLUMNS.to_vec(), vec![ColumnMeta::new("c", "one too many")]].concat();
+    let result = vis_data_from_matrix(&empty, &three);
+    assert!(matches!(result, Err(FibVisError::Columns { width: 2, names: 3 })));
+    let trace = generate_fibonacci_trace::<Mersenne31>(8, FIBONACCI_START);
+    let err = vis_data_from_matrix(&trace, &three).unwrap_err();
+    assert!(matches!(err, FibVisError::Columns { width: 2, names: 3 }), "{err}");
+    assert_eq!(err.exit_code(), 5);
+    assert!(matches!(vis_data_from_matrix(&empty, &[]), Err(FibVisError::Columns { width: 2, names: 0 })));
+}
+
+#[test]
+fn field_metadata_matches_the_backend() {
+    let m31 = FieldInfo::new(FieldChoice::Mersenne31);
+    assert_eq!(m31.modulus, "2147483647");
+    assert_eq!(m31.modulus, Mersenne31::order().to_string());
+    assert_eq!((m31.bits, m31.extension_degree), (31, 3));
+    for field in [FieldChoice::BabyBear, FieldChoice::KoalaBear] {
+        let info = FieldInfo::new(field);
+        assert_eq!((info.bits, info.extension_degree), (31, 4), "{}", info.name);
+    }
+    assert_eq!(FieldInfo::new(FieldChoice::BabyBear).modulus, "2013265921");
+    assert_eq!(FieldInfo::new(FieldChoice::KoalaBear).modulus, "2130706433");
+}
+
+#[test]
+fn proven_export_carries_proof_stats() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("trace_data.json");
+    let params = FibonacciParams::new(64);
+    let run = prove_fibonacci(params.clone()).unwrap();
+    write_trace_json(&path, &run.vis_data).unwrap();
+
+    let json: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    let stats = &json["stats"];
+    assert_eq!(stats["proof_bytes"], run.proof.len());
+    for phase in ["trace_gen_ms", "export_ms", "prove_ms"] {
+        assert!(stats["timings"][phase].as_f64().unwrap() > 0.0, "{phase}");
+    }
+    // prove_fibonacci does not verify, verify_fibonacci does that on its own
+    assert_eq!(stats["timings"]["verify_ms"], 0.0);
+    assert_eq!(stats["fri"]["num_queries"], params.fri.num_queries);
+    assert_eq!(stats["fri"]["log_blowup"], params.fri.log_blowup);
+    assert_eq!(stats["fri"]["proof_of_work_bits"], params.fri.proof_of_work_bits);
+}
+
+#[test]
+fn proving_takes_most_of_the_recorded_run() {
+    let run = prove_fibonacci(FibonacciParams::new(1 << 10)).unwrap();
+    let timings = serde_json::to_value(run.vis_data.stats.unwrap().timings).unwrap();
+    let keys: Vec<&str> = timings.as_object().unwrap().keys().map(String::as_str).collect();
+    assert_eq!(keys, ["trace_gen_ms", "export_ms", "prove_ms", "verify_ms", "total_ms"]);
+
+    let t = run.timings;
+    assert!(t.prove_ms > t.trace_gen_ms && t.prove_ms > t.export_ms, "{t:?}");
+    // The total also covers what lies between the phases, such as encoding the proof
+    assert!(t.total_ms >= t.trace_gen_ms + t.export_ms + t.prove_ms, "{t:?}");
+}
+
+#[test]
+fn tracked_memory_peaks_above_the_trace_it_held() {
+    // Other tests proving meanwhile only raise the peak, and their exports may then carry memory stats too
+    memory::enable();
+    let num_steps = 1 << 14;
+    let run = prove_fibonacci(FibonacciParams::new(num_steps)).unwrap();
+    let stats = run.vis_data.stats.unwrap();
+    let heap = stats.memory.unwrap();
+    let keys: Vec<String> = serde_json::to_value(heap).unwrap().as_object().unwrap().keys().cloned().collect();
+    assert_eq!(keys, ["peak_bytes", "trace_gen_bytes", "export_bytes", "prove_bytes", "verify_bytes"]);
+
+    // The trace alone is two 4-byte columns per row. The deltas count every thread's allocations, so with the
+    // suite running in parallel only the peak is certain.
+    let trace_bytes = num_steps * 2 * 4;
+    assert!(heap.peak_bytes >= trace_bytes as u64, "{heap:?}");
+}
+
+#[test]
+fn proof_stats_identify_the_proof_by_digest_and_head() {
+    use base64::Engine;
+
+    let params = FibonacciParams::new(16);
+    let run = prove_fibonacci(params.clone()).unwrap();
+    let stats = run.vis_data.stats.clone().unwrap();
+    let digest = stats.proof_digest.unwrap();
+    assert_eq!(digest, proof_digest(&run.proof));
+    assert_eq!(digest.len(), 64);
+    assert!(digest.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)), "{digest}");
+    let head = base64::engine::general_purpose::STANDARD.decode(stats.proof_head.unwrap()).unwrap();
+    assert_eq!(head, run.proof[..PROOF_HEAD_BYTES]);
+
+    // Proving is deterministic, so the same parameters give the same digest and another seed another one
+    assert_eq!(prove_fibonacci(params.clone()).unwrap().vis_data.stats.unwrap().proof_digest, Some(digest.clone()));
+    let reseeded = prove_fibonacci(FibonacciParams { seed: Seed(b"other".to_vec()), ..params }).unwrap();
+    assert_ne!(reseeded.vis_data.stats.unwrap().proof_digest, Some(digest));
+    assert_eq!(proof_digest(&[]), "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+}
+
+#[test]
+fn proven_export_lists_its_commitments_in_protocol_order() {
+    let order = [CommitPhase::Trace, CommitPhase::QuotientChunks, CommitPhase::FriFirstLayer, CommitPhase::FriCommitPhase];
+    let rank = |phase: CommitPhase| order.iter().position(|&p| p == phase).unwrap();
+    for (num_steps, log_final_poly_len) in [(8, 1), (64, 0), (256, 2)] {
+        let fri = FriParams { log_final_poly_len, ..FriParams::default() };
+        let params = FibonacciParams { fri, ..FibonacciParams::new(num_steps) };
+        let commitments = prove_fibonacci(params.clone()).unwrap().vis_data.commitments;
+
+        let phases: Vec<CommitPhase> = commitments.iter().map(|c| c.phase).collect();
+        assert_eq!(phases[..2], [CommitPhase::Trace, CommitPhase::QuotientChunks], "{num_steps} steps");
+        assert!(phases.windows(2).all(|w| rank(w[0]) <= rank(w[1])), "{phases:?}");
+        // One root per folding round, as many as the FRI parameters give for this size
+        let rounds: Vec<usize> =
+            commitments.iter().filter(|c| c.phase == CommitPhase::FriCommitPhase).map(|c| c.round).collect();
+        let expected = fri.commit_rounds(FieldChoice::Mersenne31, num_steps);
+        assert_eq!(rounds, (0..expected).collect::<Vec<_>>(), "{num_steps} steps, log_final_poly_len {log_final_poly_len}");
+        // Keccak256 roots are 32 bytes
+        assert!(commitments.iter().all(|c| c.root.len() == 64 && c.root.bytes().all(|b| b.is_ascii_hexdigit())));
+
+        assert_eq!(prove_fibonacci(params).unwrap().vis_data.commitments, commitments, "not deterministic");
+    }
+}
+
+#[test]
+fn export_records_whether_the_proof_verified() {
+    let dir = tempfile::tempdir().unwrap();
+
+    // An honest trace verifies, and the export read back says so
+    let params = FibonacciParams::new(16);
+    let run = prove_fibonacci(params.clone()).unwrap();
+    let start = Instant::now();
+    let result = verify_fibonacci(&params, &run.proof);
+    let verification = Verification::new(result.as_ref().err(), Some(run.proof.len()), elapsed_ms(start));
+    let path = dir.path().join("trace_data.json");
+    write_trace_json(&path, &run.vis_data.with_verification(verification)).unwrap();
+    let verification = load_vis_data(&path).unwrap().verification.unwrap();
+    assert!(verification.verified);
+    assert_eq!(verification.error, None);
+    assert_eq!(verification.proof_bytes, Some(run.proof.len()));
+    assert!(verification.verify_ms > 0.0);
+
+    // A tampered one makes the prover panic (debug builds) or the verifier reject its proof, either is recorded
+    let num_steps = 8;
+    let config = ConfigBuilder::new().num_steps(num_steps).build().unwrap();
+    let air = FibonacciAir::new(num_steps);
+    let public_values = air.public_values::<Mersenne31>(21);
+    let mut trace = generate_fibonacci_trace::<Mersenne31>(num_steps, FIBONACCI_START);
+    trace.values[3 * 2 + 1] += Mersenne31::ONE;
+    let tampered = vis_data_from_matrix(&trace, &FIBONACCI_COLUMNS).unwrap().with_tampered(vec![(3, 1)]);
+    let start = Instant::now();
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+        let proof = prove(&config, &air, trace, &public_values);
+        (postcard::to_allocvec(&proof).unwrap().len(), verify(&config, &air, &proof, &public_values))
+    }));
+    let verification = match outcome {
+        Ok((bytes, result)) => {
+            let error = result.err().map(FibVisError::from);
+            Verification::new(error.as_ref(), Some(bytes), elapsed_ms(start))
+        }
+        Err(payload) => Verification::new(Some(&FibVisError::Prove(panic_message(payload))), None, 0.0),
+    };
+    let path = dir.path().join("trace_data_invalid.json");
+    write_trace_json(&path, &tampered.with_verification(verification)).unwrap();
+    let json: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(json["verification"]["verified"], false);
+    let error = json["verification"]["error"].as_str().unwrap();
+    assert!(error.starts_with("proving failed") || error.starts_with("verification failed"), "{error}");
+    assert_eq!(json["verification"]["proof_bytes"].is_null(), error.starts_with("proving failed"));
+
+    // Exports from before the block existed load without one
+    let v2 = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/trace_v2.json");
+    assert_eq!(load_vis_data(&v2).unwrap().verification, None);
+}
+
+#[test]
+fn exported_proof_json_decodes_back_and_verifies() {
+    let dir = tempfile::tempdir().unwrap();
+    let num_steps = 16;
+    let config = ConfigBuilder::new().num_steps(num_steps).build().unwrap();
+    let air = FibonacciAir::new(num_steps);
+    let public_values = air.public_values::<Mersenne31>(987);
+    let proof = prove(&config, &air, generate_fibonacci_trace::<Mersenne31>(num_steps, FIBONACCI_START), &public_values);
+    let export = ProofExport {
+        field: FieldChoice::Mersenne31,
+        hash: HashChoice::Keccak256,
+        fri: FriParams::default(),
+        seed: Seed::default(),
+        sequence: Sequence::Fibonacci,
+        recurrence: Vec::new(),
+        num_steps,
+        initial: vec![0, 1],
+        final_value: 987,
+        public_values: public_values.iter().map(|v| v.to_string()).collect(),
+        proof_bytes: postcard::to_allocvec(&proof).unwrap().len(),
+        proof: proof_to_json(&proof).unwrap(),
+        fri_queries: fri_queries(&proof, Some(3)).unwrap(),
+        // No transcript recorded, so no points, no betas and no check
+        fri_rounds: fri_rounds(&proof, &[], Some(3)).unwrap(),
+        pow: pow_grinding(&proof, FriParams::default().proof_of_work_bits, &[], None).unwrap(),
+        ood: ood_opening(&config, &proof, &[]).unwrap(),
+        quotient: quotient_opening(&config, &air, &proof, &public_values, &[]).unwrap(),
+    };
+    assert!(export.ood.zeta.is_none() && export.quotient.check.is_none());
+    assert!(export.fri_rounds.iter().all(|round| round.beta.is_none() && round.openings.len() == 3));
+    assert!(export.pow.attempts.is_none() && export.pow.check.is_none());
+
+    for name in ["proof_data.json", "proof_data.json.gz"] {
+        let path = dir.path().join(name);
+        write_proof_json(&path, &export).unwrap();
+        let read = read_proof_json(&path).unwrap();
+        assert_eq!(read, export, "{name}");
+
+        // Verified with only what the file records
+        let config = ConfigBuilder::new().fri(read.fri).seed(read.seed.0.clone()).num_steps(read.num_steps).build().unwrap();
+        let public_values: Vec<Mersenne31> =
+            read.public_values.iter().map(|v| Mersenne31::from_u32(v.parse().unwrap())).collect();
+        let proof: Proof<M31KeccakConfig> = read.decode().unwrap();
+        verify(&config, &FibonacciAir::new(read.num_steps), &proof, &public_values).unwrap();
+    }
+
+    // Every byte array is already a hex string, encoding again finds none
+    assert_eq!(proof_to_json(&export.proof).unwrap(), export.proof);
+    let json = fs::read_to_string(dir.path().join("proof_data.json")).unwrap();
+    assert!(json.contains("\"0x"), "no hex-encoded commitments");
+    let gz = fs::metadata(dir.path().join("proof_data.json.gz")).unwrap().len();
+    assert!(gz < json.len() as u64, "{gz} >= {}", json.len());
+}
+
+#[test]
+fn merkle_tree_levels_halve_up_to_the_proofs_trace_root() {
+    let num_steps = 32;
+    let fri = FriParams::default();
+    let config = ConfigBuilder::new().fri(fri).num_steps(num_steps).build().unwrap();
+    let air = FibonacciAir::new(num_steps);
+    let final_value = fibonacci_final_value(FieldChoice::Mersenne31, num_steps, FIBONACCI_START);
+    let public_values = air.public_values::<Mersenne31>(final_value);
+    let trace = generate_fibonacci_trace::<Mersenne31>(num_steps, FIBONACCI_START);
+    let tree = merkle_tree(&config, trace.clone()).unwrap();
+    let proof = prove(&config, &air, trace, &public_values);
+
+    let roots = commitment_roots(&proof).unwrap();
+    let trace_root = roots.iter().find(|root| root.phase == CommitPhase::Trace).unwrap();
+    assert_eq!(tree.root, trace_root.root);
+    // One leaf per row of the LDE, each level half the one below, up to the root alone
+    let widths: Vec<usize> = tree.levels.iter().map(Vec::len).collect();
+    assert_eq!(widths[0], num_steps << fri.log_blowup);
+    assert!(widths.windows(2).all(|w| w[1] * 2 == w[0]), "{widths:?}");
+    assert_eq!(tree.levels.last().unwrap(), &[tree.root.clone()]);
+    assert!(tree.levels.iter().flatten().all(|digest| digest.len() == 64), "{:?}", tree.levels);
+
+    let tall = generate_fibonacci_trace::<Mersenne31>(MERKLE_MAX_ROWS * 2, FIBONACCI_START);
+    assert!(matches!(merkle_tree(&config, tall), Err(FibVisError::Config(_))));
+}
+
+#[test]
+fn pow_witness_is_ground_in_order_and_checked_by_the_verifier() {
+    let num_steps = 32;
+    let air = FibonacciAir::new(num_steps);
+    let final_value = fibonacci_final_value(FieldChoice::Mersenne31, num_steps, FIBONACCI_START);
+    let public_values = air.public_values::<Mersenne31>(final_value);
+    let prove_with = |pow_bits: usize| {
+        let config = ConfigBuilder::new().pow_bits(pow_bits).num_steps(num_steps).build_recording().unwrap();
+        let proof = prove(&config, &air, generate_fibonacci_trace::<Mersenne31>(num_steps, FIBONACCI_START), &public_values);
+        let transcript = config.initialise_challenger().take_transcript();
+        (config, proof, transcript)
+    };
+
+    // Even a single bit is ground for and checked like the default 16
+    let (config, proof, transcript) = prove_with(1);
+    verify(&config, &air, &proof, &public_values).unwrap();
+    let pow = pow_grinding(&proof, 1, &transcript, config.initialise_challenger().pow_check()).unwrap();
+    assert_eq!((pow.bits, pow.check), (1, Some(true)));
+    let witness: u64 = pow.witness.parse().unwrap();
+    assert_eq!(pow.attempts, Some(witness + 1));
+    let recorded = transcript.iter().find(|event| event.label == TranscriptLabel::PowWitness).unwrap();
+    assert_eq!(recorded.values, [pow.witness.clone()]);
+
+    // Every candidate below the witness failed, so the verifier rejects one of them
+    let (config, proof, transcript) = prove_with(16);
+    let pow = pow_grinding(&proof, 16, &transcript, None).unwrap();
+    assert_ne!(pow.witness, "0", "the smallest witness happened to be zero");
+    fn set_key(json: &mut Value, key: &str, value: &Value) -> bool {
+        match json {
+            Value::Object(fields) if fields.contains_key(key) => {
+                fields[key] = value.clone();
+                true
+            }
+            Value::Object(fields) => fields.values_mut().any(|field| set_key(field, key, value)),
+            Value::Array(items) => items.iter_mut().any(|item| set_key(item, key, value)),
+            _ => false,
+        }
+    }
+    let mut json = proof_to_json(&proof).unwrap();
+    assert!(set_key(&mut json, "pow_witness", &Value::from(0)));
+    let forged: Proof<M31KeccakRecordingConfig> = proof_from_json(json).unwrap();
+    assert!(verify(&config, &air, &forged, &public_values).is_err());
+    assert_eq!(config.initialise_challenger().pow_check(), Some(false));
+}
+
+#[test]
+fn fri_queries_open_one_level_less_per_round() {
+    let num_steps = 64;
+    let fri = FriParams { num_queries: 5, ..FriParams::default() };
+    let config = ConfigBuilder::new().fri(fri).num_steps(num_steps).build().unwrap();
+    let air = FibonacciAir::new(num_steps);
+    let final_value = fibonacci_final_value(FieldChoice::Mersenne31, num_steps, FIBONACCI_START);
+    let public_values = air.public_values::<Mersenne31>(final_value);
+    let proof = prove(&config, &air, generate_fibonacci_trace::<Mersenne31>(num_steps, FIBONACCI_START), &public_values);
+
+    let queries = fri_queries(&proof, None).unwrap();
+    assert_eq!(queries.len(), fri.num_queries);
+    let rounds = fri.commit_rounds(FieldChoice::Mersenne31, num_steps);
+    for (i, query) in queries.iter().enumerate() {
+        assert_eq!(query.query, i);
+        assert_eq!(query.rounds.len(), rounds);
+        // Each folding round halves the tree, down to the final polynomial's LDE
+        let paths: Vec<usize> = query.rounds.iter().map(|round| round.path_len).collect();
+        assert!(paths.windows(2).all(|w| w[1] + 1 == w[0]), "{paths:?}");
+        assert_eq!(paths.last(), Some(&(fri.log_blowup + fri.log_final_poly_len)));
+        // The siblings are elements of the challenge field, the degree 3 extension of Mersenne31
+        assert!(query.rounds.iter().all(|round| round.values.len() == M31_CHALLENGE_DEGREE));
+        // The trace and the quotient chunks are opened from trees at least as tall as the first round's
+        assert!(query.input_openings.len() >= 2);
+        assert!(query.input_openings.iter().all(|opening| opening.path_len >= paths[0]));
+        let shape = |q: &FriQuery| q.input_openings.iter().map(|o| (o.values.len(), o.path_len)).collect::<Vec<_>>();
+        assert_eq!(shape(query), shape(&queries[0]));
+        for value in query.input_openings.iter().chain(&query.rounds).flat_map(|opening| &opening.values) {
+            assert!(value.parse::<u32>().unwrap() < Mersenne31::ORDER_U32, "{value}");
+        }
+    }
+    assert_eq!(fri_queries(&proof, Some(2)).unwrap(), queries[..2]);
+}
+
+#[test]
+fn transcript_is_reproduced_from_the_seed() {
+    let num_steps = 32;
+    let air = FibonacciAir::new(num_steps);
+    let final_value = fibonacci_final_value(FieldChoice::Mersenne31, num_steps, FIBONACCI_START);
+    let public_values = air.public_values::<Mersenne31>(final_value);
+    let transcript = |seed: &[u8]| {
+        let config = ConfigBuilder::new().seed(seed).num_steps(num_steps).build_recording().unwrap();
+        let trace = generate_fibonacci_trace::<Mersenne31>(num_steps, FIBONACCI_START);
+        let proof = prove(&config, &air, trace, &public_values);
+        let proved = config.initialise_challenger().take_transcript();
+        verify(&config, &air, &proof, &public_values).unwrap();
+        // The verifier observes and samples exactly what the prover did
+        assert_eq!(config.initialise_challenger().take_transcript(), proved);
+        proved
+    };
+
+    let events = transcript(b"demo");
+    assert_eq!(transcript(b"demo"), events, "not deterministic");
+    assert_ne!(transcript(b"omed"), events);
+
+    let labels: Vec<TranscriptLabel> = events.iter().map(|event| event.label).collect();
+    let seed = TranscriptEvent { op: TranscriptOp::Observe, label: TranscriptLabel::Seed, values: vec!["64656d6f".to_string()] };
+    assert_eq!(events[0], seed);
+    let first = |label: TranscriptLabel| labels.iter().position(|&l| l == label).unwrap_or_else(|| panic!("no {label:?}"));
+    let phases = [
+        TranscriptLabel::TraceCommitment,
+        TranscriptLabel::Alpha,
+        TranscriptLabel::QuotientCommitment,
+        TranscriptLabel::Zeta,
+        TranscriptLabel::FriCommitment,
+        TranscriptLabel::FriBeta,
+        TranscriptLabel::PowWitness,
+        TranscriptLabel::QueryIndex,
+    ];
+    assert!(phases.windows(2).all(|w| first(w[0]) < first(w[1])), "{labels:?}");
+    assert_eq!(labels.iter().filter(|&&l| l == TranscriptLabel::QueryIndex).count(), FriParams::default().num_queries);
+    for event in &events {
+        match event.label {
+            TranscriptLabel::Alpha | TranscriptLabel::Zeta | TranscriptLabel::FriBeta => {
+                assert_eq!((event.op, event.values.len()), (TranscriptOp::Sample, M31_CHALLENGE_DEGREE), "{event:?}")
+            }
+            // Keccak256 roots
+            TranscriptLabel::TraceCommitment | TranscriptLabel::QuotientCommitment | TranscriptLabel::FriCommitment => {
+                assert_eq!(event.values.iter().map(String::len).collect::<Vec<_>>(), [64], "{event:?}")
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(feature = "babybear")]
+#[test]
+fn fri_round_replays_one_fold_into_the_next_rounds_opening() {
+    use p3_baby_bear::BabyBear;
+    use p3_field::TwoAdicField;
+    use p3_keccak::Keccak256Hash;
+    use p3_util::reverse_bits_len;
+    use plonky3_fibonacci::proof_json::FriRoundOpening;
+    use plonky3_fibonacci::stark_config::{byte_hash_challenger, byte_hash_mmcs, two_adic_config, BabyBearChallenge};
+    use plonky3_fibonacci::transcript::RecordingChallenger;
+
+    let num_steps = 64;
+    let fri = FriParams::default();
+    let seed = Seed::default();
+    let config = two_adic_config::<BabyBear, BabyBearChallenge, _, _>(
+        byte_hash_mmcs::<BabyBear, _>(Keccak256Hash {}),
+        RecordingChallenger::<BabyBear, _>::new(byte_hash_challenger::<BabyBear, _>(Keccak256Hash {}, &seed), &seed),
+        &fri,
+    );
+    let air = FibonacciAir::new(num_steps);
+    let final_value = fibonacci_final_value(FieldChoice::BabyBear, num_steps, FIBONACCI_START);
+    let public_values = air.public_values::<BabyBear>(final_value);
+    let proof = prove(&config, &air, generate_fibonacci_trace::<BabyBear>(num_steps, FIBONACCI_START), &public_values);
+    let transcript = config.initialise_challenger().take_transcript();
+    let rounds = fri_rounds(&proof, &transcript, None).unwrap();
+
+    // One round per commitment, each folding the codeword in half, from the LDE of the trace down
+    assert_eq!(rounds.len(), fri.commit_rounds(FieldChoice::BabyBear, num_steps));
+    let sizes: Vec<usize> = rounds.iter().map(|round| round.domain_size).collect();
+    assert_eq!(sizes[0], num_steps << fri.log_blowup);
+    assert!(sizes.windows(2).all(|w| w[1] * 2 == w[0]), "{sizes:?}");
+    let betas: Vec<&Vec<String>> =
+        transcript.iter().filter(|event| event.label == TranscriptLabel::FriBeta).map(|event| &event.values).collect();
+    assert_eq!(rounds.iter().map(|round| round.beta.as_ref().unwrap()).collect::<Vec<_>>(), betas);
+
+    let element = |coefficients: &[String]| -> BabyBearChallenge {
+        let coefficients: Vec<BabyBear> = coefficients.iter().map(|c| BabyBear::from_u32(c.parse().unwrap())).collect();
+        <BabyBearChallenge as BasedVectorSpace<BabyBear>>::from_basis_coefficients_slice(&coefficients).unwrap()
+    };
+    // Two queries opening each other's positions give both values of a pair, and the value they fold to is the
+    // sibling a third query opens in the next round
+    let replayed = rounds.windows(2).find_map(|pair| {
+        let [round, next] = pair else { unreachable!() };
+        let index = |opening: &FriRoundOpening| opening.index.unwrap();
+        round.openings.iter().find_map(|a| {
+            let b = round.openings.iter().find(|b| index(b) == index(a) ^ 1)?;
+            let c = next.openings.iter().find(|c| index(c) == (index(a) >> 1) ^ 1)?;
+            // b's sibling is a's own value and a's sibling is b's
+            let (own_a, own_b) = (element(&b.sibling_value), element(&a.sibling_value));
+            let (e0, e1) = if index(a) % 2 == 0 { (own_a, own_b) } else { (own_b, own_a) };
+            let log_folded = round.domain_size.ilog2() as usize - 1;
+            let exponent = reverse_bits_len(index(a) >> 1, log_folded) as u64;
+            let x = BabyBear::two_adic_generator(log_folded + 1).exp_u64(exponent);
+            let beta = element(round.beta.as_ref().unwrap());
+            let folded = (e0 + e1) * BabyBear::TWO.inverse() + beta * (e0 - e1) * (x + x).inverse();
+            Some((folded, element(&c.sibling_value)))
+        })
+    });
+    let (folded, opened) = replayed.expect("no query pair with a sibling opened in the next round");
+    assert_eq!(folded, opened);
+}
+
+#[test]
+fn quotient_chunks_recombine_to_the_folded_constraints() {
+    type Challenge = <M31KeccakRecordingConfig as StarkGenericConfig>::Challenge;
+    let num_steps = 64;
+    let config = ConfigBuilder::new().num_steps(num_steps).build_recording().unwrap();
+    let air = FibonacciAir::new(num_steps);
+    let final_value = fibonacci_final_value(FieldChoice::Mersenne31, num_steps, FIBONACCI_START);
+    let public_values = air.public_values::<Mersenne31>(final_value);
+    let proof = prove(&config, &air, generate_fibonacci_trace::<Mersenne31>(num_steps, FIBONACCI_START), &public_values);
+    let mut transcript = config.initialise_challenger().take_transcript();
+
+    let quotient = quotient_opening(&config, &air, &proof, &public_values, &transcript).unwrap();
+    assert!(quotient.num_chunks >= 1);
+    assert_eq!(quotient.chunks.len(), quotient.num_chunks);
+    // One column per coefficient of the challenge field, each opened at zeta to a challenge field element
+    assert!(quotient.chunks.iter().all(|chunk| chunk.len() == M31_CHALLENGE_DEGREE * M31_CHALLENGE_DEGREE));
+    let committed = transcript.iter().find(|event| event.label == TranscriptLabel::QuotientCommitment).unwrap();
+    assert_eq!(committed.values, [quotient.root.clone()]);
+
+    let element = |coefficients: &[String]| -> Challenge {
+        let coefficients: Vec<Mersenne31> = coefficients.iter().map(|c| Mersenne31::from_u32(c.parse().unwrap())).collect();
+        <Challenge as BasedVectorSpace<Mersenne31>>::from_basis_coefficients_slice(&coefficients).unwrap()
+    };
+    let check = quotient.check.unwrap();
+    let recorded = |label: TranscriptLabel| transcript.iter().find(|event| event.label == label).unwrap().values.clone();
+    assert_eq!(check.alpha, recorded(TranscriptLabel::Alpha));
+    assert_eq!(check.zeta, recorded(TranscriptLabel::Zeta));
+    assert_ne!(element(&check.vanishing), Challenge::ZERO);
+    assert_eq!(element(&check.quotient) * element(&check.vanishing), element(&check.folded_constraints));
+
+    // Folded with any other alpha, the constraints no longer match the committed quotient
+    let alpha = transcript.iter_mut().find(|event| event.label == TranscriptLabel::Alpha).unwrap();
+    alpha.values[0] = ((alpha.values[0].parse::<u32>().unwrap() + 1) % Mersenne31::ORDER_U32).to_string();
+    let check = quotient_opening(&config, &air, &proof, &public_values, &transcript).unwrap().check.unwrap();
+    assert_ne!(element(&check.quotient) * element(&check.vanishing), element(&check.folded_constraints));
+}
+
+#[test]
+fn ood_point_is_the_zeta_the_verifier_derives() {
+    let num_steps = 32;
+    let config = ConfigBuilder::new().num_steps(num_steps).build_recording().unwrap();
+    let air = FibonacciAir::new(num_steps);
+    let final_value = fibonacci_final_value(FieldChoice::Mersenne31, num_steps, FIBONACCI_START);
+    let public_values = air.public_values::<Mersenne31>(final_value);
+    let proof = prove(&config, &air, generate_fibonacci_trace::<Mersenne31>(num_steps, FIBONACCI_START), &public_values);
+    let ood = ood_opening(&config, &proof, &config.initialise_challenger().take_transcript()).unwrap();
+
+    // The verifier draws zeta again from the proof's commitments alone
+    verify(&config, &air, &proof, &public_values).unwrap();
+    let derived = config.initialise_challenger().take_transcript();
+    let zeta = derived.iter().find(|event| event.label == TranscriptLabel::Zeta).unwrap();
+    assert_eq!(ood.zeta.as_ref(), Some(&zeta.values));
+    let zeta_next = ood.zeta_next.unwrap();
+    assert_eq!(zeta_next.len(), M31_CHALLENGE_DEGREE);
+    assert_ne!(zeta_next, zeta.values);
+
+    // One value per column at each point, three base field coefficients each
+    for values in [&ood.trace_local, &ood.trace_next] {
+        assert_eq!(values.len(), 2);
+        for value in values {
+            assert_eq!(value.len(), M31_CHALLENGE_DEGREE);
+            assert!(value.iter().all(|c| c.parse::<u32>().unwrap() < Mersenne31::ORDER_U32), "{value:?}");
+        }
+    }
+    assert!(ood.preprocessed_local.is_empty() && ood.preprocessed_next.is_empty());
+}
+
+#[test]
+fn profile_nests_spans_per_thread_and_loads_before_it_is_closed() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("profile/chrome_trace.json");
+    let (layer, guard) = chrome_trace(&path).unwrap();
+    let dispatch = Dispatch::new(Registry::default().with(layer));
+    tracing::dispatcher::with_default(&dispatch, || {
+        info_span!("proving").in_scope(|| {
+            info_span!("commit to trace data").in_scope(|| {});
+            let dispatch = dispatch.clone();
+            let worker = std::thread::Builder::new().name("worker".to_string()).spawn(move || {
+                tracing::dispatcher::with_default(&dispatch, || info_span!("compute quotient").in_scope(|| {}))
+            });
+            worker.unwrap().join().unwrap();
+        });
+    });
+
+    let events = |json: &str| -> Vec<Value> { serde_json::from_str::<Value>(json).unwrap().as_array().unwrap().clone() };
+    // Until the guard closes it the array has no closing bracket, which the format allows
+    let open = fs::read_to_string(&path).unwrap();
+    assert!(!open.trim_end().ends_with(']'));
+    let before = events(&format!("{open}\n]"));
+    drop(guard);
+    let closed = events(&fs::read_to_string(&path).unwrap());
+    assert_eq!(closed, before);
+
+    let slices = |thread: &Value| -> Vec<(String, String)> {
+        let on_thread = closed.iter().filter(|e| &e["tid"] == thread && e["ph"] != "M");
+        on_thread.map(|e| (e["ph"].as_str().unwrap().to_string(), e["name"].as_str().unwrap().to_string())).collect()
+    };
+    let thread_of = |name: &str| {
+        let named = closed.iter().find(|e| e["ph"] == "M" && e["args"]["name"] == name);
+        named.unwrap_or_else(|| panic!("no thread {name}"))["tid"].clone()
+    };
+    let slice = |ph: &str, name: &str| (ph.to_string(), name.to_string());
+    let main = &closed[0]["tid"];
+    assert_eq!(
+        slices(main),
+        [slice("B", "proving"), slice("B", "commit to trace data"), slice("E", "commit to trace data"), slice("E", "proving")]
+    );
+    let worker = thread_of("worker");
+    assert_ne!(&worker, main);
+    assert_eq!(slices(&worker), [slice("B", "compute quotient"), slice("E", "compute quotient")]);
+    let ts: Vec<f64> = closed.iter().filter_map(|e| e["ts"].as_f64()).collect();
+    assert!(ts.windows(2).all(|w| w[0] <= w[1]), "{ts:?}");
+    assert!(closed.iter().all(|e| e["pid"] == std::process::id()));
+}
+
+#[test]
+fn circle_lde_shares_no_row_with_the_trace() {
+    let trace = generate_fibonacci_trace::<Mersenne31>(16, FIBONACCI_START);
+    let cells: Vec<Vec<String>> = trace.values.chunks(2).map(|row| row.iter().map(|v| v.to_string()).collect()).collect();
+
+    // Without blowup the domain is the trace's own
+    let lde = trace_lde(FieldChoice::Mersenne31, &trace, 0).unwrap();
+    assert_eq!(lde.rows, cells);
+    assert_eq!(lde.trace_rows, (0..16).map(|row| (row, row)).collect::<Vec<_>>());
+    for log_blowup in 1..=3 {
+        let lde = trace_lde(FieldChoice::Mersenne31, &trace, log_blowup).unwrap();
+        assert_eq!((lde.log_blowup, lde.rows.len()), (log_blowup, 16 << log_blowup));
+        assert!(lde.rows.iter().all(|row| row.len() == 2));
+        assert!(lde.trace_rows.is_empty(), "{log_blowup}");
+        assert_ne!(lde.rows[..16], cells[..]);
+    }
+}
+
+#[cfg(feature = "babybear")]
+#[test]
+fn two_adic_lde_rows_shared_with_the_trace_hold_its_values() {
+    let trace = generate_fibonacci_trace::<Mersenne31>(16, FIBONACCI_START);
+    let cells: Vec<Vec<String>> = trace.values.chunks(2).map(|row| row.iter().map(|v| v.to_string()).collect()).collect();
+    let ldes: Vec<Lde> = (0..=3).map(|log_blowup| trace_lde(FieldChoice::BabyBear, &trace, log_blowup).unwrap()).collect();
+    for lde in &ldes {
+        assert_eq!(lde.rows.len(), 16 << lde.log_blowup);
+        assert_eq!(lde.trace_rows.len(), 16);
+        for &(lde_row, trace_row) in &lde.trace_rows {
+            assert_eq!(lde_row, trace_row << lde.log_blowup);
+            assert_eq!(lde.rows[lde_row], cells[trace_row], "row {lde_row} at log_blowup {}", lde.log_blowup);
+        }
+    }
+    // The subgroups are nested, so a smaller blowup's LDE is every other row of the next one
+    for w in ldes.windows(2) {
+        assert_eq!(w[1].rows.iter().step_by(2).cloned().collect::<Vec<_>>(), w[0].rows);
+    }
+}
+
+#[test]
+fn selectors_mark_exactly_the_rows_the_constraints_apply_to() {
+    let vis = prove_fibonacci(FibonacciParams::new(16)).unwrap().vis_data;
+    let selectors = vis.selectors.unwrap();
+    assert_eq!(selectors.kinds, Selectors::KINDS);
+    assert_eq!(selectors.rows.len(), 16);
+    assert_eq!(selectors.lde_rows, None);
+    let column = |kind| selectors.kinds.iter().position(|&k| k == kind).unwrap();
+    let marked = |kind| selectors.rows.iter().filter(|row| row[column(kind)] == 1).count();
+    assert_eq!(marked(ConstraintKind::FirstRow), 1);
+    assert_eq!(marked(ConstraintKind::LastRow), 1);
+    assert_eq!(marked(ConstraintKind::Transition), 15);
+    assert_eq!(marked(ConstraintKind::EveryRow), 16);
+    assert!(selectors.rows.iter().flatten().all(|&v| v <= 1));
+    assert_eq!(selectors.rows[0][column(ConstraintKind::FirstRow)], 1);
+    assert_eq!(selectors.rows[15][column(ConstraintKind::LastRow)], 1);
+
+    // A constraint is evaluated on a row exactly where its kind's selector is 1
+    for (row, (evals, marks)) in vis.constraint_evals.iter().zip(&selectors.rows).enumerate() {
+        for (c, constraint) in vis.constraints.iter().enumerate() {
+            assert_eq!(!evals[c].is_empty(), marks[column(constraint.kind)] == 1, "row {row}, constraint {c}");
+        }
+    }
+    // The kinds are written as the constraints' are
+    let json = serde_json::to_value(&vis.constraints).unwrap();
+    let kinds = serde_json::to_value(&selectors.kinds).unwrap();
+    assert!(json.as_array().unwrap().iter().all(|c| kinds.as_array().unwrap().contains(&c["kind"])));
+}
+
+#[test]
+fn windows_pair_every_row_with_the_next_one_of_the_trace() {
+    // Any width: a linear recurrence over 5 columns
+    let k = 5;
+    let (coeffs, initial): (Vec<u64>, Vec<u64>) = (vec![1; k], (0..k as u64).collect());
+    let trace = generate_linear_recurrence_trace::<Mersenne31>(16, &coeffs, &initial);
+    let vis = vis_data_from_matrix(&trace, &linear_recurrence_columns(k)).unwrap();
+    assert_eq!(vis.windows, None);
+    let windows = vis.with_windows().windows.unwrap();
+    assert_eq!(windows.len(), 16);
+    let row = |i: usize| -> Vec<String> { trace.row_slice(i).unwrap().iter().map(|v| v.to_string()).collect() };
+    for (i, window) in windows.iter().enumerate() {
+        assert_eq!(window.local, row(i), "row {i}");
+        assert_eq!(window.next, row((i + 1) % 16), "row {i}");
+        assert_eq!(window.terminal, i == 15, "row {i}");
+    }
+}
+
+#[test]
+fn keyframes_replay_into_the_final_trace() {
+    // A padded trace, so the rows after the real ones are revealed too
+    let vis = prove_fibonacci(FibonacciParams::new(10)).unwrap().vis_data;
+    assert_eq!(vis.keyframes, None);
+    let of_kind = |kind| (0..vis.constraints.len()).filter(|&i| vis.constraints[i].kind == kind).collect::<Vec<_>>();
+    let (first_row, transition) = (of_kind(ConstraintKind::FirstRow), of_kind(ConstraintKind::Transition));
+    assert!(!first_row.is_empty() && !transition.is_empty());
+    for frames in [1, 3, 5, 16, 100] {
+        let keyframes = vis.clone().with_keyframes(frames).keyframes.unwrap();
+        assert_eq!(keyframes.len(), frames.min(16), "{frames}");
+        assert_eq!(replay_keyframes(&keyframes), vis.trace, "{frames}");
+
+        // Deltas only: each keyframe starts where the one before it ended, and the sizes differ by one row at most
+        let mut row = 0;
+        for frame in &keyframes {
+            assert_eq!(frame.row, row, "{frames}");
+            row += frame.rows.len();
+        }
+        let sizes: Vec<_> = keyframes.iter().map(|frame| frame.rows.len()).collect();
+        assert!(sizes.iter().max().unwrap() - sizes.iter().min().unwrap() <= 1, "{sizes:?}");
+
+        // Row 0 comes from the first-row constraints, every row after it from the transition ones
+        let first = &keyframes[0].constraints;
+        assert!(first_row.iter().all(|i| first.contains(i)), "{first:?}");
+        assert_eq!(transition.iter().all(|i| first.contains(i)), keyframes[0].rows.len() > 1, "{frames}");
+        assert!(keyframes[1..].iter().all(|frame| frame.constraints == transition), "{frames}");
+    }
+
+    // They survive the JSON export, and an export without them loads as before
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("trace_data.json");
+    write_trace_json(&path, &vis.clone().with_keyframes(4)).unwrap();
+    let loaded = load_vis_data(&path).unwrap().keyframes.unwrap();
+    assert_eq!(replay_keyframes(&loaded), vis.trace);
+    write_trace_json(&path, &vis).unwrap();
+    assert_eq!(load_vis_data(&path).unwrap().keyframes, None);
+}
+
+#[test]
+fn circle_selectors_have_no_zero_off_the_trace_domain() {
+    for log_blowup in 1..=2 {
+        let rows = selector_lde(FieldChoice::Mersenne31, 16, log_blowup).unwrap();
+        assert_eq!(rows.len(), 16 << log_blowup);
+        assert!(rows.iter().all(|row| row.len() == Selectors::KINDS.len() && row[0] == "1"));
+        assert!(rows.iter().flatten().all(|v| v != "0"), "{log_blowup}");
+    }
+    // No blowup puts every point on the trace's domain, where the circle selectors divide by zero
+    assert!(matches!(selector_lde(FieldChoice::Mersenne31, 16, 0), Err(FibVisError::Config(_))));
+}
+
+#[cfg(feature = "babybear")]
+#[test]
+fn two_adic_selectors_vanish_on_the_trace_rows_they_do_not_select() {
+    use p3_baby_bear::BabyBear;
+    use p3_field::TwoAdicField;
+
+    let log_blowup = 2;
+    let rows = selector_lde(FieldChoice::BabyBear, 16, log_blowup).unwrap();
+    assert_eq!(rows.len(), 16 << log_blowup);
+    let (first, transition, last) = (1, 2, 3);
+    let nonzero = |column: usize| -> Vec<usize> { (0..rows.len()).filter(|&r| rows[r][column] != "0").collect() };
+    // Every LDE row off the trace's subgroup is nonzero in the Lagrange selectors, on it only the selected row is
+    let on_trace = |r: &usize| r % (1 << log_blowup) == 0;
+    assert_eq!(nonzero(first).into_iter().filter(on_trace).collect::<Vec<_>>(), [0]);
+    assert_eq!(nonzero(last).into_iter().filter(on_trace).collect::<Vec<_>>(), [15 << log_blowup]);
+    assert_eq!(nonzero(first).len(), rows.len() - 15);
+    let zeros: Vec<usize> = (0..rows.len()).filter(|r| !nonzero(transition).contains(r)).collect();
+    assert_eq!(zeros, [15 << log_blowup]);
+
+    // The transition selector is x - g^(-1) at the point of the row
+    let point = BabyBear::two_adic_generator(4 + log_blowup).exp_u64(5);
+    let expected = point - BabyBear::two_adic_generator(4).inverse();
+    assert_eq!(rows[5][transition], expected.to_string());
+}
+
+#[test]
+fn csv_export_parses_back_to_the_trace() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("trace_data.csv");
+    // A wide trace: 30 columns named x0, ..., x29
+    let k = 30;
+    let (coeffs, initial): (Vec<u64>, Vec<u64>) = (vec![1; k], (0..k as u64).collect());
+    let trace = generate_linear_recurrence_trace::<Mersenne31>(64, &coeffs, &initial);
+    write_trace_csv(&path, &vis_data_from_matrix(&trace, &linear_recurrence_columns(k)).unwrap()).unwrap();
+
+    let csv = fs::read_to_string(&path).unwrap();
+    let mut lines = csv.lines();
+    let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+    assert_eq!(header[0], "step");
+    assert_eq!(header[1..], column_names(&linear_recurrence_columns(k))[..]);
+    let mut rows = 0;
+    for (step, line) in lines.enumerate() {
+        let fields: Vec<&str> = line.split(',').collect();
+        assert_eq!(fields[0], step.to_string());
+        let values: Vec<Mersenne31> = fields[1..].iter().map(|v| Mersenne31::from_u32(v.parse().unwrap())).collect();
+        assert_eq!(values, trace.row_slice(step).unwrap().to_vec(), "row {step}");
+        rows += 1;
+    }
+    assert_eq!(rows, 64);
+}
+
+#[test]
+fn repr_writes_cells_as_decimal_hex_or_both() {
+    let dir = tempfile::tempdir().unwrap();
+    let trace = generate_fibonacci_trace::<Mersenne31>(8, FIBONACCI_START);
+    let field = FieldInfo::new(FieldChoice::Mersenne31);
+    let vis = VisData { field, ..vis_data_from_matrix(&trace, &FIBONACCI_COLUMNS).unwrap() };
+    // Row 7 is (13, 21), hex digits padded to the 31-bit field's 8 nibbles
+    let expected = [
+        (ValueRepr::Decimal, serde_json::json!("13"), "step,a,b", "7,13,21"),
+        (ValueRepr::Hex, serde_json::json!("0x0000000d"), "step,a,b", "7,0x0000000d,0x00000015"),
+        (
+            ValueRepr::Both,
+            serde_json::json!({"dec": "13", "hex": "0x0000000d"}),
+            "step,a,a_hex,b,b_hex",
+            "7,13,0x0000000d,21,0x00000015",
+        ),
+    ];
+    for (repr, cell, csv_header, csv_row) in expected {
+        let vis = vis.clone().with_repr(repr);
+        let json_path = dir.path().join("trace_data.json");
+        write_trace_json(&json_path, &vis).unwrap();
+        let text = fs::read_to_string(&json_path).unwrap();
+        let json: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(json["trace"][7][0], cell, "{repr:?}");
+        // Read back, every representation gives the decimal cells again
+        assert_eq!(serde_json::from_str::<VisData>(&text).unwrap().trace, vis.trace, "{repr:?}");
+
+        let csv_path = dir.path().join("trace_data.csv");
+        write_trace_csv(&csv_path, &vis).unwrap();
+        let csv = fs::read_to_string(&csv_path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!((lines[0], lines[8]), (csv_header, csv_row), "{repr:?}");
+
+        let table = trace_table(&vis, 20, 120);
+        let last = table.lines().last().unwrap();
+        match repr {
+            ValueRepr::Decimal => assert!(last.ends_with("13  21"), "{table}"),
+            ValueRepr::Hex => assert!(last.ends_with("0x0000000d  0x00000015"), "{table}"),
+            ValueRepr::Both => assert!(last.ends_with("13 (0x0000000d)  21 (0x00000015)"), "{table}"),
+        }
+    }
+}
+
+#[test]
+fn csv_export_quotes_names_and_handles_an_empty_trace() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("empty.csv");
+    let empty = RowMajorMatrix::<Mersenne31>::new(Vec::new(), 2);
+    let columns = [ColumnMeta::new("a,b", "a name with a comma"), ColumnMeta::new("say \"b\"", "a name with quotes")];
+    write_trace_csv(&path, &vis_data_from_matrix(&empty, &columns).unwrap()).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "step,\"a,b\",\"say \"\"b\"\"\"\n");
+}
+
+#[test]
+fn ndjson_export_streams_a_header_and_one_line_per_row() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("trace_data.ndjson");
+    let trace = generate_fibonacci_trace::<Mersenne31>(1 << 10, FIBONACCI_START);
+    let mut vis = vis_data_from_matrix(&trace, &FIBONACCI_COLUMNS).unwrap();
+    vis.field = FieldInfo::new(FieldChoice::Mersenne31);
+    write_trace_ndjson(&path, &vis).unwrap();
+
+    let ndjson = fs::read_to_string(&path).unwrap();
+    let mut lines = ndjson.lines();
+    let header: Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+    assert_eq!(header["num_steps"], 1 << 10);
+    assert_eq!(header["columns"][1]["name"], "b");
+    assert_eq!(header["field"]["modulus"], "2147483647");
+    assert!(header.get("trace").is_none(), "the rows must not be repeated in the header");
+    let mut rows = 0;
+    for (step, line) in lines.enumerate() {
+        let row: Value = serde_json::from_str(line).unwrap();
+        assert_eq!(row["step"], step);
+        let values = row["values"].as_array().unwrap().iter();
+        let values: Vec<Mersenne31> = values.map(|v| Mersenne31::from_u32(v.as_str().unwrap().parse().unwrap())).collect();
+        assert_eq!(values, trace.row_slice(step).unwrap().to_vec(), "row {step}");
+        rows += 1;
+    }
+    assert_eq!(rows, 1 << 10);
+}
+
+#[test]
+fn ndjson_export_of_an_empty_trace_is_just_the_header() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("empty.ndjson");
+    let empty = RowMajorMatrix::<Mersenne31>::new(Vec::new(), 2);
+    write_trace_ndjson(&path, &vis_data_from_matrix(&empty, &FIBONACCI_COLUMNS).unwrap()).unwrap();
+    let ndjson = fs::read_to_string(&path).unwrap();
+    assert_eq!(ndjson.lines().count(), 1);
+    assert!(ndjson.ends_with('\n'));
+    let header: Value = serde_json::from_str(&ndjson).unwrap();
+    assert_eq!(header["columns"].as_array().unwrap().len(), 2);
+}
+
+// tests/fixtures/trace_v<N>*.json, one or more exports as schema version N wrote them.
+fn schema_fixtures() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut fixtures: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.file_name().unwrap().to_string_lossy().starts_with("trace_v"))
+        .collect();
+    fixtures.sort();
+    fixtures
+}
+
+#[test]
+fn every_schema_fixture_loads_into_the_current_struct() {
+    let fixtures = schema_fixtures();
+    let versions: Vec<u32> = fixtures
+        .iter()
+        .map(|path| {
+            let stem = path.file_stem().unwrap().to_string_lossy().into_owned();
+            stem["trace_v".len()..].split('_').next().unwrap().parse().unwrap()
+        })
+        .collect();
+    for version in 1..=SCHEMA_VERSION {
+        assert!(versions.contains(&version), "no fixture for schema version {version}");
+    }
+    let trace = generate_fibonacci_trace::<Mersenne31>(8, FIBONACCI_START);
+    let expected = vis_data_from_matrix(&trace, &FIBONACCI_COLUMNS).unwrap();
+    for path in fixtures {
+        let vis = load_vis_data(&path).unwrap_or_else(|e| panic!("{e}"));
+        let name = path.display();
+        assert_eq!(vis.schema_version, SCHEMA_VERSION, "{name}");
+        assert_eq!((vis.num_steps, vis.final_value), (8, 21), "{name}");
+        assert_eq!(vis.trace, expected.trace, "{name}");
+        assert_eq!(vis.columns, FIBONACCI_COLUMNS, "{name}");
+        assert_eq!(vis.sequence, "Fibonacci", "{name}");
+    }
+
+    // A bare field name becomes the field's metadata
+    let named = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/trace_v1_named_columns.json");
+    assert_eq!(load_vis_data(&named).unwrap().field, FieldInfo::new(FieldChoice::Mersenne31));
+}
+
+#[test]
+fn current_schema_fixture_has_the_shape_of_a_fresh_export() {
+    // A field added to or removed from VisData shows here: bump SCHEMA_VERSION and add a fixture for it
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("tests/fixtures/trace_v{SCHEMA_VERSION}.json"));
+    let fixture: Value = serde_json::from_str(&fs::read_to_string(fixture).unwrap()).unwrap();
+    let fresh = serde_json::to_value(prove_fibonacci(FibonacciParams::new(8)).unwrap().vis_data).unwrap();
+    let keys = |doc: &Value| doc.as_object().unwrap().keys().cloned().collect::<Vec<_>>();
+    assert_eq!(keys(&fixture), keys(&fresh));
+    assert_eq!(fresh["schema_version"], SCHEMA_VERSION);
+    for block in ["field", "fri", "air_info", "degree_report", "stats"] {
+        assert_eq!(keys(&fixture[block]), keys(&fresh[block]), "{block}");
+    }
+}
+
+#[test]
+fn json_export_validates_against_the_emitted_schema() {
+    let schema = vis_data_schema();
+    let validator = jsonschema::JSONSchema::compile(&schema).unwrap_or_else(|e| panic!("invalid schema: {e}"));
+    let validate = |json: &Value, what: &str| {
+        if let Err(errors) = validator.validate(json) {
+            let errors: Vec<String> = errors.map(|e| format!("{}: {e}", e.instance_path)).collect();
+            panic!("{what} does not match the schema:\n{}", errors.join("\n"));
+        }
+    };
+
+    // A proven run fills in every optional block: stats, constraints, air_info and the field metadata
+    let dir = tempfile::tempdir().unwrap();
+    let vis = prove_fibonacci(FibonacciParams::new(8)).unwrap().vis_data;
+    for repr in [ValueRepr::Decimal, ValueRepr::Hex, ValueRepr::Both] {
+        let path = dir.path().join("trace_data.json");
+        write_trace_json(&path, &vis.clone().with_repr(repr)).unwrap();
+        validate(&serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap(), &format!("the {repr:?} export"));
+    }
+    let unproven = vis_data_from_matrix(&generate_fibonacci_trace::<Mersenne31>(8, FIBONACCI_START), &FIBONACCI_COLUMNS);
+    validate(&serde_json::to_value(unproven.unwrap()).unwrap(), "an export without stats");
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("tests/fixtures/trace_v{SCHEMA_VERSION}.json"));
+    validate(&serde_json::from_str(&fs::read_to_string(fixture).unwrap()).unwrap(), "the current fixture");
+
+    // ... and it is not so loose that anything goes
+    let mut broken = serde_json::to_value(&vis).unwrap();
+    broken["trace"][0][0] = serde_json::json!(true);
+    assert!(!validator.is_valid(&broken));
+    assert!(!validator.is_valid(&serde_json::json!({"num_steps": 8})));
+    assert_eq!(schema["properties"]["schema_version"]["type"], "integer");
+}
+
+#[test]
+fn typescript_declarations_match_the_snapshot() {
+    // A change to VisData changes the declarations: regenerate them with
+    // `cargo run -- --emit-ts src/web/trace_data.d.ts` and review the diff
+    let snapshot = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/web/trace_data.d.ts");
+    assert_eq!(vis_data_typescript(), fs::read_to_string(snapshot).unwrap());
+
+    let ts = vis_data_typescript();
+    assert!(ts.contains("    trace: Cell[][];\n"), "{ts}");
+    assert!(ts.contains("export type Cell = number | string | { dec: string; hex: string };\n"), "{ts}");
+    assert!(ts.contains("    stats?: ProofStats | null;\n"), "{ts}");
+}
+
+#[test]
+fn export_loads_back_equal_to_the_in_memory_vis_data() {
+    let dir = tempfile::tempdir().unwrap();
+    let params = FibonacciParams { sequence: Sequence::BitDecomp, ..FibonacciParams::new(16) };
+    let vis = prove_fibonacci(params).unwrap().vis_data;
+
+    let path = dir.path().join("trace_data.json");
+    write_trace_json(&path, &vis).unwrap();
+    assert_eq!(VisData::from_path(&path).unwrap(), vis);
+
+    let gz = dir.path().join("trace_data.json.gz");
+    write_trace_gz(&vis, TraceFormat::Json, &gz).unwrap();
+    assert_eq!(VisData::from_path(&gz).unwrap(), vis);
+
+    let err = VisData::from_path(&dir.path().join("missing.json")).unwrap_err();
+    assert!(matches!(err, FibVisError::Io { .. }), "unexpected error: {err}");
+}
+
+// The names of the files in `dir`
+fn file_names(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect()
+}
+
+#[test]
+fn json_export_creates_its_directory_and_replaces_an_older_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let web = dir.path().join("web").join("runs");
+    let path = web.join("trace_data.json");
+    let long = vis_data_from_matrix(&generate_fibonacci_trace::<Mersenne31>(16, FIBONACCI_START), &FIBONACCI_COLUMNS);
+    write_trace_json(&path, &long.unwrap()).unwrap();
+
+    // The shorter export replaces the file as a whole, with no tail of the longer one and no temporary file left
+    let short = vis_data_from_matrix(&generate_fibonacci_trace::<Mersenne31>(8, FIBONACCI_START), &FIBONACCI_COLUMNS);
+    let short = short.unwrap();
+    write_trace_json(&path, &short).unwrap();
+    assert_eq!(VisData::from_path(&path).unwrap(), short);
+    assert_eq!(file_names(&web), ["trace_data.json"]);
+}
+
+#[test]
+fn export_under_a_file_is_an_export_error_with_the_io_exit_code() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("web");
+    fs::write(&file, "not a directory").unwrap();
+    let vis = vis_data_from_matrix(&generate_fibonacci_trace::<Mersenne31>(8, FIBONACCI_START), &FIBONACCI_COLUMNS);
+
+    let err = write_trace_json(&file.join("trace_data.json"), &vis.unwrap()).unwrap_err();
+    assert!(matches!(&err, FibVisError::Export { path, source: ExportError::Io(_) } if *path == file), "{err}");
+    assert_eq!(err.exit_code(), 3);
+    assert!(err.to_string().contains("web"), "{err}");
+}
+
+#[cfg(unix)]
+#[test]
+fn json_export_into_a_read_only_directory_fails_and_keeps_the_old_file() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("trace_data.json");
+    let vis = vis_data_from_matrix(&generate_fibonacci_trace::<Mersenne31>(8, FIBONACCI_START), &FIBONACCI_COLUMNS);
+    let vis = vis.unwrap();
+    write_trace_json(&path, &vis).unwrap();
+    let before = fs::read(&path).unwrap();
+
+    fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o555)).unwrap();
+    // Root writes regardless of the permission bits, there is nothing to check then
+    let writable = fs::write(dir.path().join("probe"), "").is_ok();
+    let result = (!writable).then(|| write_trace_json(&path, &vis.clone().with_tampered(vec![(3, 1)])));
+    fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o755)).unwrap();
+    let Some(result) = result else { return };
+
+    let err = result.unwrap_err();
+    assert!(matches!(&err, FibVisError::Export { path: p, source: ExportError::Io(_) } if *p == path), "{err}");
+    assert_eq!(err.exit_code(), 3);
+    assert_eq!(fs::read(&path).unwrap(), before);
+    assert_eq!(file_names(dir.path()), ["trace_data.json"]);
+}
+
+#[test]
+fn load_vis_data_reads_every_format_and_rejects_unknown_versions() {
+    let dir = tempfile::tempdir().unwrap();
+    let vis = prove_fibonacci(FibonacciParams::new(16)).unwrap().vis_data;
+    for (format, extension) in [(TraceFormat::Json, "json"), (TraceFormat::Msgpack, "msgpack"), (TraceFormat::Cbor, "cbor")] {
+        let path = dir.path().join(format!("trace_data.{extension}"));
+        write_trace(&vis, format, &path).unwrap();
+        let loaded = load_vis_data(&path).unwrap();
+        assert_eq!((loaded.schema_version, &loaded.trace), (SCHEMA_VERSION, &vis.trace), "{format:?}");
+    }
+
+    let path = dir.path().join("future.json");
+    fs::write(&path, r#"{"schema_version": 99, "num_steps": 8, "final_value": 21, "trace": []}"#).unwrap();
+    let err = load_vis_data(&path).unwrap_err();
+    assert!(matches!(&err, FibVisError::SchemaVersion { found, .. } if found == "99"), "unexpected error: {err}");
+    assert!(err.to_string().contains(&format!("reads versions 1 to {SCHEMA_VERSION}")), "{err}");
+    assert_eq!(err.exit_code(), 4);
+}
+
+#[test]
+fn json_csv_and_msgpack_exports_read_back_as_the_trace_matrix() {
+    let dir = tempfile::tempdir().unwrap();
+    // Past step 47 the values wrap around the Mersenne31 modulus
+    let trace = generate_fibonacci_trace::<Mersenne31>(64, FIBONACCI_START);
+    let rows: Vec<Vec<u32>> = (0..trace.height())
+        .map(|i| trace.row_slice(i).unwrap().iter().map(|v| v.as_canonical_u32()).collect())
+        .collect();
+    let vis = vis_data_from_matrix(&trace, &FIBONACCI_COLUMNS).unwrap();
+    let cells = |trace: &[Vec<String>]| -> Vec<Vec<u32>> {
+        trace.iter().map(|row| row.iter().map(|cell| cell.parse().unwrap()).collect()).collect()
+    };
+
+    for format in [TraceFormat::Json, TraceFormat::Msgpack] {
+        let path = dir.path().join(format!("trace_data.{}", format.exporter().extension()));
+        write_trace(&vis, format, &path).unwrap();
+        assert_eq!(cells(&load_vis_data(&path).unwrap().trace), rows, "{format:?}");
+    }
+
+    let path = dir.path().join("trace_data.csv");
+    write_trace(&vis, TraceFormat::Csv, &path).unwrap();
+    let csv = fs::read_to_string(&path).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("step,a,b"));
+    let csv_rows: Vec<Vec<u32>> = lines
+        .enumerate()
+        .map(|(i, line)| {
+            let mut fields = line.split(',').map(|field| field.parse::<u32>().unwrap());
+            assert_eq!(fields.next(), Some(i as u32), "step of line {i}");
+            fields.collect()
+        })
+        .collect();
+    assert_eq!(csv_rows, rows);
+}
+
+#[test]
+fn msgpack_export_round_trips_with_integer_cells() {
+    let run = prove_fibonacci(FibonacciParams::new(64)).unwrap();
+    let bytes = TraceFormat::Msgpack.exporter().serialize(&run.vis_data).unwrap();
+    let json = TraceFormat::Json.exporter().serialize(&run.vis_data).unwrap();
+    assert!(bytes.len() < json.len(), "{} bytes of MessagePack, {} of JSON", bytes.len(), json.len());
+
+    // The cells are MessagePack integers, everything else is as in the JSON
+    let raw: Value = rmp_serde::from_slice(&bytes).unwrap();
+    assert_eq!(raw["trace"][7][1], 21);
+    assert_eq!(raw["public_values"][2], run.vis_data.public_values[2]);
+
+    let decoded = read_trace_msgpack(&bytes).unwrap();
+    assert_eq!(decoded.trace, run.vis_data.trace);
+    assert_eq!(serde_json::to_value(&decoded).unwrap(), serde_json::to_value(&run.vis_data).unwrap());
+    // The JSON export decodes into the same VisData
+    let from_json: VisData = serde_json::from_slice(&json).unwrap();
+    assert_eq!(from_json.trace, run.vis_data.trace);
+}
+
+#[test]
+fn cbor_export_round_trips_and_is_smaller_than_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("trace_data.cbor");
+    let run = prove_fibonacci(FibonacciParams::new(256)).unwrap();
+    write_trace(&run.vis_data, TraceFormat::Cbor, &path).unwrap();
+    let bytes = fs::read(&path).unwrap();
+    assert_eq!(TraceFormat::Cbor.exporter().encoded_len(&run.vis_data).unwrap(), bytes.len() as u64);
+    let json_len = TraceFormat::Json.exporter().encoded_len(&run.vis_data).unwrap();
+    assert!((bytes.len() as u64) < json_len, "{} bytes of CBOR, {json_len} of JSON", bytes.len());
+
+    let raw: Value = ciborium::from_reader(&bytes[..]).unwrap();
+    assert_eq!(raw["trace"][7][1], 21);
+    assert_eq!(raw["field"]["name"], "Mersenne31");
+
+    let decoded = read_trace_cbor(&bytes).unwrap();
+    assert_eq!(decoded.trace, run.vis_data.trace);
+    assert_eq!(decoded.columns, run.vis_data.columns);
+    assert_eq!(serde_json::to_value(&decoded).unwrap(), serde_json::to_value(&run.vis_data).unwrap());
+}
+
+#[test]
+fn compressed_exports_decompress_to_the_plain_ones() {
+    let dir = tempfile::tempdir().unwrap();
+    let run = prove_fibonacci(FibonacciParams::new(1 << 10)).unwrap();
+    for format in [TraceFormat::Json, TraceFormat::Ndjson, TraceFormat::Csv] {
+        let extension = format.exporter().extension();
+        let plain = dir.path().join(format!("trace_data.{extension}"));
+        let gz = dir.path().join(format!("trace_data.{extension}.gz"));
+        write_trace(&run.vis_data, format, &plain).unwrap();
+        write_trace_gz(&run.vis_data, format, &gz).unwrap();
+
+        let compressed = fs::read(&gz).unwrap();
+        let mut decompressed = Vec::new();
+        flate2::read::GzDecoder::new(&compressed[..]).read_to_end(&mut decompressed).unwrap();
+        let plain = fs::read(&plain).unwrap();
+        assert!(decompressed == plain, "{extension}: the decompressed export differs");
+        assert!(compressed.len() < plain.len(), "{extension}: {} bytes compressed, {} plain", compressed.len(), plain.len());
+    }
+}
+
+#[test]
+fn binary_export_round_trips_losslessly() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("trace_data.bin");
+    // Values close to the prime, so a lossy encoding would show
+    let (coeffs, initial) = (vec![1, 1, 1], vec![Mersenne31::ORDER_U32 as u64 - 3, 5, Mersenne31::ORDER_U32 as u64 - 1]);
+    let trace = generate_linear_recurrence_trace::<Mersenne31>(256, &coeffs, &initial);
+    let mut vis = vis_data_from_matrix(&trace, &linear_recurrence_columns(3)).unwrap();
+    vis.field = FieldInfo::new(FieldChoice::Mersenne31);
+    write_trace_bin(&path, &vis).unwrap();
+
+    let bytes = fs::read(&path).unwrap();
+    assert_eq!(&bytes[..4], b"P3TR");
+    assert_eq!(bytes.len(), TRACE_BIN_HEADER_LEN + 4 * 256 * 3);
+    assert_eq!(bytes, trace_bin(&trace, 256, FieldChoice::Mersenne31).unwrap());
+
+    let bin = read_trace_bin(&bytes).unwrap();
+    assert_eq!((bin.field, bin.num_steps, bin.width, bin.height()), (FieldChoice::Mersenne31, 256, 3, 256));
+    assert_eq!(bin.values, trace.values.iter().map(|v| v.as_canonical_u32()).collect::<Vec<_>>());
+    assert_eq!(bin.to_matrix::<Mersenne31>(), trace);
+
+    let meta: Value = serde_json::from_str(&fs::read_to_string(dir.path().join("trace_data.meta.json")).unwrap()).unwrap();
+    assert_eq!(meta["width"], 3);
+    assert_eq!(meta["columns"][0]["name"], "x0");
+    assert_eq!(meta["field"]["modulus"], "2147483647");
+
+    // A truncated file or a foreign one is rejected rather than misread
+    assert!(read_trace_bin(&bytes[..bytes.len() - 4]).is_err());
+    assert!(read_trace_bin(b"not a trace at all, really").is_err());
+}
+
+#[test]
+fn html_export_embeds_the_json_safely() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("trace_data.html");
+    let trace = generate_fibonacci_trace::<Mersenne31>(16, FIBONACCI_START);
+    // A column name that would end the <script> element if it were inlined as is
+    let hostile = ColumnMeta::new("</script><script>alert(1)</script>", "a <!-- comment & more \u{2028}");
+    let vis = vis_data_from_matrix(&trace, &[FIBONACCI_COLUMNS[0].clone(), hostile]).unwrap();
+    write_trace(&vis, TraceFormat::Html, &path).unwrap();
+
+    let html = fs::read_to_string(&path).unwrap();
+    let open = r#"<script type="application/json" id="trace-data">"#;
+    let start = html.find(open).unwrap() + open.len();
+    let embedded = &html[start..start + html[start..].find("</script>").unwrap()];
+    assert!(!embedded.contains('<') && !embedded.contains('\u{2028}'));
+    let expected = serde_json::to_value(&vis).unwrap();
+    assert_eq!(serde_json::from_str::<Value>(embedded).unwrap(), expected);
+}
+
+#[test]
+fn html_export_refuses_large_traces() {
+    let trace = generate_fibonacci_trace::<Mersenne31>(2 * HTML_MAX_ROWS, FIBONACCI_START);
+    let vis = vis_data_from_matrix(&trace, &FIBONACCI_COLUMNS).unwrap();
+    let err = TraceFormat::Html.exporter().serialize(&vis).unwrap_err();
+    assert!(err.to_string().contains("ndjson"), "{err}");
+}
+
+#[test]
+fn bundle_pairs_traces_differing_in_exactly_the_tampered_cells() {
+    let dir = tempfile::tempdir().unwrap();
+    let num_steps = 16;
+    let air = FibonacciAir::new(num_steps);
+    let public_values = air.public_values::<Mersenne31>(987);
+    let export = |trace: &RowMajorMatrix<Mersenne31>| {
+        vis_data_from_matrix(trace, &FIBONACCI_COLUMNS).unwrap().with_violations(find_violations(&air, trace, &public_values))
+    };
+    let trace = generate_fibonacci_trace::<Mersenne31>(num_steps, FIBONACCI_START);
+    let mut tampered = trace.clone();
+    let cells = [(5, 1), (9, 0)];
+    for &(row, col) in &cells {
+        tampered.values[row * 2 + col] += Mersenne31::from_u32(1000);
+    }
+    let bundle = TraceBundle::new(export(&trace), export(&tampered).with_tampered(cells.to_vec()));
+    assert!(bundle.valid.violations.is_empty());
+
+    let path = dir.path().join("bundle.json");
+    write_trace_bundle(&path, &bundle).unwrap();
+    let json: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    let (valid, invalid) = (json["valid"]["trace"].as_array().unwrap(), json["invalid"]["trace"].as_array().unwrap());
+    let mut differing = Vec::new();
+    for (row, (v, i)) in valid.iter().zip(invalid).enumerate() {
+        for col in 0..2 {
+            if v[col] != i[col] {
+                differing.push((row, col));
+            }
+        }
+    }
+    assert_eq!(differing, cells);
+
+    // Row 5's b is 8, row 9's a is 34
+    assert_eq!(json["tamper"][0], serde_json::json!({"row": 5, "column": 1, "old_value": "8", "new_value": "1008"}));
+    assert_eq!(json["tamper"][1], serde_json::json!({"row": 9, "column": 0, "old_value": "34", "new_value": "1034"}));
+    assert!(!bundle.violations.is_empty());
+    assert_eq!(json["violations"], json["invalid"]["violations"]);
+    assert!(bundle.violations.iter().all(|v| v.cells.iter().any(|cell| cells.contains(cell))));
+}
+
+#[test]
+fn svg_export_samples_rows_and_marks_violations() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("trace_data.svg");
+    let num_steps = 64;
+    let air = FibonacciWithIndexAir::new(num_steps, FIBONACCI_START);
+    let mut trace = generate_fibonacci_with_index_trace::<Mersenne31>(num_steps, FIBONACCI_START);
+    let final_value = trace.row_slice(num_steps - 1).unwrap()[2].as_canonical_u32();
+    let public_values = air.public_values::<Mersenne31>(final_value.into());
+    trace.values[3 * 3 + 1] = Mersenne31::from_u32(1000);
+    let vis = vis_data_from_matrix(&trace, &FIBONACCI_WITH_INDEX_COLUMNS)
+        .unwrap()
+        .with_constraints(extract_constraints::<Mersenne31, _>(&air, 0, public_values.len()))
+        .with_violations(find_violations(&air, &trace, &public_values));
+    render_svg(&vis, &path, 16).unwrap();
+
+    let svg = fs::read_to_string(&path).unwrap();
+    let doc = roxmltree::Document::parse(&svg).expect("the SVG is not well-formed XML");
+    let with_class = |class: &str| doc.descendants().filter(|n| n.attribute("class") == Some(class)).count();
+    // The first and the last 8 rows, an arrow between each consecutive pair of them, and one gap
+    assert_eq!(with_class("row"), 16);
+    assert_eq!(with_class("transition"), 14);
+    assert_eq!(with_class("elided"), 1);
+    let red: Vec<&str> =
+        doc.descendants().filter(|n| n.attribute("fill") == Some("#b00020")).filter_map(|n| n.text()).collect();
+    assert!(red.contains(&"1000"), "the tampered cell is not marked: {red:?}");
+}
+
+#[test]
+fn cell_records_are_the_trace_cell_by_cell_with_the_violations_joined_in() {
+    let num_steps = 8;
+    let air = FibonacciAir::new(num_steps);
+    let public_values = air.public_values::<Mersenne31>(21);
+    let mut trace = generate_fibonacci_trace::<Mersenne31>(num_steps, FIBONACCI_START);
+    let honest = vis_data_from_matrix(&trace, &FIBONACCI_COLUMNS).unwrap();
+    let records: Vec<CellRecord> = cell_records(&honest).collect();
+    assert_eq!(records.len(), 2 * num_steps);
+    assert_eq!(records[0], CellRecord { step: 0, column: "a".to_string(), value: "0".to_string(), violates: vec![] });
+    assert_eq!((records[15].step, records[15].column.as_str(), records[15].value.as_str()), (7, "b", "21"));
+    assert!(records.iter().all(|record| record.violates.is_empty()));
+
+    // b on row 3 breaks the transitions into and out of row 3, each listed once on the cells they read
+    trace.values[3 * 2 + 1] = Mersenne31::from_u32(7);
+    let vis = vis_data_from_matrix(&trace, &FIBONACCI_COLUMNS)
+        .unwrap()
+        .with_constraints(extract_constraints::<Mersenne31, _>(&air, 0, public_values.len()))
+        .with_violations(find_violations(&air, &trace, &public_values));
+    let records: Vec<CellRecord> = cell_records(&vis).collect();
+    let tampered = &records[3 * 2 + 1];
+    assert_eq!((tampered.step, tampered.column.as_str(), tampered.value.as_str()), (3, "b", "7"));
+    let reading = vis.violations.iter().filter(|v| v.cells.contains(&(3, 1)));
+    let mut expected: Vec<usize> = reading.map(|v| v.constraint).collect();
+    expected.sort_unstable();
+    expected.dedup();
+    assert!(!expected.is_empty());
+    assert_eq!(tampered.violates, expected);
+    assert!(tampered.violates.iter().all(|&c| c < vis.constraints.len()));
+    // Rows 0 and 1 are only read by the transitions that hold
+    assert!(records[..4].iter().all(|record| record.violates.is_empty()));
+
+    // The two projections of the same records
+    let array = TraceFormat::Cells.exporter().serialize(&vis).unwrap();
+    assert_eq!(serde_json::from_slice::<Vec<CellRecord>>(&array).unwrap(), records);
+    let ndjson = TraceFormat::CellsNdjson.exporter().serialize(&vis).unwrap();
+    let lines: Vec<CellRecord> =
+        ndjson.split(|&b| b == b'\n').filter(|l| !l.is_empty()).map(|l| serde_json::from_slice(l).unwrap()).collect();
+    assert_eq!(lines, records);
+    assert_eq!(TraceFormat::Cells.exporter().extension(), "cells.json");
+    let empty = vis_data_from_matrix(&RowMajorMatrix::<Mersenne31>::new(Vec::new(), 2), &FIBONACCI_COLUMNS).unwrap();
+    assert_eq!(TraceFormat::Cells.exporter().serialize(&empty).unwrap(), b"[\n]\n");
+}
+
+#[test]
+fn comparison_deltas_are_each_run_minus_the_first() {
+    let run = |label: &str, params: FibonacciParams| {
+        let run = prove_fibonacci(params.clone()).unwrap();
+        verify_fibonacci(&params, &run.proof).unwrap();
+        RunStats {
+            label: label.to_string(),
+            num_steps: params.num_steps,
+            sequence: run.vis_data.sequence.clone(),
+            field: FieldChoice::Mersenne31,
+            hash: HashChoice::Keccak256,
+            fri: params.fri,
+            trace_path: Some(PathBuf::from(format!("web/trace_data_{label}.json"))),
+            proof_path: None,
+            proof_bytes: Some(run.proof.len()),
+            timings: run.timings,
+            verified: true,
+            error: None,
+        }
+    };
+    let fewer_queries = FriParams { num_queries: 20, ..FriParams::default() };
+    let runs = vec![
+        run("small", FibonacciParams::new(8)),
+        run("large", FibonacciParams::new(64)),
+        run("queries", FibonacciParams { fri: fewer_queries, ..FibonacciParams::new(8) }),
+    ];
+    let comparison = Comparison::new(runs.clone());
+    assert_eq!(comparison.runs, runs);
+    assert_eq!(comparison.deltas.len(), 2);
+
+    for (delta, run) in comparison.deltas.iter().zip(&runs[1..]) {
+        let baseline = &runs[0];
+        assert_eq!((delta.label.as_str(), delta.baseline.as_str()), (run.label.as_str(), "small"));
+        assert_eq!(delta.num_steps, run.num_steps as i64 - 8);
+        let (a, b) = (baseline.proof_bytes.unwrap(), run.proof_bytes.unwrap());
+        assert_eq!(delta.proof_bytes, Some(b as i64 - a as i64));
+        // The delta added back gives the run's numbers
+        assert_eq!((a as i64 + delta.proof_bytes.unwrap()) as usize, b);
+        assert!((delta.proof_bytes_ratio.unwrap() * a as f64 - b as f64).abs() < 1e-6);
+        let (t, d) = (&baseline.timings, &delta.timings);
+        assert_eq!(d.prove_ms, run.timings.prove_ms - t.prove_ms);
+        assert_eq!(d.verify_ms, run.timings.verify_ms - t.verify_ms);
+        assert_eq!(d.trace_gen_ms, run.timings.trace_gen_ms - t.trace_gen_ms);
+        assert!((t.total_ms + d.total_ms - run.timings.total_ms).abs() < 1e-9);
+    }
+    // A longer trace makes a larger proof, fewer queries a smaller one
+    assert!(comparison.deltas[0].proof_bytes.unwrap() > 0);
+    assert!(comparison.deltas[1].proof_bytes_ratio.unwrap() < 1.0);
+
+    // comparison.json names the traces rather than embedding them, and reads back
+    let json = serde_json::to_value(&comparison).unwrap();
+    assert_eq!(json["runs"][1]["trace_path"], "web/trace_data_large.json");
+    assert!(json["runs"][0].get("trace").is_none());
+    assert_eq!(serde_json::from_value::<Comparison>(json).unwrap(), comparison);
+
+    // Without a proof on either side there is nothing to subtract
+    let mut failed = runs[1].clone();
+    failed.proof_bytes = None;
+    let delta = &Comparison::new(vec![runs[0].clone(), failed]).deltas[0];
+    assert_eq!((delta.proof_bytes, delta.proof_bytes_ratio), (None, None));
+    assert!(Comparison::new(Vec::new()).deltas.is_empty());
+}
+
+#[test]
+fn js_export_is_the_json_export_as_a_script() {
+    let dir = tempfile::tempdir().unwrap();
+    let trace = generate_fibonacci_trace::<Mersenne31>(8, FIBONACCI_START);
+    let mut vis = vis_data_from_matrix(&trace, &FIBONACCI_COLUMNS).unwrap();
+    // Nothing in the data can end a <script> element or the statement
+    vis.columns[0].description = "</script><script>alert(1)</script> \u{2028}".into();
+    let json: Value = serde_json::from_slice(&TraceFormat::Json.exporter().serialize(&vis).unwrap()).unwrap();
+
+    let script = String::from_utf8(TraceFormat::Js.exporter().serialize(&vis).unwrap()).unwrap();
+    let object = script.strip_prefix("window.TRACE_DATA = ").and_then(|s| s.strip_suffix(";\n")).unwrap();
+    assert_eq!(serde_json::from_str::<Value>(object).unwrap(), json);
+    assert!(!script.contains("</") && !script.contains('\u{2028}'));
+    assert_balanced(object);
+    assert_eq!(TraceFormat::Js.exporter().extension(), "js");
+
+    let module_path = dir.path().join("trace_data.mjs");
+    write_trace_js(&module_path, &vis, true).unwrap();
+    let module = fs::read_to_string(&module_path).unwrap();
+    let object = module.strip_prefix("export default ").and_then(|s| s.strip_suffix(";\n")).unwrap();
+    assert_eq!(serde_json::from_str::<Value>(object).unwrap(), json);
+
+    // Run both through node where it is installed
+    let script_path = dir.path().join("trace_data.js");
+    write_trace_js(&script_path, &vis, false).unwrap();
+    let check = format!(
+        "globalThis.window = globalThis; require({:?}); import({:?}).then(m => {{ \
+         if (JSON.stringify(m.default) !== JSON.stringify(window.TRACE_DATA) || window.TRACE_DATA.num_steps !== 8) \
+         process.exit(1); }})",
+        script_path,
+        module_path
+    );
+    match std::process::Command::new("node").args(["-e", &check]).status() {
+        Ok(status) => assert!(status.success(), "node rejected the js exports"),
+        Err(e) => eprintln!("node not found, the js exports are only checked as JSON: {e}"),
+    }
+}
+
+// Brackets outside strings pair up and every string is closed.
+fn assert_balanced(text: &str) {
+    let (mut open, mut in_string, mut escaped) = (Vec::new(), false, false);
+    for c in text.chars() {
+        match (in_string, escaped, c) {
+            (true, true, _) => escaped = false,
+            (true, false, '\\') => escaped = true,
+            (true, false, '"') => in_string = false,
+            (true, false, _) => {}
+            (false, _, '"') => in_string = true,
+            (false, _, '{' | '[') => open.push(c),
+            (false, _, '}') => assert_eq!(open.pop(), Some('{')),
+            (false, _, ']') => assert_eq!(open.pop(), Some('[')),
+            _ => {}
+        }
+    }
+    assert!(open.is_empty() && !in_string);
+}
+
+#[test]
+fn viewer_is_scaffolded_once_next_to_the_export() {
+    let dir = tempfile::tempdir().unwrap();
+    let web = dir.path().join("web");
+    let written = write_assets(&web, "trace_data.json", false).unwrap();
+    let index = web.join("index.html");
+    let viewer = web.join("viewer.js");
+    assert_eq!(written, [(index.clone(), AssetStatus::Written), (viewer.clone(), AssetStatus::Written)]);
+    let html = fs::read_to_string(&index).unwrap();
+    assert!(html.contains(r#"<script src="viewer.js" data-trace="trace_data.json"></script>"#), "{html}");
+    assert!(html.contains(r#"<script src="trace_data.js"></script>"#), "{html}");
+    assert!(!html.contains(TRACE_FILE) && !html.contains(TRACE_SCRIPT));
+    assert_eq!(fs::read_to_string(&viewer).unwrap(), VIEWER_JS);
+    assert!(VIEWER_JS.contains("dataset.trace") && VIEWER_JS.contains("TRACE_DATA") && INDEX_HTML.contains(TRACE_FILE));
+
+    // A page that is there is someone's, edited or the full visualizer: kept, viewer.js included
+    fs::write(&index, "my own page").unwrap();
+    fs::remove_file(&viewer).unwrap();
+    assert_eq!(write_assets(&web, "run.json", false).unwrap(), [(index.clone(), AssetStatus::Kept)]);
+    assert_eq!(fs::read_to_string(&index).unwrap(), "my own page");
+    assert!(!viewer.exists());
+
+    // ... unless forced, with the export's name as given
+    let written = write_assets(&web, "run.json", true).unwrap();
+    assert!(written.iter().all(|(_, status)| *status == AssetStatus::Written));
+    assert!(fs::read_to_string(&index).unwrap().contains(r#"data-trace="run.json""#));
+    assert!(viewer.exists());
+    assert!(index_html(r#"a"b<c>.json"#).contains(r#"data-trace="a&quot;b&lt;c&gt;.json""#));
+}
+
+#[test]
+fn trace_table_aligns_elides_and_fits_the_width() {
+    let trace = generate_fibonacci_trace::<Mersenne31>(4, FIBONACCI_START);
+    let vis = vis_data_from_matrix(&trace, &FIBONACCI_COLUMNS).unwrap();
+    assert_eq!(
+        trace_table(&vis, 20, 80),
+        "step  a  b\n----  -  -\n   0  0  1\n   1  1  1\n   2  1  2\n   3  2  3\n"
+    );
+
+    let trace = generate_fibonacci_trace::<Mersenne31>(64, FIBONACCI_START);
+    let table = trace_table(&vis_data_from_matrix(&trace, &FIBONACCI_COLUMNS).unwrap(), 6, 80);
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 2 + 6 + 1);
+    assert_eq!(lines[5], "… 58 rows omitted …");
+    assert!(lines[2].trim_start().starts_with("0 ") && lines[8].trim_start().starts_with("63 "));
+
+    // 30 columns do not fit into 60 characters: the ones that do are shown, and how many are not
+    let (coeffs, initial): (Vec<u64>, Vec<u64>) = (vec![1; 30], (0..30).collect());
+    let trace = generate_linear_recurrence_trace::<Mersenne31>(8, &coeffs, &initial);
+    let table = trace_table(&vis_data_from_matrix(&trace, &linear_recurrence_columns(30)).unwrap(), 20, 60);
+    assert!(table.lines().all(|line| line.chars().count() <= 60), "{table}");
+    assert!(table.lines().next().unwrap().ends_with("more columns"), "{table}");
+}
+
+#[test]
+fn trace_table_prints_hex_and_both_representations() {
+    let trace = generate_fibonacci_trace::<Mersenne31>(4, FIBONACCI_START);
+    let vis = vis_data_from_matrix(&trace, &FIBONACCI_COLUMNS).unwrap();
+    assert_eq!(
+        trace_table(&vis.clone().with_repr(ValueRepr::Hex), 20, 80),
+        "step           a           b\n\
+         ----  ----------  ----------\n\
+         \x20  0  0x00000000  0x00000001\n\
+         \x20  1  0x00000001  0x00000001\n\
+         \x20  2  0x00000001  0x00000002\n\
+         \x20  3  0x00000002  0x00000003\n"
+    );
+    assert_eq!(
+        trace_table(&vis.with_repr(ValueRepr::Both), 20, 80),
+        "step               a               b\n\
+         ----  --------------  --------------\n\
+         \x20  0  0 (0x00000000)  1 (0x00000001)\n\
+         \x20  1  1 (0x00000001)  1 (0x00000001)\n\
+         \x20  2  1 (0x00000001)  2 (0x00000002)\n\
+         \x20  3  2 (0x00000002)  3 (0x00000003)\n"
+    );
+}
+
+#[test]
+fn markdown_export_snapshot() {
+    let trace = generate_fibonacci_trace::<Mersenne31>(4, FIBONACCI_START);
+    let mut vis = vis_data_from_matrix(&trace, &FIBONACCI_COLUMNS).unwrap().with_tampered(vec![(1, 1)]);
+    vis.sequence = "Fibonacci".to_string();
+    vis.final_value = 3;
+    vis.public_values = vec!["0".to_string(), "1".to_string(), "3".to_string()];
+    vis.field = FieldInfo::new(FieldChoice::Mersenne31);
+    vis.hash = "Keccak256".to_string();
+    let timings = Timings { trace_gen_ms: 0.5, export_ms: 1.0, prove_ms: 12.5, verify_ms: 3.0, total_ms: 20.0 };
+    let fri = vis.fri;
+    let vis = vis.with_stats(ProofStats { proof_bytes: 1234, timings, fri, ..ProofStats::default() });
+
+    let md = String::from_utf8(TraceFormat::Md.exporter().serialize(&vis).unwrap()).unwrap();
+    assert_eq!(
+        md,
+        "\
+# Fibonacci trace
+
+| Parameter | Value |
+| --- | --- |
+| Steps | 4 |
+| Final value | 3 |
+| Public values | 0, 1, 3 |
+| Field | Mersenne31 (p = 2147483647) |
+| Hash | Keccak256 |
+| FRI | log_blowup 1, 100 queries, 16 proof-of-work bits, log_final_poly_len 1 |
+| Seed | none |
+
+## Proof
+
+| Proof size | Trace generation | Export | Proving | Verification | Total |
+| ---: | ---: | ---: | ---: | ---: | ---: |
+| 1234 bytes | 0.5 ms | 1.0 ms | 12.5 ms | 3.0 ms | 20.0 ms |
+
+## Trace
+
+| step | a | b |
+| ---: | ---: | ---: |
+| 0 | 0 | 1 |
+| 1 | 1 | **1** |
+| 2 | 1 | 2 |
+| 3 | 2 | 3 |
+
+- `a`: current Fibonacci number
+- `b`: next Fibonacci number
+"
+    );
+}
+
+#[test]
+fn markdown_export_elides_long_traces() {
+    let trace = generate_fibonacci_trace::<Mersenne31>(64, FIBONACCI_START);
+    let vis = vis_data_from_matrix(&trace, &FIBONACCI_COLUMNS).unwrap();
+    let md = String::from_utf8(TraceFormat::Md.exporter().serialize(&vis).unwrap()).unwrap();
+    assert!(md.contains("| … | 44 rows omitted | |\n"), "{md}");
+    assert!(md.contains("\n| 63 | "), "{md}");
+    assert!(md.contains("Not proven."));
+}
+
+// The status line, headers and body of a GET of `path`, read until the server closes the connection.
+fn http_get(addr: std::net::SocketAddr, path: &str) -> (String, String, Vec<u8>) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "GET {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\r\n").unwrap();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).unwrap();
+    let split = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+    let head = String::from_utf8(response[..split].to_vec()).unwrap();
+    let (status, headers) = head.split_once("\r\n").unwrap_or((&head, ""));
+    (status.to_string(), headers.to_ascii_lowercase(), response[split + 4..].to_vec())
+}
+
+#[test]
+fn serve_hands_out_the_export_uncached_and_nothing_outside_its_root() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("web");
+    let vis = prove_fibonacci(FibonacciParams::new(8)).unwrap().vis_data;
+    write_trace_json(&root.join("trace_data.json"), &vis).unwrap();
+    fs::write(root.join("index.html"), "<!doctype html>").unwrap();
+    fs::write(dir.path().join("secret.txt"), "outside").unwrap();
+
+    let server = StaticServer::bind(&root, 0).unwrap();
+    let addr = server.addr();
+    assert_ne!(addr.port(), 0);
+    assert_eq!(server.url(), format!("http://{addr}/"));
+    let shutdown = server.shutdown_handle();
+    let running = std::thread::spawn(move || server.run());
+
+    let (status, headers, body) = http_get(addr, "/trace_data.json?reload=1");
+    assert!(status.ends_with("200 OK"), "{status}");
+    assert!(headers.contains("content-type: application/json"), "{headers}");
+    assert!(headers.contains("cache-control: no-cache"), "{headers}");
+    assert_eq!(body, fs::read(root.join("trace_data.json")).unwrap());
+    assert_eq!(serde_json::from_slice::<VisData>(&body).unwrap().trace, vis.trace);
+
+    let (status, headers, body) = http_get(addr, "/");
+    assert!(status.ends_with("200 OK"), "{status}");
+    assert!(headers.contains("content-type: text/html"), "{headers}");
+    assert!(!headers.contains("cache-control"), "{headers}");
+    assert_eq!(body, b"<!doctype html>");
+    for path in ["/missing.json", "/../secret.txt", "/%2e%2e/secret.txt"] {
+        let (status, _, _) = http_get(addr, path);
+        assert!(status.contains(" 404 "), "{path}: {status}");
+    }
+
+    // A second server cannot take the same port
+    assert!(matches!(StaticServer::bind(&root, addr.port()), Err(FibVisError::Config(_))));
+    assert!(matches!(StaticServer::bind(&dir.path().join("missing"), 0), Err(FibVisError::Config(_))));
+    shutdown.shutdown();
+    running.join().unwrap();
+}
+
+#[test]
+fn serve_pushes_one_update_per_burst_of_exports_to_every_live_client() {
+    let dir = tempfile::tempdir().unwrap();
+    // Proven up front, so the exports below follow each other closer than LIVE_DEBOUNCE
+    let exports: Vec<VisData> =
+        [8, 16, 32, 64].into_iter().map(|n| prove_fibonacci(FibonacciParams::new(n)).unwrap().vis_data).collect();
+    write_trace_json(&dir.path().join(LIVE_FILE), &exports[0]).unwrap();
+    let server = StaticServer::bind(dir.path(), 0).unwrap();
+    let addr = server.addr();
+    let shutdown = server.shutdown_handle();
+    let running = std::thread::spawn(move || server.run());
+
+    let connect = || {
+        let (socket, _) = tungstenite::connect(format!("ws://{addr}{LIVE_PATH}")).unwrap();
+        if let tungstenite::stream::MaybeTlsStream::Plain(stream) = socket.get_ref() {
+            stream.set_read_timeout(Some(std::time::Duration::from_secs(5))).unwrap();
+        }
+        socket
+    };
+    let mut clients = vec![connect(), connect()];
+    // A client that leaves before the update is dropped without holding up the others
+    drop(connect());
+    std::thread::sleep(LIVE_POLL * 2);
+
+    // Three exports in a row, as a sweep or a quick rerun writes them, are a single update with the last one
+    for vis in &exports[1..] {
+        write_trace_json(&dir.path().join(LIVE_FILE), vis).unwrap();
+    }
+    for socket in &mut clients {
+        let message = socket.read().unwrap();
+        let update: LiveUpdate = serde_json::from_str(message.to_text().unwrap()).unwrap();
+        let LiveUpdate::Update { path, data } = update else { panic!("expected the export inline: {update:?}") };
+        assert_eq!(path, LIVE_FILE);
+        assert_eq!(data["num_steps"], 64);
+    }
+    if let tungstenite::stream::MaybeTlsStream::Plain(stream) = clients[0].get_ref() {
+        stream.set_read_timeout(Some((LIVE_POLL + LIVE_DEBOUNCE) * 3)).unwrap();
+    }
+    assert!(clients[0].read().is_err(), "a second update for the same burst");
+
+    // A page that does not speak WebSocket is told so
+    let (status, _, _) = http_get(addr, LIVE_PATH);
+    assert!(status.contains(" 400 "), "{status}");
+    // and there is no pipeline to follow
+    let (status, _, _) = http_get(addr, EVENTS_PATH);
+    assert!(status.contains(" 404 "), "{status}");
+    shutdown.shutdown();
+    running.join().unwrap();
+}
+
+// Opens the event stream of the server at `addr`, past its head.
+fn open_events(addr: std::net::SocketAddr) -> BufReader<TcpStream> {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(std::time::Duration::from_secs(5))).unwrap();
+    write!(stream, "GET {EVENTS_PATH} HTTP/1.1\r\nHost: {addr}\r\n\r\n").unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut head = String::new();
+    while !head.ends_with("\r\n\r\n") {
+        assert_ne!(reader.read_line(&mut head).unwrap(), 0, "{head}");
+    }
+    assert!(head.starts_with("HTTP/1.1 200 "), "{head}");
+    assert!(head.to_ascii_lowercase().contains("content-type: text/event-stream"), "{head}");
+    reader
+}
+
+// The next event on the stream, None once the server ended it.
+fn next_event(reader: &mut BufReader<TcpStream>) -> Option<PipelineEvent> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).unwrap() == 0 {
+            return None;
+        }
+        if let Some(json) = line.trim_end().strip_prefix("data: ") {
+            return Some(serde_json::from_str(json).unwrap());
+        }
+    }
+}
+
+#[test]
+fn serve_streams_the_pipeline_events_with_the_history_of_the_current_run() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = EventLog::default();
+    let server = StaticServer::bind(dir.path(), 0).unwrap().with_events(log.clone());
+    let addr = server.addr();
+    let shutdown = server.shutdown_handle();
+    let running = std::thread::spawn(move || server.run());
+
+    // The phases as the binary opens them, with the fields it records
+    let dispatch = Dispatch::new(Registry::default().with(PipelineEvents::new(log.clone())));
+    let generate = |rows: usize| {
+        info_span!("generate trace", rows = field::Empty).in_scope(|| {
+            Span::current().record("rows", rows);
+        });
+    };
+    let (mut early, mut late, history) = tracing::dispatcher::with_default(&dispatch, || {
+        generate(64);
+        let early = open_events(addr);
+        let proving = info_span!("proving", proof_bytes = field::Empty);
+        // Joining midway, after the trace and with the prover still running
+        let late = proving.in_scope(|| {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            open_events(addr)
+        });
+        proving.record("proof_bytes", 1234);
+        drop(proving);
+        info_span!("verification", ok = field::Empty).in_scope(|| {
+            Span::current().record("ok", true);
+        });
+        info_span!("write export", path = "web/trace_data.json", bytes = field::Empty).in_scope(|| {
+            Span::current().record("bytes", 5678);
+        });
+        // A failed write has no size, and no event
+        info_span!("write export", path = "unwritable/trace_data.json", bytes = field::Empty).in_scope(|| {});
+        let history = log.events();
+        generate(8);
+        (early, late, history)
+    });
+
+    let first_run = vec![
+        PipelineEvent::TraceGenerated { rows: 64 },
+        PipelineEvent::ProvingStarted,
+        PipelineEvent::ProvingFinished { ms: 0.0, proof_bytes: Some(1234) },
+        PipelineEvent::Verified { ok: true },
+        PipelineEvent::Exported { path: "web/trace_data.json".to_string(), bytes: 5678 },
+    ];
+    let without_time = |event: PipelineEvent| match event {
+        PipelineEvent::ProvingFinished { ms, proof_bytes } => {
+            assert!(ms >= 20.0, "{ms} ms for a prover that took 20 at least");
+            PipelineEvent::ProvingFinished { ms: 0.0, proof_bytes }
+        }
+        event => event,
+    };
+    assert_eq!(history.into_iter().map(without_time).collect::<Vec<_>>(), first_run);
+    // Both joined midway: they get the history of the first run, the rest of it as it went, then go on with the next
+    for client in [&mut early, &mut late] {
+        let events: Vec<_> = (0..first_run.len()).map(|_| without_time(next_event(client).unwrap())).collect();
+        assert_eq!(events, first_run);
+        assert_eq!(next_event(client), Some(PipelineEvent::TraceGenerated { rows: 8 }));
+    }
+    // One that joins now only sees the run under way
+    let mut newest = open_events(addr);
+    assert_eq!(next_event(&mut newest), Some(PipelineEvent::TraceGenerated { rows: 8 }));
+    assert_eq!(log.events(), vec![PipelineEvent::TraceGenerated { rows: 8 }]);
+
+    // Shutting down ends the streams
+    shutdown.shutdown();
+    running.join().unwrap();
+    for client in [&mut early, &mut late, &mut newest] {
+        assert_eq!(next_event(client), None);
+    }
+}
+
+#[test]
+fn serve_proves_what_a_page_asks_for_one_run_at_a_time() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = StaticServer::bind(dir.path(), 0).unwrap().with_max_steps(1 << 12);
+    let base = server.url().trim_end_matches('/').to_string();
+    let shutdown = server.shutdown_handle();
+    let running = std::thread::spawn(move || server.run());
+
+    let client = reqwest::blocking::Client::new();
+    let prove = |body: Value| client.post(format!("{base}{PROVE_PATH}")).json(&body).send().unwrap();
+    // Polls the run a POST started until it is done with
+    let finish = |started: reqwest::blocking::Response| -> ApiRun {
+        assert_eq!(started.status(), 202);
+        let location = started.headers()["location"].to_str().unwrap().to_string();
+        let run: ApiRun = started.json().unwrap();
+        assert_eq!((run.status, format!("/api/runs/{}", run.id)), (RunStatus::Proving, location.clone()));
+        let deadline = Instant::now() + std::time::Duration::from_secs(120);
+        loop {
+            let run: ApiRun = client.get(format!("{base}{location}")).send().unwrap().json().unwrap();
+            if run.status != RunStatus::Proving {
+                return run;
+            }
+            assert!(Instant::now() < deadline, "run {} still proving", run.id);
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+    };
+
+    let run = finish(prove(serde_json::json!({"num_steps": 8})));
+    assert_eq!((run.id, run.status, run.verified), (1, RunStatus::Done, true));
+    assert_eq!((run.final_value, run.error), (Some(21), None));
+    assert!(run.proof_bytes.unwrap() > 0 && run.timings.unwrap().verify_ms > 0.0);
+    // The export the open pages are updated with
+    let exported = load_vis_data(&dir.path().join(LIVE_FILE)).unwrap();
+    assert_eq!(exported.num_steps, 8);
+    assert!(exported.verification.unwrap().verified);
+
+    // A tampered trace is a run too, a rejected one
+    let rejected = finish(prove(serde_json::json!({"num_steps": 8, "tamper": [{"row": 3, "column": 1, "value": 7}]})));
+    assert_eq!((rejected.id, rejected.status, rejected.verified), (2, RunStatus::Failed, false));
+    assert!(rejected.error.is_some());
+
+    // While one run proves, the next is turned away rather than started next to it
+    let long = prove(serde_json::json!({"num_steps": 1 << 12}));
+    assert_eq!(prove(serde_json::json!({"num_steps": 8})).status(), 409);
+    assert_eq!(finish(long).status, RunStatus::Done);
+
+    let error = |response: reqwest::blocking::Response| (response.status().as_u16(), response.json::<Value>().unwrap());
+    let (status, body) = error(prove(serde_json::json!({"num_steps": 1 << 13})));
+    assert_eq!(status, 400);
+    assert!(body["error"].as_str().unwrap().contains("at most 4096"), "{body}");
+    assert_eq!(error(prove(serde_json::json!({"steps": 8}))).0, 400);
+    let plain = client.post(format!("{base}{PROVE_PATH}")).body(r#"{"num_steps": 8}"#).send().unwrap();
+    assert_eq!(plain.status(), 415);
+    assert_eq!(client.get(format!("{base}{PROVE_PATH}")).send().unwrap().status(), 405);
+    assert_eq!(client.get(format!("{base}/api/runs/4")).send().unwrap().status(), 404);
+    shutdown.shutdown();
+    running.join().unwrap();
+}
+
+#[test]
+fn proof_file_round_trips_and_a_damaged_one_is_an_error() {
+    let params = FibonacciParams::new(8);
+    let run = prove_fibonacci(params.clone()).unwrap();
+    let header = ProofHeader {
+        field: FieldChoice::Mersenne31,
+        hash: HashChoice::Keccak256,
+        fri: FriParams::default(),
+        num_steps: 8,
+        sequence: Sequence::Fibonacci,
+        recurrence: Vec::new(),
+        initial: vec![0, 1],
+        final_value: 21,
+        public_values: run.vis_data.public_values.clone(),
+        seed: Seed::default(),
+    };
+    let file = ProofFile { header, proof: run.proof.clone() };
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("proofs/proof.bin");
+    let written = file.write(&path).unwrap();
+    let (read, size) = ProofFile::read(&path).unwrap();
+    assert_eq!((&read, size), (&file, written));
+    read.decode::<M31KeccakConfig>().unwrap();
+    verify_fibonacci(&params, &read.proof).unwrap();
+
+    // The magic and the version come first, so a file of another format or version is named as such
+    let bytes = file.to_bytes().unwrap();
+    assert_eq!(bytes[..4], PROOF_MAGIC);
+    assert_eq!(u16::from(bytes[4]), PROOF_FORMAT_VERSION);
+    let error = |bytes: &[u8]| ProofFile::from_bytes(bytes).unwrap_err().to_string();
+    assert!(error(b"not a proof").contains("not a proof file"), "{}", error(b"not a proof"));
+    let mut older = bytes.clone();
+    older[4] = 5;
+    assert!(error(&older).contains("version 5"), "{}", error(&older));
+
+    // A flipped byte in the proof fails its digest, and a truncated file its length, before the proof is decoded
+    let body = bytes.len() - file.proof.len();
+    for i in [body, body + file.proof.len() / 2, bytes.len() - 1] {
+        let mut flipped = bytes.clone();
+        flipped[i] ^= 0x01;
+        assert!(error(&flipped).contains("digest"), "byte {i}: {}", error(&flipped));
+    }
+    for len in [3, body / 2, body, bytes.len() / 2, bytes.len() - 1] {
+        assert!(matches!(ProofFile::from_bytes(&bytes[..len]), Err(FibVisError::Serialization(_))), "{len} bytes");
+    }
+
+    // A flipped byte in front of the proof is an error too, or reads back as a header `verify` rejects
+    for i in 0..body {
+        let mut flipped = bytes.clone();
+        flipped[i] ^= 0x01;
+        match ProofFile::from_bytes(&flipped) {
+            Ok(damaged) => assert_ne!(damaged.header, file.header, "byte {i}"),
+            Err(e) => assert!(matches!(e, FibVisError::Serialization(_)), "byte {i}: {e}"),
+        }
+    }
+    fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+    let e = ProofFile::read(&path).unwrap_err();
+    assert!(e.to_string().contains("proof.bin"), "{e}");
+    assert_eq!(e.exit_code(), 4);
+}
+
+#[test]
+fn inspect_proof_counts_the_queries_of_the_fri_config_and_sizes_the_proof() {
+    let params = FibonacciParams::new(8);
+    let run = prove_fibonacci(params.clone()).unwrap();
+    let header = ProofHeader {
+        field: FieldChoice::Mersenne31,
+        hash: HashChoice::Keccak256,
+        fri: params.fri,
+        num_steps: 8,
+        sequence: Sequence::Fibonacci,
+        recurrence: Vec::new(),
+        initial: vec![0, 1],
+        final_value: 21,
+        public_values: run.vis_data.public_values.clone(),
+        seed: Seed::default(),
+    };
+    let file = ProofFile { header, proof: run.proof.clone() };
+    let file_bytes = file.to_bytes().unwrap().len();
+    let inspection = inspect_proof::<M31KeccakConfig>(&file, file_bytes).unwrap();
+    assert_eq!(inspection.num_queries, FriParams::default().num_queries);
+    assert_eq!((&inspection.header, inspection.file_bytes), (&file.header, file_bytes));
+
+    // The commitments, the rounds and the witness are the ones the proof export reads
+    let proof = file.decode::<M31KeccakConfig>().unwrap();
+    assert_eq!(inspection.commitments, commitment_roots(&proof).unwrap());
+    let rounds = inspection.commitments.iter().filter(|c| c.phase == CommitPhase::FriCommitPhase).count();
+    assert!(rounds > 0);
+    assert_eq!(inspection.commit_phase_rounds, rounds);
+    assert_eq!(inspection.pow, pow_grinding(&proof, params.fri.proof_of_work_bits, &[], None).unwrap());
+
+    // The trace is opened at zeta and at its successor, a challenge field element per column
+    let opened = |name: &str| inspection.opened_values.iter().find(|o| o.name == name).map(|o| o.values);
+    let trace_local = opened("trace_local").unwrap();
+    assert!(trace_local > 0 && trace_local % M31_CHALLENGE_DEGREE == 0, "{trace_local}");
+    assert_eq!(opened("trace_next"), Some(trace_local));
+    assert!(opened("quotient_chunks").unwrap() > 0);
+
+    // The sections break the file's body down, and the proof's fields make up all of it
+    let sections = &inspection.sections;
+    assert_eq!(sections.bytes, run.proof.len());
+    assert_eq!(sections.sections.iter().map(|s| s.bytes).sum::<usize>(), sections.bytes);
+    for name in ["commitments", "opened_values", "opening_proof"] {
+        assert!(sections.sections.iter().any(|s| s.name == name && !s.sections.is_empty()), "{name}: {sections:?}");
+    }
+
+    let json = serde_json::to_string(&inspection).unwrap();
+    assert_eq!(serde_json::from_str::<ProofInspection>(&json).unwrap(), inspection);
+}
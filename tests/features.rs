@@ -0,0 +1,24 @@
+// Checks that the crate still compiles with only some of its backend features, which the default build never
+// tries. They run a nested cargo that builds the crate again, so they are opt-in:
+//
+//     cargo test --test features -- --ignored
+
+use std::process::Command;
+
+fn cargo() -> Command {
+    Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+}
+
+#[test]
+#[ignore = "runs a nested cargo build; run with --ignored"]
+fn checks_with_babybear_as_the_only_backend_feature() {
+    let target_dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("babybear-check");
+    let status = cargo()
+        .args(["check", "--lib", "--bins", "--no-default-features", "--features", "babybear", "--manifest-path"])
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+        .arg("--target-dir")
+        .arg(target_dir)
+        .status()
+        .expect("could not run cargo");
+    assert!(status.success(), "cargo check --no-default-features --features babybear failed");
+}
@@ -0,0 +1,51 @@
+// The demo registry: every demo builds a trace its own AIR accepts, and the --sequence names find their demos.
+
+use clap::ValueEnum;
+use p3_mersenne_31::Mersenne31;
+use plonky3_fibonacci::constraints::find_violations;
+use plonky3_fibonacci::registry::{self, DemoParams, DEMOS};
+use plonky3_fibonacci::stark_config::FieldChoice;
+use plonky3_fibonacci::trace::{check_final_value, check_trace_shape};
+use plonky3_fibonacci::Sequence;
+
+#[test]
+fn every_demo_builds_a_trace_its_air_accepts() {
+    for demo in DEMOS.iter() {
+        let (initial, recurrence): (&[u64], &[u64]) = match demo.name() {
+            "collatz" => (&[27], &[]),
+            "linear-recurrence" => (&[0, 1], &[2, 1]),
+            _ => (&[0, 1], &[]),
+        };
+        let params = DemoParams { num_steps: 128, initial, recurrence };
+        demo.check(&params).unwrap();
+
+        let air = demo.air(&params);
+        let trace = demo.build_trace::<Mersenne31>(&params);
+        let final_value = demo.final_value(FieldChoice::Mersenne31, &params);
+        check_trace_shape(&air, &trace).unwrap();
+        check_final_value(&air, final_value, &trace).unwrap();
+        let columns = demo.columns(&params);
+        assert_eq!(columns.len(), trace.width, "{}", demo.name());
+        assert!(columns.iter().all(|c| !c.name.is_empty() && !c.description.is_empty()), "{}", demo.name());
+        let violations = find_violations(&air, &trace, &air.public_values::<Mersenne31>(final_value));
+        assert!(violations.is_empty(), "{}: {}", demo.name(), violations[0]);
+    }
+}
+
+#[test]
+fn sequences_are_found_by_their_command_line_names() {
+    for sequence in Sequence::value_variants() {
+        let name = sequence.to_possible_value().unwrap().get_name().to_string();
+        let demo = registry::get(&name).unwrap_or_else(|| panic!("no demo called {name}"));
+        assert_eq!(demo.label(), sequence.name());
+    }
+    assert_eq!(registry::get("linear-recurrence").unwrap().label(), "LinearRecurrence");
+    assert!(registry::get("fib").is_none());
+}
+
+#[test]
+fn only_fibonacci_pads() {
+    let params = DemoParams { num_steps: 10, initial: &[0, 1], recurrence: &[] };
+    registry::get("fibonacci").unwrap().check(&params).unwrap();
+    assert!(registry::get("lucas").unwrap().check(&params).is_err());
+}
@@ -0,0 +1,121 @@
+// Checks run on a generated trace before it is exported or proven.
+
+use p3_field::{PrimeCharacteristicRing, PrimeField32};
+use p3_matrix::dense::RowMajorMatrix;
+use num_bigint::BigUint;
+use p3_mersenne_31::Mersenne31;
+use plonky3_fibonacci::export::VisData;
+use plonky3_fibonacci::stark_config::FieldChoice;
+use plonky3_fibonacci::trace::{check_trace_shape, fibonacci_true_values, first_reduced_row, FIBONACCI_START};
+use plonky3_fibonacci::{
+    generate_fibonacci_trace, generate_padded_fibonacci_trace, generate_tribonacci_trace, prove_fibonacci, FibVisError, FibonacciAir, FibonacciParams, TribonacciAir,
+};
+
+#[test]
+fn generated_trace_has_the_air_shape() {
+    let trace = generate_fibonacci_trace::<Mersenne31>(16, FIBONACCI_START);
+    check_trace_shape(&FibonacciAir::new(16), &trace).unwrap();
+}
+
+#[test]
+fn wrong_width_is_rejected() {
+    let trace = RowMajorMatrix::new(vec![Mersenne31::ZERO; 8 * 3], 3);
+    let err = check_trace_shape(&FibonacciAir::new(8), &trace).unwrap_err();
+    assert!(
+        matches!(err, FibVisError::ShapeMismatch { expected_width: 2, expected_height: 8, width: 3, height: 8 }),
+        "unexpected error: {err}"
+    );
+    assert_eq!(err.exit_code(), 5);
+}
+
+#[test]
+fn wrong_height_is_rejected() {
+    let trace = generate_fibonacci_trace::<Mersenne31>(8, FIBONACCI_START);
+    let err = check_trace_shape(&FibonacciAir::new(16), &trace).unwrap_err();
+    assert!(
+        matches!(err, FibVisError::ShapeMismatch { expected_width: 2, expected_height: 16, width: 2, height: 8 }),
+        "unexpected error: {err}"
+    );
+}
+
+#[test]
+fn first_reduced_row_is_where_mersenne31_wraps() {
+    // F(46) = 1836311903 still fits below 2^31 - 1, F(47) = 2971215073 does not, and row i holds F(i + 1).
+    let modulus = FieldChoice::Mersenne31.modulus();
+    let true_values = fibonacci_true_values(64, FIBONACCI_START);
+    assert_eq!(first_reduced_row(modulus, &true_values), Some(46));
+    assert_eq!(first_reduced_row(modulus, &true_values[..46]), None);
+
+    let trace = generate_fibonacci_trace::<Mersenne31>(64, FIBONACCI_START);
+    assert_eq!(true_values[46][1].to_string(), "2971215073");
+    assert_eq!(trace.values[46 * 2 + 1].as_canonical_u32(), 2971215073 - modulus);
+    assert_eq!(true_values[45][1].to_string(), trace.values[45 * 2 + 1].to_string());
+}
+
+#[test]
+fn export_marks_the_reduced_rows() {
+    let run = prove_fibonacci(FibonacciParams::new(64)).unwrap();
+    let vis = run.vis_data;
+    assert_eq!(vis.first_reduced_row, Some(46));
+    assert_eq!(vis.reduced.iter().position(|&r| r), Some(46));
+    assert!(vis.reduced[46..].iter().all(|&r| r));
+    assert_eq!(vis.true_values.len(), 64);
+    assert_eq!(vis.true_values[63][1], "10610209857723");
+    assert_ne!(vis.trace[63][1], vis.true_values[63][1]);
+}
+
+#[test]
+fn reduced_rows_are_marked_one_by_one() {
+    // An orbit that climbs past the modulus and comes back under it, as Collatz does; the row past the exact values
+    // is taken to be like the last of them
+    let true_values = [1u32, 9, 3].map(|v| vec![BigUint::from(v)]).to_vec();
+    let vis = VisData { num_steps: 4, ..VisData::default() }.with_true_values(7, true_values);
+    assert_eq!(vis.first_reduced_row, Some(1));
+    assert_eq!(vis.reduced, [false, true, false, false]);
+}
+
+#[test]
+fn column_stats_find_where_each_column_wraps() {
+    // Below the threshold nothing wraps and both columns only grow
+    let vis = prove_fibonacci(FibonacciParams::new(16)).unwrap().vis_data;
+    let ranges: Vec<_> = vis.column_stats.iter().map(|c| (c.name.as_str(), c.min.as_str(), c.max.as_str())).collect();
+    assert_eq!(ranges, [("a", "0", "610"), ("b", "1", "987")]);
+    assert!(vis.column_stats.iter().all(|c| c.increasing && c.first_wrap_row.is_none()));
+
+    // Above it, b holds F(i + 1) and wraps a row before a; the value after the wrap is smaller than the one before it
+    let vis = prove_fibonacci(FibonacciParams::new(64)).unwrap().vis_data;
+    let wraps: Vec<_> = vis.column_stats.iter().map(|c| c.first_wrap_row).collect();
+    assert_eq!(wraps, [Some(47), Some(46)]);
+    assert_eq!(wraps.iter().flatten().min().copied(), vis.first_reduced_row);
+    assert!(vis.column_stats.iter().all(|c| !c.increasing));
+    let modulus = u64::from(FieldChoice::Mersenne31.modulus());
+    assert!(vis.column_stats.iter().all(|c| c.max.parse::<u64>().unwrap() < modulus));
+
+    // The padding rows are left out: their zeros would stop the columns of a padded trace from increasing
+    let vis = prove_fibonacci(FibonacciParams::new(10)).unwrap().vis_data;
+    assert_eq!(vis.column_stats.len(), 4);
+    assert_eq!((vis.column_stats[1].min.as_str(), vis.column_stats[1].max.as_str()), ("1", "55"));
+    assert!(vis.column_stats.iter().all(|c| c.increasing));
+}
+
+#[test]
+fn tribonacci_trace_is_three_columns_wide() {
+    let trace = generate_tribonacci_trace::<Mersenne31>(8);
+    check_trace_shape(&TribonacciAir::new(8), &trace).unwrap();
+    let last: Vec<u32> = trace.values[7 * 3..].iter().map(|v| v.as_canonical_u32()).collect();
+    assert_eq!(last, [13, 24, 44]);
+}
+
+#[test]
+fn padded_trace_fills_up_to_a_power_of_two() {
+    let trace = generate_padded_fibonacci_trace::<Mersenne31>(10, FIBONACCI_START);
+    check_trace_shape(&FibonacciAir::padded(10, FIBONACCI_START), &trace).unwrap();
+    let column = |col: usize| -> Vec<u32> {
+        (0..16).map(|row| trace.values[row * 4 + col].as_canonical_u32()).collect()
+    };
+    assert_eq!(column(2), [[1; 10].as_slice(), &[0; 6]].concat());
+    assert_eq!(column(3), (0..16).collect::<Vec<u32>>());
+    assert_eq!(trace.values[9 * 4 + 1].as_canonical_u32(), 55);
+    // The padding is zeros but for its row index
+    assert_eq!(&trace.values[10 * 4..10 * 4 + 3], &[Mersenne31::ZERO; 3]);
+}
@@ -0,0 +1,152 @@
+// Checks that the library still compiles for the browser, and that the `wasm` feature's bindings work there. The
+// wasm32 builds need the target installed (`rustup target add wasm32-unknown-unknown`), the node test also
+// wasm-pack and node, and they run a nested cargo, so they are opt-in:
+//
+//     cargo test --test wasm -- --ignored
+//
+// What the bindings compute is checked natively too, with `cargo test --features wasm --test wasm`.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn cargo() -> Command {
+    Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+}
+
+// The --export-proof JSON of an 8-step Fibonacci proof, made by the binary in `dir`
+fn exported_proof(dir: &Path) -> PathBuf {
+    let path = dir.join("proof_data.json");
+    let status = Command::new(env!("CARGO_BIN_EXE_plonky3_fibonacci"))
+        .current_dir(dir)
+        .args(["prove", "--num-steps", "8", "--output", "trace_data.json", "--proof", "proof.bin", "--export-proof"])
+        .arg(&path)
+        .status()
+        .expect("could not run the binary");
+    assert!(status.success(), "prove --export-proof failed");
+    path
+}
+
+#[test]
+#[ignore = "needs the wasm32-unknown-unknown target; run with --ignored"]
+fn library_checks_for_wasm32() {
+    let target_dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("wasm32-check");
+    let status = cargo()
+        .args(["check", "--lib", "--target", "wasm32-unknown-unknown", "--manifest-path"])
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+        .arg("--target-dir")
+        .arg(target_dir)
+        .status()
+        .expect("could not run cargo");
+    assert!(status.success(), "cargo check --target wasm32-unknown-unknown failed");
+}
+
+#[test]
+#[ignore = "needs the wasm32-unknown-unknown target; run with --ignored"]
+fn wasm_bindings_check_for_wasm32() {
+    let target_dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("wasm32-check");
+    let status = cargo()
+        .args(["check", "--lib", "--target", "wasm32-unknown-unknown", "--features", "wasm", "--manifest-path"])
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+        .arg("--target-dir")
+        .arg(target_dir)
+        .status()
+        .expect("could not run cargo");
+    assert!(status.success(), "cargo check --target wasm32-unknown-unknown --features wasm failed");
+}
+
+#[test]
+#[ignore = "needs wasm-pack, node and the wasm32-unknown-unknown target; run with --ignored"]
+fn wasm_bindings_run_in_node() {
+    let out_dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("wasm-pkg");
+    let status = Command::new("wasm-pack")
+        .args(["build", "--target", "nodejs", "--dev", "--out-dir"])
+        .arg(&out_dir)
+        .arg(env!("CARGO_MANIFEST_DIR"))
+        .args(["--", "--features", "wasm"])
+        .status()
+        .expect("could not run wasm-pack");
+    assert!(status.success(), "wasm-pack build --features wasm failed");
+    let dir = tempfile::tempdir().unwrap();
+    let status = Command::new("node")
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/wasm_smoke.js"))
+        .arg(out_dir.join("plonky3_fibonacci.js"))
+        .arg(exported_proof(dir.path()))
+        .status()
+        .expect("could not run node");
+    assert!(status.success(), "the node smoke test failed");
+}
+
+#[cfg(feature = "wasm")]
+#[test]
+fn browser_trace_is_the_export_and_its_edits_are_caught() {
+    use plonky3_fibonacci::export::vis_data_from_json;
+    use plonky3_fibonacci::wasm::{check_trace_json, fibonacci_trace_json};
+    use plonky3_fibonacci::FibVisError;
+
+    let json = fibonacci_trace_json(8, (0, 1)).unwrap();
+    let vis = vis_data_from_json(&json).unwrap();
+    assert_eq!((vis.num_steps, vis.final_value, vis.trace.len()), (8, 21, 8));
+    assert_eq!(vis.trace[7], ["13", "21"]);
+    assert!(!vis.constraints.is_empty());
+    assert_eq!(check_trace_json(&json).unwrap().violations, []);
+
+    // Padded like for proving, from any start pair
+    let padded = vis_data_from_json(&fibonacci_trace_json(10, (2, 1)).unwrap()).unwrap();
+    assert_eq!((padded.num_steps, padded.trace.len()), (10, 16));
+    assert_eq!(padded.trace[0][..2], ["2", "1"]);
+    assert!(fibonacci_trace_json(1, (0, 1)).is_err());
+
+    // One cell changed breaks the transitions it takes part in, the first row the first-row constraints too
+    let mut doc: serde_json::Value = serde_json::from_str(&json).unwrap();
+    doc["trace"][3][1] = "7".into();
+    let check = check_trace_json(&doc.to_string()).unwrap();
+    assert!(!check.ok);
+    assert!(check.violations.iter().all(|v| v.row == 2 || v.row == 3), "{:?}", check.violations);
+    doc["trace"][3][1] = "3".into();
+    doc["trace"][0][0] = "1".into();
+    assert!(check_trace_json(&doc.to_string()).unwrap().violations.iter().any(|v| v.row == 0));
+    assert!(check_trace_json("{\"trace\": 1}").is_err());
+
+    // A trace of another shape than the AIR's is an error, not a panic in the constraint evaluation
+    let one_column: Vec<serde_json::Value> = (0..8).map(|row| serde_json::json!([row.to_string()])).collect();
+    doc["trace"] = one_column.into();
+    let error = check_trace_json(&doc.to_string()).unwrap_err();
+    assert!(matches!(error, FibVisError::ShapeMismatch { expected_width: 2, width: 1, .. }), "{error}");
+    doc["trace"] = serde_json::json!([["0", "1"], ["1", "1"]]);
+    let error = check_trace_json(&doc.to_string()).unwrap_err();
+    assert!(matches!(error, FibVisError::ShapeMismatch { height: 2, .. }), "{error}");
+}
+
+#[cfg(feature = "wasm")]
+#[test]
+fn native_proofs_verify_in_the_browser_path_and_corrupted_ones_do_not() {
+    use plonky3_fibonacci::wasm::{verify_proof, verify_proof_json};
+
+    let dir = tempfile::tempdir().unwrap();
+    let json = std::fs::read_to_string(exported_proof(dir.path())).unwrap();
+    verify_proof_json(&json, 8, 21).unwrap();
+    assert!(verify_proof(&json, 8, 21));
+
+    // Not what was proven
+    assert!(!verify_proof(&json, 8, 22));
+    assert!(verify_proof_json(&json, 1, 21).is_err());
+
+    // One digit of the trace commitment changed, so the openings no longer match it
+    let mut doc: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let commitment = first_hex_string(&mut doc["proof"]["commitments"]["trace"]).expect("no hex commitment");
+    let last = if commitment.ends_with('0') { '1' } else { '0' };
+    commitment.pop();
+    commitment.push(last);
+    assert!(!verify_proof(&doc.to_string(), 8, 21));
+    assert!(!verify_proof("{\"proof\": 1}", 8, 21));
+}
+
+#[cfg(feature = "wasm")]
+fn first_hex_string(value: &mut serde_json::Value) -> Option<&mut String> {
+    match value {
+        serde_json::Value::String(s) if s.starts_with("0x") => Some(s),
+        serde_json::Value::Array(values) => values.iter_mut().find_map(first_hex_string),
+        serde_json::Value::Object(fields) => fields.values_mut().find_map(first_hex_string),
+        _ => None,
+    }
+}
@@ -0,0 +1,43 @@
+// Calls the `wasm` feature's bindings from node, with the module wasm-pack built and the --export-proof JSON of an
+// 8-step Fibonacci proof made natively as arguments; see wasm_bindings_run_in_node in tests/wasm.rs.
+const assert = require('assert');
+const fs = require('fs');
+const wasm = require(process.argv[2]);
+const proofJson = fs.readFileSync(process.argv[3], 'utf8');
+
+const json = wasm.generate_trace_json(8, 0, 1);
+const data = JSON.parse(json);
+assert.strictEqual(data.num_steps, 8);
+assert.strictEqual(data.final_value, 21);
+assert.deepStrictEqual(data.trace[7], ['13', '21']);
+assert.deepStrictEqual(wasm.check_trace(json), { ok: true, violations: [] });
+
+data.trace[3][1] = '7';
+const check = wasm.check_trace(JSON.stringify(data));
+assert.strictEqual(check.ok, false);
+assert.ok(check.violations.length > 0);
+
+assert.throws(() => wasm.generate_trace_json(1, 0, 1));
+assert.throws(() => wasm.check_trace('not json'));
+assert.strictEqual(wasm.verify_proof(proofJson, 8, 21), true);
+assert.strictEqual(wasm.verify_proof(proofJson, 8, 22), false);
+
+// One digit of the trace commitment changed
+const firstHex = (value, path) => {
+  if (typeof value === 'string') return value.startsWith('0x') ? path : null;
+  if (value === null || typeof value !== 'object') return null;
+  for (const key of Object.keys(value)) {
+    const found = firstHex(value[key], [...path, key]);
+    if (found) return found;
+  }
+  return null;
+};
+const proof = JSON.parse(proofJson);
+const path = firstHex(proof.proof.commitments.trace, ['proof', 'commitments', 'trace']);
+assert.ok(path, 'no hex commitment');
+const parent = path.slice(0, -1).reduce((value, key) => value[key], proof);
+const key = path[path.length - 1];
+parent[key] = parent[key].slice(0, -1) + (parent[key].endsWith('0') ? '1' : '0');
+assert.strictEqual(wasm.verify_proof(JSON.stringify(proof), 8, 21), false);
+assert.strictEqual(wasm.verify_proof('{"proof": 1}', 8, 21), false);
+console.log('wasm bindings ok');